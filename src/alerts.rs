@@ -0,0 +1,129 @@
+use crate::config::{Alert, Period};
+use crate::model::RepoCommit;
+use crate::utils::as_datetime;
+use chrono::{DateTime, FixedOffset};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A repository exceeding the commit frequency threshold of an alert
+#[derive(Debug, PartialEq)]
+pub struct Violation {
+    pub repo: String,
+    /// the hour, day or week, e.g. `2020-05-17`
+    pub period: String,
+    pub commits: usize,
+    pub max_commits: usize,
+    pub per: Period,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} commits in {}, more than {} per {}",
+            self.repo,
+            self.commits,
+            self.period,
+            self.max_commits,
+            match self.per {
+                Period::Hour => "hour",
+                Period::Day => "day",
+                Period::Week => "week",
+            }
+        )
+    }
+}
+
+/// the violations of the given alerts by the given commits, ordered by
+/// alert, repository and period
+pub fn check(alerts: &[Alert], commits: &[RepoCommit]) -> Vec<Violation> {
+    alerts
+        .iter()
+        .flat_map(|alert| {
+            let keys = commits
+                .iter()
+                .filter(|commit| {
+                    alert
+                        .repo
+                        .as_ref()
+                        .is_none_or(|repo| repo.is_match(&commit.repo.rel_path))
+                })
+                .map(|commit| {
+                    (
+                        commit.repo.rel_path.as_str(),
+                        period_of(alert.per, &as_datetime(&commit.commit_time)),
+                    )
+                });
+            violations(alert, keys)
+        })
+        .collect()
+}
+
+fn violations<'a>(alert: &Alert, keys: impl Iterator<Item = (&'a str, String)>) -> Vec<Violation> {
+    let mut counts: BTreeMap<(&str, String), usize> = BTreeMap::new();
+    for key in keys {
+        *counts.entry(key).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .filter(|(_, commits)| *commits > alert.max_commits)
+        .map(|((repo, period), commits)| Violation {
+            repo: repo.to_string(),
+            period,
+            commits,
+            max_commits: alert.max_commits,
+            per: alert.per,
+        })
+        .collect()
+}
+
+/// the hour, day or ISO week of the given time, in its time zone
+fn period_of(per: Period, time: &DateTime<FixedOffset>) -> String {
+    let format = match per {
+        Period::Hour => "%Y-%m-%d %H:00",
+        Period::Day => "%Y-%m-%d",
+        Period::Week => "%G-W%V",
+    };
+    time.format(format).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_exceeded_thresholds() {
+        let time = |s: &str| DateTime::parse_from_rfc3339(s).unwrap();
+        assert_eq!(
+            period_of(Period::Hour, &time("2020-05-17T13:45:00+02:00")),
+            "2020-05-17 13:00"
+        );
+        assert_eq!(
+            period_of(Period::Week, &time("2021-01-03T12:00:00+00:00")),
+            "2020-W53"
+        );
+
+        let alert = Alert {
+            repo: None,
+            max_commits: 1,
+            per: Period::Day,
+        };
+        let day = |repo, date: &str| (repo, String::from(date));
+        let keys = vec![
+            day("kernel/msm", "2020-05-17"),
+            day("kernel/msm", "2020-05-17"),
+            day("kernel/msm", "2020-05-18"),
+            day("kernel/common", "2020-05-17"),
+        ];
+        assert_eq!(
+            violations(&alert, keys.into_iter()),
+            vec![Violation {
+                repo: String::from("kernel/msm"),
+                period: String::from("2020-05-17"),
+                commits: 2,
+                max_commits: 1,
+                per: Period::Day,
+            }]
+        );
+    }
+}
@@ -0,0 +1,454 @@
+use crate::config;
+use crate::manifest;
+use crate::model::Repo;
+use crate::utils::{expand_placeholders, find_repo_base_folder, shell_command, shell_quote};
+use anyhow::{anyhow, Context, Result};
+use git2::{Commit, Oid, Repository, Tree};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const DEFAULT_CHECKOUT: &str = "repo sync -d -m {manifest}";
+/// Number of commits listed per changed project
+const MAX_LISTED_COMMITS: usize = 20;
+
+/// The command checking out a workspace state, its placeholders are
+/// passed to the shell as parameters (see `shell_command`)
+#[derive(Clone)]
+pub struct Checkout {
+    script: String,
+    placeholders: Vec<(&'static str, String)>,
+    /// root folder of the workspace the command is run in
+    base_folder: PathBuf,
+}
+
+impl Checkout {
+    /// the command, run in the root folder of the workspace
+    pub fn command(&self) -> Command {
+        let mut command = shell_command(&self.script, &self.placeholders);
+        command.current_dir(&self.base_folder);
+        command
+    }
+}
+
+impl fmt::Display for Checkout {
+    /// the command as typed into a shell, with quoted placeholders
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let quoted: Vec<(&str, String)> = self
+            .placeholders
+            .iter()
+            .map(|(name, value)| (*name, shell_quote(value)))
+            .collect();
+        write!(f, "{}", expand_placeholders(&self.script, &quoted))
+    }
+}
+
+/// The answer of the user for a workspace state
+enum Verdict {
+    Good,
+    Bad,
+    Skip,
+    Quit,
+}
+
+/// Bisects the history of the manifest repository (or of a superproject,
+/// see `[bisect]` in the config file) between the given good and bad
+/// revisions (`<good>..<bad>`): successive workspace states are checked
+/// out until the first bad one is found, then the changes of the projects
+/// between the last good and the first bad state are listed.
+pub fn run(range: &str, settings: Option<&config::Bisect>) -> Result<()> {
+    let (good, bad) = range
+        .split_once("..")
+        .ok_or_else(|| anyhow!("Expected a range of the form <good>..<bad>"))?;
+    let base_folder = find_repo_base_folder()?;
+    let repo_path = states_repo(&base_folder, settings);
+    let repo = Repository::open(&repo_path)
+        .with_context(|| format!("Failed to open {}", repo_path.display()))?;
+    let checkout = checkout_of(settings);
+    let manifest_file = manifest::file_name(&base_folder);
+
+    let good = repo.revparse_single(good)?.peel_to_commit()?;
+    let bad = repo.revparse_single(bad)?.peel_to_commit()?;
+    // the state of the workspace before bisecting, restored on quit
+    let current = repo.head()?.peel_to_commit()?;
+    let mut states = states_between(&repo, &good, &bad)?;
+    println!(
+        "Bisecting {} workspace states between good {} and bad {}",
+        states.len(),
+        short(good.id()),
+        short(bad.id())
+    );
+
+    // states[..=low] are good (-1: the given good one), states[high..] bad
+    let mut low: isize = -1;
+    let mut high = states.len() - 1;
+    let mut skipped = Vec::new();
+    while high as isize - low > 1 {
+        let mid = ((low + high as isize) / 2) as usize;
+        let state = repo.find_commit(states[mid])?;
+        println!(
+            "\n{} states left, checking out {} {}",
+            high as isize - low - 1,
+            short(state.id()),
+            state.summary().unwrap_or_default()
+        );
+        let verdict = match prepare_checkout(
+            &repo,
+            &state,
+            &manifest_file,
+            &checkout,
+            &base_folder,
+            "oper-bisect.xml",
+        ) {
+            Ok(command) => {
+                run_checkout(&command)?;
+                ask()?
+            }
+            Err(error) => {
+                println!("Skipping the state: {:#}", error);
+                Verdict::Skip
+            }
+        };
+        match verdict {
+            Verdict::Good => low = mid as isize,
+            Verdict::Bad => high = mid,
+            Verdict::Skip => {
+                skipped.push(states.remove(mid));
+                high -= 1;
+            }
+            Verdict::Quit => {
+                println!(
+                    "\nRestoring the workspace state {} {}",
+                    short(current.id()),
+                    current.summary().unwrap_or_default()
+                );
+                let command = prepare_checkout(
+                    &repo,
+                    &current,
+                    &manifest_file,
+                    &checkout,
+                    &base_folder,
+                    "oper-bisect.xml",
+                )?;
+                return run_checkout(&command);
+            }
+        }
+    }
+
+    let first_bad = repo.find_commit(states[high])?;
+    let last_good = match low {
+        -1 => good,
+        low => repo.find_commit(states[low as usize])?,
+    };
+    println!(
+        "\nThe first bad workspace state is {} {}",
+        first_bad.id(),
+        first_bad.summary().unwrap_or_default()
+    );
+    println!(
+        "The last good workspace state is {} {}",
+        last_good.id(),
+        last_good.summary().unwrap_or_default()
+    );
+    if !skipped.is_empty() {
+        println!("These skipped states might be the first bad one as well:");
+        for oid in skipped {
+            let state = repo.find_commit(oid)?;
+            println!("  {} {}", state.id(), state.summary().unwrap_or_default());
+        }
+    }
+    print_changes(&repo, &last_good, &first_bad, &manifest_file, &base_folder)?;
+    if checkout.contains("{manifest}") {
+        println!("\nRun `repo sync -d` to return to the current manifest.");
+    }
+    Ok(())
+}
+
+/// the repository whose history gives the states of the workspace
+fn states_repo(base_folder: &Path, settings: Option<&config::Bisect>) -> PathBuf {
+    settings
+        .and_then(|s| s.repo.clone())
+        .unwrap_or_else(|| manifest::repo_folder(base_folder))
+}
+
+fn checkout_of(settings: Option<&config::Bisect>) -> String {
+    settings
+        .and_then(|s| s.checkout.clone())
+        .unwrap_or_else(|| String::from(DEFAULT_CHECKOUT))
+}
+
+/// the command reproducing the state of the workspace given by a commit
+/// of the manifest repository (or of the superproject, see `[bisect]`),
+/// `None` if the commit is of another repository. The manifest file of
+/// the state is written to `.repo/oper-checkout.xml`.
+pub fn checkout_command(
+    repo: &Repo,
+    commit_id: Oid,
+    settings: Option<&config::Bisect>,
+) -> Result<Option<Checkout>> {
+    // of the workspace of the commit, several might be scanned at once
+    let base_folder = repo.workspace_folder();
+    let states_repo = states_repo(base_folder, settings);
+    if fs::canonicalize(&repo.abs_path).ok() != fs::canonicalize(&states_repo).ok() {
+        return Ok(None);
+    }
+    let repo = Repository::open(&states_repo)?;
+    let state = repo.find_commit(commit_id)?;
+    let manifest_file = manifest::file_name(base_folder);
+    prepare_checkout(
+        &repo,
+        &state,
+        &manifest_file,
+        &checkout_of(settings),
+        base_folder,
+        "oper-checkout.xml",
+    )
+    .map(Some)
+}
+
+/// the commits of the first-parent history after `good` up to `bad`,
+/// oldest first
+fn states_between(repo: &Repository, good: &Commit, bad: &Commit) -> Result<Vec<Oid>> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.simplify_first_parent()?;
+    revwalk.push(bad.id())?;
+    revwalk.hide(good.id())?;
+    let mut states = revwalk.collect::<Result<Vec<Oid>, git2::Error>>()?;
+    if states.is_empty() {
+        return Err(anyhow!("{} isn't a descendant of {}", bad.id(), good.id()));
+    }
+    states.reverse();
+    Ok(states)
+}
+
+/// runs the checkout command of a state
+fn run_checkout(command: &Checkout) -> Result<()> {
+    println!("$ {}", command);
+    let status = command
+        .command()
+        .status()
+        .with_context(|| format!("Failed to run {}", command))?;
+    if !status.success() {
+        return Err(anyhow!("{} failed with {}", command, status));
+    }
+    Ok(())
+}
+
+/// the placeholders of the checkout command for the given state of the
+/// workspace at `base_folder`, its manifest is written to the file named
+/// `manifest_copy` in `.repo` if the command needs it
+fn prepare_checkout(
+    repo: &Repository,
+    state: &Commit,
+    manifest_file: &str,
+    checkout: &str,
+    base_folder: &Path,
+    manifest_copy: &str,
+) -> Result<Checkout> {
+    let mut placeholders = vec![
+        ("sha", state.id().to_string()),
+        (
+            "repo",
+            repo.workdir().unwrap_or(repo.path()).display().to_string(),
+        ),
+    ];
+    if checkout.contains("{manifest}") {
+        let manifest = read_file(&state.tree()?, repo, manifest_file).ok_or_else(|| {
+            anyhow!(
+                "{} has no manifest file {}",
+                short(state.id()),
+                manifest_file
+            )
+        })?;
+        // repo sync -m takes the manifest from a file
+        let manifest_copy = base_folder.join(".repo").join(manifest_copy);
+        fs::write(&manifest_copy, manifest)?;
+        placeholders.push(("manifest", manifest_copy.display().to_string()));
+    }
+    Ok(Checkout {
+        script: checkout.to_string(),
+        placeholders,
+        base_folder: base_folder.to_path_buf(),
+    })
+}
+
+fn ask() -> Result<Verdict> {
+    let stdin = io::stdin();
+    loop {
+        print!("Is this state good or bad? [g]ood, [b]ad, [s]kip, [q]uit: ");
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        if stdin.lock().read_line(&mut answer)? == 0 {
+            return Ok(Verdict::Quit);
+        }
+        match answer.trim() {
+            "g" | "good" => return Ok(Verdict::Good),
+            "b" | "bad" => return Ok(Verdict::Bad),
+            "s" | "skip" => return Ok(Verdict::Skip),
+            "q" | "quit" => return Ok(Verdict::Quit),
+            _ => {}
+        }
+    }
+}
+
+fn read_file(tree: &Tree, repo: &Repository, path: &str) -> Option<String> {
+    let blob = tree.get_path(Path::new(path)).ok()?.to_object(repo).ok()?;
+    Some(String::from_utf8_lossy(blob.as_blob()?.content()).into_owned())
+}
+
+/// lists the projects whose revision differs between both workspace
+/// states, with their new commits as far as they are available locally
+fn print_changes(
+    repo: &Repository,
+    good: &Commit,
+    bad: &Commit,
+    manifest_file: &str,
+    base_folder: &Path,
+) -> Result<()> {
+    let changes = match (
+        read_file(&good.tree()?, repo, manifest_file),
+        read_file(&bad.tree()?, repo, manifest_file),
+    ) {
+        (Some(good), Some(bad)) => diff(&revisions(&good), &revisions(&bad)),
+        _ => diff(&gitlinks(&good.tree()?), &gitlinks(&bad.tree()?)),
+    };
+    if changes.is_empty() {
+        println!("No project revisions differ between these states.");
+        return Ok(());
+    }
+    println!("Projects changed between these states:");
+    for (path, old, new) in changes {
+        let (old, new) = match (old, new) {
+            (Some(old), Some(new)) => (old, new),
+            (None, _) => {
+                println!("  {} (added)", path);
+                continue;
+            }
+            (_, None) => {
+                println!("  {} (removed)", path);
+                continue;
+            }
+        };
+        let commits = commits_between(&base_folder.join(&path), &old, &new);
+        match commits {
+            Ok(commits) => {
+                println!("  {} {}..{} ({} commits)", path, old, new, commits.len());
+                for summary in commits.iter().take(MAX_LISTED_COMMITS) {
+                    println!("      {}", summary);
+                }
+            }
+            Err(_) => println!("  {} {}..{}", path, old, new),
+        }
+    }
+    Ok(())
+}
+
+/// the revisions of the projects of a manifest, by their path
+fn revisions(manifest: &str) -> BTreeMap<String, String> {
+    manifest::projects_of(manifest)
+        .into_iter()
+        .map(|project| (project.path, project.revision))
+        .collect()
+}
+
+/// the commits of the submodules of a superproject, by their path
+fn gitlinks(tree: &Tree) -> BTreeMap<String, String> {
+    let mut gitlinks = BTreeMap::new();
+    let _ = tree.walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+        if entry.kind() == Some(git2::ObjectType::Commit) {
+            let path = format!("{}{}", root, entry.name().unwrap_or_default());
+            gitlinks.insert(path, entry.id().to_string());
+        }
+        git2::TreeWalkResult::Ok
+    });
+    gitlinks
+}
+
+type Change = (String, Option<String>, Option<String>);
+
+fn diff(good: &BTreeMap<String, String>, bad: &BTreeMap<String, String>) -> Vec<Change> {
+    let mut paths: Vec<&String> = good.keys().chain(bad.keys()).collect();
+    paths.sort();
+    paths.dedup();
+    paths
+        .into_iter()
+        .filter(|path| good.get(*path) != bad.get(*path))
+        .map(|path| {
+            (
+                path.clone(),
+                good.get(path).cloned(),
+                bad.get(path).cloned(),
+            )
+        })
+        .collect()
+}
+
+/// the summaries of the commits reachable from `new` but not from `old`
+fn commits_between(repo_path: &Path, old: &str, new: &str) -> Result<Vec<String>> {
+    let repo = Repository::open(repo_path)?;
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(repo.revparse_single(new)?.peel_to_commit()?.id())?;
+    revwalk.hide(repo.revparse_single(old)?.peel_to_commit()?.id())?;
+    revwalk
+        .map(|oid| {
+            let commit = repo.find_commit(oid?)?;
+            Ok(format!(
+                "{} {}",
+                short(commit.id()),
+                commit.summary().unwrap_or_default()
+            ))
+        })
+        .collect()
+}
+
+fn short(oid: Oid) -> String {
+    oid.to_string()[..8].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_the_placeholders_of_checkouts() {
+        let checkout = Checkout {
+            script: String::from("repo sync -d -m {manifest}"),
+            placeholders: vec![("manifest", String::from("/my ws/it's.xml"))],
+            base_folder: PathBuf::from("/my ws"),
+        };
+        assert_eq!(
+            checkout.to_string(),
+            "repo sync -d -m '/my ws/it'\\''s.xml'"
+        );
+    }
+
+    #[test]
+    fn diffs_manifest_revisions() {
+        let good = revisions(
+            r#"<manifest><default revision="main"/>
+            <project name="kernel/msm" revision="abc"/>
+            <project name="platform/build" path="build"/></manifest>"#,
+        );
+        let bad = revisions(
+            r#"<manifest><default revision="main"/>
+            <project name="kernel/msm" revision="def"/>
+            <project name="platform/build" path="build"/>
+            <project name="vendor/foo" revision="v1"/></manifest>"#,
+        );
+        assert_eq!(good.get("build").map(String::as_str), Some("main"));
+        assert_eq!(
+            diff(&good, &bad),
+            vec![
+                (
+                    String::from("kernel/msm"),
+                    Some(String::from("abc")),
+                    Some(String::from("def"))
+                ),
+                (String::from("vendor/foo"), None, Some(String::from("v1"))),
+            ]
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use crate::config::Builds;
+use crate::manifest;
+use crate::model::RepoCommit;
+use crate::utils::find_repo_base_folder;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use git2::Repository;
+use std::fs::read_to_string;
+use std::process::{Command, Stdio};
+
+/// A build and where it has been cut: at a point in time or at a commit,
+/// e.g. of the manifest repository
+#[derive(Clone, Debug, PartialEq)]
+pub struct Build {
+    pub id: String,
+    pub cut: Cut,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cut {
+    /// seconds since the epoch
+    Time(i64),
+    /// SHA-1 of a commit, or a unique prefix of it
+    Commit(String),
+}
+
+/// A build whose cut has been resolved to a point in time
+#[derive(Clone, Debug, PartialEq)]
+pub struct BuildCut {
+    pub id: String,
+    /// seconds since the epoch, commits up to then are part of the build
+    pub time: i64,
+}
+
+/// reads the builds from the file or the output of the command given in
+/// the `[builds]` section of the config file. Each line maps a build ID
+/// to a date or a commit SHA, e.g. `1234 2020-05-17 13:00`.
+pub fn load(builds: &Builds) -> Result<Vec<Build>> {
+    let text = match (&builds.file, &builds.command) {
+        (Some(file), _) => read_to_string(file)
+            .with_context(|| format!("Failed to read builds from {}", file.display()))?,
+        (None, Some(command)) => run(command)?,
+        (None, None) => return Ok(Vec::new()),
+    };
+    parse(&text)
+}
+
+fn run(command: &str) -> Result<String> {
+    let output = Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()
+        .with_context(|| format!("Failed to run {}", command))?;
+    if !output.status.success() {
+        return Err(anyhow!("{} failed with {}", command, output.status));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// parses lines of the form `<build id> <date or commit SHA>`, empty lines
+/// and lines starting with `#` are skipped
+fn parse(text: &str) -> Result<Vec<Build>> {
+    let mut builds = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (id, cut) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| anyhow!("Line {} of the builds lacks a date or commit", number + 1))?;
+        let cut = parse_cut(cut.trim())
+            .ok_or_else(|| anyhow!("Unknown date or commit '{}' of build {}", cut.trim(), id))?;
+        builds.push(Build {
+            id: id.to_string(),
+            cut,
+        });
+    }
+    Ok(builds)
+}
+
+/// a date (RFC 3339, or `YYYY-MM-DD [HH:MM[:SS]]` in local time) or a SHA
+fn parse_cut(cut: &str) -> Option<Cut> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(cut) {
+        return Some(Cut::Time(time.timestamp()));
+    }
+    let naive = NaiveDateTime::parse_from_str(cut, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(cut, "%Y-%m-%d %H:%M"))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(cut, "%Y-%m-%d")
+                .ok()?
+                .and_hms_opt(0, 0, 0)
+        });
+    if let Some(naive) = naive {
+        return Some(Cut::Time(
+            Local.from_local_datetime(&naive).earliest()?.timestamp(),
+        ));
+    }
+    if cut.len() >= 7 && cut.len() <= 40 && cut.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Some(Cut::Commit(cut.to_lowercase()));
+    }
+    None
+}
+
+/// resolves the cuts of the given builds to points in time, newest first.
+/// Commits are looked up in the given history and in the manifest
+/// repository; builds whose commit isn't found are skipped.
+pub fn resolve(builds: &[Build], commits: &[RepoCommit]) -> Vec<BuildCut> {
+    let mut ids: Vec<(String, i64)> = Vec::new();
+    if builds.iter().any(|b| matches!(b.cut, Cut::Commit(_))) {
+        ids = commits
+            .iter()
+            .map(|c| (c.commit_id.to_string(), c.commit_time.seconds()))
+            .collect();
+        ids.sort_unstable();
+    }
+    let manifests = find_repo_base_folder()
+        .ok()
+        .and_then(|folder| Repository::open(manifest::repo_folder(&folder)).ok());
+
+    let mut cuts: Vec<BuildCut> = builds
+        .iter()
+        .filter_map(|build| {
+            let time = match &build.cut {
+                Cut::Time(time) => *time,
+                Cut::Commit(sha) => {
+                    let index = ids.partition_point(|(id, _)| id.as_str() < sha.as_str());
+                    match ids.get(index) {
+                        Some((id, time)) if id.starts_with(sha.as_str()) => *time,
+                        _ => manifests
+                            .as_ref()?
+                            .revparse_single(sha)
+                            .ok()?
+                            .peel_to_commit()
+                            .ok()?
+                            .time()
+                            .seconds(),
+                    }
+                }
+            };
+            Some(BuildCut {
+                id: build.id.clone(),
+                time,
+            })
+        })
+        .collect();
+    cuts.sort_by_key(|cut| std::cmp::Reverse(cut.time));
+    cuts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_builds() {
+        let builds =
+            parse("# nightly builds\n1234 2020-05-17T13:00:00+02:00\n\n1235  A1b2C3d4\n").unwrap();
+        assert_eq!(
+            builds,
+            vec![
+                Build {
+                    id: String::from("1234"),
+                    cut: Cut::Time(1589713200),
+                },
+                Build {
+                    id: String::from("1235"),
+                    cut: Cut::Commit(String::from("a1b2c3d4")),
+                },
+            ]
+        );
+        assert!(matches!(
+            parse("1236 2020-05-17").unwrap()[0].cut,
+            Cut::Time(_)
+        ));
+        assert!(parse("1237").is_err());
+        assert!(parse("1238 yesterday").is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use crate::model::Repo;
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Progress of a CSV report written while scanning: the repositories whose
+/// commits have been written and the length of the report file by then.
+/// Kept next to the report (e.g. `report.csv.checkpoint`) until the report
+/// is complete, so `--resume` continues an interrupted run instead of
+/// starting over.
+pub struct Checkpoint {
+    file: PathBuf,
+    /// absolute paths of the repositories written into the report
+    repos: HashSet<PathBuf>,
+}
+
+impl Checkpoint {
+    /// an empty checkpoint of the given report, replacing the one of a
+    /// previous run. It records the length the report starts from, i.e.
+    /// its current length if rows are appended to it and 0 otherwise, so
+    /// resuming doesn't drop the rows of earlier runs.
+    pub fn new(report: &Path, appending: bool) -> Result<Checkpoint> {
+        let file = file_of(report);
+        let length = match report.metadata() {
+            Ok(metadata) if appending => metadata.len(),
+            _ => 0,
+        };
+        File::create(&file)
+            .and_then(|mut checkpoint| checkpoint.write_all(format!("{}\n", length).as_bytes()))
+            .with_context(|| format!("Failed to create checkpoint {}", file.display()))?;
+        Ok(Checkpoint {
+            file,
+            repos: HashSet::new(),
+        })
+    }
+
+    /// the checkpoint of an interrupted run writing the given report. The
+    /// report is truncated to the rows of the repositories written
+    /// completely.
+    pub fn resume(report: &Path) -> Result<Checkpoint> {
+        let file = file_of(report);
+        let content = match fs::read_to_string(&file) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(anyhow!(
+                    "Nothing to resume, no checkpoint of {} found",
+                    report.display()
+                ))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let (repos, length) = parse(&content);
+        OpenOptions::new()
+            .write(true)
+            .open(report)
+            .and_then(|report| report.set_len(length))
+            .with_context(|| format!("Failed to truncate {}", report.display()))?;
+        Ok(Checkpoint { file, repos })
+    }
+
+    /// true if the commits of the given repository have been written
+    pub fn contains(&self, repo: &Repo) -> bool {
+        self.repos.contains(&repo.abs_path)
+    }
+
+    /// number of repositories whose commits have been written
+    pub fn written_repos(&self) -> usize {
+        self.repos.len()
+    }
+
+    /// records that the commits of the given repository have been written
+    /// and the report has grown to the given length
+    pub fn record(&mut self, repo: &Repo, length: u64) -> Result<()> {
+        let mut file = OpenOptions::new().append(true).open(&self.file)?;
+        // a line by a single write, an interrupted one is ignored by parse()
+        file.write_all(format!("{} {}\n", length, repo.abs_path.display()).as_bytes())?;
+        self.repos.insert(repo.abs_path.clone());
+        Ok(())
+    }
+
+    /// removes the checkpoint once the report is complete
+    pub fn finish(self) -> Result<()> {
+        Ok(fs::remove_file(&self.file)?)
+    }
+}
+
+fn file_of(report: &Path) -> PathBuf {
+    let mut file = report.as_os_str().to_owned();
+    file.push(".checkpoint");
+    PathBuf::from(file)
+}
+
+/// the repositories and the length of the report recorded by the lines
+/// (`<length> <path>`) of a checkpoint, following the length the report
+/// started from (`<length>`); an incomplete last line is ignored
+fn parse(content: &str) -> (HashSet<PathBuf>, u64) {
+    let mut repos = HashSet::new();
+    let mut length = 0;
+    for line in content.split_inclusive('\n') {
+        let line = match line.strip_suffix('\n') {
+            Some(line) => line,
+            None => continue,
+        };
+        let (len, path) = match line.split_once(' ') {
+            Some((len, path)) => (len, Some(path)),
+            None => (line, None),
+        };
+        if let Ok(len) = len.parse::<u64>() {
+            repos.extend(path.map(PathBuf::from));
+            length = len;
+        }
+    }
+    (repos, length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignores_interrupted_lines() {
+        let (repos, length) = parse("80\n120 /ws/kernel/msm\n480 /ws/platform/my build\n52");
+        assert_eq!(length, 480);
+        assert_eq!(repos.len(), 2);
+        assert!(repos.contains(Path::new("/ws/platform/my build")));
+        assert_eq!(parse(""), (HashSet::new(), 0));
+        assert_eq!(
+            file_of(Path::new("out/report.csv")),
+            PathBuf::from("out/report.csv.checkpoint")
+        );
+    }
+
+    #[test]
+    fn resumes_an_empty_checkpoint_from_the_initial_length() {
+        let dir = std::env::temp_dir().join(format!("oper-checkpoint-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let report = dir.join("report.csv");
+        fs::write(&report, "Header\nrow of an earlier run\n").unwrap();
+
+        let checkpoint = Checkpoint::new(&report, true).unwrap();
+        assert_eq!(checkpoint.written_repos(), 0);
+        // interrupted after a partial row of the first repository
+        fs::write(&report, "Header\nrow of an earlier run\npartial").unwrap();
+        let checkpoint = Checkpoint::resume(&report).unwrap();
+        assert_eq!(checkpoint.written_repos(), 0);
+        assert_eq!(
+            fs::read_to_string(&report).unwrap(),
+            "Header\nrow of an earlier run\n"
+        );
+
+        Checkpoint::new(&report, false).unwrap();
+        Checkpoint::resume(&report).unwrap();
+        assert_eq!(fs::read_to_string(&report).unwrap(), "");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,186 @@
+use crate::model::RepoCommit;
+use git2::{Oid, Patch, Repository};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Component, Path, PathBuf};
+
+/// Lines changed within a top-level directory of a repository
+#[derive(Debug, PartialEq)]
+pub struct Area {
+    /// local path of the repository followed by the directory, e.g.
+    /// `kernel/msm/drivers`; the local path of the repository only for
+    /// files in its root folder
+    pub name: String,
+    pub added: usize,
+    pub deleted: usize,
+    pub commits: usize,
+}
+
+impl Area {
+    pub fn lines(&self) -> usize {
+        self.added + self.deleted
+    }
+}
+
+/// The lines changed by commits per area, the most changed area first
+pub struct Churn {
+    pub areas: Vec<Area>,
+    /// commits whose changes couldn't be read, e.g. due to blobs missing
+    /// in partial clones
+    pub skipped: usize,
+}
+
+/// the churn of the given commits, compared to their first parent. Merge
+/// commits, stash entries and uncommitted changes are left out.
+pub fn of(commits: &[RepoCommit]) -> Churn {
+    let mut by_repo: HashMap<&PathBuf, (String, Vec<Oid>)> = HashMap::new();
+    for commit in commits {
+        if commit.is_merge() || commit.wip.is_some() {
+            continue;
+        }
+        by_repo
+            .entry(&commit.repo.abs_path)
+            .or_insert_with(|| (commit.repo.name(), Vec::new()))
+            .1
+            .push(commit.commit_id);
+    }
+    let per_repo: Vec<(Vec<Area>, usize)> = by_repo
+        .into_par_iter()
+        .map(|(abs_path, (repo_name, commit_ids))| churn_of_repo(abs_path, &repo_name, &commit_ids))
+        .collect();
+    let mut areas = Vec::new();
+    let mut skipped = 0;
+    for (repo_areas, repo_skipped) in per_repo {
+        areas.extend(repo_areas);
+        skipped += repo_skipped;
+    }
+    areas.sort_by(|a, b| b.lines().cmp(&a.lines()).then_with(|| a.name.cmp(&b.name)));
+    Churn { areas, skipped }
+}
+
+/// the areas of a repository changed by the given commits, and the
+/// number of commits whose changes couldn't be read
+fn churn_of_repo(abs_path: &Path, repo_name: &str, commit_ids: &[Oid]) -> (Vec<Area>, usize) {
+    let git_repo = match Repository::open(abs_path) {
+        Ok(git_repo) => git_repo,
+        Err(_) => return (Vec::new(), commit_ids.len()),
+    };
+    let mut areas: HashMap<String, Area> = HashMap::new();
+    let mut skipped = 0;
+    for commit_id in commit_ids {
+        let changes = match lines_changed(&git_repo, *commit_id) {
+            Ok(changes) => changes,
+            Err(_) => {
+                skipped += 1;
+                continue;
+            }
+        };
+        for (name, (added, deleted)) in changes {
+            let area = areas.entry(name.clone()).or_insert_with(|| Area {
+                name: area_name(repo_name, &name),
+                added: 0,
+                deleted: 0,
+                commits: 0,
+            });
+            area.added += added;
+            area.deleted += deleted;
+            area.commits += 1;
+        }
+    }
+    (areas.into_values().collect(), skipped)
+}
+
+/// the lines added and deleted by a commit per top-level directory (empty
+/// for files in the root folder)
+fn lines_changed(
+    git_repo: &Repository,
+    commit_id: Oid,
+) -> Result<HashMap<String, (usize, usize)>, git2::Error> {
+    let commit = git_repo.find_commit(commit_id)?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let diff = git_repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    let mut changes: HashMap<String, (usize, usize)> = HashMap::new();
+    for index in 0..diff.deltas().len() {
+        let patch = match Patch::from_diff(&diff, index)? {
+            Some(patch) => patch,
+            None => continue,
+        };
+        let delta = patch.delta();
+        let path = delta.new_file().path().or_else(|| delta.old_file().path());
+        let (_, added, deleted) = patch.line_stats()?;
+        let change = changes.entry(top_level_dir(path)).or_default();
+        change.0 += added;
+        change.1 += deleted;
+    }
+    Ok(changes)
+}
+
+/// the first folder of the given path within a repository, empty for
+/// files in its root folder
+fn top_level_dir(path: Option<&Path>) -> String {
+    let mut components = path.into_iter().flat_map(Path::components);
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(dir)), Some(_)) => dir.to_string_lossy().into_owned(),
+        _ => String::new(),
+    }
+}
+
+fn area_name(repo_name: &str, dir: &str) -> String {
+    match dir {
+        "" => repo_name.to_string(),
+        dir => format!("{}/{}", repo_name, dir),
+    }
+}
+
+/// formats the areas as table, the most changed first
+pub fn to_table(areas: &[Area]) -> String {
+    let title = "Directory";
+    let width = areas
+        .iter()
+        .map(|area| area.name.chars().count())
+        .chain(std::iter::once(title.len()))
+        .max()
+        .unwrap_or(0);
+    let mut table = format!(
+        "{:width$}  {:>7}  {:>7}  {:>7}  {:>7}\n",
+        title,
+        "Lines",
+        "Added",
+        "Deleted",
+        "Commits",
+        width = width
+    );
+    for area in areas {
+        let _ = writeln!(
+            table,
+            "{:width$}  {:>7}  {:>7}  {:>7}  {:>7}",
+            area.name,
+            area.lines(),
+            area.added,
+            area.deleted,
+            area.commits,
+            width = width
+        );
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_areas_by_top_level_dir() {
+        let dir = |path: &str| top_level_dir(Some(Path::new(path)));
+        assert_eq!(
+            area_name("kernel/msm", &dir("drivers/gpu/drm.c")),
+            "kernel/msm/drivers"
+        );
+        assert_eq!(area_name("kernel/msm", &dir("Makefile")), "kernel/msm");
+        assert_eq!(top_level_dir(None), "");
+    }
+}
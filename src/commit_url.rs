@@ -0,0 +1,176 @@
+use crate::config::{Config, Remote};
+use crate::credentials::CredentialStore;
+use crate::model::RepoCommit;
+use crate::pull_request::{self, GITHUB_HOST};
+use crate::utils::expand_placeholders;
+
+/// Placeholders which need the URL of the repository's remote
+const REMOTE_PLACEHOLDERS: [&str; 3] = ["{remote_url}", "{host}", "{path}"];
+
+/// Resolves the URL of a commit (e.g. on a code review or hosting site)
+/// from the URL of its repository's remote and a template given in the
+/// config file, either globally or by the `[[remote]]` section matching
+/// the remote. All outputs showing commit URLs use this resolver.
+pub struct CommitUrlResolver {
+    template: Option<String>,
+    remotes: Vec<Remote>,
+    credentials: CredentialStore,
+}
+
+impl CommitUrlResolver {
+    pub fn new(config: &Config) -> Self {
+        CommitUrlResolver {
+            template: config.commit_url.clone(),
+            remotes: config.remote.clone(),
+            credentials: CredentialStore::new(config.credentials.clone()),
+        }
+    }
+
+    /// returns the secret to authenticate at the remote of the given
+    /// commit's repository, `None` if no credentials are configured
+    fn secret_of(&self, commit: &RepoCommit) -> Option<Result<String, String>> {
+        let name = self.remote_of(commit)?.credentials.as_ref()?;
+        Some(self.credentials.secret(name))
+    }
+
+    /// returns the URL of the given commit, `None` if no template is
+    /// configured or the template needs a remote the repository lacks
+    pub fn resolve(&self, commit: &RepoCommit) -> Option<String> {
+        let template = self
+            .remote_of(commit)
+            .and_then(|remote| remote.commit_url.as_ref())
+            .or(self.template.as_ref())?;
+        self.expand(template, commit)
+    }
+
+    /// returns the URL of the web page of the given commit's repository,
+    /// derived from the URL of its remote
+    pub fn repo_url(&self, commit: &RepoCommit) -> Option<String> {
+        let (host, path) = split_remote_url(commit.repo.remote_url()?)?;
+        Some(format!("https://{}/{}", host, path))
+    }
+
+    /// returns the URL of the code review of the given commit, in case
+    /// the matching `[[remote]]` section has a template for it
+    pub fn review_url(&self, commit: &RepoCommit) -> Option<String> {
+        let template = self.remote_of(commit)?.review_url.as_ref()?;
+        self.expand(template, commit)
+    }
+
+    /// returns the number of the pull request merged by the given commit
+    /// as told by its summary, for repositories hosted on GitHub only
+    pub fn pull_request(&self, commit: &RepoCommit) -> Option<u64> {
+        self.github_path(commit)?;
+        pull_request::number_from_summary(&commit.summary)
+    }
+
+    /// like `pull_request()`, but asks the GitHub API in case the summary
+    /// doesn't tell, authenticated by the credentials of the remote
+    pub fn query_pull_request(&self, commit: &RepoCommit) -> Result<Option<u64>, String> {
+        let path = match self.github_path(commit) {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        if let Some(number) = pull_request::number_from_summary(&commit.summary) {
+            return Ok(Some(number));
+        }
+        let token = self.secret_of(commit).transpose()?;
+        pull_request::query(&path, &commit.commit_id.to_string(), token.as_deref())
+    }
+
+    /// returns the URL of the pull request with the given number of the
+    /// given commit's repository
+    pub fn pull_request_url(&self, commit: &RepoCommit, number: u64) -> Option<String> {
+        let path = self.github_path(commit)?;
+        Some(format!("https://{}/{}/pull/{}", GITHUB_HOST, path, number))
+    }
+
+    /// the path (`owner/project`) of the given commit's repository in
+    /// case it is hosted on GitHub
+    fn github_path(&self, commit: &RepoCommit) -> Option<String> {
+        let (host, path) = split_remote_url(commit.repo.remote_url()?)?;
+        if host == GITHUB_HOST {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    /// the settings of the remote of the given commit's repository
+    pub fn remote_of(&self, commit: &RepoCommit) -> Option<&Remote> {
+        let url = commit.repo.remote_url()?;
+        self.remotes
+            .iter()
+            .find(|remote| remote.pattern.is_match(url))
+    }
+
+    fn expand(&self, template: &str, commit: &RepoCommit) -> Option<String> {
+        let id = commit.commit_id.to_string();
+        let mut placeholders = vec![
+            ("id", id.clone()),
+            ("short_id", id[..8].to_string()),
+            ("repo", commit.repo.rel_path.clone()),
+        ];
+
+        match commit.repo.remote_url().and_then(split_remote_url) {
+            Some((host, path)) => {
+                placeholders.push(("remote_url", format!("https://{}/{}", host, path)));
+                placeholders.push(("host", host));
+                placeholders.push(("path", path));
+            }
+            None if REMOTE_PLACEHOLDERS.iter().any(|p| template.contains(p)) => return None,
+            None => {}
+        }
+        match self
+            .remote_of(commit)
+            .and_then(|r| r.default_branch.clone())
+        {
+            Some(branch) => placeholders.push(("branch", branch)),
+            None if template.contains("{branch}") => return None,
+            None => {}
+        }
+
+        Some(expand_placeholders(template, &placeholders))
+    }
+}
+
+/// splits the URL of a remote (e.g. `https://host/path.git`,
+/// `ssh://user@host:29418/path` or `user@host:path`) into its host
+/// and its path without a trailing `.git`
+fn split_remote_url(url: &str) -> Option<(String, String)> {
+    let (host, path) = match url.split_once("://") {
+        Some((_scheme, rest)) => rest.split_once('/')?,
+        None => url.split_once(':')?,
+    };
+    let host = host.rsplit('@').next()?;
+    let host = host.split(':').next()?;
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    if host.is_empty() || path.is_empty() {
+        return None;
+    }
+    Some((host.to_string(), path.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_remote_urls() {
+        let expected = Some(("example.com".to_string(), "platform/build".to_string()));
+        assert_eq!(
+            split_remote_url("https://example.com/platform/build.git"),
+            expected
+        );
+        assert_eq!(
+            split_remote_url("ssh://me@example.com:29418/platform/build"),
+            expected
+        );
+        assert_eq!(
+            split_remote_url("git@example.com:platform/build.git"),
+            expected
+        );
+        assert_eq!(split_remote_url("/local/mirror"), None);
+    }
+}
@@ -1,13 +1,248 @@
 use app_dirs::*;
+use cursive::theme::Color;
+use regex::Regex;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fs::read_to_string;
 use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
-const APP_INFO: AppInfo = AppInfo {
+pub const APP_INFO: AppInfo = AppInfo {
     name: "oper",
     author: "Florian Bramer",
 };
 const DEFAULT_CONFIG: &str = r#"
+# Optional columns of the commit table, available are:
+# "parents" (number of parents of a commit), "pull_request"
+# (number of the pull request merged by a commit, GitHub only),
+# "organization" (of the author, see [organizations]) and "team"
+# (owning the repository, see owners), "author_date" (differs from
+# the commit date e.g. for cherry-picked or rebased commits) and "landed"
+# (when the commit arrived in the workspace, as far as oper saw it)
+# extra_columns = ["parents"]
+
+# Content of the commit bar (below the commit table) and of the left
+# part of the status bar. Available placeholders are {index}, {total},
+# {filtered}, {repo}, {hash}, {author}, {date}, {age} (e.g. "3 days ago"),
+# {summary} and {url} (see commit_url) of the selected commit as well as {commits}, {repos} and {missing} (number
+# of parent commits not found locally) of the whole history.
+# commit_bar_format = "Commit {index} of {total}{filtered} - {repo}"
+# status_bar_format = "Found {commits} commits across {repos} repositories"
+
+# Language of the UI: column titles, the commit and the status bar and
+# relative dates like {age}. Available are "en" (default) and "de".
+# language = "de"
+
+# Number of rows PageUp/PageDown move by, defaults to the
+# height of the commit table/diff view.
+# page_step = 10
+
+# Template of the URL of a commit, used in reports and copied by
+# pressing 'y'. Available placeholders are {id} and {short_id} of the
+# commit, {repo} (local path of the repository) and, derived from the
+# URL of the repository's remote, {host}, {path} and {remote_url}
+# (https://{host}/{path}).
+# commit_url = "{remote_url}/commit/{id}"
+
+# Authors classified as bots, by regular expressions matching their
+# name or email. Commits of bots are dimmed, press 'b' to hide them.
+# bots = ['(?i)\bbot\b', 'jenkins@example\.com']
+
+# File mapping the local paths of projects (or of their parent
+# folders) to the teams owning them, e.g. "kernel" = "Kernel Team".
+# Defaults to owners.toml in the root folder of the workspace.
+# owners = "/home/me/owners.toml"
+
+# Git notes shown above the diff of a commit, e.g. results attached by
+# build or test systems. Refs not starting with "refs/" are taken from
+# refs/notes/.
+# notes = ["refs/notes/commits", "ci"]
+
+# Rhai script defining the hooks filter(commit), returning false for
+# commits to leave out, and annotate(commit), returning a map of
+# annotations shown with the commit, e.g.
+#   fn filter(commit) { !commit.summary.starts_with("WIP") }
+#   fn annotate(commit) { #{ size: commit.message.len() } }
+# script = "/home/me/oper.rhai"
+
+# The commit date is colored green for commits younger than
+# fresh_hours and yellow for commits younger than recent_hours.
+# [date_colors]
+# fresh_hours = 24
+# recent_hours = 168
+
+# Limits of walking the history of each repository. The history is
+# walked newest first (by commit date) until a commit older than the
+# day window is found; skew_hours keeps walking past commits which are
+# older by up to this number of hours, so commits with skewed clocks
+# don't end the walk early. max_commits stops walking a repository
+# after this number of commits, so pathological histories terminate.
+# [scan]
+# skew_hours = 24
+# max_commits = 100000
+
+# The diffs of recently viewed commits are cached, up to cache_mb
+# megabytes of diff output (0 disables the cache). The diffs of up to
+# prefetch commits above and below the selected one are added to the
+# cache in the background (0 disables prefetching). Only the first
+# max_lines lines of a diff are shown, press 'L' to load the next ones
+# (0 shows all lines at once).
+# [diff]
+# cache_mb = 32
+# prefetch = 3
+# max_lines = 5000
+
+# Issues mentioned in commit messages, their IDs are matched by the
+# regular expression pattern (defaults to JIRA-like IDs, e.g. PROJ-123).
+# If jira_url is given, the summary and status of the issues are queried
+# from the JIRA server (authenticated by the named credentials, "user:token"
+# secrets are sent for basic authentication) and shown by pressing 'I'
+# and in reports.
+# [issues]
+# pattern = '\b[A-Z][A-Z0-9_]+-\d+\b'
+# jira_url = "https://jira.example.com"
+# credentials = "jira"
+
+# Organizations of the authors, derived from the domain of their email
+# (e.g. google.com for alice@android.google.com) unless the domain (or
+# a parent domain) is mapped to a name here. Shown by the organization
+# column and by the statistics (press 'S').
+# [organizations]
+# "google.com" = "Google"
+# "example.com" = "Example Corp"
+
+# Builds, listed in a file or printed by a command (file takes
+# precedence), one build per line: its ID followed by the date or the
+# commit SHA (e.g. of the manifest repository) it has been cut at. The
+# commit table shows a separator row where a build has been cut.
+# [builds]
+# file = "/home/me/builds.txt"
+# command = "fetch-builds --branch main"
+
+# Bisecting (oper --bisect <good>..<bad>) checks out the states of the
+# workspace given by the history of the manifest repository, by running
+# the checkout command with the placeholders {sha} (of the state),
+# {manifest} (manifest file of the state) and {repo}. Set repo to bisect
+# the history of a superproject instead. The same command reproduces the
+# state given by the selected commit of that repository in the UI (press
+# 'M', include the manifest repository with -x).
+# [bisect]
+# checkout = "repo sync -d -m {manifest}"
+# repo = "/path/to/superproject"
+# checkout = "git -C {repo} checkout {sha} && git -C {repo} submodule update"
+
+# Rules for commit messages, checked for each commit: the maximum
+# length of the subject, words the subject must not start with (to
+# enforce the imperative mood, a prefix like "net:" is skipped),
+# trailers the message must end with and words it must not contain
+# (case insensitive). Violations are shown by the lint column and
+# listed in reports, whose summary counts them.
+# [lint]
+# max_subject_length = 72
+# non_imperative = ["added", "adds", "fixed", "fixes", "updated"]
+# required_trailers = ["Signed-off-by"]
+# forbidden_words = ["WIP", "fixup!"]
+
+# Dates in .ods and .xlsx reports are real date cells, so spreadsheets
+# sort and filter them by date. They are given in the local time zone and
+# displayed by date_format, a format code of the spreadsheet application
+# (e.g. "dd.mm.yyyy hh:mm" or "mm/dd/yyyy"). CSV and XML reports aren't
+# affected.
+#
+# Spreadsheet applications handle a limited number of rows (1048576 for
+# .xlsx), .ods and .xlsx reports of more than max_rows rows (including the
+# header) are split into numbered files (e.g. report-1.xlsx, report-2.xlsx).
+# The last row of each of them lists the files of the report, it counts
+# against max_rows as well.
+# [report]
+# date_format = "yyyy-mm-dd hh:mm"
+# max_rows = 1000000
+
+# Highlighting section:
+#
+# Rows of the commit table can be highlighted based on regular
+# expressions matching the author (name or email), the summary
+# or the repo (local path) of a commit. The first matching rule
+# wins. Colors are given by name (e.g. "red" or "light cyan") or as
+# hex value, available effects are "bold", "underline", "italic"
+# and "reverse". Rules with a column ("date", "repo", "committer" or
+# "summary") color only that cell of the row, without effects.
+
+# Color the summary of reverts red
+# [[highlight]]
+# field = "summary"
+# pattern = "(?i)^revert"
+# color = "red"
+# column = "summary"
+
+# Highlight my own commits
+# [[highlight]]
+# field = "author"
+# pattern = "me@example.com"
+# color = "cyan"
+# effects = ["bold"]
+
+# Alert section:
+#
+# Thresholds of the commit frequency of repositories, checked when
+# writing a report without --ui: repositories exceeding max_commits within an
+# hour, a day or a week (per, defaults to "day") are listed and oper
+# exits with an error. repo restricts the alert to the repositories
+# whose local path matches the regular expression, otherwise it
+# applies to each repository.
+
+# [[alert]]
+# repo = '^kernel/'
+# max_commits = 50
+# per = "day"
+
+# Column section:
+#
+# Columns of the commit table whose values are printed by a command,
+# e.g. the state of a commit in an internal tracking system. The
+# command is run in the repository of a commit when its row is shown
+# for the first time by sh; the placeholders {id}, {repo} and {author}
+# (email) are passed as quoted shell parameters, so don't quote them
+# ('{id}' works as well, but not within longer single quoted text).
+# The first line of its output is shown, up to jobs
+# commands run in parallel per column.
+
+# [[column]]
+# title = "CI"
+# command = "ci-status {id}"
+# width = 10
+# jobs = 4
+
+# Remote section:
+#
+# Settings of the remotes whose fetch URL matches the regular
+# expression given by pattern; the first matching section wins.
+# commit_url overrides the global template, review_url is the URL
+# of a commit's code review (opened by pressing 'o', the commit URL
+# is opened if missing). Both templates support the placeholders
+# of commit_url, plus {branch} given by default_branch. credentials
+# names the credentials to authenticate with (see below).
+
+# [[remote]]
+# pattern = 'review\.example\.com'
+# commit_url = "https://review.example.com/plugins/gitiles/{path}/+/{id}"
+# review_url = "https://review.example.com/q/commit:{id}"
+# default_branch = "main"
+# credentials = "review"
+
+# Credentials section:
+#
+# Secrets like API tokens are never stored in this file. Instead they
+# are read from an environment variable (env), from the first line of
+# the output of a command or from the keyring of the OS (keyring names
+# the service, looked up with secret-tool or security on macOS); the
+# first available source wins.
+
+# [[credentials]]
+# name = "review"
+# env = "REVIEW_TOKEN"
+# command = "pass show review/token"
+
 # Custom command section:
 #
 # You can map keys to custom commands. These commands are
@@ -33,9 +268,65 @@ args = "-- git show {}"
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct Config {
+    #[serde(default)]
+    pub extra_columns: Vec<ExtraColumn>,
+    #[serde(default)]
+    pub commit_bar_format: Option<String>,
+    #[serde(default)]
+    pub status_bar_format: Option<String>,
+    #[serde(default)]
+    pub language: Language,
+    #[serde(default)]
+    pub page_step: Option<usize>,
+    #[serde(default)]
+    pub commit_url: Option<String>,
+    #[serde(default = "default_bots")]
+    pub bots: Vec<Pattern>,
+    #[serde(default)]
+    pub owners: Option<PathBuf>,
+    #[serde(default = "default_notes")]
+    pub notes: Vec<String>,
+    #[serde(default)]
+    pub script: Option<PathBuf>,
+    #[serde(default)]
+    pub date_colors: DateColors,
+    #[serde(default)]
+    pub scan: Scan,
+    #[serde(default)]
+    pub diff: Diff,
+    #[serde(default)]
+    pub issues: Option<Issues>,
+    #[serde(default)]
+    pub organizations: HashMap<String, String>,
+    #[serde(default)]
+    pub builds: Option<Builds>,
+    #[serde(default)]
+    pub bisect: Option<Bisect>,
+    #[serde(default)]
+    pub lint: Option<Lint>,
+    #[serde(default)]
+    pub report: Report,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub highlight: Vec<Highlight>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alert: Vec<Alert>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub column: Vec<PluginColumn>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remote: Vec<Remote>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub credentials: Vec<Credentials>,
     pub custom_command: Vec<CustomCommand>,
 }
 
+fn default_bots() -> Vec<Pattern> {
+    vec![Pattern(Regex::new(r"(?i)\bbot\b").unwrap())]
+}
+
+fn default_notes() -> Vec<String> {
+    vec![String::from("refs/notes/commits")]
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct CustomCommand {
     pub key: char,
@@ -43,10 +334,335 @@ pub struct CustomCommand {
     pub args: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtraColumn {
+    Parents,
+    PullRequest,
+    Organization,
+    Team,
+    AuthorDate,
+    Landed,
+}
+
+/// Language of the UI, see `i18n`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    #[default]
+    En,
+    De,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(default)]
+pub struct DateColors {
+    pub fresh_hours: u32,
+    pub recent_hours: u32,
+}
+
+impl Default for DateColors {
+    fn default() -> DateColors {
+        DateColors {
+            fresh_hours: 24,
+            recent_hours: 7 * 24,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(default)]
+pub struct Diff {
+    /// megabytes of `git diff` output cached for recently viewed commits
+    pub cache_mb: usize,
+    /// number of commits above and below the selected one whose diffs
+    /// are cached in advance
+    pub prefetch: usize,
+    /// number of diff lines loaded at once, more are loaded on request
+    pub max_lines: usize,
+}
+
+impl Default for Diff {
+    fn default() -> Self {
+        Diff {
+            cache_mb: 32,
+            prefetch: 3,
+            max_lines: 5000,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+#[serde(default)]
+pub struct Scan {
+    pub skew_hours: u32,
+    pub max_commits: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Highlight {
+    pub field: HighlightField,
+    pub pattern: Pattern,
+    pub color: Option<ColorName>,
+    #[serde(default)]
+    pub effects: Vec<HighlightEffect>,
+    /// the cell to color, the whole row if missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<HighlightColumn>,
+}
+
+/// Threshold of the number of commits of a repository within a period
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Alert {
+    /// local paths of the repositories the alert applies to, all if missing
+    pub repo: Option<Pattern>,
+    pub max_commits: usize,
+    #[serde(default)]
+    pub per: Period,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Period {
+    Hour,
+    #[default]
+    Day,
+    Week,
+}
+
+/// A column of the commit table whose values are printed by a command
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct PluginColumn {
+    pub title: String,
+    pub command: String,
+    #[serde(default = "default_plugin_column_width")]
+    pub width: usize,
+    /// number of commands run in parallel
+    #[serde(default = "default_plugin_column_jobs")]
+    pub jobs: usize,
+}
+
+fn default_plugin_column_width() -> usize {
+    10
+}
+
+fn default_plugin_column_jobs() -> usize {
+    4
+}
+
+/// Settings of the remotes whose fetch URL matches the pattern
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Remote {
+    pub pattern: Pattern,
+    /// template of the URL of a commit, overrides the global one
+    pub commit_url: Option<String>,
+    /// template of the URL of a commit's code review
+    pub review_url: Option<String>,
+    pub default_branch: Option<String>,
+    /// name of the credentials to authenticate with
+    pub credentials: Option<String>,
+}
+
+/// Settings of the issues mentioned in commit messages
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Issues {
+    /// matches the IDs of issues
+    #[serde(default = "default_issue_pattern")]
+    pub pattern: Pattern,
+    /// base URL of the JIRA server to query the issues from
+    pub jira_url: Option<String>,
+    /// name of the credentials to authenticate with
+    pub credentials: Option<String>,
+}
+
+fn default_issue_pattern() -> Pattern {
+    Pattern(Regex::new(r"\b[A-Z][A-Z0-9_]+-\d+\b").unwrap())
+}
+
+/// Where to find the builds, to show where they have been cut
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Builds {
+    pub file: Option<PathBuf>,
+    pub command: Option<String>,
+}
+
+/// Settings of `--bisect`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Bisect {
+    /// repository whose history is bisected, defaults to the manifests
+    pub repo: Option<PathBuf>,
+    /// command checking out a state of the workspace
+    pub checkout: Option<String>,
+}
+
+/// Rules for commit messages, see `Linter`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+#[serde(default)]
+pub struct Lint {
+    /// in characters
+    pub max_subject_length: Option<usize>,
+    /// words the subject must not start with, case insensitive
+    pub non_imperative: Vec<String>,
+    /// keys of trailers, e.g. `Signed-off-by`
+    pub required_trailers: Vec<String>,
+    pub forbidden_words: Vec<String>,
+}
+
+/// How reports are written, see `report::Details`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(default)]
+pub struct Report {
+    /// format code of date cells in .ods and .xlsx reports
+    pub date_format: String,
+    /// rows per file of .ods and .xlsx reports, larger reports are split
+    /// into numbered files
+    pub max_rows: usize,
+}
+
+impl Default for Report {
+    fn default() -> Report {
+        Report {
+            date_format: String::from("yyyy-mm-dd hh:mm"),
+            max_rows: 1_000_000,
+        }
+    }
+}
+
+/// Where to find the secret (e.g. an API token) of named credentials:
+/// in an environment variable, in the output of a command or in the
+/// keyring of the OS (tried in that order). Unknown fields are rejected
+/// to keep secrets out of the config file.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Credentials {
+    pub name: String,
+    pub env: Option<String>,
+    pub command: Option<String>,
+    /// name of the service in the keyring
+    pub keyring: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum HighlightField {
+    Author,
+    Summary,
+    Repo,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum HighlightColumn {
+    Date,
+    Repo,
+    Committer,
+    Summary,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum HighlightEffect {
+    Bold,
+    Underline,
+    Italic,
+    Reverse,
+}
+
+/// A regular expression, validated while parsing the config file
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(try_from = "String", into = "String")]
+pub struct Pattern(Regex);
+
+impl Pattern {
+    pub fn is_match(&self, text: &str) -> bool {
+        self.0.is_match(text)
+    }
+
+    /// the texts matched in the given text
+    pub fn find_iter<'a>(&'a self, text: &'a str) -> impl Iterator<Item = &'a str> {
+        self.0.find_iter(text).map(|m| m.as_str())
+    }
+}
+
+impl TryFrom<String> for Pattern {
+    type Error = regex::Error;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        Ok(Pattern(Regex::new(&pattern)?))
+    }
+}
+
+impl From<Pattern> for String {
+    fn from(pattern: Pattern) -> String {
+        pattern.0.as_str().to_string()
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+/// A color given by its name, validated while parsing the config file
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(try_from = "String", into = "String")]
+pub struct ColorName {
+    name: String,
+    color: Color,
+}
+
+impl ColorName {
+    pub fn color(&self) -> Color {
+        self.color
+    }
+}
+
+impl TryFrom<String> for ColorName {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        match Color::parse(&name) {
+            Some(color) => Ok(ColorName { name, color }),
+            None => Err(format!("unknown color '{}'", name)),
+        }
+    }
+}
+
+impl From<ColorName> for String {
+    fn from(color: ColorName) -> String {
+        color.name
+    }
+}
+
 impl Config {
     #[cfg(test)]
     pub fn new() -> Config {
         Config {
+            extra_columns: vec![],
+            commit_bar_format: None,
+            status_bar_format: None,
+            language: Language::default(),
+            page_step: None,
+            commit_url: None,
+            bots: default_bots(),
+            owners: None,
+            notes: default_notes(),
+            script: None,
+            date_colors: DateColors::default(),
+            scan: Scan::default(),
+            diff: Diff::default(),
+            issues: None,
+            organizations: HashMap::new(),
+            builds: None,
+            bisect: None,
+            lint: None,
+            report: Report::default(),
+            highlight: vec![],
+            alert: vec![],
+            column: vec![],
+            remote: vec![],
+            credentials: vec![],
             custom_command: vec![],
         }
     }
@@ -63,14 +679,14 @@ impl CustomCommand {
     }
 }
 
-fn config_file() -> PathBuf {
-    let folder = app_root(AppDataType::UserConfig, &APP_INFO)
-        .expect("Failed to access oper's config folder");
-    folder.join("config.toml")
+/// the path of oper's config file, its folder is created if necessary
+pub fn config_file() -> Result<PathBuf, AppDirsError> {
+    let folder = app_root(AppDataType::UserConfig, &APP_INFO)?;
+    Ok(folder.join("config.toml"))
 }
 
 pub fn read() -> Config {
-    let config_file = config_file();
+    let config_file = config_file().expect("Failed to access oper's config folder");
 
     //create default config file?
     if !config_file.is_file() {
@@ -86,7 +702,7 @@ pub fn read() -> Config {
     }
 }
 
-fn deserialize(content: &str) -> Result<Config, toml::de::Error> {
+pub fn deserialize(content: &str) -> Result<Config, toml::de::Error> {
     toml::from_str(content)
 }
 
@@ -114,10 +730,243 @@ fn test_serialize_deserialze() {
     assert_eq!(deserialize(&serialize(&config)).unwrap(), config);
 }
 
+#[test]
+fn test_deserialize_extra_columns() {
+    let config = deserialize(
+        "extra_columns = [\"parents\", \"pull_request\", \"organization\", \"team\", \"author_date\", \"landed\"]\ncustom_command = []",
+    )
+    .unwrap();
+    assert_eq!(
+        config.extra_columns,
+        vec![
+            ExtraColumn::Parents,
+            ExtraColumn::PullRequest,
+            ExtraColumn::Organization,
+            ExtraColumn::Team,
+            ExtraColumn::AuthorDate,
+            ExtraColumn::Landed
+        ]
+    );
+    assert!(deserialize("extra_columns = [\"foo\"]\ncustom_command = []").is_err());
+}
+
+#[test]
+fn test_deserialize_bar_formats() {
+    let config =
+        deserialize("commit_bar_format = \"{index}/{total}\"\ncustom_command = []").unwrap();
+    assert_eq!(
+        config.commit_bar_format,
+        Some("{index}/{total}".to_string())
+    );
+    assert_eq!(config.status_bar_format, None);
+}
+
+#[test]
+fn test_deserialize_language() {
+    let config = deserialize("custom_command = []").unwrap();
+    assert_eq!(config.language, Language::En);
+    let config = deserialize("language = \"de\"\ncustom_command = []").unwrap();
+    assert_eq!(config.language, Language::De);
+    assert!(deserialize("language = \"fr\"\ncustom_command = []").is_err());
+}
+
+#[test]
+fn test_deserialize_bots() {
+    let config = deserialize("custom_command = []").unwrap();
+    assert!(config.bots[0].is_match("build-bot"));
+    assert!(config.bots[0].is_match("dependabot[bot]"));
+    assert!(!config.bots[0].is_match("Abbott"));
+
+    let config = deserialize("bots = ['^ci@']\ncustom_command = []").unwrap();
+    assert_eq!(config.bots.len(), 1);
+    assert!(deserialize("bots = ['(']\ncustom_command = []").is_err());
+}
+
+#[test]
+fn test_deserialize_notes() {
+    let config = deserialize("custom_command = []").unwrap();
+    assert_eq!(config.notes, vec!["refs/notes/commits"]);
+
+    let config = deserialize("notes = [\"ci\"]\ncustom_command = []").unwrap();
+    assert_eq!(config.notes, vec!["ci"]);
+}
+
+#[test]
+fn test_deserialize_scan() {
+    let config = deserialize("custom_command = []\n[scan]\nskew_hours = 24").unwrap();
+    assert_eq!(
+        config.scan,
+        Scan {
+            skew_hours: 24,
+            max_commits: None
+        }
+    );
+    let config = deserialize("custom_command = []").unwrap();
+    assert_eq!(config.scan, Scan::default());
+    assert_eq!(config.diff, Diff::default());
+    assert_eq!(config.report.date_format, "yyyy-mm-dd hh:mm");
+    let config = deserialize("custom_command = []\n[report]\nmax_rows = 50000").unwrap();
+    assert_eq!(config.report.max_rows, 50000);
+    assert_eq!(config.report.date_format, "yyyy-mm-dd hh:mm");
+}
+
+#[test]
+fn test_deserialize_date_colors() {
+    let config = deserialize(
+        "[date_colors]\nfresh_hours = 2\n[[custom_command]]\nkey = 'x'\nexecutable = 'y'",
+    )
+    .unwrap();
+    assert_eq!(
+        config.date_colors,
+        DateColors {
+            fresh_hours: 2,
+            recent_hours: 168
+        }
+    );
+}
+
+#[test]
+fn test_deserialize_highlight() {
+    let config = deserialize(
+        r#"
+[[highlight]]
+field = "summary"
+pattern = "(?i)^revert"
+color = "light red"
+effects = ["bold", "underline"]
+
+[[custom_command]]
+key = "i"
+executable = "gitk"
+"#,
+    )
+    .unwrap();
+    let highlight = &config.highlight[0];
+    assert_eq!(highlight.field, HighlightField::Summary);
+    assert_eq!(highlight.column, None);
+    assert!(highlight.pattern.is_match("Revert \"Fix makefile\""));
+    assert_eq!(
+        highlight.color.as_ref().unwrap().color(),
+        Color::parse("light red").unwrap()
+    );
+    assert_eq!(deserialize(&serialize(&config)).unwrap(), config);
+
+    let valid = "custom_command = []\n[[highlight]]\nfield = 'repo'\npattern = 'x'";
+    assert!(deserialize(valid).is_ok());
+    let invalid_pattern = "custom_command = []\n[[highlight]]\nfield = 'repo'\npattern = '('";
+    assert!(deserialize(invalid_pattern).is_err());
+    let invalid_color = format!("{}\ncolor = 'rot'", valid);
+    assert!(deserialize(&invalid_color).is_err());
+    let cell = format!("{}\ncolumn = 'summary'", valid);
+    assert_eq!(
+        deserialize(&cell).unwrap().highlight[0].column,
+        Some(HighlightColumn::Summary)
+    );
+    let invalid_column = format!("{}\ncolumn = 'graph'", valid);
+    assert!(deserialize(&invalid_column).is_err());
+}
+
+#[test]
+fn test_deserialize_alert() {
+    let config = deserialize(
+        r#"
+[[alert]]
+repo = '^kernel/'
+max_commits = 50
+
+[[alert]]
+max_commits = 500
+per = "week"
+
+[[custom_command]]
+key = "i"
+executable = "gitk"
+"#,
+    )
+    .unwrap();
+    assert_eq!(config.alert.len(), 2);
+    assert!(config.alert[0]
+        .repo
+        .as_ref()
+        .unwrap()
+        .is_match("kernel/msm"));
+    assert_eq!(config.alert[0].per, Period::Day);
+    assert_eq!(config.alert[1].repo, None);
+    assert_eq!(config.alert[1].per, Period::Week);
+    assert_eq!(deserialize(&serialize(&config)).unwrap(), config);
+    assert!(deserialize("custom_command = []\n[[alert]]\nmax_commits = 1\nper = 'month'").is_err());
+}
+
+#[test]
+fn test_deserialize_column() {
+    let config = deserialize(
+        r#"
+[[column]]
+title = "CI"
+command = "ci-status {id}"
+
+[[custom_command]]
+key = "i"
+executable = "gitk"
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.column,
+        vec![PluginColumn {
+            title: String::from("CI"),
+            command: String::from("ci-status {id}"),
+            width: 10,
+            jobs: 4,
+        }]
+    );
+    assert_eq!(deserialize(&serialize(&config)).unwrap(), config);
+}
+
+#[test]
+fn test_deserialize_issues() {
+    let config = deserialize("custom_command = []\n[issues]\njira_url = 'https://jira'").unwrap();
+    let issues = config.issues.unwrap();
+    assert_eq!(issues.pattern, default_issue_pattern());
+    assert_eq!(issues.jira_url.as_deref(), Some("https://jira"));
+    assert!(deserialize("custom_command = []\n[issues]\npattern = '('").is_err());
+}
+
+#[test]
+fn test_deserialize_remote() {
+    let config = deserialize(
+        r#"
+commit_url = "{remote_url}/commit/{id}"
+
+[[remote]]
+pattern = "review\\.example\\.com"
+review_url = "https://review.example.com/q/{id}"
+default_branch = "main"
+
+[[custom_command]]
+key = "i"
+executable = "gitk"
+"#,
+    )
+    .unwrap();
+    let remote = &config.remote[0];
+    assert!(remote
+        .pattern
+        .is_match("ssh://review.example.com:29418/kernel"));
+    assert_eq!(remote.commit_url, None);
+    assert_eq!(remote.default_branch.as_deref(), Some("main"));
+    assert_eq!(deserialize(&serialize(&config)).unwrap(), config);
+
+    let credentials = "custom_command = []\n[[credentials]]\nname = 'review'\nenv = 'TOKEN'";
+    assert!(deserialize(credentials).is_ok());
+    let plaintext = format!("{}\ntoken = 's3cret'", credentials);
+    assert!(deserialize(&plaintext).is_err());
+}
+
 #[ignore]
 #[test]
 fn test_config_file() {
-    assert_eq!(config_file(), PathBuf::from(""));
+    assert_eq!(config_file().unwrap(), PathBuf::from(""));
 }
 
 #[test]
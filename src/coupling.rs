@@ -0,0 +1,244 @@
+use crate::model::RepoCommit;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+
+/// Commits of the same author within this time span are considered to
+/// be parts of the same change
+const WINDOW_SECONDS: i64 = 60 * 60;
+
+/// Trailers naming the change a commit is part of, commits of several
+/// repositories share them for cross-repository changes
+const CHANGE_TRAILERS: [&str; 2] = ["change-id", "topic"];
+
+/// Two repositories modified by the same changes
+#[derive(Debug, PartialEq)]
+pub struct Pair {
+    pub repos: (String, String),
+    /// number of changes modifying both repositories
+    pub changes: usize,
+    /// share of the changes of the less frequently changed repository
+    /// which modified the other one as well, in percent
+    pub coupling: usize,
+}
+
+/// A commit as far as needed to find the commits of the same change
+struct Entry<'a> {
+    /// relative path of the repository, prefixed by its workspace if
+    /// several workspaces are scanned
+    repo: String,
+    author: &'a str,
+    /// seconds since the epoch
+    time: i64,
+    /// values of the change trailers, e.g. `change-id:I1234`
+    changes: Vec<String>,
+}
+
+/// the pairs of repositories modified by the same changes, the most
+/// frequently co-changed first. Commits are part of the same change if
+/// they share a Change-Id or Topic trailer, or if they have been committed
+/// by the same author within an hour. Stash entries and uncommitted
+/// changes are left out.
+pub fn of(commits: &[RepoCommit]) -> Vec<Pair> {
+    let entries: Vec<Entry> = commits
+        .iter()
+        .filter(|commit| commit.wip.is_none())
+        .map(|commit| Entry {
+            repo: commit.repo.name(),
+            author: &commit.author_email,
+            time: commit.commit_time.seconds(),
+            changes: change_trailers(&commit.message),
+        })
+        .collect();
+    pairs_of(&entries)
+}
+
+fn pairs_of(entries: &[Entry]) -> Vec<Pair> {
+    let mut groups = Groups::new(entries.len());
+    let mut by_change: HashMap<&str, usize> = HashMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        for change in &entry.changes {
+            let first = *by_change.entry(change).or_insert(index);
+            groups.join(first, index);
+        }
+    }
+    let mut by_author: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        by_author.entry(entry.author).or_default().push(index);
+    }
+    for mut indices in by_author.into_values() {
+        indices.sort_by_key(|index| entries[*index].time);
+        // a change spans an hour from its first commit, a steady stream
+        // of commits isn't chained into a single change
+        let mut first = indices[0];
+        for index in indices {
+            if entries[index].time - entries[first].time <= WINDOW_SECONDS {
+                groups.join(first, index);
+            } else {
+                first = index;
+            }
+        }
+    }
+
+    // the repositories modified by each change
+    let mut changes: HashMap<usize, BTreeSet<&str>> = HashMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        changes
+            .entry(groups.root(index))
+            .or_default()
+            .insert(entry.repo.as_str());
+    }
+    let mut changes_of_repo: HashMap<&str, usize> = HashMap::new();
+    let mut co_changes: HashMap<(&str, &str), usize> = HashMap::new();
+    for repos in changes.values() {
+        for repo in repos {
+            *changes_of_repo.entry(repo).or_default() += 1;
+        }
+        let repos: Vec<&str> = repos.iter().copied().collect();
+        for (i, a) in repos.iter().enumerate() {
+            for b in &repos[i + 1..] {
+                *co_changes.entry((a, b)).or_default() += 1;
+            }
+        }
+    }
+    let mut pairs: Vec<Pair> = co_changes
+        .into_iter()
+        .map(|((a, b), changes)| {
+            let fewer = changes_of_repo[a].min(changes_of_repo[b]);
+            Pair {
+                repos: (a.to_string(), b.to_string()),
+                changes,
+                coupling: changes * 100 / fewer,
+            }
+        })
+        .collect();
+    pairs.sort_by(|a, b| {
+        b.changes
+            .cmp(&a.changes)
+            .then_with(|| b.coupling.cmp(&a.coupling))
+            .then_with(|| a.repos.cmp(&b.repos))
+    });
+    pairs
+}
+
+/// the change trailers of a commit message, e.g. `change-id:I1234`
+fn change_trailers(message: &str) -> Vec<String> {
+    message
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .filter_map(|(key, value)| {
+            let key = key.trim().to_lowercase();
+            let value = value.trim();
+            (CHANGE_TRAILERS.contains(&key.as_str()) && !value.is_empty())
+                .then(|| format!("{}:{}", key, value))
+        })
+        .collect()
+}
+
+/// Disjoint sets of commits (by index), i.e. the changes
+struct Groups {
+    parents: Vec<usize>,
+}
+
+impl Groups {
+    fn new(len: usize) -> Groups {
+        Groups {
+            parents: (0..len).collect(),
+        }
+    }
+
+    fn root(&mut self, mut index: usize) -> usize {
+        while self.parents[index] != index {
+            self.parents[index] = self.parents[self.parents[index]];
+            index = self.parents[index];
+        }
+        index
+    }
+
+    fn join(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.root(a), self.root(b));
+        self.parents[b] = a;
+    }
+}
+
+/// formats the pairs as table, the most frequently co-changed first
+pub fn to_table(pairs: &[Pair]) -> String {
+    let width = |repo: fn(&Pair) -> &String, title: &str| {
+        pairs
+            .iter()
+            .map(|pair| repo(pair).chars().count())
+            .chain(std::iter::once(title.len()))
+            .max()
+            .unwrap_or(0)
+    };
+    let width_a = width(|pair| &pair.repos.0, "Repository");
+    let width_b = width(|pair| &pair.repos.1, "Repository");
+    let mut table = format!(
+        "{:width_a$}  {:width_b$}  {:>7}  {:>8}\n",
+        "Repository",
+        "Repository",
+        "Changes",
+        "Coupling",
+        width_a = width_a,
+        width_b = width_b
+    );
+    for pair in pairs {
+        let _ = writeln!(
+            table,
+            "{:width_a$}  {:width_b$}  {:>7}  {:>7}%",
+            pair.repos.0,
+            pair.repos.1,
+            pair.changes,
+            pair.coupling,
+            width_a = width_a,
+            width_b = width_b
+        );
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry<'a>(repo: &str, author: &'a str, time: i64, message: &str) -> Entry<'a> {
+        Entry {
+            repo: repo.to_string(),
+            author,
+            time,
+            changes: change_trailers(message),
+        }
+    }
+
+    #[test]
+    fn pairs_repos_changed_together() {
+        let entries = [
+            // by the same author within an hour
+            entry("kernel", "alice", 0, "Add API"),
+            entry("hal", "alice", 1800, "Use API"),
+            entry("kernel", "alice", 100_000, "Fix API"),
+            // by a shared topic, days apart
+            entry("kernel", "bob", 200_000, "Extend API\n\nTopic: api-v2"),
+            entry("hal", "carol", 500_000, "Adapt\n\nTopic: api-v2"),
+            entry("app", "carol", 900_000, "Unrelated"),
+            // a commit every 40 minutes, only the first two are a change
+            entry("docs", "dave", 1_000_000, "Draft"),
+            entry("tools", "dave", 1_002_400, "Draft"),
+            entry("build", "dave", 1_004_800, "Draft"),
+        ];
+        assert_eq!(
+            pairs_of(&entries),
+            vec![
+                Pair {
+                    repos: (String::from("hal"), String::from("kernel")),
+                    changes: 2,
+                    coupling: 100,
+                },
+                Pair {
+                    repos: (String::from("docs"), String::from("tools")),
+                    changes: 1,
+                    coupling: 100,
+                },
+            ]
+        );
+    }
+}
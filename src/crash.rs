@@ -0,0 +1,173 @@
+use crate::config::APP_INFO;
+use app_dirs::{app_dir, AppDataType};
+use chrono::Local;
+use std::backtrace::Backtrace;
+use std::env;
+use std::fs;
+use std::panic;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+
+lazy_static! {
+    static ref STATE: Mutex<State> = Mutex::new(State::default());
+}
+
+/// What a crash report tells besides the panic itself
+#[derive(Default)]
+struct State {
+    /// e.g. `/ws: 120 repositories, 3400 commits`
+    workspace: Option<String>,
+    /// e.g. the key pressed last in the UI
+    last_action: Option<String>,
+    /// true while the UI owns the terminal, which is restored only once
+    /// the panic unwound out of it
+    ui_active: bool,
+    /// the message of the last panic and the file its report went to
+    crash: Option<(String, Option<PathBuf>)>,
+}
+
+/// Installs a panic hook writing a crash report (the panic, a backtrace,
+/// oper's version, the workspace and the last action) into oper's data
+/// folder. Outside of the UI the report's path is printed right away,
+/// within the UI once it restored the terminal, see `after_ui()`.
+pub fn install_hook() {
+    panic::set_hook(Box::new(|info| {
+        let message = match info.payload().downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => match info.payload().downcast_ref::<String>() {
+                Some(message) => message.clone(),
+                None => String::from("Box<dyn Any>"),
+            },
+        };
+        let location = info
+            .location()
+            .map(|location| location.to_string())
+            .unwrap_or_default();
+        let mut state = match STATE.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let report = report_of(
+            &state,
+            &format!("{} at {}", message, location),
+            &Backtrace::force_capture().to_string(),
+        );
+        let file = write_report(&report);
+        let summary = summary_of(&message, file.as_ref());
+        if state.ui_active {
+            state.crash = Some((summary, file));
+        } else {
+            eprintln!("{}", summary);
+        }
+    }));
+}
+
+/// sets the workspace mentioned by crash reports
+pub fn set_workspace(workspace: String) {
+    if let Ok(mut state) = STATE.lock() {
+        state.workspace = Some(workspace);
+    }
+}
+
+/// records the action of the user mentioned by crash reports, e.g.
+/// `key 'E'`
+pub fn record_action(action: String) {
+    if let Ok(mut state) = STATE.lock() {
+        state.last_action = Some(action);
+    }
+}
+
+/// tells that the UI owns the terminal, panics aren't printed until
+/// `after_ui()`
+pub fn before_ui() {
+    if let Ok(mut state) = STATE.lock() {
+        state.ui_active = true;
+    }
+}
+
+/// prints the crash within the UI, if any, once it restored the terminal
+pub fn after_ui() {
+    let crash = match STATE.lock() {
+        Ok(mut state) => {
+            state.ui_active = false;
+            state.crash.take()
+        }
+        Err(_) => None,
+    };
+    if let Some((summary, _)) = crash {
+        eprintln!("{}", summary);
+    }
+}
+
+fn report_of(state: &State, panic: &str, backtrace: &str) -> String {
+    let args: Vec<String> = env::args().collect();
+    format!(
+        "oper {} crashed at {}\n\
+         Platform: {} {}\n\
+         Command line: {}\n\
+         Workspace: {}\n\
+         Last action: {}\n\
+         Thread: {}\n\
+         Panic: {}\n\
+         \n\
+         Backtrace:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        Local::now().format("%Y-%m-%d %H:%M:%S %z"),
+        env::consts::OS,
+        env::consts::ARCH,
+        args.join(" "),
+        state.workspace.as_deref().unwrap_or("-"),
+        state.last_action.as_deref().unwrap_or("-"),
+        thread::current().name().unwrap_or("unnamed"),
+        panic,
+        backtrace
+    )
+}
+
+/// writes the report into the `crashes` folder of oper's data folder, or
+/// into the temporary folder if that fails
+fn write_report(report: &str) -> Option<PathBuf> {
+    let name = format!(
+        "crash-{}-{}.txt",
+        Local::now().format("%Y%m%d-%H%M%S"),
+        std::process::id()
+    );
+    let folder =
+        app_dir(AppDataType::UserData, &APP_INFO, "crashes").unwrap_or_else(|_| env::temp_dir());
+    [folder, env::temp_dir()]
+        .iter()
+        .map(|folder| folder.join(&name))
+        .find(|file| fs::write(file, report).is_ok())
+}
+
+fn summary_of(message: &str, file: Option<&PathBuf>) -> String {
+    match file {
+        Some(file) => format!(
+            "oper crashed: {}\nA crash report has been written to {}, please attach it to an issue at https://github.com/elektronenhirn/oper/issues",
+            message,
+            file.display()
+        ),
+        None => format!("oper crashed: {}", message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_the_context_of_crashes() {
+        let state = State {
+            workspace: Some(String::from("/ws: 3 repositories, 8 commits")),
+            last_action: Some(String::from("key 'E'")),
+            ..State::default()
+        };
+        let report = report_of(&state, "boom at src/ui.rs:1:1", "0: main");
+        assert!(report.starts_with(&format!("oper {} crashed", env!("CARGO_PKG_VERSION"))));
+        assert!(report.contains("\nWorkspace: /ws: 3 repositories, 8 commits\n"));
+        assert!(report.contains("\nLast action: key 'E'\n"));
+        assert!(report.contains("\nPanic: boom at src/ui.rs:1:1\n"));
+        assert!(report.ends_with("Backtrace:\n0: main\n"));
+    }
+}
@@ -0,0 +1,186 @@
+use crate::config::Credentials;
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, Write};
+use std::process::{Command, Output, Stdio};
+use std::sync::Mutex;
+
+/// Provides the secrets (e.g. API tokens) of the credentials configured
+/// in `[[credentials]]` sections. Secrets are never part of the config
+/// file, they are taken from an environment variable, the output of a
+/// command (e.g. `pass show github/token`) or the keyring of the OS.
+pub struct CredentialStore {
+    credentials: Vec<Credentials>,
+    /// secrets already looked up, by name of the credentials
+    secrets: Mutex<HashMap<String, String>>,
+}
+
+impl CredentialStore {
+    pub fn new(credentials: Vec<Credentials>) -> Self {
+        CredentialStore {
+            credentials,
+            secrets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// returns the secret of the credentials with the given name
+    pub fn secret(&self, name: &str) -> Result<String, String> {
+        if let Some(secret) = self.secrets.lock().unwrap().get(name) {
+            return Ok(secret.clone());
+        }
+        let credentials = self
+            .credentials
+            .iter()
+            .find(|credentials| credentials.name == name)
+            .ok_or_else(|| format!("No credentials named '{}' configured", name))?;
+        let secret = lookup(credentials)?;
+        self.secrets
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), secret.clone());
+        Ok(secret)
+    }
+}
+
+/// runs curl with the given arguments; the given options holding secrets
+/// (e.g. `("header", "Authorization: Bearer …")`) are passed by its
+/// standard input instead of its arguments, which any local user can read
+/// (e.g. by `ps`)
+pub fn curl(args: &[&str], secret_options: &[(&str, String)]) -> io::Result<Output> {
+    let mut child = Command::new("curl")
+        .args(["--config", "-"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let written = child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(curl_config(secret_options).as_bytes());
+    let output = child.wait_with_output()?;
+    written.map(|_| output)
+}
+
+/// the given options in the syntax of curl's config files
+fn curl_config(options: &[(&str, String)]) -> String {
+    options
+        .iter()
+        .map(|(option, value)| {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            format!("{} = \"{}\"\n", option, value)
+        })
+        .collect()
+}
+
+/// looks the secret up from the sources configured, in the order of the
+/// doc comment of `CredentialStore`: a source failing falls back to the
+/// next one
+fn lookup(credentials: &Credentials) -> Result<String, String> {
+    if let Some(variable) = &credentials.env {
+        if let Ok(secret) = env::var(variable) {
+            return Ok(secret);
+        }
+    }
+    let mut errors = Vec::new();
+    if let Some(command) = &credentials.command {
+        match run("sh", &["-c", command]) {
+            Ok(secret) => return Ok(secret),
+            Err(error) => errors.push(error),
+        }
+    }
+    if let Some(service) = &credentials.keyring {
+        let keyring = if cfg!(target_os = "macos") {
+            run("security", &["find-generic-password", "-s", service, "-w"])
+        } else {
+            run("secret-tool", &["lookup", "service", service])
+        };
+        match keyring {
+            Ok(secret) => return Ok(secret),
+            Err(error) => errors.push(error),
+        }
+    }
+    if errors.is_empty() {
+        errors.push(format!(
+            "No secret found for credentials '{}'",
+            credentials.name
+        ));
+    }
+    Err(errors.join("; "))
+}
+
+/// runs the given command and returns the first line of its output
+fn run(executable: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(executable)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", executable, e))?;
+    if !output.status.success() {
+        return Err(format!("{} failed with {}", executable, output.status));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match stdout.lines().next() {
+        Some(secret) if !secret.is_empty() => Ok(secret.to_string()),
+        _ => Err(format!("{} returned no secret", executable)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credentials(env: Option<&str>, command: Option<&str>) -> Credentials {
+        Credentials {
+            name: String::from("review"),
+            env: env.map(String::from),
+            command: command.map(String::from),
+            keyring: None,
+        }
+    }
+
+    #[test]
+    fn passes_secrets_by_config() {
+        let config = curl_config(&[
+            ("header", String::from("Authorization: Bearer t0k\"en")),
+            ("user", String::from("me:s\\ecret")),
+        ]);
+        assert_eq!(
+            config,
+            "header = \"Authorization: Bearer t0k\\\"en\"\nuser = \"me:s\\\\ecret\"\n"
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_command() {
+        let store = CredentialStore::new(vec![credentials(
+            Some("OPER_TEST_UNSET_TOKEN"),
+            Some("echo s3cret; echo second line"),
+        )]);
+        assert_eq!(store.secret("review"), Ok(String::from("s3cret")));
+        assert!(store.secret("jira").is_err());
+        assert!(CredentialStore::new(vec![credentials(None, Some("false"))])
+            .secret("review")
+            .is_err());
+    }
+
+    #[test]
+    fn falls_back_to_the_keyring() {
+        let store = CredentialStore::new(vec![Credentials {
+            keyring: Some(String::from("oper-test-unknown-service")),
+            ..credentials(None, Some("false"))
+        }]);
+        // the keyring is asked after the command failed, it doesn't know
+        // the service (or isn't available at all)
+        let keyring = if cfg!(target_os = "macos") {
+            "security"
+        } else {
+            "secret-tool"
+        };
+        let error = store.secret("review").unwrap_err();
+        assert!(error.starts_with("sh failed"), "{}", error);
+        assert!(error.contains(keyring), "{}", error);
+    }
+}
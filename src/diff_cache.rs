@@ -0,0 +1,195 @@
+use crate::model::RepoCommit;
+use git2::{Oid, Repository};
+use std::collections::VecDeque;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// The output of `git diff` for the most recently viewed commits, so
+/// moving the selection back and forth doesn't run git again. The least
+/// recently viewed diffs are dropped once the cached output exceeds the
+/// budget (in bytes).
+pub struct DiffCache {
+    budget: usize,
+    size: usize,
+    /// least recently viewed first
+    diffs: VecDeque<(PathBuf, Oid, Arc<String>)>,
+}
+
+impl DiffCache {
+    pub fn new(budget: usize) -> Self {
+        DiffCache {
+            budget,
+            size: 0,
+            diffs: VecDeque::new(),
+        }
+    }
+
+    fn position(&self, repo: &Path, commit_id: Oid) -> Option<usize> {
+        self.diffs
+            .iter()
+            .position(|(path, id, _)| *id == commit_id && path == repo)
+    }
+
+    pub fn contains(&self, repo: &Path, commit_id: Oid) -> bool {
+        self.position(repo, commit_id).is_some()
+    }
+
+    /// the cached diff of the commit of the repository at the given path
+    pub fn get(&mut self, repo: &Path, commit_id: Oid) -> Option<Arc<String>> {
+        let index = self.position(repo, commit_id)?;
+        let entry = self.diffs.remove(index)?;
+        let diff = entry.2.clone();
+        self.diffs.push_back(entry);
+        Some(diff)
+    }
+
+    /// caches the diff, unless it exceeds the budget on its own
+    pub fn insert(&mut self, repo: &Path, commit_id: Oid, diff: Arc<String>) {
+        // uncommitted changes (the zero ID) keep changing
+        if diff.len() > self.budget || commit_id.is_zero() {
+            return;
+        }
+        self.size += diff.len();
+        self.diffs.push_back((repo.to_path_buf(), commit_id, diff));
+        while self.size > self.budget {
+            match self.diffs.pop_front() {
+                Some((_, _, dropped)) => self.size -= dropped.len(),
+                None => break,
+            }
+        }
+    }
+}
+
+/// the diff between the given commit and its first parent
+#[rustfmt::skip]
+pub fn git_diff(repo: &Path, commit_id: Oid) -> io::Result<String> {
+    let output = Command::new("git")
+                 .current_dir(repo)
+                 .arg("--no-pager")
+                 .arg("diff")
+                 .arg("--patch-with-stat")
+                 .arg("--encoding=UTF-8")
+                 .arg("--pretty=fuller")
+                 .arg("--patch-with-stat")
+                 .arg("--no-color")
+                 .arg(if commit_id.is_zero() {
+                     // the uncommitted changes, see `Wip::Uncommitted`
+                     String::from("HEAD")
+                 } else {
+                     format!("{}..{}^", commit_id, commit_id)
+                 })
+                 .output()?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// the number of blobs the diff of the given commit needs which aren't
+/// available locally, i.e. which `git diff` would fetch on demand from the
+/// promisor remote of a partial clone (e.g. cloned with `--filter=blob:none`)
+pub fn missing_blobs(repo: &Path, commit_id: Oid) -> usize {
+    let git_repo = match Repository::open(repo) {
+        Ok(git_repo) if !commit_id.is_zero() && is_partial_clone(&git_repo) => git_repo,
+        _ => return 0,
+    };
+    let missing = || -> Result<usize, git2::Error> {
+        let commit = git_repo.find_commit(commit_id)?;
+        let parent_tree = commit.parent(0).and_then(|parent| parent.tree()).ok();
+        // compares the IDs of the blobs only, without reading them
+        let diff = git_repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        let odb = git_repo.odb()?;
+        Ok(diff
+            .deltas()
+            .flat_map(|delta| vec![delta.old_file().id(), delta.new_file().id()])
+            .filter(|id| !id.is_zero() && !odb.exists(*id))
+            .count())
+    };
+    missing().unwrap_or(0)
+}
+
+fn is_partial_clone(git_repo: &Repository) -> bool {
+    let config = match git_repo.config() {
+        Ok(config) => config,
+        Err(_) => return false,
+    };
+    config.get_string("extensions.partialclone").is_ok()
+        || config
+            .entries(Some(r"remote\..*\.promisor"))
+            .is_ok_and(|mut entries| entries.next().is_some())
+}
+
+/// Adds the diffs of commits likely to be viewed next (e.g. the neighbours
+/// of the selected commit) to the cache in the background
+pub struct Prefetcher {
+    /// number of commits above and below the selected one
+    count: usize,
+    sender: Sender<Vec<(PathBuf, Oid)>>,
+}
+
+impl Prefetcher {
+    pub fn new(cache: Arc<Mutex<DiffCache>>, count: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Vec<(PathBuf, Oid)>>();
+        thread::spawn(move || {
+            while let Ok(mut commits) = receiver.recv() {
+                // only the latest request matters, the selection moved on
+                commits = receiver.try_iter().last().unwrap_or(commits);
+                for (repo, commit_id) in commits {
+                    // never fetches blobs of partial clones unasked
+                    if cache.lock().unwrap().contains(&repo, commit_id)
+                        || missing_blobs(&repo, commit_id) > 0
+                    {
+                        continue;
+                    }
+                    if let Ok(diff) = git_diff(&repo, commit_id) {
+                        cache
+                            .lock()
+                            .unwrap()
+                            .insert(&repo, commit_id, Arc::new(diff));
+                    }
+                }
+            }
+        });
+        Prefetcher { count, sender }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// prefetches the diffs of the given commits, in their order
+    pub fn prefetch(&self, commits: &[RepoCommit]) {
+        let commits = commits
+            .iter()
+            .map(|commit| (commit.repo.abs_path.clone(), commit.commit_id))
+            .collect();
+        let _ = self.sender.send(commits);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_least_recently_viewed_diffs() {
+        let repo = Path::new("/ws/kernel");
+        let id = |n: u8| Oid::from_bytes(&[n; 20]).unwrap();
+        let diff = |len: usize| Arc::new("x".repeat(len));
+
+        let mut cache = DiffCache::new(10);
+        cache.insert(repo, id(1), diff(4));
+        cache.insert(repo, id(2), diff(4));
+        assert!(cache.get(repo, id(1)).is_some());
+        cache.insert(repo, id(3), diff(4));
+        assert!(!cache.contains(repo, id(2)));
+        assert_eq!(cache.get(repo, id(1)).unwrap().len(), 4);
+        assert!(cache.get(Path::new("/ws/build"), id(1)).is_none());
+
+        cache.insert(repo, id(4), diff(11));
+        assert!(cache.get(repo, id(4)).is_none());
+        assert!(cache.get(repo, id(3)).is_some());
+    }
+}
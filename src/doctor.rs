@@ -0,0 +1,368 @@
+use crate::config::{self, APP_INFO};
+use crate::fingerprints::Fingerprints;
+use crate::manifest::{self, Project};
+use crate::utils::find_project_file;
+use crate::workspace::{self, WorkspaceType};
+use crate::{gclient, tsrc, west};
+use anyhow::{anyhow, Result};
+use app_dirs::{app_root, AppDataType};
+use console::{style, Term};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Terminals known to report mouse events, by the prefix of `TERM`
+const MOUSE_TERMINALS: [&str; 8] = [
+    "xterm",
+    "screen",
+    "tmux",
+    "rxvt",
+    "alacritty",
+    "kitty",
+    "foot",
+    "wezterm",
+];
+
+/// The outcome of a check
+enum Status {
+    Ok,
+    /// oper works, but a feature doesn't
+    Warning,
+    Failure,
+}
+
+struct Check {
+    status: Status,
+    topic: &'static str,
+    result: String,
+    /// what to do about a warning or failure
+    hint: Option<String>,
+}
+
+impl Check {
+    fn ok(topic: &'static str, result: String) -> Check {
+        Check {
+            status: Status::Ok,
+            topic,
+            result,
+            hint: None,
+        }
+    }
+
+    fn warning(topic: &'static str, result: String, hint: &str) -> Check {
+        Check {
+            status: Status::Warning,
+            topic,
+            result,
+            hint: Some(hint.to_string()),
+        }
+    }
+
+    fn failure(topic: &'static str, result: String, hint: &str) -> Check {
+        Check {
+            status: Status::Failure,
+            topic,
+            result,
+            hint: Some(hint.to_string()),
+        }
+    }
+}
+
+/// Checks the environment oper runs in: its config file, the workspace
+/// and its manifest, the external tools used, the terminal and the data
+/// oper keeps between runs. Prints what to do about the problems found,
+/// fails if oper can't work at all.
+pub fn run(workspace_type: Option<WorkspaceType>) -> Result<()> {
+    let mut checks = vec![config_check()];
+    checks.extend(workspace_checks(workspace_type));
+    checks.push(tool_check(
+        "git",
+        "needed for the diff view, patches and --manifest-url",
+    ));
+    checks.push(tool_check(
+        "curl",
+        "needed for pull requests, JIRA issues and self-update",
+    ));
+    checks.extend(terminal_checks());
+    checks.push(data_check());
+
+    let mut failures = 0;
+    for check in &checks {
+        let status = match check.status {
+            Status::Ok => style("ok  ").green(),
+            Status::Warning => style("warn").yellow(),
+            Status::Failure => {
+                failures += 1;
+                style("fail").red()
+            }
+        };
+        println!(
+            "[{}] {}: {}",
+            status,
+            style(check.topic).bold(),
+            check.result
+        );
+        if let Some(hint) = &check.hint {
+            println!("       {}", hint);
+        }
+    }
+    if failures > 0 {
+        return Err(anyhow!("{} of {} checks failed", failures, checks.len()));
+    }
+    Ok(())
+}
+
+fn config_check() -> Check {
+    let file = match config::config_file() {
+        Ok(file) => file,
+        Err(e) => {
+            return Check::failure(
+                "config",
+                format!("no config folder: {}", e),
+                "check that HOME is set and writable",
+            )
+        }
+    };
+    if !file.is_file() {
+        return Check::ok(
+            "config",
+            format!("{} not created yet, the defaults apply", file.display()),
+        );
+    }
+    match fs::read_to_string(&file).map(|content| config::deserialize(&content)) {
+        Ok(Ok(_)) => Check::ok("config", format!("{} is valid", file.display())),
+        Ok(Err(e)) => Check::failure(
+            "config",
+            format!("{} is invalid: {}", file.display(), e),
+            "fix it, or delete it to get the default config",
+        ),
+        Err(e) => Check::failure(
+            "config",
+            format!("{} can't be read: {}", file.display(), e),
+            "check its permissions",
+        ),
+    }
+}
+
+/// the workspace of the working directory and its manifest
+fn workspace_checks(workspace_type: Option<WorkspaceType>) -> Vec<Check> {
+    let base_folder = match env::current_dir()
+        .and_then(|folder| workspace::base_folder_of(&folder, workspace_type))
+    {
+        Ok(base_folder) => base_folder,
+        Err(e) => {
+            return vec![Check::failure(
+                "workspace",
+                e.to_string(),
+                "run oper within a workspace (git-repo, west, tsrc or gclient), or pass --cwd or --scan",
+            )]
+        }
+    };
+    let workspace_type = workspace_type
+        .or_else(|| WorkspaceType::of(&base_folder))
+        .unwrap_or(WorkspaceType::Repo);
+    vec![
+        Check::ok(
+            "workspace",
+            format!(
+                "{} workspace in {}",
+                workspace_type.name(),
+                base_folder.display()
+            ),
+        ),
+        manifest_check(&base_folder, workspace_type),
+    ]
+}
+
+fn manifest_check(base_folder: &Path, workspace_type: WorkspaceType) -> Check {
+    let read = match workspace_type {
+        WorkspaceType::Repo => manifest::read,
+        WorkspaceType::West => west::read,
+        WorkspaceType::Tsrc => tsrc::read,
+        WorkspaceType::Gclient => gclient::read,
+    };
+    match read(base_folder) {
+        Ok(projects) => {
+            let checked_out = checked_out(base_folder, &projects);
+            let result = format!(
+                "{} projects, {} of them checked out",
+                projects.len(),
+                checked_out
+            );
+            match checked_out < projects.len() {
+                true => Check::warning(
+                    "manifest",
+                    result,
+                    "projects which aren't checked out are skipped, sync the workspace to include them",
+                ),
+                false => Check::ok("manifest", result),
+            }
+        }
+        // oper falls back to the project list of git-repo
+        Err(e) if workspace_type == WorkspaceType::Repo => match find_project_file(base_folder) {
+            Ok(file) => Check::warning(
+                "manifest",
+                format!("{:#}, using {} instead", e, file.display()),
+                "groups aren't known without the manifest",
+            ),
+            Err(_) => Check::failure(
+                "manifest",
+                format!("{:#}", e),
+                "run `repo sync` to complete the workspace",
+            ),
+        },
+        Err(e) => Check::failure(
+            "manifest",
+            format!("{:#}", e),
+            "fix the manifest or pass the right --workspace-type",
+        ),
+    }
+}
+
+fn checked_out(base_folder: &Path, projects: &[Project]) -> usize {
+    projects
+        .iter()
+        .filter(|project| base_folder.join(&project.path).is_dir())
+        .count()
+}
+
+/// the version of an external tool, in case it's found
+fn tool_check(tool: &'static str, needed_for: &str) -> Check {
+    let output = Command::new(tool)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            let version = String::from_utf8_lossy(&output.stdout);
+            Check::ok(tool, version.lines().next().unwrap_or_default().to_string())
+        }
+        _ => Check::warning(
+            tool,
+            String::from("not found"),
+            &format!("install {} and add it to PATH, it's {}", tool, needed_for),
+        ),
+    }
+}
+
+fn terminal_checks() -> Vec<Check> {
+    let term = env::var("TERM").unwrap_or_default();
+    if term.is_empty() || term == "dumb" || !Term::stdout().is_term() {
+        return vec![Check::warning(
+            "terminal",
+            format!("no interactive terminal (TERM={:?})", term),
+            "the UI needs one, reports can be written with --report anyway",
+        )];
+    }
+    let colors = Command::new("tput")
+        .arg("colors")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8_lossy(&output.stdout).trim().parse().ok())
+        .unwrap_or(0);
+    let truecolor = matches!(env::var("COLORTERM").as_deref(), Ok("truecolor" | "24bit"));
+    let colors = match (truecolor, colors) {
+        (true, _) => Check::ok("colors", String::from("true color")),
+        (false, colors) if colors >= 256 => Check::ok("colors", format!("{} colors", colors)),
+        (false, colors) => Check::warning(
+            "colors",
+            format!("{} colors (TERM={})", colors, term),
+            "highlighting and styles may look off, e.g. set TERM=xterm-256color",
+        ),
+    };
+    let mouse = match MOUSE_TERMINALS
+        .iter()
+        .any(|prefix| term.starts_with(prefix))
+    {
+        true => Check::ok("mouse", format!("supported by {}", term)),
+        false => Check::warning(
+            "mouse",
+            format!("{} may not report mouse events", term),
+            "use the keyboard, or a terminal like xterm or tmux",
+        ),
+    };
+    let (rows, columns) = Term::stdout().size();
+    vec![
+        Check::ok("terminal", format!("{}, {}x{}", term, columns, rows)),
+        colors,
+        mouse,
+    ]
+}
+
+/// the folder oper keeps its data between runs in, e.g. the fingerprints
+/// of `--changed-only`
+fn data_check() -> Check {
+    let folder = match app_root(AppDataType::UserData, &APP_INFO) {
+        Ok(folder) => folder,
+        Err(e) => {
+            return Check::failure(
+                "data",
+                format!("no data folder: {}", e),
+                "check that HOME is set and writable",
+            )
+        }
+    };
+    let probe = folder.join(".doctor");
+    if let Err(e) = fs::write(&probe, "").and_then(|_| fs::remove_file(&probe)) {
+        return Check::failure(
+            "data",
+            format!("{} isn't writable: {}", folder.display(), e),
+            "check its permissions",
+        );
+    }
+    let fingerprints = Fingerprints::load();
+    let stale = fingerprints.repos().filter(|repo| !repo.is_dir()).count();
+    let result = format!(
+        "{}, fingerprints of {} repositories",
+        folder.display(),
+        fingerprints.repos().count()
+    );
+    match stale {
+        0 => Check::ok("data", result),
+        stale => Check::warning(
+            "data",
+            format!("{}, {} of them no longer exist", result, stale),
+            &format!(
+                "harmless, delete {} to forget them",
+                folder.join("fingerprints").display()
+            ),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_checked_out_projects() {
+        let base = env::temp_dir().join(format!("oper-doctor-{}", std::process::id()));
+        fs::create_dir_all(base.join(".repo/manifests")).unwrap();
+        fs::write(
+            base.join(".repo/manifest.xml"),
+            r#"<manifest><include name="default.xml"/></manifest>"#,
+        )
+        .unwrap();
+        fs::write(
+            base.join(".repo/manifests/default.xml"),
+            r#"<manifest><default revision="main"/>
+            <project name="kernel/msm"/>
+            <project name="platform/build" path="build"/></manifest>"#,
+        )
+        .unwrap();
+        fs::create_dir_all(base.join("kernel/msm")).unwrap();
+
+        let check = manifest_check(&base, WorkspaceType::Repo);
+        assert!(matches!(check.status, Status::Warning));
+        assert_eq!(check.result, "2 projects, 1 of them checked out");
+
+        fs::create_dir_all(base.join("build")).unwrap();
+        let check = manifest_check(&base, WorkspaceType::Repo);
+        assert!(matches!(check.status, Status::Ok));
+        assert_eq!(check.result, "2 projects, 2 of them checked out");
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -0,0 +1,346 @@
+use crate::model::RepoCommit;
+use regex::Regex;
+use std::fmt;
+
+/// A filter entered into the filter bar of the UI. It consists of
+/// whitespace separated terms which all have to match a commit:
+///
+/// - `word` matches summary, author or repository (case insensitive)
+/// - `repo:<pattern>` matches the local path of the repository
+/// - `author:<pattern>` matches the author's name or email
+/// - `team:<pattern>` matches the team owning the repository
+/// - `workspace:<pattern>` matches the workspace of the repository, in
+///   case several workspaces are scanned at once
+/// - `group:<pattern>` matches a group of the project in the manifest
+/// - `annotation:<pattern>` matches an annotation added by the script,
+///   given as `key=value`
+/// - `date:<prefix>` matches the commit date, e.g. `date:2020-05-17`
+///   or `date:2020-05` for a whole month
+/// - `is:merge` matches merge commits
+/// - `is:side` matches commits of merged side branches
+/// - `is:bot` matches commits of bots (see `bots` in the config file)
+/// - `is:future` matches commits dated in the future (wrong clocks)
+/// - `is:wip` matches stash entries and uncommitted changes
+/// - `is:rewritten` matches commits which replaced a commit listed by the
+///   previous run, i.e. which have been amended or rebased since then
+/// - `parents:<n>` matches commits with exactly `<n>` parents
+///
+/// Every term can be negated by prefixing it with `-`. Patterns match
+/// anywhere within the value, a pattern in double quotes (e.g.
+/// `repo:"kernel"` or `repo:"vendor/my app"`) matches the whole value only.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Filter {
+    terms: Vec<Term>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct Term {
+    negated: bool,
+    kind: TermKind,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum TermKind {
+    Text(String),
+    Repo(Pattern),
+    Author(Pattern),
+    Team(Pattern),
+    Workspace(Pattern),
+    Group(Pattern),
+    Annotation(Pattern),
+    Date(String),
+    Merge,
+    SideBranch,
+    Bot,
+    Future,
+    Wip,
+    Rewritten,
+    Parents(usize),
+}
+
+/// the lower case value of a term like `repo:<pattern>`
+#[derive(Clone, PartialEq, Debug)]
+enum Pattern {
+    Substring(String),
+    Exact(String),
+}
+
+impl Filter {
+    pub fn parse(text: &str) -> Result<Filter, String> {
+        let terms = split_terms(text)?
+            .into_iter()
+            .map(Term::parse)
+            .collect::<Result<Vec<Term>, String>>()?;
+        Ok(Filter { terms })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// adds the given term (e.g. `-is:side`) to the filter, or removes
+    /// it in case the filter already contains it
+    pub fn toggle(&mut self, term: &str) -> Result<(), String> {
+        let term = Term::parse(term)?;
+        match self.terms.iter().position(|t| *t == term) {
+            Some(index) => {
+                self.terms.remove(index);
+            }
+            None => self.terms.push(term),
+        }
+        Ok(())
+    }
+
+    /// returns a regex matching the (not negated) words of the filter,
+    /// used to highlight why a commit matched
+    pub fn highlighter(&self) -> Option<Regex> {
+        let words: Vec<String> = self
+            .terms
+            .iter()
+            .filter_map(|term| match &term.kind {
+                TermKind::Text(text) if !term.negated => Some(regex::escape(text)),
+                _ => None,
+            })
+            .collect();
+        if words.is_empty() {
+            return None;
+        }
+        Regex::new(&format!("(?i){}", words.join("|"))).ok()
+    }
+
+    pub fn matches(&self, commit: &RepoCommit) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term.kind.matches(commit) != term.negated)
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let terms = self
+            .terms
+            .iter()
+            .map(Term::to_string)
+            .collect::<Vec<String>>();
+        write!(f, "{}", terms.join(" "))
+    }
+}
+
+/// splits the given text at whitespace which isn't within double quotes
+fn split_terms(text: &str) -> Result<Vec<&str>, String> {
+    let mut terms = Vec::new();
+    let mut start = None;
+    let mut quoted = false;
+    for (index, c) in text.char_indices() {
+        if c.is_whitespace() && !quoted {
+            if let Some(start) = start.take() {
+                terms.push(&text[start..index]);
+            }
+            continue;
+        }
+        if c == '"' {
+            quoted = !quoted;
+        }
+        start.get_or_insert(index);
+    }
+    if quoted {
+        return Err(String::from("Missing closing quote"));
+    }
+    if let Some(start) = start {
+        terms.push(&text[start..]);
+    }
+    Ok(terms)
+}
+
+impl Term {
+    fn parse(token: &str) -> Result<Term, String> {
+        let (negated, token) = match token.strip_prefix('-') {
+            Some(rest) if !rest.is_empty() => (true, rest),
+            _ => (false, token),
+        };
+
+        let kind = match token.split_once(':') {
+            Some(("repo", pattern)) => TermKind::Repo(Pattern::parse(pattern)),
+            Some(("author", pattern)) => TermKind::Author(Pattern::parse(pattern)),
+            Some(("team", pattern)) => TermKind::Team(Pattern::parse(pattern)),
+            Some(("workspace", pattern)) => TermKind::Workspace(Pattern::parse(pattern)),
+            Some(("group", pattern)) => TermKind::Group(Pattern::parse(pattern)),
+            Some(("annotation", pattern)) => TermKind::Annotation(Pattern::parse(pattern)),
+            Some(("date", prefix)) => TermKind::Date(prefix.to_string()),
+            Some(("is", "merge")) => TermKind::Merge,
+            Some(("is", "side")) => TermKind::SideBranch,
+            Some(("is", "bot")) => TermKind::Bot,
+            Some(("is", "future")) => TermKind::Future,
+            Some(("is", "wip")) => TermKind::Wip,
+            Some(("is", "rewritten")) => TermKind::Rewritten,
+            Some(("is", what)) => return Err(format!("Unknown filter 'is:{}'", what)),
+            Some(("parents", count)) => TermKind::Parents(
+                count
+                    .parse()
+                    .map_err(|_| format!("Invalid number of parents '{}'", count))?,
+            ),
+            _ => TermKind::Text(token.to_lowercase()),
+        };
+
+        Ok(Term { negated, kind })
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negated {
+            write!(f, "-")?;
+        }
+        match &self.kind {
+            TermKind::Text(text) => write!(f, "{}", text),
+            TermKind::Repo(pattern) => write!(f, "repo:{}", pattern),
+            TermKind::Author(pattern) => write!(f, "author:{}", pattern),
+            TermKind::Team(pattern) => write!(f, "team:{}", pattern),
+            TermKind::Workspace(pattern) => write!(f, "workspace:{}", pattern),
+            TermKind::Group(pattern) => write!(f, "group:{}", pattern),
+            TermKind::Annotation(pattern) => write!(f, "annotation:{}", pattern),
+            TermKind::Date(prefix) => write!(f, "date:{}", prefix),
+            TermKind::Merge => write!(f, "is:merge"),
+            TermKind::SideBranch => write!(f, "is:side"),
+            TermKind::Bot => write!(f, "is:bot"),
+            TermKind::Future => write!(f, "is:future"),
+            TermKind::Wip => write!(f, "is:wip"),
+            TermKind::Rewritten => write!(f, "is:rewritten"),
+            TermKind::Parents(count) => write!(f, "parents:{}", count),
+        }
+    }
+}
+
+impl Pattern {
+    fn parse(pattern: &str) -> Pattern {
+        let pattern = pattern.to_lowercase();
+        match pattern.strip_prefix('"').and_then(|p| p.strip_suffix('"')) {
+            Some(exact) => Pattern::Exact(exact.to_string()),
+            None => Pattern::Substring(pattern),
+        }
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        let value = value.to_lowercase();
+        match self {
+            Pattern::Substring(pattern) => value.contains(pattern.as_str()),
+            Pattern::Exact(pattern) => value == *pattern,
+        }
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pattern::Substring(pattern) => write!(f, "{}", pattern),
+            Pattern::Exact(pattern) => write!(f, "\"{}\"", pattern),
+        }
+    }
+}
+
+impl TermKind {
+    fn matches(&self, commit: &RepoCommit) -> bool {
+        match self {
+            TermKind::Text(text) => {
+                commit.summary.to_lowercase().contains(text)
+                    || commit.author_name.to_lowercase().contains(text)
+                    || commit.repo.name().to_lowercase().contains(text)
+            }
+            TermKind::Repo(pattern) => pattern.matches(&commit.repo.name()),
+            TermKind::Author(pattern) => {
+                pattern.matches(&commit.author_name) || pattern.matches(&commit.author_email)
+            }
+            TermKind::Team(pattern) => commit
+                .repo
+                .team
+                .as_ref()
+                .is_some_and(|team| pattern.matches(team)),
+            TermKind::Workspace(pattern) => commit
+                .repo
+                .workspace
+                .as_ref()
+                .is_some_and(|workspace| pattern.matches(workspace)),
+            TermKind::Group(pattern) => commit
+                .repo
+                .groups
+                .iter()
+                .any(|group| pattern.matches(group)),
+            TermKind::Annotation(pattern) => commit
+                .annotations
+                .iter()
+                .any(|(key, value)| pattern.matches(&format!("{}={}", key, value))),
+            TermKind::Date(prefix) => commit.date_as_str().starts_with(prefix),
+            TermKind::Merge => commit.is_merge(),
+            TermKind::SideBranch => commit.is_on_side_branch(),
+            TermKind::Bot => commit.is_bot,
+            TermKind::Future => commit.is_future_dated(),
+            TermKind::Wip => commit.wip.is_some(),
+            TermKind::Rewritten => commit.replaces.is_some(),
+            TermKind::Parents(count) => commit.parent_ids.len() == *count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_display_roundtrip() {
+        let filter = Filter::parse(
+            "  Fix -is:merge repo:Kernel parents:2 date:2020-05 -is:bot team:Core is:future annotation:Kind=revert is:wip workspace:Product-A group:Tools",
+        )
+        .unwrap();
+        assert_eq!(
+            filter.to_string(),
+            "fix -is:merge repo:kernel parents:2 date:2020-05 -is:bot team:core is:future annotation:kind=revert is:wip workspace:product-a group:tools"
+        );
+        assert_eq!(Filter::parse(&filter.to_string()).unwrap(), filter);
+    }
+
+    #[test]
+    fn quoted_patterns_match_whole_values() {
+        let filter =
+            Filter::parse("repo:\"Vendor/My App\"  -author:\"bot@example.com\" fix").unwrap();
+        assert_eq!(
+            filter.to_string(),
+            "repo:\"vendor/my app\" -author:\"bot@example.com\" fix"
+        );
+        assert_eq!(Filter::parse(&filter.to_string()).unwrap(), filter);
+        assert!(Pattern::parse("\"kernel\"").matches("Kernel"));
+        assert!(!Pattern::parse("\"kernel\"").matches("kernel-headers"));
+        assert!(Pattern::parse("kernel").matches("kernel-headers"));
+        assert!(Filter::parse("repo:\"vendor/my").is_err());
+    }
+
+    #[test]
+    fn toggle_adds_and_removes_terms() {
+        let mut filter = Filter::parse("fix").unwrap();
+        filter.toggle("-is:side").unwrap();
+        assert_eq!(filter.to_string(), "fix -is:side");
+        filter.toggle("-is:side").unwrap();
+        assert_eq!(filter.to_string(), "fix");
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Filter::parse("is:octopus").is_err());
+        assert!(Filter::parse("parents:many").is_err());
+        assert!(Filter::parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn highlighter_matches_words_only() {
+        let filter = Filter::parse("fix -revert repo:kernel a.b").unwrap();
+        let highlighter = filter.highlighter().unwrap();
+        let matched: Vec<&str> = highlighter
+            .find_iter("Revert FIX of a.b and axb in kernel")
+            .map(|m| m.as_str())
+            .collect();
+        assert_eq!(matched, vec!["FIX", "a.b"]);
+        assert!(Filter::parse("-fix is:merge")
+            .unwrap()
+            .highlighter()
+            .is_none());
+    }
+}
@@ -0,0 +1,251 @@
+use crate::config::APP_INFO;
+use crate::model::Repo;
+use app_dirs::{app_root, AppDataType};
+use git2::{Oid, Repository};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Files of a git folder which are touched whenever HEAD or a ref moves
+const TOUCHED_FILES: [&str; 3] = ["HEAD", "packed-refs", "logs/HEAD"];
+
+/// State of a repository which changes whenever its HEAD or one of its
+/// refs moves, cheap to take compared to walking its history
+#[derive(Debug, PartialEq, Clone)]
+pub struct Fingerprint {
+    pub head: String,
+    pub refs: usize,
+    /// latest modification of the files touched when refs move, in seconds
+    /// since the epoch
+    pub mtime: u64,
+}
+
+impl Fingerprint {
+    pub fn of(repo: &Repo) -> Option<Fingerprint> {
+        let git_repo = Repository::open(&repo.abs_path).ok()?;
+        let head = git_repo.head().ok()?.target()?.to_string();
+        let refs = git_repo.references().ok()?.count();
+        Some(Fingerprint {
+            head,
+            refs,
+            mtime: latest_mtime(git_repo.path()),
+        })
+    }
+
+    /// the fingerprint of a repository of which only the HEAD is known:
+    /// the previous one if HEAD didn't move, else one differing from any
+    /// complete fingerprint
+    fn with_head(previous: Option<&Fingerprint>, head: String) -> Fingerprint {
+        match previous {
+            Some(previous) if previous.head == head => previous.clone(),
+            _ => Fingerprint {
+                head,
+                refs: 0,
+                mtime: 0,
+            },
+        }
+    }
+}
+
+/// the latest modification of the files of the git folder which are touched
+/// when HEAD or a ref moves, in seconds since the epoch
+pub fn latest_mtime(git_folder: &Path) -> u64 {
+    TOUCHED_FILES
+        .iter()
+        .filter_map(|file| fs::metadata(git_folder.join(file)).ok()?.modified().ok())
+        .filter_map(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+        .max()
+        .unwrap_or(0)
+}
+
+/// true if HEAD or a ref of the repository has been modified since the
+/// given time, judged by the modification times of the files storing them
+pub fn touched_since(repo: &Repo, since: SystemTime) -> bool {
+    let git_folder = match Repository::open(&repo.abs_path) {
+        Ok(git_repo) => git_repo.path().to_path_buf(),
+        // scanning it reports the error
+        Err(_) => return true,
+    };
+    TOUCHED_FILES
+        .iter()
+        .chain(std::iter::once(&"refs"))
+        .any(|file| modified_since(&git_folder.join(file), since))
+}
+
+/// true if the file, or any file within the folder, has been modified
+/// since the given time
+fn modified_since(path: &Path, since: SystemTime) -> bool {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return false,
+    };
+    if metadata.is_dir() {
+        fs::read_dir(path)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .any(|entry| modified_since(&entry.path(), since))
+            })
+            .unwrap_or(false)
+    } else {
+        metadata.modified().is_ok_and(|time| time >= since)
+    }
+}
+
+/// Fingerprints of repositories by their absolute path, persisted in
+/// oper's data folder to tell the repositories changed since the last run
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Fingerprints(HashMap<PathBuf, Fingerprint>);
+
+impl Fingerprints {
+    /// takes the fingerprints of the given repositories, skipping the
+    /// ones which can't be opened
+    pub fn of(repos: &[Arc<Repo>]) -> Fingerprints {
+        Fingerprints(
+            repos
+                .par_iter()
+                .filter_map(|repo| Some((repo.abs_path.clone(), Fingerprint::of(repo)?)))
+                .collect(),
+        )
+    }
+
+    /// takes only the HEAD of the given repositories, cheaper than `of()`
+    /// for runs which don't compare fingerprints themselves but record
+    /// the HEADs for the next run. A repository whose HEAD moved since
+    /// the previous fingerprint is taken for changed by `has_changed()`.
+    pub fn heads_of(repos: &[Arc<Repo>], previous: &Fingerprints) -> Fingerprints {
+        Fingerprints(
+            repos
+                .par_iter()
+                .filter_map(|repo| {
+                    let git_repo = Repository::open(&repo.abs_path).ok()?;
+                    let head = git_repo.head().ok()?.target()?.to_string();
+                    let fingerprint = Fingerprint::with_head(previous.0.get(&repo.abs_path), head);
+                    Some((repo.abs_path.clone(), fingerprint))
+                })
+                .collect(),
+        )
+    }
+
+    /// loads the fingerprints recorded by previous runs, none in case
+    /// they cannot be read
+    pub fn load() -> Fingerprints {
+        file()
+            .and_then(|file| fs::read_to_string(file).ok())
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    /// the absolute paths of the repositories with a fingerprint
+    pub fn repos(&self) -> impl Iterator<Item = &Path> {
+        self.0.keys().map(PathBuf::as_path)
+    }
+
+    /// true if the fingerprint of the repository differs from the
+    /// previous one or if either is missing
+    pub fn has_changed(&self, previous: &Fingerprints, repo: &Repo) -> bool {
+        match (self.0.get(&repo.abs_path), previous.0.get(&repo.abs_path)) {
+            (Some(current), Some(previous)) => current != previous,
+            _ => true,
+        }
+    }
+
+    /// the HEAD of each repository, by its absolute path
+    pub fn heads(&self) -> HashMap<PathBuf, Oid> {
+        self.0
+            .iter()
+            .filter_map(|(path, fingerprint)| {
+                Some((path.clone(), Oid::from_str(&fingerprint.head).ok()?))
+            })
+            .collect()
+    }
+
+    /// adds or replaces the fingerprints by the given ones
+    pub fn extend(&mut self, fingerprints: Fingerprints) {
+        self.0.extend(fingerprints.0);
+    }
+
+    pub fn save(&self) {
+        if let Some(file) = file() {
+            // rescanning all repositories is the worst case of losing them
+            let _ = fs::write(file, self.to_string());
+        }
+    }
+
+    /// parses lines of the form `<path>\t<head>\t<refs>\t<mtime>`
+    fn parse(content: &str) -> Fingerprints {
+        Fingerprints(
+            content
+                .lines()
+                .filter_map(|line| {
+                    let mut fields = line.split('\t');
+                    let path = PathBuf::from(fields.next()?);
+                    let fingerprint = Fingerprint {
+                        head: fields.next()?.to_string(),
+                        refs: fields.next()?.parse().ok()?,
+                        mtime: fields.next()?.parse().ok()?,
+                    };
+                    Some((path, fingerprint))
+                })
+                .collect(),
+        )
+    }
+}
+
+impl std::fmt::Display for Fingerprints {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut paths: Vec<&PathBuf> = self.0.keys().collect();
+        paths.sort();
+        for path in paths {
+            let fingerprint = &self.0[path];
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}",
+                path.display(),
+                fingerprint.head,
+                fingerprint.refs,
+                fingerprint.mtime
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn file() -> Option<PathBuf> {
+    app_root(AppDataType::UserData, &APP_INFO)
+        .ok()
+        .map(|folder| folder.join("fingerprints"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrips_and_compares_fingerprints() {
+        let content = "/ws/a\t0123abcd\t12\t1589713200\n/ws/b\t4567ef01\t3\t1589713300\n";
+        let previous = Fingerprints::parse(content);
+        assert_eq!(previous.to_string(), content);
+
+        let mut current = Fingerprints::parse(
+            "/ws/a\t0123abcd\t12\t1589713200\n/ws/b\t4567ef01\t4\t1589713300\n/ws/c\t89ab\t1\t0\n",
+        );
+        let repo = |path: &str| Repo::from(PathBuf::from(path), String::new());
+        assert!(!current.has_changed(&previous, &repo("/ws/a")));
+        assert!(current.has_changed(&previous, &repo("/ws/b")));
+        assert!(current.has_changed(&previous, &repo("/ws/c")));
+
+        let unmoved = Fingerprint::with_head(previous.0.get(Path::new("/ws/b")), "4567ef01".into());
+        assert_eq!(unmoved, previous.0[Path::new("/ws/b")]);
+        let moved = Fingerprint::with_head(previous.0.get(Path::new("/ws/b")), "89ab".into());
+        assert_eq!((moved.refs, moved.mtime), (0, 0));
+
+        current.extend(previous);
+        assert_eq!(current.0[Path::new("/ws/b")].refs, 3);
+        assert_eq!(current.0.len(), 3);
+    }
+}
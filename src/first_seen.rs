@@ -0,0 +1,145 @@
+use crate::config::APP_INFO;
+use crate::model::RepoCommit;
+use app_dirs::{app_root, AppDataType};
+use git2::{Oid, Repository, Time};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Number of commits recorded per repository, the ones seen first are
+/// dropped beyond
+const MAX_RECORDED_COMMITS: usize = 20000;
+
+/// When commits landed in the local workspace, i.e. became reachable from
+/// HEAD since the previous run. Persisted in oper's data folder; commits
+/// which aren't recorded have been present ever since their repository
+/// got tracked.
+#[derive(Debug, Default)]
+pub struct FirstSeen {
+    /// seconds since the epoch by repository (absolute path) and commit
+    seen: HashMap<PathBuf, HashMap<Oid, i64>>,
+    file: Option<PathBuf>,
+}
+
+impl FirstSeen {
+    /// loads the commits recorded by previous runs, none in case they
+    /// cannot be read
+    pub fn load() -> FirstSeen {
+        let file = app_root(AppDataType::UserData, &APP_INFO)
+            .ok()
+            .map(|folder| folder.join("first_seen"));
+        let content = file
+            .as_ref()
+            .and_then(|file| fs::read_to_string(file).ok())
+            .unwrap_or_default();
+        FirstSeen {
+            file,
+            ..Self::parse(&content)
+        }
+    }
+
+    /// sets `RepoCommit::first_seen` of the given commits of a repository,
+    /// the ones which `arrived` and aren't recorded yet are recorded as
+    /// landed at the given time (seconds since the epoch)
+    pub fn record(
+        &mut self,
+        repo: &Path,
+        commits: &mut [RepoCommit],
+        arrived: &HashSet<Oid>,
+        time: i64,
+    ) {
+        let seen = self.seen.entry(repo.to_path_buf()).or_default();
+        let offset = chrono::Local::now().offset().local_minus_utc() / 60;
+        for commit in commits.iter_mut().filter(|commit| commit.wip.is_none()) {
+            if arrived.contains(&commit.commit_id) {
+                seen.entry(commit.commit_id).or_insert(time);
+            }
+            if let Some(time) = seen.get(&commit.commit_id) {
+                commit.first_seen = Some(Time::new(*time, offset));
+            }
+        }
+        if seen.len() > MAX_RECORDED_COMMITS {
+            let mut times: Vec<i64> = seen.values().copied().collect();
+            times.sort_unstable();
+            let oldest = times[seen.len() - MAX_RECORDED_COMMITS];
+            seen.retain(|_, time| *time >= oldest);
+        }
+    }
+
+    pub fn save(&self) {
+        if let Some(file) = &self.file {
+            // commits not recorded are considered present ever since
+            let _ = fs::write(file, self.to_string());
+        }
+    }
+
+    /// the commits reachable from HEAD but not from the given HEAD of the
+    /// previous run, i.e. the ones which arrived since then
+    pub fn arrived_since(git_repo: &Repository, previous_head: Oid) -> HashSet<Oid> {
+        let mut revwalk = match git_repo.revwalk() {
+            Ok(revwalk) => revwalk,
+            Err(_) => return HashSet::new(),
+        };
+        if revwalk
+            .push_head()
+            .and_then(|_| revwalk.hide(previous_head))
+            .is_err()
+        {
+            return HashSet::new();
+        }
+        revwalk.flatten().collect()
+    }
+
+    /// parses lines of the form `<path>\t<commit id>\t<seconds>`
+    fn parse(content: &str) -> FirstSeen {
+        let mut seen: HashMap<PathBuf, HashMap<Oid, i64>> = HashMap::new();
+        for line in content.lines() {
+            let mut fields = line.split('\t');
+            let entry = (|| {
+                let path = PathBuf::from(fields.next()?);
+                let id = Oid::from_str(fields.next()?).ok()?;
+                let time = fields.next()?.parse().ok()?;
+                Some((path, id, time))
+            })();
+            if let Some((path, id, time)) = entry {
+                seen.entry(path).or_default().insert(id, time);
+            }
+        }
+        FirstSeen { seen, file: None }
+    }
+}
+
+impl std::fmt::Display for FirstSeen {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut paths: Vec<&PathBuf> = self.seen.keys().collect();
+        paths.sort();
+        for path in paths {
+            let mut commits: Vec<(&Oid, &i64)> = self.seen[path].iter().collect();
+            commits.sort_by_key(|(id, time)| (**time, **id));
+            for (id, time) in commits {
+                writeln!(f, "{}\t{}\t{}", path.display(), id, time)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrips_first_seen_commits() {
+        let content = format!(
+            "/ws/a\t{}\t1589713100\n/ws/a\t{}\t1589713200\n",
+            Oid::from_bytes(&[1; 20]).unwrap(),
+            Oid::from_bytes(&[2; 20]).unwrap()
+        );
+        let first_seen = FirstSeen::parse(&format!("{}garbage\n", content));
+        assert_eq!(first_seen.to_string(), content);
+        assert_eq!(
+            first_seen.seen[Path::new("/ws/a")][&Oid::from_bytes(&[2; 20]).unwrap()],
+            1589713200
+        );
+    }
+}
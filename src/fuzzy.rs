@@ -0,0 +1,65 @@
+/// Score of a matched character
+const MATCH_SCORE: i64 = 16;
+/// Bonus for a character matched right after the previous one
+const CONSECUTIVE_BONUS: i64 = 8;
+/// Bonus for a character matched at the start of a word
+const WORD_START_BONUS: i64 = 8;
+
+/// Matches the given pattern fuzzily against the text: each whitespace
+/// separated word of the pattern has to be found in the text as a
+/// subsequence of its characters (case insensitive). Returns `None` if
+/// a word doesn't match, otherwise a score which is higher for matches
+/// of consecutive characters and of word starts.
+pub fn score(pattern: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    pattern
+        .to_lowercase()
+        .split_whitespace()
+        .map(|word| score_word(word, &text))
+        .sum()
+}
+
+fn score_word(word: &str, text: &[char]) -> Option<i64> {
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for ch in word.chars() {
+        let found = position + text[position..].iter().position(|c| *c == ch)?;
+        score += MATCH_SCORE;
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += WORD_START_BONUS;
+        }
+        match previous {
+            Some(previous) if previous + 1 == found => score += CONSECUTIVE_BONUS,
+            Some(previous) => score -= (found - previous - 1) as i64,
+            None => {}
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_subsequences_only() {
+        assert!(score("fxbg", "Fix a bug").is_some());
+        assert!(score("bug fix", "Fix a bug").is_some());
+        assert!(score("gub", "Fix a bug").is_none());
+        assert_eq!(score("", "Fix a bug"), Some(0));
+    }
+
+    #[test]
+    fn prefers_consecutive_characters_and_word_starts() {
+        let consecutive = score("msm", "kernel/msm: fix").unwrap();
+        let scattered = score("msm", "kernel/most small").unwrap();
+        assert!(consecutive > scattered);
+
+        let word_start = score("b", "fix a bug").unwrap();
+        let inner = score("b", "fix abug").unwrap();
+        assert!(word_start > inner);
+    }
+}
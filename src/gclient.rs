@@ -0,0 +1,269 @@
+use crate::manifest::Project;
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// A token of the Python subset `.gclient` and `DEPS` files are written in
+#[derive(Debug, PartialEq)]
+enum Token {
+    Str(String),
+    Char(char),
+}
+
+/// the solutions of the workspace and the git dependencies of their `DEPS`
+/// files, following `recursedeps`. Dependencies which aren't git
+/// repositories (e.g. CIPD packages) are left out.
+pub fn read(base_folder: &Path) -> Result<Vec<Project>> {
+    let path = base_folder.join(".gclient");
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut projects = Vec::new();
+    let mut visited = HashSet::new();
+    for name in solutions_of(&content) {
+        projects.push(project(&name, String::from("HEAD")));
+        read_deps(base_folder, &name, &mut projects, &mut visited)?;
+    }
+    projects.retain(|project| base_folder.join(&project.path).join(".git").exists());
+    Ok(projects)
+}
+
+/// adds the dependencies of the `DEPS` file in the given folder of the
+/// workspace, and the ones of the dependencies it recurses into
+fn read_deps(
+    base_folder: &Path,
+    folder: &str,
+    projects: &mut Vec<Project>,
+    visited: &mut HashSet<String>,
+) -> Result<()> {
+    if !visited.insert(folder.to_string()) {
+        return Ok(());
+    }
+    let path = base_folder.join(folder).join("DEPS");
+    if !path.is_file() {
+        return Ok(());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let deps = deps_of(&content);
+    let relative = content
+        .lines()
+        .any(|line| line.replace(' ', "") == "use_relative_paths=True");
+    let local_path = |dep: &str| match relative {
+        true => format!("{}/{}", folder, dep),
+        false => dep.to_string(),
+    };
+    for (dep, revision) in &deps {
+        projects.push(project(&local_path(dep), revision.clone()));
+    }
+    for dep in recursedeps_of(&content) {
+        if deps.iter().any(|(name, _)| *name == dep) {
+            read_deps(base_folder, &local_path(&dep), projects, visited)?;
+        }
+    }
+    Ok(())
+}
+
+fn project(path: &str, revision: String) -> Project {
+    Project {
+        name: path.to_string(),
+        path: path.to_string(),
+        revision,
+        remote: None,
+        groups: Vec::new(),
+    }
+}
+
+/// the names of the solutions listed by a `.gclient` file
+fn solutions_of(gclient: &str) -> Vec<String> {
+    let tokens = tokens_of(value_of(gclient, "solutions").unwrap_or_default());
+    tokens
+        .windows(3)
+        .filter_map(|window| match window {
+            [(Token::Str(key), 1), (Token::Char(':'), 1), (Token::Str(name), 1)]
+                if key == "name" =>
+            {
+                Some(name.clone())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// the local paths of the dependencies of a `DEPS` file with the revisions
+/// they are pinned to (`HEAD` if none); dependencies named by variables
+/// are left out
+fn deps_of(deps: &str) -> Vec<(String, String)> {
+    let tokens = tokens_of(value_of(deps, "deps").unwrap_or_default());
+    let mut deps = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        let path = match (token, tokens.get(index + 1)) {
+            ((Token::Str(path), 0), Some((Token::Char(':'), 0))) if !path.contains('{') => path,
+            _ => continue,
+        };
+        // e.g. Var('chromium_git') + '/v8.git' + '@' + 'abc123', or a
+        // dictionary with the URL in `url`
+        let value: Vec<&(Token, usize)> = tokens[index + 2..]
+            .iter()
+            .take_while(|token| **token != (Token::Char(','), 0))
+            .collect();
+        let url_start = value
+            .windows(2)
+            .position(
+                |w| matches!(w, [(Token::Str(key), 1), (Token::Char(':'), 1)] if key == "url"),
+            )
+            .map(|position| position + 2);
+        let url: String = match url_start {
+            Some(start) => value[start..]
+                .iter()
+                .take_while(|token| **token != &(Token::Char(','), 1))
+                .filter_map(|token| match token {
+                    (Token::Str(part), _) => Some(part.as_str()),
+                    _ => None,
+                })
+                .collect(),
+            None => value
+                .iter()
+                .filter_map(|token| match token {
+                    (Token::Str(part), 0) => Some(part.as_str()),
+                    _ => None,
+                })
+                .collect(),
+        };
+        let revision = match url.rsplit_once('@') {
+            Some((_, revision)) if !revision.is_empty() => revision.to_string(),
+            _ => String::from("HEAD"),
+        };
+        deps.push((path.clone(), revision));
+    }
+    deps
+}
+
+/// the dependencies whose `DEPS` files are read as well
+fn recursedeps_of(deps: &str) -> Vec<String> {
+    tokens_of(value_of(deps, "recursedeps").unwrap_or_default())
+        .into_iter()
+        .filter_map(|token| match token {
+            (Token::Str(dep), 0) => Some(dep),
+            // e.g. ('src/third_party/foo', 'DEPS.chromium') of old files
+            (Token::Str(dep), 1) => Some(dep),
+            _ => None,
+        })
+        .collect()
+}
+
+/// the content of the dictionary or list assigned to the given variable,
+/// without its enclosing brackets
+fn value_of<'a>(file: &'a str, variable: &str) -> Option<&'a str> {
+    let start = file.lines().find_map(|line| {
+        let (name, value) = line.split_once('=')?;
+        let value = value.trim_start();
+        (name.trim() == variable && (value.starts_with('{') || value.starts_with('[')))
+            .then(|| value.as_ptr() as usize - file.as_ptr() as usize)
+    })?;
+    let mut depth = 0;
+    let mut quote = None;
+    let mut comment = false;
+    for (offset, c) in file[start..].char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            _ if comment => comment = c != '\n',
+            (None, '#') => comment = true,
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '{' | '[' | '(') => depth += 1,
+            (None, '}' | ']' | ')') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&file[start + 1..start + offset]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// the string literals and punctuation of a Python expression with their
+/// nesting depth; comments are left out
+fn tokens_of(expression: &str) -> Vec<(Token, usize)> {
+    let mut tokens = Vec::new();
+    let mut depth: usize = 0;
+    let mut chars = expression.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '#' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '\'' | '"' => {
+                let literal: String = chars.by_ref().take_while(|next| *next != c).collect();
+                tokens.push((Token::Str(literal), depth));
+            }
+            '{' | '[' | '(' => {
+                tokens.push((Token::Char(c), depth));
+                depth += 1;
+            }
+            '}' | ']' | ')' => {
+                depth = depth.saturating_sub(1);
+                tokens.push((Token::Char(c), depth));
+            }
+            c if c.is_whitespace() => {}
+            c => tokens.push((Token::Char(c), depth)),
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_gclient_files() {
+        let gclient = r#"solutions = [
+  { "name"        : 'src',
+    "url"         : 'https://chromium.googlesource.com/chromium/src.git',
+    "managed"     : False,
+  },
+]
+"#;
+        assert_eq!(solutions_of(gclient), vec![String::from("src")]);
+
+        let deps = r#"
+vars = {
+  'chromium_git': 'https://chromium.googlesource.com',
+}
+
+deps = {
+  # the JavaScript engine
+  'src/v8': Var('chromium_git') + '/v8/v8.git' + '@' + 'abc123',
+  'src/third_party/skia': {
+    'url': Var('chromium_git') + '/skia.git' + '@' + 'def456',
+    'condition': 'checkout_skia',
+  },
+  'src/{variable}': Var('chromium_git') + '/other.git',
+  'src/tools/gn': {
+    'packages': [{'package': 'gn/gn', 'version': 'git_revision:1'}],
+    'dep_type': 'cipd',
+  },
+}
+
+recursedeps = [
+  'src/v8',
+]
+"#;
+        assert_eq!(
+            deps_of(deps),
+            vec![
+                (String::from("src/v8"), String::from("abc123")),
+                (String::from("src/third_party/skia"), String::from("def456")),
+                (String::from("src/tools/gn"), String::from("HEAD")),
+            ]
+        );
+        assert_eq!(recursedeps_of(deps), vec![String::from("src/v8")]);
+    }
+}
@@ -0,0 +1,138 @@
+use git2::Oid;
+use std::collections::HashMap;
+
+/// Computes the glyphs of a `git log --graph` like column, one row
+/// per commit. Commits have to be passed in the order they are
+/// walked (children before their parents).
+#[derive(Default)]
+pub struct GraphBuilder {
+    lanes: Vec<Option<Oid>>,
+}
+
+impl GraphBuilder {
+    pub fn new() -> GraphBuilder {
+        GraphBuilder { lanes: Vec::new() }
+    }
+
+    /// returns the glyphs for the given commit and updates the
+    /// lanes to expect the commit's parents next
+    pub fn row(&mut self, id: Oid, parent_ids: &[Oid]) -> String {
+        let column = match self.lanes.iter().position(|lane| *lane == Some(id)) {
+            Some(column) => column,
+            None => self.free_lane(),
+        };
+
+        let glyphs = self
+            .lanes
+            .iter()
+            .enumerate()
+            .map(|(i, lane)| match lane {
+                _ if i == column => "*",
+                Some(expected) if *expected == id => "/",
+                Some(_) => "|",
+                None => " ",
+            })
+            .collect::<Vec<&str>>()
+            .join(" ");
+
+        // all other lanes waiting for this commit are merged into it
+        for lane in self.lanes.iter_mut() {
+            if *lane == Some(id) {
+                *lane = None;
+            }
+        }
+        self.lanes[column] = parent_ids.first().copied();
+        for parent_id in parent_ids.iter().skip(1) {
+            if !self.lanes.contains(&Some(*parent_id)) {
+                let lane = self.free_lane();
+                self.lanes[lane] = Some(*parent_id);
+            }
+        }
+        while let Some(None) = self.lanes.last() {
+            self.lanes.pop();
+        }
+
+        glyphs.trim_end().to_string()
+    }
+
+    fn free_lane(&mut self) -> usize {
+        match self.lanes.iter().position(Option::is_none) {
+            Some(lane) => lane,
+            None => {
+                self.lanes.push(None);
+                self.lanes.len() - 1
+            }
+        }
+    }
+}
+
+/// Computes the first-parent distance of commits, i.e. the number of
+/// merges which have to be traversed to get from the walked head to a
+/// commit: 0 for commits of the first-parent history, 1 for commits of
+/// a merged side branch, 2 for side branches merged into side branches,...
+/// Commits have to be passed in the order they are walked.
+#[derive(Default)]
+pub struct FirstParentDistances {
+    distances: HashMap<Oid, usize>,
+}
+
+impl FirstParentDistances {
+    pub fn new() -> FirstParentDistances {
+        FirstParentDistances {
+            distances: HashMap::new(),
+        }
+    }
+
+    /// returns the distance of the given commit
+    pub fn visit(&mut self, id: Oid, parent_ids: &[Oid]) -> usize {
+        let distance = self.distances.remove(&id).unwrap_or(0);
+        for (n, parent_id) in parent_ids.iter().enumerate() {
+            let parent_distance = if n == 0 { distance } else { distance + 1 };
+            self.distances
+                .entry(*parent_id)
+                .and_modify(|d| *d = std::cmp::min(*d, parent_distance))
+                .or_insert(parent_distance);
+        }
+        distance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oid(n: u8) -> Oid {
+        Oid::from_bytes(&[n; 20]).unwrap()
+    }
+
+    #[test]
+    fn linear_history_uses_a_single_lane() {
+        let mut graph = GraphBuilder::new();
+        assert_eq!(graph.row(oid(3), &[oid(2)]), "*");
+        assert_eq!(graph.row(oid(2), &[oid(1)]), "*");
+        assert_eq!(graph.row(oid(1), &[]), "*");
+    }
+
+    #[test]
+    fn merged_side_branch_gets_its_own_lane() {
+        let (merge, mainline, side_2, side_1, base) = (oid(5), oid(4), oid(3), oid(2), oid(1));
+        let mut graph = GraphBuilder::new();
+        assert_eq!(graph.row(merge, &[mainline, side_2]), "*");
+        assert_eq!(graph.row(side_2, &[side_1]), "| *");
+        assert_eq!(graph.row(mainline, &[base]), "* |");
+        assert_eq!(graph.row(side_1, &[base]), "| *");
+        assert_eq!(graph.row(base, &[]), "* /");
+        assert!(graph.lanes.is_empty());
+    }
+
+    #[test]
+    fn side_branch_commits_have_a_distance() {
+        let (merge, mainline, side_2, side_1, base) = (oid(5), oid(4), oid(3), oid(2), oid(1));
+        let mut distances = FirstParentDistances::new();
+        assert_eq!(distances.visit(merge, &[mainline, side_2]), 0);
+        assert_eq!(distances.visit(side_2, &[side_1]), 1);
+        assert_eq!(distances.visit(mainline, &[base]), 0);
+        assert_eq!(distances.visit(side_1, &[base]), 1);
+        assert_eq!(distances.visit(base, &[]), 0);
+    }
+}
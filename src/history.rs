@@ -0,0 +1,115 @@
+use crate::config::APP_INFO;
+use app_dirs::{app_root, AppDataType};
+use std::fs;
+use std::path::PathBuf;
+
+/// Maximum number of entries kept in a history
+const HISTORY_LIMIT: usize = 100;
+
+/// History of the entries submitted in an input field, browsed like
+/// the history of a shell. Persisted in oper's data folder.
+pub struct InputHistory {
+    entries: Vec<String>,
+    /// index of the entry currently shown, `entries.len()` while
+    /// not browsing the history
+    position: usize,
+    /// the input before browsing the history
+    draft: String,
+    file: Option<PathBuf>,
+}
+
+impl InputHistory {
+    pub fn new(entries: Vec<String>, file: Option<PathBuf>) -> Self {
+        InputHistory {
+            position: entries.len(),
+            entries,
+            draft: String::new(),
+            file,
+        }
+    }
+
+    /// loads the history with the given name, an empty history
+    /// is returned in case it cannot be read
+    pub fn load(name: &str) -> Self {
+        let file = app_root(AppDataType::UserData, &APP_INFO)
+            .ok()
+            .map(|folder| folder.join(format!("{}.history", name)));
+        let entries = file
+            .as_ref()
+            .and_then(|file| fs::read_to_string(file).ok())
+            .map(|content| content.lines().map(String::from).collect())
+            .unwrap_or_default();
+        InputHistory::new(entries, file)
+    }
+
+    /// returns the entry before the one currently shown, `current` is
+    /// the input to come back to once browsing forward again
+    pub fn previous(&mut self, current: &str) -> Option<&str> {
+        if self.position == 0 {
+            return None;
+        }
+        if self.position == self.entries.len() {
+            self.draft = current.to_string();
+        }
+        self.position -= 1;
+        Some(&self.entries[self.position])
+    }
+
+    /// returns the entry after the one currently shown
+    pub fn next(&mut self) -> Option<&str> {
+        if self.position >= self.entries.len() {
+            return None;
+        }
+        self.position += 1;
+        match self.entries.get(self.position) {
+            Some(entry) => Some(entry),
+            None => Some(&self.draft),
+        }
+    }
+
+    /// appends the given entry and stops browsing, an older identical
+    /// entry is removed
+    pub fn push(&mut self, entry: &str) {
+        if !entry.trim().is_empty() {
+            self.entries.retain(|e| e != entry);
+            self.entries.push(entry.to_string());
+            let excess = self.entries.len().saturating_sub(HISTORY_LIMIT);
+            self.entries.drain(..excess);
+            self.save();
+        }
+        self.position = self.entries.len();
+    }
+
+    fn save(&self) {
+        if let Some(file) = &self.file {
+            // losing the history is not worth bothering the user
+            let _ = fs::write(file, self.entries.join("\n"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn browses_entries_and_returns_to_draft() {
+        let mut history = InputHistory::new(vec!["a".into(), "b".into()], None);
+        assert_eq!(history.next(), None);
+        assert_eq!(history.previous("draft"), Some("b"));
+        assert_eq!(history.previous("b"), Some("a"));
+        assert_eq!(history.previous("a"), None);
+        assert_eq!(history.next(), Some("b"));
+        assert_eq!(history.next(), Some("draft"));
+        assert_eq!(history.next(), None);
+    }
+
+    #[test]
+    fn push_moves_duplicates_to_the_end() {
+        let mut history = InputHistory::new(vec!["a".into(), "b".into()], None);
+        history.push("a");
+        history.push(" ");
+        assert_eq!(history.entries, vec!["b", "a"]);
+        assert_eq!(history.previous(""), Some("a"));
+    }
+}
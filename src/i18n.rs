@@ -0,0 +1,286 @@
+use crate::config::Language;
+use chrono::Duration;
+use std::sync::OnceLock;
+
+/// Language of the UI, set once from the config file
+static LANGUAGE: OnceLock<Language> = OnceLock::new();
+
+/// German texts of the UI and of reports by their English original, which
+/// is shown for texts missing here. Placeholders like `{commits}` are kept
+/// as they are. Terms used in German as well are listed nevertheless, to
+/// tell them from missing translations.
+const GERMAN: &[(&str, &str)] = &[
+    ("CommitDate", "Commit-Datum"),
+    ("AuthorDate", "Autor-Datum"),
+    ("Landed", "Eingetroffen"),
+    ("Git Repo", "Git-Repo"),
+    ("Committer", "Committer"),
+    ("Summary", "Zusammenfassung"),
+    ("Organization", "Organisation"),
+    ("Parents", "Eltern"),
+    ("Lane", "Spur"),
+    ("Lint", "Regelverstöße"),
+    ("Team", "Team"),
+    ("Workspace", "Arbeitsbereich"),
+    ("PR", "PR"),
+    (
+        "Commit {index} of {total}{filtered} - {repo}",
+        "Commit {index} von {total}{filtered} - {repo}",
+    ),
+    (" (filtered from {commits})", " (gefiltert aus {commits})"),
+    (
+        "Found {commits} commits across {repos} repositories",
+        "{commits} Commits in {repos} Repositories gefunden",
+    ),
+    (
+        "Found {commits} commits across {repos} repositories - {missing} parent commits not found locally (shallow git clone?)",
+        "{commits} Commits in {repos} Repositories gefunden - {missing} Eltern-Commits lokal nicht gefunden (flacher git-Klon?)",
+    ),
+    ("runs folded", "Serien eingeklappt"),
+    ("grouped by week", "nach Woche gruppiert"),
+    ("grouped by month", "nach Monat gruppiert"),
+    ("1 repo hidden by filter", "1 Repo durch Filter ausgeblendet"),
+    (
+        "{repos} repos hidden by filter",
+        "{repos} Repos durch Filter ausgeblendet",
+    ),
+    ("commit", "Commit"),
+    ("commits", "Commits"),
+    ("build {build} cut here", "Build {build} hier abgezweigt"),
+    (
+        "▸ {label} · {count} {commits} collapsed, press e to expand",
+        "▸ {label} · {count} {commits} eingeklappt, e klappt sie aus",
+    ),
+    (
+        "… {count} {commits} of {repo} hidden by the filter, press e to show …",
+        "… {count} {commits} von {repo} durch den Filter ausgeblendet, e zeigt sie …",
+    ),
+    (
+        "[{count} commits collapsed] {summary}",
+        "[{count} Commits eingeklappt] {summary}",
+    ),
+    (
+        "[{count} commits by {author}] {summary}",
+        "[{count} Commits von {author}] {summary}",
+    ),
+    ("Cannot close tab", "Tab kann nicht geschlossen werden"),
+    ("it is the last one", "es ist der letzte"),
+    ("all", "alle"),
+    ("No commits match the filter", "Keine Commits passen zum Filter"),
+    ("Nothing to undo", "Nichts rückgängig zu machen"),
+    ("Nothing to redo", "Nichts wiederherzustellen"),
+    ("Invalid filter", "Ungültiger Filter"),
+    (
+        "No commits hidden below the selected commit",
+        "Keine Commits unter dem ausgewählten Commit ausgeblendet",
+    ),
+    (
+        "Showing {hash} - {summary}, which isn't listed",
+        "{hash} - {summary} wird angezeigt, ist aber nicht gelistet",
+    ),
+    (
+        "Commit {hash} not found locally",
+        "Commit {hash} lokal nicht gefunden",
+    ),
+    ("Commit {hash} not found", "Commit {hash} nicht gefunden"),
+    (
+        "Cannot change the day window",
+        "Das Tagesfenster kann nicht geändert werden",
+    ),
+    ("Cannot refresh", "Aktualisieren nicht möglich"),
+    ("scan in progress", "Suche läuft"),
+    ("comparing refs", "Refs werden verglichen"),
+    ("Failed to scan", "Suche fehlgeschlagen"),
+    ("Failed to refresh", "Aktualisieren fehlgeschlagen"),
+    ("Find commit", "Commit suchen"),
+    ("Export", "Exportieren"),
+    ("Cancel", "Abbrechen"),
+    (
+        "No further commit of this repository",
+        "Kein weiterer Commit dieses Repositorys",
+    ),
+    (
+        "No further commit of this author",
+        "Kein weiterer Commit dieses Autors",
+    ),
+    (
+        "Copied the diff to the clipboard",
+        "Diff in die Zwischenablage kopiert",
+    ),
+    ("Failed to copy the diff", "Diff konnte nicht kopiert werden"),
+    ("Failed to copy the command", "Befehl konnte nicht kopiert werden"),
+    ("Copied {text}", "{text} kopiert"),
+    ("Failed to copy {text}", "{text} konnte nicht kopiert werden"),
+    ("in the future", "in der Zukunft"),
+    ("just now", "gerade eben"),
+    // reports
+    ("Commit Report", "Commit-Bericht"),
+    ("Commit Date", "Commit-Datum"),
+    ("Local Path of Repo", "Lokaler Pfad des Repos"),
+    ("Commit Author", "Commit-Autor"),
+    ("Message", "Nachricht"),
+    ("Commit URL", "Commit-URL"),
+    ("Pull Request URL", "Pull-Request-URL"),
+    ("Issues", "Tickets"),
+    ("Author Date", "Autor-Datum"),
+    ("Landed Locally", "Lokal eingetroffen"),
+    ("Lint Violations", "Regelverstöße"),
+    ("Date", "Datum"),
+    ("Commit", "Commit"),
+    ("Author", "Autor"),
+    ("Repository", "Repository"),
+    ("Churn", "Änderungen"),
+    ("Directory", "Verzeichnis"),
+    ("Lines Changed", "Geänderte Zeilen"),
+    ("Added", "Hinzugefügt"),
+    ("Deleted", "Gelöscht"),
+    ("Commits", "Commits"),
+    ("Coupling", "Kopplung"),
+    ("Changes", "Änderungen"),
+    ("Coupling (%)", "Kopplung (%)"),
+    (
+        "{commits} commits of {repos} repositories",
+        "{commits} Commits aus {repos} Repositories",
+    ),
+    (" from {first} to {last}", " vom {first} bis {last}"),
+    (", generated {date}", ", erstellt {date}"),
+];
+
+/// sets the language of the UI and of reports, only the first call takes
+/// effect
+pub fn set_language(language: Language) {
+    let _ = LANGUAGE.set(language);
+}
+
+/// the given text of the UI or of a report in its language
+pub fn tr(text: &'static str) -> &'static str {
+    tr_in(LANGUAGE.get().copied().unwrap_or_default(), text)
+}
+
+fn tr_in(language: Language, text: &'static str) -> &'static str {
+    match language {
+        Language::En => text,
+        Language::De => GERMAN
+            .iter()
+            .find(|(english, _)| *english == text)
+            .map_or(text, |(_, german)| german),
+    }
+}
+
+/// the given age of a commit in words, e.g. `3 days ago`
+pub fn relative_date(age: Duration) -> String {
+    relative_date_in(LANGUAGE.get().copied().unwrap_or_default(), age)
+}
+
+fn relative_date_in(language: Language, age: Duration) -> String {
+    let (count, unit) = match age {
+        age if age < Duration::zero() => return tr_in(language, "in the future").to_string(),
+        age if age.num_minutes() < 1 => return tr_in(language, "just now").to_string(),
+        age if age.num_hours() < 1 => (age.num_minutes(), Unit::Minute),
+        age if age.num_days() < 1 => (age.num_hours(), Unit::Hour),
+        age if age.num_days() < 14 => (age.num_days(), Unit::Day),
+        age if age.num_days() < 60 => (age.num_weeks(), Unit::Week),
+        age if age.num_days() < 365 => (age.num_days() / 30, Unit::Month),
+        age => (age.num_days() / 365, Unit::Year),
+    };
+    let (singular, plural) = unit.names(language);
+    let unit = if count == 1 { singular } else { plural };
+    match language {
+        Language::En => format!("{} {} ago", count, unit),
+        Language::De => format!("vor {} {}", count, unit),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Unit {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Unit {
+    /// singular and plural, the latter in the dative case for German
+    /// (e.g. `vor 3 Tagen`)
+    fn names(self, language: Language) -> (&'static str, &'static str) {
+        match (language, self) {
+            (Language::En, Unit::Minute) => ("minute", "minutes"),
+            (Language::En, Unit::Hour) => ("hour", "hours"),
+            (Language::En, Unit::Day) => ("day", "days"),
+            (Language::En, Unit::Week) => ("week", "weeks"),
+            (Language::En, Unit::Month) => ("month", "months"),
+            (Language::En, Unit::Year) => ("year", "years"),
+            (Language::De, Unit::Minute) => ("Minute", "Minuten"),
+            (Language::De, Unit::Hour) => ("Stunde", "Stunden"),
+            (Language::De, Unit::Day) => ("Tag", "Tagen"),
+            (Language::De, Unit::Week) => ("Woche", "Wochen"),
+            (Language::De, Unit::Month) => ("Monat", "Monaten"),
+            (Language::De, Unit::Year) => ("Jahr", "Jahren"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    #[test]
+    fn translates_texts_and_relative_dates() {
+        assert_eq!(tr_in(Language::De, "Summary"), "Zusammenfassung");
+        assert_eq!(tr_in(Language::De, "not translated"), "not translated");
+        assert_eq!(tr_in(Language::En, "Summary"), "Summary");
+        // translations keep the placeholders of the original
+        let placeholders = |text: &str| -> Vec<String> {
+            let mut names: Vec<String> = Regex::new(r"\{\w+\}")
+                .unwrap()
+                .find_iter(text)
+                .map(|name| name.as_str().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+        for (english, german) in GERMAN.iter() {
+            assert_eq!(placeholders(english), placeholders(german), "{}", german);
+        }
+
+        assert_eq!(
+            relative_date_in(Language::En, Duration::seconds(30)),
+            "just now"
+        );
+        assert_eq!(
+            relative_date_in(Language::En, Duration::minutes(1)),
+            "1 minute ago"
+        );
+        assert_eq!(
+            relative_date_in(Language::En, Duration::hours(5)),
+            "5 hours ago"
+        );
+        assert_eq!(
+            relative_date_in(Language::De, Duration::days(1)),
+            "vor 1 Tag"
+        );
+        assert_eq!(
+            relative_date_in(Language::De, Duration::days(3)),
+            "vor 3 Tagen"
+        );
+        assert_eq!(
+            relative_date_in(Language::De, Duration::days(21)),
+            "vor 3 Wochen"
+        );
+        assert_eq!(
+            relative_date_in(Language::En, Duration::days(90)),
+            "3 months ago"
+        );
+        assert_eq!(
+            relative_date_in(Language::De, Duration::days(800)),
+            "vor 2 Jahren"
+        );
+        assert_eq!(
+            relative_date_in(Language::De, Duration::hours(-2)),
+            "in der Zukunft"
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use crate::config::{Config, Issues};
+use crate::credentials::{self, CredentialStore};
+use crate::model::RepoCommit;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+/// Seconds to wait for an answer of the JIRA server
+const QUERY_TIMEOUT: &str = "10";
+
+/// Issues queried from JIRA at once
+const BATCH_SIZE: usize = 50;
+
+/// An issue mentioned in a commit message, its summary and status are
+/// known if they have been queried from JIRA
+#[derive(Clone, Debug, PartialEq)]
+pub struct Issue {
+    pub key: String,
+    pub summary: Option<String>,
+    pub status: Option<String>,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.key)?;
+        if let Some(status) = &self.status {
+            write!(f, " [{}]", status)?;
+        }
+        if let Some(summary) = &self.summary {
+            write!(f, " {}", summary)?;
+        }
+        Ok(())
+    }
+}
+
+/// Extracts the IDs of issues (e.g. `PROJ-123`) from commit messages and,
+/// if a JIRA server is configured, queries their summary and status.
+/// Queried issues are cached for the lifetime of the tracker.
+pub struct IssueTracker {
+    settings: Option<Issues>,
+    credentials: CredentialStore,
+    issues: Mutex<HashMap<String, Result<Issue, String>>>,
+}
+
+impl IssueTracker {
+    pub fn new(config: &Config) -> Self {
+        IssueTracker {
+            settings: config.issues.clone(),
+            credentials: CredentialStore::new(config.credentials.clone()),
+            issues: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// the IDs of the issues mentioned in the message of the given
+    /// commit, without duplicates; empty if no `[issues]` are configured
+    pub fn keys(&self, commit: &RepoCommit) -> Vec<String> {
+        let settings = match &self.settings {
+            Some(settings) => settings,
+            None => return Vec::new(),
+        };
+        let mut keys: Vec<String> = Vec::new();
+        for key in settings.pattern.find_iter(&commit.message) {
+            if !keys.iter().any(|k| k == key) {
+                keys.push(key.to_string());
+            }
+        }
+        keys
+    }
+
+    /// the issues mentioned in the message of the given commit by their
+    /// IDs, queried from JIRA if configured
+    pub fn issues(&self, commit: &RepoCommit) -> Vec<(String, Result<Issue, String>)> {
+        self.keys(commit)
+            .into_iter()
+            .map(|key| (key.clone(), self.issue(key)))
+            .collect()
+    }
+
+    /// the URL of the issue with the given ID on the JIRA server
+    pub fn url(&self, key: &str) -> Option<String> {
+        let jira_url = self.settings.as_ref()?.jira_url.as_ref()?;
+        Some(format!("{}/browse/{}", jira_url.trim_end_matches('/'), key))
+    }
+
+    /// queries the issues mentioned in the given commits which aren't
+    /// cached yet, in batches instead of one request per issue
+    pub fn prefetch(&self, commits: &[RepoCommit]) {
+        let jira_url = match self.settings.as_ref().and_then(|s| s.jira_url.as_ref()) {
+            Some(jira_url) => jira_url,
+            None => return,
+        };
+        let mut keys: Vec<String> = {
+            let cached = self.issues.lock().unwrap();
+            commits
+                .iter()
+                .flat_map(|commit| self.keys(commit))
+                .filter(|key| !cached.contains_key(key))
+                .collect()
+        };
+        keys.sort();
+        keys.dedup();
+        if keys.is_empty() {
+            return;
+        }
+        let secret = self.secret();
+        for batch in keys.chunks(BATCH_SIZE) {
+            let found = secret
+                .clone()
+                .and_then(|secret| search(jira_url, batch, secret.as_deref()));
+            let mut issues = self.issues.lock().unwrap();
+            for key in batch {
+                let issue = match &found {
+                    Ok(found) => found
+                        .get(key)
+                        .cloned()
+                        .ok_or_else(|| format!("{} not found", key)),
+                    Err(error) => Err(error.clone()),
+                };
+                issues.insert(key.clone(), issue);
+            }
+        }
+    }
+
+    fn issue(&self, key: String) -> Result<Issue, String> {
+        let settings = self.settings.as_ref().unwrap();
+        let jira_url = match &settings.jira_url {
+            Some(jira_url) => jira_url,
+            None => {
+                return Ok(Issue {
+                    key,
+                    summary: None,
+                    status: None,
+                })
+            }
+        };
+        if let Some(issue) = self.issues.lock().unwrap().get(&key) {
+            return issue.clone();
+        }
+        let issue = self
+            .secret()
+            .and_then(|secret| query(jira_url, &key, secret.as_deref()));
+        self.issues.lock().unwrap().insert(key, issue.clone());
+        issue
+    }
+
+    fn secret(&self) -> Result<Option<String>, String> {
+        match self.settings.as_ref().and_then(|s| s.credentials.as_ref()) {
+            Some(name) => self.credentials.secret(name).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// asks the JIRA server for the summary and status of the issue with the
+/// given ID
+fn query(jira_url: &str, key: &str, secret: Option<&str>) -> Result<Issue, String> {
+    let url = format!(
+        "{}/rest/api/2/issue/{}?fields=summary,status",
+        jira_url.trim_end_matches('/'),
+        percent_encode(key)
+    );
+    let json = get(&[&url], secret).map_err(|e| format!("Failed to query {}: {}", key, e))?;
+    parse_issue(key, &json)
+}
+
+/// the given path segment with everything but unreserved characters
+/// percent-encoded, see RFC 3986
+fn percent_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// asks the JIRA server for the summary and status of the issues with the
+/// given IDs at once; IDs unknown to the server are missing in the result
+fn search(
+    jira_url: &str,
+    keys: &[String],
+    secret: Option<&str>,
+) -> Result<HashMap<String, Issue>, String> {
+    let url = format!("{}/rest/api/2/search", jira_url.trim_end_matches('/'));
+    let keys: Vec<String> = keys
+        .iter()
+        .map(|key| format!("\"{}\"", key.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect();
+    let jql = format!("jql=key in ({})", keys.join(","));
+    let max_results = format!("maxResults={}", keys.len());
+    let json = get(
+        &[
+            "--get",
+            "--data-urlencode",
+            &jql,
+            "--data",
+            "fields=summary,status",
+            "--data",
+            // unknown IDs are skipped instead of failing the whole query
+            "validateQuery=warn",
+            "--data",
+            &max_results,
+            &url,
+        ],
+        secret,
+    )
+    .map_err(|e| format!("Failed to query the issues: {}", e))?;
+    parse_search(&json)
+}
+
+/// runs curl with the given arguments and returns what it has received;
+/// secrets of the form `user:token` are sent for basic authentication
+/// (JIRA Cloud), others as bearer token
+fn get(args: &[&str], secret: Option<&str>) -> Result<String, String> {
+    let secret_options = match secret {
+        Some(secret) if secret.contains(':') => vec![("user", secret.to_string())],
+        Some(token) => vec![("header", format!("Authorization: Bearer {}", token))],
+        None => Vec::new(),
+    };
+    let mut curl_args = vec!["--silent", "--fail", "--max-time", QUERY_TIMEOUT];
+    curl_args.extend_from_slice(args);
+    let output = credentials::curl(&curl_args, &secret_options)
+        .map_err(|e| format!("Failed to run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("curl {}", output.status));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// takes the summary and status from an issue as returned by the
+/// REST API of JIRA
+fn parse_issue(key: &str, json: &str) -> Result<Issue, String> {
+    let issue: Value = serde_json::from_str(json)
+        .map_err(|e| format!("Failed to parse the answer for {}: {}", key, e))?;
+    Ok(issue_of(key, &issue))
+}
+
+/// takes the issues from the result of a search of the REST API of JIRA
+fn parse_search(json: &str) -> Result<HashMap<String, Issue>, String> {
+    let result: Value =
+        serde_json::from_str(json).map_err(|e| format!("Failed to parse the issues: {}", e))?;
+    Ok(result["issues"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|issue| {
+            let key = issue["key"].as_str()?;
+            Some((key.to_string(), issue_of(key, issue)))
+        })
+        .collect())
+}
+
+fn issue_of(key: &str, issue: &Value) -> Issue {
+    let fields = &issue["fields"];
+    Issue {
+        key: key.to_string(),
+        summary: fields["summary"].as_str().map(String::from),
+        status: fields["status"]["name"].as_str().map(String::from),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_issues() {
+        let json = r#"{"key":"OS-12","fields":{"status":{"self":"x","name":"In Progress",
+            "statusCategory":{"name":"To Do"}},"summary":"Crash in \"init\" ä"}}"#;
+        assert_eq!(
+            parse_issue("OS-12", json),
+            Ok(Issue {
+                key: String::from("OS-12"),
+                summary: Some(String::from("Crash in \"init\" ä")),
+                status: Some(String::from("In Progress")),
+            })
+        );
+        let json = r#"{"fields":{"summary":"Smile \ud83d\ude00"}}"#;
+        assert_eq!(
+            parse_issue("OS-13", json).unwrap().summary.as_deref(),
+            Some("Smile \u{1F600}")
+        );
+        assert!(parse_issue("OS-14", "<html>").is_err());
+    }
+
+    #[test]
+    fn percent_encodes_keys() {
+        assert_eq!(percent_encode("OS-12"), "OS-12");
+        assert_eq!(
+            percent_encode("../a?b#c d/ä"),
+            "..%2Fa%3Fb%23c%20d%2F%C3%A4"
+        );
+    }
+
+    #[test]
+    fn parses_search_results() {
+        let json = r#"{"startAt":0,"total":2,"issues":[
+            {"key":"OS-1","fields":{"summary":"One","status":{"name":"Done"}}},
+            {"key":"OS-2","fields":{"summary":"Two","status":{"name":"Open"}}}]}"#;
+        let issues = parse_search(json).unwrap();
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues["OS-2"].summary.as_deref(), Some("Two"));
+        assert_eq!(issues["OS-1"].status.as_deref(), Some("Done"));
+    }
+}
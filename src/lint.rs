@@ -0,0 +1,225 @@
+use crate::config::Config;
+use crate::model::RepoCommit;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A commit message breaking one of the rules of the `[lint]` section
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum Violation {
+    LongSubject { max: usize },
+    NotImperative { word: String },
+    MissingTrailer { key: String },
+    ForbiddenWord { word: String },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::LongSubject { max } => write!(f, "subject longer than {}", max),
+            Violation::NotImperative { word } => write!(f, "not imperative: {}", word),
+            Violation::MissingTrailer { key } => write!(f, "missing {}", key),
+            Violation::ForbiddenWord { word } => write!(f, "forbidden: {}", word),
+        }
+    }
+}
+
+/// Checks commit messages against the rules of the `[lint]` section of
+/// the config file, no rules apply if it's missing
+pub struct Linter {
+    max_subject_length: Option<usize>,
+    /// lower case
+    non_imperative: Vec<String>,
+    required_trailers: Vec<String>,
+    forbidden_words: Vec<String>,
+}
+
+impl Linter {
+    pub fn new(config: &Config) -> Linter {
+        let lint = config.lint.clone().unwrap_or_default();
+        Linter {
+            max_subject_length: lint.max_subject_length,
+            non_imperative: lint
+                .non_imperative
+                .iter()
+                .map(|word| word.to_lowercase())
+                .collect(),
+            required_trailers: lint.required_trailers,
+            forbidden_words: lint.forbidden_words,
+        }
+    }
+
+    /// true if there is any rule to check
+    pub fn is_active(&self) -> bool {
+        self.max_subject_length.is_some()
+            || !self.non_imperative.is_empty()
+            || !self.required_trailers.is_empty()
+            || !self.forbidden_words.is_empty()
+    }
+
+    /// the rules broken by the message of the given commit, none for
+    /// stash entries and uncommitted changes
+    pub fn check(&self, commit: &RepoCommit) -> Vec<Violation> {
+        if commit.wip.is_some() {
+            return Vec::new();
+        }
+        self.check_message(&commit.summary, &commit.message)
+    }
+
+    fn check_message(&self, summary: &str, message: &str) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        if let Some(max) = self.max_subject_length {
+            if summary.chars().count() > max {
+                violations.push(Violation::LongSubject { max });
+            }
+        }
+        // e.g. "Fixed" of "net: Fixed timeout", prefixes end with a colon
+        let first_word = summary
+            .split_whitespace()
+            .find(|word| !word.ends_with(':'))
+            .unwrap_or_default();
+        let first_word = first_word.trim_matches(|c: char| !c.is_alphanumeric());
+        if self.non_imperative.contains(&first_word.to_lowercase()) {
+            violations.push(Violation::NotImperative {
+                word: first_word.to_string(),
+            });
+        }
+        let trailers = trailers_of(message);
+        for key in &self.required_trailers {
+            if !trailers
+                .iter()
+                .any(|trailer| trailer.eq_ignore_ascii_case(key))
+            {
+                violations.push(Violation::MissingTrailer { key: key.clone() });
+            }
+        }
+        for word in &self.forbidden_words {
+            if contains_word(message, word) {
+                violations.push(Violation::ForbiddenWord { word: word.clone() });
+            }
+        }
+        violations
+    }
+}
+
+/// the keys of the trailers of a commit message, e.g. `Signed-off-by`,
+/// given by the lines of its last paragraph
+fn trailers_of(message: &str) -> Vec<&str> {
+    let paragraphs: Vec<&str> = message
+        .trim_end()
+        .split("\n\n")
+        .filter(|paragraph| !paragraph.trim().is_empty())
+        .collect();
+    if paragraphs.len() < 2 {
+        // the subject isn't followed by trailers
+        return Vec::new();
+    }
+    paragraphs[paragraphs.len() - 1]
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, _)| key.trim())
+        .filter(|key| !key.is_empty() && !key.contains(char::is_whitespace))
+        .collect()
+}
+
+/// true if the text contains the word (case insensitive), not just as
+/// part of a longer word
+fn contains_word(text: &str, word: &str) -> bool {
+    let text = text.to_lowercase();
+    let word = word.to_lowercase();
+    let is_word_char = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+    text.match_indices(&word).any(|(start, _)| {
+        !is_word_char(text[..start].chars().next_back())
+            && !is_word_char(text[start + word.len()..].chars().next())
+    })
+}
+
+/// Counts the commits violating the rules, e.g. for the summary of a
+/// report
+#[derive(Default)]
+pub struct Summary {
+    commits: usize,
+    violating_commits: usize,
+    violations: HashMap<Violation, usize>,
+}
+
+impl Summary {
+    pub fn add(&mut self, linter: &Linter, commits: &[RepoCommit]) {
+        for commit in commits {
+            let violations = linter.check(commit);
+            self.commits += 1;
+            if !violations.is_empty() {
+                self.violating_commits += 1;
+            }
+            for violation in violations {
+                *self.violations.entry(violation).or_default() += 1;
+            }
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} commits violate the lint rules",
+            self.violating_commits, self.commits
+        )?;
+        let mut violations: Vec<(String, usize)> = self
+            .violations
+            .iter()
+            .map(|(violation, count)| (violation.to_string(), *count))
+            .collect();
+        // the most frequent first
+        violations.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        for (violation, count) in violations {
+            write!(f, "\n  {:>5}  {}", count, violation)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linter() -> Linter {
+        Linter {
+            max_subject_length: Some(20),
+            non_imperative: vec![String::from("added"), String::from("fixes")],
+            required_trailers: vec![String::from("Signed-off-by")],
+            forbidden_words: vec![String::from("WIP")],
+        }
+    }
+
+    #[test]
+    fn checks_messages() {
+        let message = "net: Add timeout\n\nSigned-off-by: Alice <alice@example.com>\n";
+        assert_eq!(linter().check_message("net: Add timeout", message), vec![]);
+
+        let message = "net: Added timeouts (wip)\n\nSee the WIPE protocol.\n";
+        assert_eq!(
+            linter().check_message("net: Added timeouts (wip)", message),
+            vec![
+                Violation::LongSubject { max: 20 },
+                Violation::NotImperative {
+                    word: String::from("Added")
+                },
+                Violation::MissingTrailer {
+                    key: String::from("Signed-off-by")
+                },
+                Violation::ForbiddenWord {
+                    word: String::from("WIP")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_trailers() {
+        assert_eq!(
+            trailers_of("Fix\n\nBody: not a trailer\n\nChange-Id: I12\nSigned-off-by: A\n"),
+            vec!["Change-Id", "Signed-off-by"]
+        );
+        assert!(trailers_of("Signed-off-by: A").is_empty());
+    }
+}
@@ -0,0 +1,31 @@
+use crate::model::RepoCommit;
+use console::style;
+use std::io::{self, Write};
+
+/// Prints the given commits one per line, like `git log --oneline` across
+/// all repositories: the short commit ID, the commit date, the repository,
+/// the summary and the author. Colors are used on terminals only, so the
+/// output can be piped into other tools.
+pub fn print(commits: &[RepoCommit], mut out: impl Write) -> io::Result<()> {
+    for commit in commits {
+        let summary = match commit.wip {
+            Some(_) => style(commit.summary.as_str()).magenta(),
+            None => style(commit.summary.as_str()),
+        };
+        let result = writeln!(
+            out,
+            "{} {} {} {} {}",
+            style(&commit.commit_id.to_string()[..8]).yellow(),
+            style(commit.time_as_str()).dim(),
+            style(commit.repo.name()).blue().bold(),
+            summary,
+            style(format!("<{}>", commit.author_name)).green()
+        );
+        match result {
+            // e.g. piped into `head`
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            result => result?,
+        }
+    }
+    Ok(())
+}
@@ -9,28 +9,85 @@ extern crate lazy_static;
 extern crate serde;
 extern crate toml;
 
+mod alerts;
+mod bisect;
+mod builds;
+mod checkpoint;
+mod churn;
+mod commit_url;
 mod config;
+mod coupling;
+mod crash;
+mod credentials;
+mod diff_cache;
+mod doctor;
+mod filter;
+mod fingerprints;
+mod first_seen;
+mod fuzzy;
+mod gclient;
+mod graph;
+mod history;
+mod i18n;
+mod issues;
+mod lint;
+mod log;
+mod manifest;
 mod model;
+mod organizations;
+mod owners;
+mod patch;
+mod plugin_columns;
+mod pseudonyms;
+mod pull_request;
+mod remote;
+mod repo_list;
 mod report;
+mod rewrites;
+mod script;
+mod self_update;
+mod stats;
 mod styles;
+mod tsrc;
 mod ui;
+mod undo;
 mod utils;
+mod verify;
 mod views;
+mod west;
+mod workspace;
 
-use anyhow::Result;
-use clap::{App, Arg};
+use anyhow::{anyhow, Result};
+use checkpoint::Checkpoint;
+use chrono::{Local, NaiveDate, TimeZone};
+use clap::{App, Arg, SubCommand};
+use commit_url::CommitUrlResolver;
+use fingerprints::Fingerprints;
+use first_seen::FirstSeen;
+use indicatif::ProgressDrawTarget;
+use issues::IssueTracker;
+use lint::Linter;
 use model::{MultiRepoHistory, Repo, RevWalkStrategy};
+use owners::Owners;
+use rayon::prelude::*;
+use report::ReportMode;
+use script::Script;
+use std::collections::HashMap;
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io;
 use std::io::{BufRead, BufReader};
-use std::path::Path;
-use std::sync::Arc;
-use utils::{find_project_file, find_repo_base_folder};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use utils::{find_git_repos, find_project_file};
+use workspace::WorkspaceType;
 
 const MAX_NUMBER_OF_THREADS: usize = 18; //tests on a 36 core INTEL Xeon showed that parsing becomes slower again if more than 18 threads are used
 
 fn main() -> Result<(), String> {
+    crash::install_hook();
     let original_cwd = env::current_dir().expect("cwd not found");
     let matches = App::new("oper")
         .version(crate_version!())
@@ -38,6 +95,7 @@ fn main() -> Result<(), String> {
         .about("git-repo history tool")
         .arg(
             Arg::with_name("days")
+                .global(true)
                 .short("d")
                 .long("days")
                 .value_name("days")
@@ -47,6 +105,7 @@ fn main() -> Result<(), String> {
         )
         .arg(
             Arg::with_name("author")
+                .global(true)
                 .short("a")
                 .long("author")
                 .value_name("pattern")
@@ -57,14 +116,30 @@ fn main() -> Result<(), String> {
         )
         .arg(
             Arg::with_name("message")
+                .global(true)
                 .short("m")
                 .long("message")
                 .value_name("pattern")
                 .help("only include commits where message contains <pattern> (case insensitive)")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("regex")
+                .global(true)
+                .long("regex")
+                .help("interpret the patterns of --author and --message as regular expressions"),
+        )
+        .arg(
+            Arg::with_name("branch")
+                .global(true)
+                .long("branch")
+                .value_name("name")
+                .help("walk the history of branch <name> instead of HEAD, in repositories lacking it HEAD is walked")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("revwalk-strategy")
+                .global(true)
                 .short("r")
                 .long("revwalk")
                 .value_name("strategy")
@@ -74,100 +149,917 @@ fn main() -> Result<(), String> {
         )
         .arg(
             Arg::with_name("cwd")
+                .global(true)
                 .short("C")
                 .long("cwd")
                 .value_name("cwd")
-                .help("change working directory (mostly useful for testing)")
+                .help("change working directory (mostly useful for testing); given several times, the workspaces of the further directories are scanned as well")
                 .default_value(original_cwd.to_str().unwrap())
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("scan")
+                .global(true)
+                .long("scan")
+                .value_name("dir")
+                .help("scan the git repositories found in <dir> and its subfolders instead of the projects of a git-repo workspace")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("workspace-type")
+                .global(true)
+                .long("workspace-type")
+                .value_name("type")
+                .possible_values(&WorkspaceType::NAMES)
+                .help("reads the projects of a workspace of this type, detected by the folder of the tool (e.g. .repo) if not given")
                 .takes_value(true),
         )
         .arg(
             Arg::with_name("manifest")
+                .global(true)
                 .short("x")
                 .long("manifest")
                 .help("include changes to the manifest repository")
         )
+        .arg(
+            Arg::with_name("changed-only")
+                .global(true)
+                .long("changed-only")
+                .help("only scan repositories whose HEAD or refs moved since the last run")
+        )
+        .arg(
+            Arg::with_name("wip")
+                .global(true)
+                .long("wip")
+                .help("list the stash entries and the uncommitted changes of each repository along with the commits")
+        )
+        .arg(
+            Arg::with_name("touched-only")
+                .global(true)
+                .long("touched-only")
+                .help("skip repositories whose refs haven't been modified within the day window (judged by file modification times)")
+        )
         .arg(
             Arg::with_name("report")
+            .global(true)
             .long("report")
             .value_name("file")
-            .help("writes a report to a file given by <path> - supported formats: .csv, .ods, .xlsx")
+            .help("writes a report to a file given by <path> - supported formats: .csv, .csv.gz, .html, .md, .ods, .xlsx, .xml")
+            .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("report-mode")
+            .global(true)
+            .long("report-mode")
+            .value_name("mode")
+            .help("how to deal with an existing report file: 'overwrite', 'append' (.csv only) or 'fail-if-exists'")
+            .possible_values(&["overwrite", "append", "fail-if-exists"])
+            .default_value("overwrite")
+            .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("bisect")
+            .long("bisect")
+            .value_name("good..bad")
+            .help("bisects the history of the manifest repository between two revisions, asking whether the checked out workspace states are good or bad")
+            .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("select")
+            .global(true)
+            .long("select")
+            .value_name("hash")
+            .help("selects the commit whose hash starts with <hash> once the UI is shown, searching all repositories")
+            .validator(|hash| {
+                if hash.len() >= 4 && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                    Ok(())
+                } else {
+                    Err(String::from("expected at least 4 hexadecimal digits"))
+                }
+            })
+            .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("focus-repo")
+            .global(true)
+            .long("focus-repo")
+            .value_name("path")
+            .help("restricts the listed commits to the repository at <path> (absolute or relative to the workspace, may point into the repository) once the UI is shown")
+            .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("since")
+            .global(true)
+            .long("since")
+            .value_name("date")
+            .help("include commits from the start of the given day, month or year on (e.g. 2024-01-01 or 2024-01) instead of the last <n> days")
+            .validator(|date| match period_of(&date) {
+                Some(_) => Ok(()),
+                None => Err(String::from("expected a date like 2020-05-17, 2020-05 or 2020")),
+            })
+            .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("until")
+            .global(true)
+            .long("until")
+            .value_name("date")
+            .help("include commits up to the end of the given day, month or year (e.g. 2024-03-31 or 2024-03); without --since, of the <n> days before")
+            .validator(|date| match period_of(&date) {
+                Some(_) => Ok(()),
+                None => Err(String::from("expected a date like 2020-05-17, 2020-05 or 2020")),
+            })
+            .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("manifest-url")
+            .global(true)
+            .long("manifest-url")
+            .value_name("url")
+            .help("scans the workspace given by the manifest repository at <url> without a local checkout: the projects are fetched (shallow, reusing mirrors of previous runs) into oper's cache folder")
+            .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("manifest-branch")
+            .global(true)
+            .long("manifest-branch")
+            .value_name("branch")
+            .help("branch of the manifest repository given by --manifest-url, its default branch if not given")
+            .requires("manifest-url")
             .takes_value(true)
         )
+        .arg(
+            Arg::with_name("manifest-name")
+            .global(true)
+            .long("manifest-name")
+            .value_name("file")
+            .help("manifest file of the manifest repository given by --manifest-url [default: default.xml]")
+            .requires("manifest-url")
+            .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("focus-date")
+            .global(true)
+            .long("focus-date")
+            .value_name("date")
+            .help("restricts the listed commits to the given day, month or year (e.g. 2020-05-17 or 2020-05) once the UI is shown, extending the day window to reach it")
+            .validator(|date| match days_to_reach(&date) {
+                Some(_) => Ok(()),
+                None => Err(String::from("expected a date like 2020-05-17, 2020-05 or 2020")),
+            })
+            .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("ui")
+            .global(true)
+            .long("ui")
+            .requires("report")
+            .help("opens the UI after writing the report")
+        )
+        .arg(
+            Arg::with_name("no-tui")
+            .global(true)
+            .long("no-tui")
+            .conflicts_with_all(&["ui", "report"])
+            .help("prints the commits to the terminal like `git log --oneline` instead of opening the UI, same as the log subcommand")
+        )
+        .arg(
+            Arg::with_name("dry-run")
+            .global(true)
+            .long("dry-run")
+            .conflicts_with_all(&["ui", "report", "no-tui"])
+            .help("scans the repositories and prints the number of commits matched per repository, without opening the UI, e.g. to tune the filters")
+        )
+        .arg(
+            Arg::with_name("anonymize")
+            .global(true)
+            .long("anonymize")
+            .requires("report")
+            .help("replaces the names of authors by stable pseudonyms and strips email addresses in the report")
+        )
+        .arg(
+            Arg::with_name("resume")
+            .global(true)
+            .long("resume")
+            .requires("report")
+            .conflicts_with("ui")
+            .help("continues an interrupted run writing a .csv report, skipping the repositories already written")
+        )
+        .subcommand(
+            SubCommand::with_name("compare")
+            .about("shows the commits of all repositories which are in <refB> but not in <refA>, like `git log <refA>..<refB>` in each repository where both refs exist")
+            .arg(Arg::with_name("refA").required(true).help("e.g. a tag or branch of the previous release"))
+            .arg(Arg::with_name("refB").required(true).help("e.g. a tag or branch of the next release"))
+        )
+        .subcommand(
+            SubCommand::with_name("log")
+            .about("prints the commits to the terminal like `git log --oneline`, newest first, instead of opening the UI")
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+            .about("checks that each project is checked out at the revision pinned by the manifest, failing if any project drifted")
+        )
+        .subcommand(
+            SubCommand::with_name("doctor")
+            .about("checks the config file, the workspace, the external tools, the terminal and oper's data folder, telling how to fix the problems found")
+        )
+        .subcommand(
+            SubCommand::with_name("self-update")
+            .about("replaces oper by its latest release, if there is a newer one with a binary for this platform")
+            .arg(
+                Arg::with_name("check")
+                .long("check")
+                .help("tells whether there is a newer release, without installing it")
+            )
+        )
+        .subcommand(
+            SubCommand::with_name("list-repos")
+            .about("lists the projects of the workspace with their remote, branch, HEAD and the date of its commit")
+            .arg(
+                Arg::with_name("output")
+                .long("output")
+                .value_name("format")
+                .help("format of the list written to stdout")
+                .possible_values(&["csv", "json"])
+                .default_value("csv")
+                .takes_value(true)
+            )
+        )
         .get_matches();
 
-    let days = value_t!(matches.value_of("days"), u32).unwrap_or_else(|e| e.exit());
-    let classifier = model::Classifier::new(
+    // global args given after the subcommand are found in its matches only
+    let (subcommand, subcommand_matches) = matches.subcommand();
+    let matches = subcommand_matches.unwrap_or(&matches);
+    let mut days = value_t!(matches.value_of("days"), u32).unwrap_or_else(|e| e.exit());
+
+    if subcommand == "self-update" {
+        return self_update::run(matches.is_present("check")).map_err(|e| format!("{:#}", e));
+    }
+
+    let since = matches.value_of("since").and_then(period_of);
+    let until = matches.value_of("until").and_then(period_of);
+    match (since, until) {
+        (Some((start, _)), _) => days = days_back_to(start),
+        (None, Some((_, end))) => days += days_back_to(end),
+        (None, None) => {}
+    }
+
+    let mut focus = Vec::new();
+    if let Some(date) = matches.value_of("focus-date") {
+        focus.push(format!("date:{}", date));
+        days = days.max(days_to_reach(date).unwrap());
+    }
+    let startup = ui::Startup {
+        select: matches.value_of("select").map(String::from),
+        focus_repo: matches.value_of("focus-repo").map(String::from),
+        filter: (!focus.is_empty()).then(|| focus.join(" ")),
+    };
+
+    let mut classifier = model::Classifier::new(
         days,
         matches.value_of("author"),
         matches.value_of("message"),
+    )
+    .with_wip(matches.is_present("wip"))
+    .with_branch(matches.value_of("branch"))
+    .with_period(
+        since.map(|(start, _)| local_timestamp(start)),
+        until.map(|(_, end)| local_timestamp(end)),
     );
-    let cwd = Path::new(matches.value_of("cwd").unwrap());
+    if matches.is_present("regex") {
+        classifier = classifier
+            .with_regex()
+            .map_err(|e| format!("Invalid pattern: {}", e))?;
+    }
+    if subcommand == "compare" {
+        classifier = classifier.with_range(
+            matches.value_of("refA").unwrap(),
+            matches.value_of("refB").unwrap(),
+        );
+    }
+    let mut workspaces = matches.values_of("cwd").unwrap().map(Path::new);
+    let cwd = workspaces.next().unwrap();
+    // relative to the original working directory
+    let workspaces: Vec<PathBuf> = workspaces
+        .map(|folder| folder.canonicalize())
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let scan = matches
+        .value_of("scan")
+        .map(|folder| Path::new(folder).canonicalize())
+        .transpose()
+        .map_err(|e| format!("Failed to scan: {}", e))?;
+    let workspace_type = matches
+        .value_of("workspace-type")
+        .and_then(WorkspaceType::from_name);
     let revwalk_strategy = match matches.value_of("revwalk-strategy") {
         Some("first") => Ok(RevWalkStrategy::FirstParent),
         Some("all") => Ok(RevWalkStrategy::AllParents),
-        _ => Err(format!("Unknown revwalk strategy given")),
+        _ => Err("Unknown revwalk strategy given".to_string()),
     }?;
+    let report_mode = match matches.value_of("report-mode") {
+        Some("append") => ReportMode::Append,
+        Some("fail-if-exists") => ReportMode::FailIfExists,
+        _ => ReportMode::Overwrite,
+    };
 
+    env::set_current_dir(cwd).map_err(|e| e.to_string())?;
+
+    if let Some(url) = matches.value_of("manifest-url") {
+        let manifest = remote::RemoteManifest {
+            url: url.to_string(),
+            branch: matches.value_of("manifest-branch").map(String::from),
+            name: matches
+                .value_of("manifest-name")
+                .unwrap_or("default.xml")
+                .to_string(),
+        };
+        // the history of the day window is fetched
+        let since = Local::now().date_naive() - chrono::Duration::days(i64::from(days));
+        let workspace =
+            remote::prepare(&manifest, &since.to_string()).map_err(|e| format!("{:#}", e))?;
+        env::set_current_dir(workspace).map_err(|e| e.to_string())?;
+    }
+
+    if let Some(range) = matches.value_of("bisect") {
+        let config = config::read();
+        return bisect::run(range, config.bisect.as_ref()).map_err(|e| e.to_string());
+    }
+
+    if subcommand == "doctor" {
+        return doctor::run(workspace_type).map_err(|e| e.to_string());
+    }
+
+    if subcommand == "verify" {
+        return verify::run().map_err(|e| e.to_string());
+    }
+
+    if subcommand == "log" && (matches.is_present("report") || matches.is_present("dry-run")) {
+        return Err(String::from(
+            "The log subcommand prints the commits, it takes neither --report nor --dry-run",
+        ));
+    }
+
+    if subcommand == "list-repos" {
+        return list_repos(
+            matches.is_present("manifest"),
+            scan.as_deref(),
+            workspace_type,
+            matches.value_of("output").unwrap(),
+        )
+        .map_err(|e| e.to_string());
+    }
+
+    let selection = Selection {
+        manifest: matches.is_present("manifest"),
+        changed_only: matches.is_present("changed-only"),
+        touched_only: matches.is_present("touched-only"),
+        workspaces,
+        scan,
+        workspace_type,
+    };
     do_main(
         &classifier,
         &revwalk_strategy,
-        cwd,
-        matches.is_present("manifest"),
-        matches.value_of("report"),
+        &selection,
+        &ReportOptions {
+            file: matches.value_of("report"),
+            mode: report_mode,
+            anonymize: matches.is_present("anonymize"),
+            resume: matches.is_present("resume"),
+        },
+        matches.is_present("ui"),
+        match (subcommand, matches.is_present("no-tui")) {
+            _ if matches.is_present("dry-run") => Output::Statistics,
+            ("log", _) | (_, true) => Output::Log,
+            _ => Output::Ui,
+        },
+        startup,
     )
-    .or_else(|e| Err(e.to_string()))
+    .map_err(|e| e.to_string())
+}
+
+/// the repository at the given path, which is absolute or relative to the
+/// workspace and might point to a file or folder within the repository
+fn focused_repo<'a>(repos: &'a [Arc<Repo>], path: &str) -> Option<&'a Arc<Repo>> {
+    let path = Path::new(path);
+    let absolute = path.is_absolute();
+    let path = if absolute {
+        fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+    } else {
+        path.to_path_buf()
+    };
+    repos
+        .iter()
+        .filter(|repo| {
+            if absolute {
+                path.starts_with(&repo.abs_path)
+            } else {
+                path.starts_with(&repo.rel_path)
+            }
+        })
+        // the innermost one of nested repositories
+        .max_by_key(|repo| repo.abs_path.components().count())
+}
+
+/// the first day of the given day, month (e.g. `2020-05`) or year and the
+/// first day after it
+fn period_of(date: &str) -> Option<(NaiveDate, NaiveDate)> {
+    let (padded, months) = match date.len() {
+        4 => (format!("{}-01-01", date), 12),
+        7 => (format!("{}-01", date), 1),
+        _ => (date.to_string(), 0),
+    };
+    let start = NaiveDate::parse_from_str(&padded, "%Y-%m-%d").ok()?;
+    let end = match months {
+        0 => start.succ_opt()?,
+        months => start.checked_add_months(chrono::Months::new(months))?,
+    };
+    Some((start, end))
+}
+
+/// seconds since the epoch at the start of the given day in local time
+fn local_timestamp(day: NaiveDate) -> i64 {
+    Local
+        .from_local_datetime(&day.and_hms_opt(0, 0, 0).unwrap())
+        .earliest()
+        .map_or(0, |time| time.timestamp())
+}
+
+/// the size of the day window reaching back to the start of the given
+/// day, month (e.g. `2020-05`) or year
+fn days_to_reach(date: &str) -> Option<u32> {
+    Some(days_back_to(period_of(date)?.0))
+}
+
+/// the size of the day window reaching back to the start of the given day
+fn days_back_to(day: NaiveDate) -> u32 {
+    let days = Local::now()
+        .date_naive()
+        .signed_duration_since(day)
+        .num_days();
+    days.max(0) as u32 + 1
+}
+
+/// Selects the repositories of the workspace to scan
+struct Selection {
+    /// include the manifest repository
+    manifest: bool,
+    /// only the repositories changed since the last run
+    changed_only: bool,
+    /// only the repositories whose refs have been modified within the
+    /// day window
+    touched_only: bool,
+    /// further workspaces scanned along with the one of the working
+    /// directory
+    workspaces: Vec<PathBuf>,
+    /// folder whose git repositories are scanned instead of a workspace
+    scan: Option<PathBuf>,
+    /// type of the workspaces, detected if not given
+    workspace_type: Option<WorkspaceType>,
+}
+
+/// What to do with the history once scanned
+#[derive(Clone, Copy, PartialEq)]
+enum Output {
+    /// open the UI, unless a report is written without `--ui`
+    Ui,
+    /// print the commits, see `log::print()`
+    Log,
+    /// print the number of commits matched per repository, see `--dry-run`
+    Statistics,
+}
+
+/// The report to write, see `--report`
+struct ReportOptions<'a> {
+    file: Option<&'a str>,
+    mode: ReportMode,
+    /// replace the identities of authors by pseudonyms
+    anonymize: bool,
+    /// continue an interrupted run, see `Checkpoint`
+    resume: bool,
 }
 
 fn do_main(
     classifier: &model::Classifier,
     revwalk_strategy: &RevWalkStrategy,
-    cwd: &Path,
-    include_manifest: bool,
-    report_file_path: Option<&str>,
+    selection: &Selection,
+    report: &ReportOptions,
+    show_ui: bool,
+    output: Output,
+    mut startup: ui::Startup,
 ) -> Result<()> {
     let config = config::read();
+    i18n::set_language(config.language);
+    let script = match &config.script {
+        Some(file) => Some(Arc::new(Script::load(file)?)),
+        None => None,
+    };
+    let classifier = &classifier
+        .clone()
+        .with_bots(config.bots.clone())
+        .with_scan(config.scan.clone())
+        .with_script(script);
 
-    env::set_current_dir(cwd)?;
     rayon::ThreadPoolBuilder::new()
         .num_threads(std::cmp::min(num_cpus::get(), MAX_NUMBER_OF_THREADS))
         .build_global()
         .unwrap();
 
-    let project_file = File::open(find_project_file()?)?;
-    let repos = repos_from(&project_file, include_manifest)?;
+    let started = Instant::now();
+    let commit_url = CommitUrlResolver::new(&config);
+    let issues = IssueTracker::new(&config);
+    let linter = Linter::new(&config);
+    let details = report::Details {
+        commit_url: &commit_url,
+        issues: &issues,
+        linter: &linter,
+        anonymize: report.anonymize,
+        date_format: &config.report.date_format,
+        max_rows: config.report.max_rows,
+    };
+    let builds = match &config.builds {
+        Some(builds) => builds::load(builds)?,
+        None => Vec::new(),
+    };
 
-    let history = MultiRepoHistory::from(repos, &classifier, revwalk_strategy)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+    let owners = Owners::load(config.owners.as_deref())?;
+    let mut repos = workspace_repos(selection, &owners)?;
 
-    //TUI or report?
-    match report_file_path {
-        None => ui::show(history, config),
-        Some(file) => {
+    let mut previous_fingerprints = Fingerprints::load();
+    // compared by --changed-only now and by refreshing the UI later,
+    // other runs just record the HEADs for the next run
+    let shows_ui = output == Output::Ui && (report.file.is_none() || show_ui);
+    let fingerprints = if selection.changed_only || shows_ui {
+        Fingerprints::of(&repos)
+    } else {
+        Fingerprints::heads_of(&repos, &previous_fingerprints)
+    };
+    let classifier = &classifier
+        .clone()
+        .with_previous_heads(previous_fingerprints.heads())
+        .with_first_seen(FirstSeen::load());
+    if selection.changed_only {
+        repos.retain(|repo| fingerprints.has_changed(&previous_fingerprints, repo));
+        println!("{} repositories changed since the last run", repos.len());
+    }
+    if selection.touched_only && classifier.range().is_none() {
+        let window = Duration::from_secs(u64::from(classifier.days()) * 24 * 60 * 60);
+        let since = SystemTime::now() - window;
+        repos = repos
+            .into_par_iter()
+            .filter(|repo| fingerprints::touched_since(repo, since))
+            .collect();
+    }
+    let mut record_fingerprints = || {
+        // a dry run leaves --changed-only alone
+        if output != Output::Statistics {
+            previous_fingerprints.extend(fingerprints.clone());
+            previous_fingerprints.save();
+        }
+    };
+
+    if report.resume && !report.file.is_some_and(report::is_resumable) {
+        return Err(anyhow!("--resume is supported for .csv reports only"));
+    }
+
+    // CSV reports are written while scanning, without keeping the history
+    if let Some(file) = report.file.filter(|file| !show_ui && report::is_csv(file)) {
+        println!("Skipping UI - generating report...");
+        let mut mode = report.mode;
+        let checkpoint = match (report.resume, report::is_resumable(file)) {
+            (true, _) => {
+                let checkpoint = Checkpoint::resume(Path::new(file))?;
+                repos.retain(|repo| !checkpoint.contains(repo));
+                println!(
+                    "Resuming after {} repositories written already",
+                    checkpoint.written_repos()
+                );
+                mode = ReportMode::Append;
+                Some(checkpoint)
+            }
+            (false, true) => {
+                // claimed before the checkpoint of another run is replaced
+                if mode == ReportMode::FailIfExists {
+                    report::claim(&[PathBuf::from(file)], mode)?;
+                    mode = ReportMode::Overwrite;
+                }
+                Some(Checkpoint::new(
+                    Path::new(file),
+                    mode == ReportMode::Append,
+                )?)
+            }
+            (false, false) => None,
+        };
+        let (sender, receiver) = mpsc::channel();
+        let mut violations = Vec::new();
+        let (summary, missing_commits) = thread::scope(|scope| {
+            let scan = scope
+                .spawn(|| MultiRepoHistory::stream(&repos, classifier, revwalk_strategy, sender));
+            // each batch holds the commits of one repository
+            let batches = receiver
+                .into_iter()
+                .inspect(|(_, commits)| violations.extend(alerts::check(&config.alert, commits)));
+            let summary = report::generate_csv_streaming(
+                batches,
+                file,
+                mode,
+                &details,
+                repos.len(),
+                checkpoint,
+                ProgressDrawTarget::stderr(),
+            );
+            (
+                summary,
+                scan.join().expect("scanning the repositories failed"),
+            )
+        });
+        record_fingerprints();
+        println!("{}", summary?);
+        if missing_commits > 0 {
+            println!(
+                "{} parent commits not found locally (shallow git clone?)",
+                missing_commits
+            );
+        }
+        return report_alerts(&violations);
+    }
+
+    let history = MultiRepoHistory::from(repos, classifier, revwalk_strategy)
+        .map_err(|e| io::Error::other(e.to_string()))?;
+    record_fingerprints();
+    crash::set_workspace(format!(
+        "{}: {} repositories, {} commits",
+        env::current_dir()?.display(),
+        history.repos.len(),
+        history.commits.len()
+    ));
+
+    match output {
+        Output::Log => return Ok(log::print(&history.commits, io::stdout().lock())?),
+        Output::Statistics => {
+            print_statistics(&history, classifier, started.elapsed());
+            return Ok(());
+        }
+        Output::Ui => {}
+    }
+
+    //report and/or TUI?
+    if let Some(file) = report.file {
+        if !show_ui {
             println!("Skipping UI - generating report...");
-            report::generate(&history, file)?
+        }
+        let summary = report::generate(
+            &history.commits,
+            file,
+            report.mode,
+            &details,
+            ProgressDrawTarget::stderr(),
+        )?;
+        println!("{}", summary);
+        if !show_ui {
+            return report_alerts(&alerts::check(&config.alert, &history.commits));
         }
     }
+    if shows_ui {
+        if let Some(path) = startup.focus_repo.take() {
+            let repo = focused_repo(&history.repos, &path)
+                .ok_or_else(|| anyhow!("No repository found at {}", path))?;
+            let term = format!("repo:\"{}\"", repo.name());
+            startup.filter = Some(match startup.filter {
+                Some(filter) => format!("{} {}", term, filter),
+                None => term,
+            });
+        }
+        ui::show(history, fingerprints, config, builds, startup);
+    }
 
     Ok(())
 }
 
-fn repos_from(
-    project_file: &std::fs::File,
+/// prints the number of commits matched by the classifier, in total and
+/// per repository
+fn print_statistics(history: &MultiRepoHistory, classifier: &model::Classifier, took: Duration) {
+    let groups = stats::group_by(&history.commits, |commit| commit.repo.name());
+    println!(
+        "Scanned {} repositories in {:.1}s",
+        history.repos.len(),
+        took.as_secs_f64()
+    );
+    println!(
+        "{} commits matched ({}) in {} repositories",
+        history.commits.len(),
+        classifier,
+        groups.len()
+    );
+    if history.locally_missing_commits > 0 {
+        println!(
+            "{} parent commits not found locally (shallow git clone?)",
+            history.locally_missing_commits
+        );
+    }
+    if !groups.is_empty() {
+        print!("\n{}", stats::to_table("Repository", &groups));
+    }
+}
+
+fn list_repos(
     include_manifest: bool,
-) -> Result<Vec<Arc<Repo>>, io::Error> {
-    let mut repos = Vec::new();
+    scan: Option<&Path>,
+    workspace_type: Option<WorkspaceType>,
+    format: &str,
+) -> Result<()> {
+    let config = config::read();
+    i18n::set_language(config.language);
+    let owners = Owners::load(config.owners.as_deref())?;
+    let repos = match scan {
+        Some(folder) => scanned_repos(folder, &owners)?,
+        None => {
+            let base_folder = workspace::base_folder_of(&env::current_dir()?, workspace_type)?;
+            repos_from(
+                &base_folder,
+                workspace_type,
+                include_manifest,
+                &owners,
+                None,
+            )?
+        }
+    };
+    repo_list::write(&repos, format, io::stdout().lock())
+}
 
-    let base_folder = find_repo_base_folder()?;
-    for project in BufReader::new(project_file).lines() {
-        let rel_path = project.expect("project.list read error");
-        repos.push(Arc::new(Repo::from(base_folder.join(&rel_path), rel_path)));
+/// lists the violated alert thresholds, failing if there are any
+fn report_alerts(violations: &[alerts::Violation]) -> Result<()> {
+    for violation in violations {
+        eprintln!("Alert: {}", violation);
     }
+    if !violations.is_empty() {
+        return Err(anyhow!("{} alert thresholds exceeded", violations.len()));
+    }
+    Ok(())
+}
 
-    if include_manifest {
-        let rel_path = String::from(".repo/manifests");
-        repos.push(Arc::new(Repo::from(base_folder.join(&rel_path), rel_path)));
+/// the repositories of the workspace of the working directory and of the
+/// further workspaces given by `-C`, tagged with the name of their
+/// workspace if there are several
+fn workspace_repos(selection: &Selection, owners: &Owners) -> Result<Vec<Arc<Repo>>> {
+    if let Some(folder) = &selection.scan {
+        return Ok(scanned_repos(folder, owners)?);
+    }
+    let mut base_folders = vec![workspace::base_folder_of(
+        &env::current_dir()?,
+        selection.workspace_type,
+    )?];
+    for folder in &selection.workspaces {
+        base_folders.push(workspace::base_folder_of(folder, selection.workspace_type)?);
+    }
+    if base_folders.len() == 1 {
+        return Ok(repos_from(
+            &base_folders[0],
+            selection.workspace_type,
+            selection.manifest,
+            owners,
+            None,
+        )?);
     }
+    let mut repos = Vec::new();
+    for (base_folder, name) in base_folders.iter().zip(workspace_names(&base_folders)) {
+        repos.extend(repos_from(
+            base_folder,
+            selection.workspace_type,
+            selection.manifest,
+            owners,
+            Some(name),
+        )?);
+    }
+    Ok(repos)
+}
 
+/// the git repositories found in the given folder, without a git-repo
+/// workspace; their local paths are relative to the folder
+fn scanned_repos(folder: &Path, owners: &Owners) -> Result<Vec<Arc<Repo>>, io::Error> {
+    let repos = find_git_repos(folder)?
+        .into_iter()
+        .map(|abs_path| {
+            let rel_path = match abs_path.strip_prefix(folder) {
+                Ok(path) if !path.as_os_str().is_empty() => path.to_string_lossy().into_owned(),
+                // the folder is a repository itself
+                _ => abs_path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| abs_path.display().to_string()),
+            };
+            let mut repo = Repo::from(abs_path, rel_path);
+            repo.team = owners.team_of(&repo.rel_path);
+            Arc::new(repo)
+        })
+        .collect();
     Ok(repos)
 }
+
+/// the names of the given workspaces: the names of their folders, or their
+/// paths in case these are ambiguous
+fn workspace_names(base_folders: &[PathBuf]) -> Vec<String> {
+    let name = |folder: &PathBuf| {
+        folder
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| folder.display().to_string())
+    };
+    base_folders
+        .iter()
+        .map(|folder| {
+            let ambiguous = base_folders
+                .iter()
+                .filter(|other| name(other) == name(folder))
+                .count()
+                > 1;
+            if ambiguous {
+                folder.display().to_string()
+            } else {
+                name(folder)
+            }
+        })
+        .collect()
+}
+
+fn repos_from(
+    base_folder: &Path,
+    workspace_type: Option<WorkspaceType>,
+    include_manifest: bool,
+    owners: &Owners,
+    workspace: Option<String>,
+) -> Result<Vec<Arc<Repo>>, io::Error> {
+    let workspace_type = workspace_type
+        .or_else(|| WorkspaceType::of(base_folder))
+        .unwrap_or(WorkspaceType::Repo);
+    let mut projects = projects_of(base_folder, workspace_type)?;
+    let manifest_path = match workspace_type {
+        WorkspaceType::Repo => Some(manifest::rel_path(base_folder)),
+        WorkspaceType::West => Some(west::rel_path(base_folder)),
+        WorkspaceType::Tsrc => Some(tsrc::rel_path()),
+        // the DEPS files are part of the solutions
+        WorkspaceType::Gclient => None,
+    };
+    if let Some(manifest_path) = manifest_path.filter(|_| include_manifest) {
+        projects.push((manifest_path, Vec::new()));
+    }
+
+    let repos = projects
+        .into_iter()
+        .map(|(rel_path, groups)| {
+            let mut repo = Repo::from(base_folder.join(&rel_path), rel_path);
+            repo.team = owners.team_of(&repo.rel_path);
+            repo.workspace = workspace.clone();
+            repo.groups = groups;
+            Arc::new(repo)
+        })
+        .collect();
+
+    Ok(repos)
+}
+
+/// the local paths of the projects of a workspace with their groups, as
+/// declared by the manifest in use; projects which aren't checked out
+/// (e.g. of groups not synced) are left out. The projects of a git-repo
+/// workspace are taken from `.repo/project.list`, as synced by repo, only
+/// their groups from the manifest; the manifest is read for them if there
+/// is no project.list.
+fn projects_of(
+    base_folder: &Path,
+    workspace_type: WorkspaceType,
+) -> Result<Vec<(String, Vec<String>)>, io::Error> {
+    let read = match workspace_type {
+        WorkspaceType::Repo => manifest::read,
+        WorkspaceType::West => west::read,
+        WorkspaceType::Tsrc => tsrc::read,
+        WorkspaceType::Gclient => gclient::read,
+    };
+    if workspace_type == WorkspaceType::Repo {
+        if let Ok(project_file) = find_project_file(base_folder) {
+            let mut groups: HashMap<String, Vec<String>> = read(base_folder)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|project| (project.path, project.groups))
+                .collect();
+            let mut projects = Vec::new();
+            for project in BufReader::new(File::open(project_file)?).lines() {
+                let project = project?;
+                let groups = groups.remove(&project).unwrap_or_default();
+                projects.push((project, groups));
+            }
+            return Ok(projects);
+        }
+    }
+    let mut projects: Vec<(String, Vec<String>)> = read(base_folder)
+        .map_err(|e| io::Error::other(format!("{:#}", e)))?
+        .into_iter()
+        .filter(|project| base_folder.join(&project.path).is_dir())
+        .map(|project| (project.path, project.groups))
+        .collect();
+    projects.sort();
+    projects.dedup_by(|a, b| a.0 == b.0);
+    Ok(projects)
+}
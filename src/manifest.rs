@@ -0,0 +1,501 @@
+use anyhow::{Context, Result};
+use git2::{Commit, Repository};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// An element of a manifest file below `<manifest>`, e.g. a `<project>`
+struct Element {
+    tag: String,
+    attributes: BTreeMap<String, String>,
+}
+
+/// the elements of the given manifest file, in document order
+fn parse(manifest: &str) -> Result<Vec<Element>, roxmltree::Error> {
+    let document = roxmltree::Document::parse(manifest)?;
+    Ok(document
+        .root_element()
+        .children()
+        .filter(|node| node.is_element())
+        .map(|node| Element {
+            tag: node.tag_name().name().to_string(),
+            attributes: node
+                .attributes()
+                .map(|attribute| (attribute.name().to_string(), attribute.value().to_string()))
+                .collect(),
+        })
+        .collect())
+}
+
+/// the values of the given attribute of the elements with the given tag
+fn values_of<'a>(
+    elements: &'a [Element],
+    tag: &'a str,
+    attribute: &'a str,
+) -> impl Iterator<Item = &'a String> {
+    elements
+        .iter()
+        .filter(move |element| element.tag == tag)
+        .filter_map(move |element| element.attributes.get(attribute))
+}
+
+/// A project of a git-repo manifest
+#[derive(Debug, PartialEq)]
+pub struct Project {
+    pub name: String,
+    /// local path, relative to the root folder of the workspace
+    pub path: String,
+    /// branch, tag or SHA the project is pinned to
+    pub revision: String,
+    pub remote: Option<String>,
+    /// e.g. `notdefault` for projects synced only if requested
+    pub groups: Vec<String>,
+}
+
+/// the work tree of the manifest repository: the folder `.repo/manifest.xml`
+/// links into (older versions of git-repo), `.repo/manifests` otherwise
+pub fn repo_folder(base_folder: &Path) -> PathBuf {
+    let repo_folder = base_folder.join(".repo");
+    fs::read_link(repo_folder.join("manifest.xml"))
+        .ok()
+        .and_then(|target| Some(repo_folder.join(target.parent()?)))
+        .filter(|folder| folder != &repo_folder && folder.is_dir())
+        .unwrap_or_else(|| repo_folder.join("manifests"))
+}
+
+/// the local path of the manifest repository, relative to the root folder
+/// of the workspace
+pub fn rel_path(base_folder: &Path) -> String {
+    repo_folder(base_folder)
+        .strip_prefix(base_folder)
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| String::from(".repo/manifests"))
+}
+
+/// the local manifests amending the manifest in use (`.repo/local_manifests`
+/// and the deprecated `.repo/local_manifest.xml`), in the order git-repo
+/// applies them
+fn local_manifests(base_folder: &Path) -> Vec<PathBuf> {
+    let repo_folder = base_folder.join(".repo");
+    let mut files: Vec<PathBuf> = fs::read_dir(repo_folder.join("local_manifests"))
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "xml"))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files.insert(0, repo_folder.join("local_manifest.xml"));
+    files.retain(|file| file.is_file());
+    files
+}
+
+/// the name of the manifest file in use, `.repo/manifest.xml` either links
+/// to it or includes it
+pub fn file_name(base_folder: &Path) -> String {
+    let manifest = base_folder.join(".repo/manifest.xml");
+    if let Ok(target) = fs::read_link(&manifest) {
+        if let Some(name) = target.file_name() {
+            return name.to_string_lossy().into_owned();
+        }
+    }
+    fs::read_to_string(&manifest)
+        .ok()
+        .and_then(|content| {
+            values_of(&parse(&content).ok()?, "include", "name")
+                .next()
+                .cloned()
+        })
+        .unwrap_or_else(|| String::from("default.xml"))
+}
+
+/// the projects of the manifest in use, including the ones of the
+/// manifests it includes, as amended by the local manifests
+pub fn read(base_folder: &Path) -> Result<Vec<Project>> {
+    Ok(merge(&manifests(base_folder)?))
+}
+
+/// the fetch URLs of the remotes declared by the manifest in use, the
+/// manifests it includes and the local manifests, by the name of the
+/// remote
+pub fn remotes(base_folder: &Path) -> Result<BTreeMap<String, String>> {
+    Ok(manifests(base_folder)?
+        .iter()
+        .flatten()
+        .filter(|element| element.tag == "remote")
+        .filter_map(|remote| {
+            let attributes = &remote.attributes;
+            Some((
+                attributes.get("name")?.clone(),
+                attributes.get("fetch")?.clone(),
+            ))
+        })
+        .collect())
+}
+
+/// the manifest in use, the manifests it includes (each once, even if
+/// included repeatedly or in a cycle) and the local manifests, in the
+/// order git-repo applies them
+fn manifests(base_folder: &Path) -> Result<Vec<Vec<Element>>> {
+    let folder = repo_folder(base_folder);
+    let mut manifests = Vec::new();
+    let mut visited = HashSet::new();
+    let mut files = vec![folder.join(file_name(base_folder))];
+    while let Some(path) = files.pop() {
+        if !visited.insert(path.clone()) {
+            continue;
+        }
+        let manifest = read_manifest(&path)?;
+        // the first include is read next
+        let includes: Vec<PathBuf> = values_of(&manifest, "include", "name")
+            .map(|name| folder.join(name))
+            .collect();
+        files.extend(includes.into_iter().rev());
+        manifests.push(manifest);
+    }
+    for path in local_manifests(base_folder) {
+        manifests.push(read_manifest(&path)?);
+    }
+    Ok(manifests)
+}
+
+fn read_manifest(path: &Path) -> Result<Vec<Element>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the manifest {}", path.display()))?;
+    parse(&content).with_context(|| format!("Failed to parse the manifest {}", path.display()))
+}
+
+/// the projects of the given manifest files, in the order they are
+/// applied; `<remove-project>` elements remove projects of the previous
+/// files. The `<default>` and the revisions of the remotes apply across
+/// all files, e.g. to the projects of included and local manifests.
+fn merge(manifests: &[Vec<Element>]) -> Vec<Project> {
+    let defaults = Defaults::of(manifests);
+    let mut projects: Vec<Project> = Vec::new();
+    for manifest in manifests {
+        for removed in manifest.iter().filter(|e| e.tag == "remove-project") {
+            let removed = &removed.attributes;
+            projects.retain(|project| {
+                removed.get("name") != Some(&project.name)
+                    && removed.get("path") != Some(&project.path)
+            });
+        }
+        projects.extend(defaults.projects_of(manifest));
+    }
+    projects
+}
+
+/// The `<default>` of a manifest and the revisions of its remotes by
+/// their name, which projects lacking a revision or remote fall back to
+#[derive(Default)]
+struct Defaults {
+    default: BTreeMap<String, String>,
+    remote_revisions: BTreeMap<String, String>,
+}
+
+impl Defaults {
+    fn of(manifests: &[Vec<Element>]) -> Defaults {
+        let mut defaults = Defaults::default();
+        for element in manifests.iter().flatten() {
+            let attributes = &element.attributes;
+            match element.tag.as_str() {
+                "default" => defaults.default.extend(attributes.clone()),
+                "remote" => {
+                    if let (Some(name), Some(revision)) =
+                        (attributes.get("name"), attributes.get("revision"))
+                    {
+                        defaults
+                            .remote_revisions
+                            .insert(name.clone(), revision.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+        defaults
+    }
+
+    /// the projects of a single manifest file, without the included ones
+    fn projects_of(&self, manifest: &[Element]) -> Vec<Project> {
+        manifest
+            .iter()
+            .filter(|element| element.tag == "project")
+            .filter_map(|element| {
+                let mut project = element.attributes.clone();
+                let name = project.remove("name")?;
+                let path = project.remove("path").unwrap_or_else(|| name.clone());
+                let remote = project
+                    .remove("remote")
+                    .or_else(|| self.default.get("remote").cloned());
+                let revision = project
+                    .remove("revision")
+                    .or_else(|| self.remote_revisions.get(remote.as_ref()?).cloned())
+                    .or_else(|| self.default.get("revision").cloned())
+                    .unwrap_or_else(|| String::from("HEAD"));
+                let groups = project
+                    .remove("groups")
+                    .map(|groups| {
+                        groups
+                            .split(|c: char| c == ',' || c.is_whitespace())
+                            .filter(|group| !group.is_empty())
+                            .map(String::from)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Some(Project {
+                    name,
+                    path,
+                    revision,
+                    remote,
+                    groups,
+                })
+            })
+            .collect()
+    }
+}
+
+/// the projects of a single manifest file, without the included ones;
+/// none if it isn't valid XML
+pub fn projects_of(manifest: &str) -> Vec<Project> {
+    let manifest = parse(manifest).unwrap_or_default();
+    Defaults::of(std::slice::from_ref(&manifest)).projects_of(&manifest)
+}
+
+/// How a commit of the manifest repository changed a project
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    Pinned {
+        path: String,
+        old: String,
+        new: String,
+    },
+    Added {
+        path: String,
+        revision: String,
+    },
+    Removed {
+        path: String,
+    },
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Change::Pinned { path, old, new } => {
+                write!(f, "project {}: rev {} → {}", path, short(old), short(new))
+            }
+            Change::Added { path, revision } => {
+                write!(f, "added project {} at {}", path, short(revision))
+            }
+            Change::Removed { path } => write!(f, "removed project {}", path),
+        }
+    }
+}
+
+/// abbreviates SHAs, branches and tags are kept
+fn short(revision: &str) -> &str {
+    if revision.len() == 40 && revision.chars().all(|c| c.is_ascii_hexdigit()) {
+        &revision[..8]
+    } else {
+        revision
+    }
+}
+
+/// the changes to the projects made by a commit of the manifest
+/// repository, compared to its first parent, for each manifest file it
+/// touched; `None` if it changed no project (e.g. only remotes)
+pub fn changes_of(git_repo: &Repository, commit: &Commit) -> Option<Vec<Change>> {
+    let tree = commit.tree().ok()?;
+    let parent_tree = commit.parent(0).ok().and_then(|parent| parent.tree().ok());
+    let diff = git_repo
+        .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+        .ok()?;
+    let content = |id: git2::Oid| {
+        git_repo
+            .find_blob(id)
+            .map(|blob| String::from_utf8_lossy(blob.content()).into_owned())
+            .unwrap_or_default()
+    };
+    let mut changes = Vec::new();
+    for delta in diff.deltas() {
+        let is_manifest = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())
+            .is_some_and(|path| path.extension().is_some_and(|ext| ext == "xml"));
+        if is_manifest {
+            changes.extend(diff_projects(
+                &content(delta.old_file().id()),
+                &content(delta.new_file().id()),
+            ));
+        }
+    }
+    Some(changes).filter(|changes| !changes.is_empty())
+}
+
+/// the projects whose revision differs between two versions of a
+/// manifest file, ordered by path
+fn diff_projects(old: &str, new: &str) -> Vec<Change> {
+    let revisions = |manifest: &str| -> BTreeMap<String, String> {
+        projects_of(manifest)
+            .into_iter()
+            .map(|project| (project.path, project.revision))
+            .collect()
+    };
+    let (old, new) = (revisions(old), revisions(new));
+    let mut changes: Vec<(&String, Change)> = Vec::new();
+    for (path, revision) in &new {
+        match old.get(path) {
+            Some(old) if old != revision => changes.push((
+                path,
+                Change::Pinned {
+                    path: path.clone(),
+                    old: old.clone(),
+                    new: revision.clone(),
+                },
+            )),
+            Some(_) => {}
+            None => changes.push((
+                path,
+                Change::Added {
+                    path: path.clone(),
+                    revision: revision.clone(),
+                },
+            )),
+        }
+    }
+    for path in old.keys().filter(|path| !new.contains_key(*path)) {
+        changes.push((path, Change::Removed { path: path.clone() }));
+    }
+    changes.sort_by(|a, b| a.0.cmp(b.0));
+    changes.into_iter().map(|(_, change)| change).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_projects() {
+        let projects = projects_of(
+            r#"<manifest>
+            <remote name="origin" fetch=".."/>
+            <remote name="vendor" fetch="https://example.com" revision="stable"/>
+            <default remote="origin" revision="main"/>
+            <project name="kernel/msm" revision="abc"/>
+            <project name="platform/build" path="build" groups="tools, notdefault"/>
+            <project name="foo" path="vendor/foo" remote="vendor"/></manifest>"#,
+        );
+        let project = |name: &str, path: &str, revision: &str, remote: &str| Project {
+            name: name.to_string(),
+            path: path.to_string(),
+            revision: revision.to_string(),
+            remote: Some(remote.to_string()),
+            groups: Vec::new(),
+        };
+        assert_eq!(
+            projects,
+            vec![
+                project("kernel/msm", "kernel/msm", "abc", "origin"),
+                Project {
+                    groups: vec![String::from("tools"), String::from("notdefault")],
+                    ..project("platform/build", "build", "main", "origin")
+                },
+                project("foo", "vendor/foo", "stable", "vendor"),
+            ]
+        );
+    }
+
+    fn parsed(manifests: &[&str]) -> Vec<Vec<Element>> {
+        manifests
+            .iter()
+            .map(|manifest| parse(manifest).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn parses_quotes_and_entities() {
+        let projects = projects_of(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <manifest><default revision='main' remote="origin"/>
+            <project name='R&amp;D/tools' path="r&#38;d"/></manifest>"#,
+        );
+        let paths: Vec<(&str, &str, &str)> = projects
+            .iter()
+            .map(|p| (p.name.as_str(), p.path.as_str(), p.revision.as_str()))
+            .collect();
+        assert_eq!(paths, vec![("R&D/tools", "r&d", "main")]);
+        assert!(projects_of("<manifest><project name=\"a\"></manifest>").is_empty());
+    }
+
+    #[test]
+    fn applies_local_manifests() {
+        let projects = merge(&parsed(&[
+            r#"<manifest><default revision="main"/>
+            <project name="kernel/msm"/>
+            <project name="platform/build" path="build"/></manifest>"#,
+            r#"<manifest>
+            <remove-project name="kernel/msm"/>
+            <project name="kernel/msm" path="kernel" revision="topic" remote="fork"/>
+            </manifest>"#,
+        ]));
+        let paths: Vec<(&str, &str)> = projects
+            .iter()
+            .map(|p| (p.path.as_str(), p.revision.as_str()))
+            .collect();
+        assert_eq!(paths, vec![("build", "main"), ("kernel", "topic")]);
+    }
+
+    #[test]
+    fn resolves_defaults_across_files() {
+        let projects = merge(&parsed(&[
+            r#"<manifest>
+            <remote name="origin" fetch=".." revision="stable"/>
+            <default remote="origin"/>
+            <!-- <project name="commented/out"/> -->
+            <include name="platform.xml"/></manifest>"#,
+            r#"<manifest><project name="platform/build" path="build"/>
+            <project name="platform/old"/></manifest>"#,
+            r#"<manifest><remove-project name="platform/old"/></manifest>"#,
+        ]));
+        assert_eq!(
+            projects,
+            vec![Project {
+                name: String::from("platform/build"),
+                path: String::from("build"),
+                revision: String::from("stable"),
+                remote: Some(String::from("origin")),
+                groups: Vec::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn summarizes_changed_projects() {
+        let sha = "0123456789abcdef0123456789abcdef01234567";
+        let changes = diff_projects(
+            r#"<manifest><default revision="main"/>
+            <project name="kernel/msm" revision="abc123"/>
+            <project name="platform/old"/>
+            <project name="platform/build" path="build"/></manifest>"#,
+            &format!(
+                r#"<manifest><default revision="main"/>
+                <project name="kernel/msm" revision="def456"/>
+                <project name="vendor/foo" revision="{}"/>
+                <project name="platform/build" path="build"/></manifest>"#,
+                sha
+            ),
+        );
+        let summary: Vec<String> = changes.iter().map(Change::to_string).collect();
+        assert_eq!(
+            summary,
+            vec![
+                "project kernel/msm: rev abc123 → def456",
+                "removed project platform/old",
+                "added project vendor/foo at 01234567",
+            ]
+        );
+    }
+}
@@ -1,20 +1,36 @@
+use crate::config::{Pattern, Scan};
+use crate::fingerprints;
+use crate::first_seen::FirstSeen;
+use crate::graph::{FirstParentDistances, GraphBuilder};
+use crate::rewrites;
+use crate::script::Script;
 use crate::utils::{as_datetime, as_datetime_utc};
-use chrono::{Datelike, Duration, Timelike};
+use chrono::{Datelike, Duration, TimeZone, Timelike};
 use console::style;
-use git2::{Commit, Oid, Repository, Time};
-use indicatif::{MultiProgress, ParallelProgressIterator, ProgressBar, ProgressStyle};
+use git2::{Commit, Oid, Repository, StatusOptions, Time};
+use indicatif::{
+    MultiProgress, ParallelProgressIterator, ProgressBar, ProgressDrawTarget, ProgressStyle,
+};
 use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::thread;
 
+/// Commits dated later than this after now are considered future-dated,
+/// clocks of different machines are never exactly in sync
+const FUTURE_TOLERANCE_MINUTES: i64 = 60;
+
 /// A history of commits across multiple repositories
 pub struct MultiRepoHistory {
     pub repos: Vec<Arc<Repo>>,
     pub commits: Vec<RepoCommit>,
     pub locally_missing_commits: usize,
+    pub revwalk_strategy: RevWalkStrategy,
+    pub classifier: Classifier,
 }
 
 impl MultiRepoHistory {
@@ -22,90 +38,361 @@ impl MultiRepoHistory {
         repos: Vec<Arc<Repo>>,
         classifier: &Classifier,
         rewalk_strategy: &RevWalkStrategy,
+    ) -> Result<MultiRepoHistory, git2::Error> {
+        Self::scan(
+            repos,
+            classifier,
+            rewalk_strategy,
+            ProgressDrawTarget::stderr(),
+        )
+    }
+
+    /// same as `from()` but without showing any progress, e.g. for
+    /// rescanning the repositories while the UI is shown
+    pub fn from_quietly(
+        repos: Vec<Arc<Repo>>,
+        classifier: &Classifier,
+        rewalk_strategy: &RevWalkStrategy,
+    ) -> Result<MultiRepoHistory, git2::Error> {
+        Self::scan(
+            repos,
+            classifier,
+            rewalk_strategy,
+            ProgressDrawTarget::hidden(),
+        )
+    }
+
+    fn scan(
+        repos: Vec<Arc<Repo>>,
+        classifier: &Classifier,
+        rewalk_strategy: &RevWalkStrategy,
+        draw_target: ProgressDrawTarget,
     ) -> Result<MultiRepoHistory, git2::Error> {
         let (progress, progress_bars, overall_progress) = Self::create_progress_bars(&repos);
+        progress.set_draw_target(draw_target);
 
         thread::spawn(move || {
             progress.join_and_clear().unwrap();
         });
 
-        let missing_commits = Arc::new(AtomicUsize::new(0));
-        let missing_commits_result = missing_commits.clone();
+        let missing_commits = AtomicUsize::new(0);
 
         let mut commits: Vec<RepoCommit> = repos
             .par_iter()
-            .map(move |repo| {
-                let progress_bar = &progress_bars[rayon::current_thread_index()?];
-                progress_bar.set_message(&format!("Scanning {}", repo.rel_path));
-
-                let progress_error = |msg: &str, error: &dyn std::error::Error| {
-                    progress_bar.println(format!(
-                        "{}: {}: {}",
-                        style(&msg).red(),
-                        style(&repo.rel_path).blue(),
-                        error
-                    ));
-                    progress_bar.inc(1);
-                    progress_bar.set_message("Idle");
-                };
-
-                let git_repo = Repository::open(&repo.abs_path)
-                    .map_err(|e| progress_error("Failed to open", &e))
-                    .ok()?;
-
-                let mut revwalk = git_repo
-                    .revwalk()
-                    .map_err(|e| progress_error("Failed create revwalk", &e))
-                    .ok()?;
-
-                revwalk
-                    .push_head()
-                    .map_err(|e| progress_error("Failed query history", &e))
-                    .ok()?;
-                if rewalk_strategy == &RevWalkStrategy::FirstParent {
-                    revwalk.simplify_first_parent().ok()?;
-                }
-                revwalk.set_sorting(git2::Sort::TIME).ok()?;
-
-                let mut commits = Vec::new();
-                for commit_id in revwalk {
-                    let commit = commit_id
-                        .and_then(|commit_id| git_repo.find_commit(commit_id))
-                        .map_err(|_e| {
-                            missing_commits.fetch_add(1, std::sync::atomic::Ordering::SeqCst)
-                        })
-                        .ok()?;
-                    let (include, abort) = classifier.classify(&commit);
-                    if include {
-                        commits.push(RepoCommit::from(repo.clone(), &commit));
-                    }
-                    if abort {
-                        break;
-                    }
-                }
-                progress_bar.set_message("Idle");
-                if commits.is_empty() {
-                    None
-                } else {
-                    Some(commits)
-                }
+            .map(|repo| {
+                let progress_bar = Self::progress_bar_of_thread(&progress_bars);
+                Self::scan_repo(
+                    repo,
+                    classifier,
+                    rewalk_strategy,
+                    progress_bar,
+                    &missing_commits,
+                )
             })
             .progress_with(overall_progress)
             .filter_map(|x| x)
             .flatten()
             .collect();
 
-        commits.sort_unstable_by(|a, b| a.commit_time.cmp(&b.commit_time).reverse());
+        commits.sort_unstable_by(newest_first);
+        classifier.save_first_seen();
         Ok(MultiRepoHistory {
             repos,
             commits,
-            locally_missing_commits: missing_commits_result.load(Ordering::Relaxed),
+            locally_missing_commits: missing_commits.load(Ordering::Relaxed),
+            revwalk_strategy: *rewalk_strategy,
+            classifier: classifier.clone(),
         })
     }
 
-    fn create_progress_bars(
-        repos: &Vec<Arc<Repo>>,
-    ) -> (MultiProgress, Vec<ProgressBar>, ProgressBar) {
+    /// scans the repositories like `from()`, but instead of collecting
+    /// the commits of all repositories they are sent to the given channel
+    /// repository by repository (along with it), as soon as a repository
+    /// has been scanned.
+    /// Returns the number of parent commits which are missing locally.
+    pub fn stream(
+        repos: &[Arc<Repo>],
+        classifier: &Classifier,
+        rewalk_strategy: &RevWalkStrategy,
+        sender: Sender<(Arc<Repo>, Vec<RepoCommit>)>,
+    ) -> usize {
+        let progress_bars: Vec<ProgressBar> = (0..rayon::current_num_threads())
+            .map(|_| ProgressBar::hidden())
+            .collect();
+        let missing_commits = AtomicUsize::new(0);
+        repos.par_iter().for_each_with(sender, |sender, repo| {
+            let progress_bar = Self::progress_bar_of_thread(&progress_bars);
+            if let Some(commits) = Self::scan_repo(
+                repo,
+                classifier,
+                rewalk_strategy,
+                progress_bar,
+                &missing_commits,
+            ) {
+                // the receiver stops listening only in case of an error
+                let _ = sender.send((repo.clone(), commits));
+            }
+        });
+        classifier.save_first_seen();
+        missing_commits.load(Ordering::Relaxed)
+    }
+
+    /// the progress bar of the current thread, the scan might run on
+    /// the calling thread instead of a thread of the pool
+    fn progress_bar_of_thread(progress_bars: &[ProgressBar]) -> &ProgressBar {
+        &progress_bars[rayon::current_thread_index().unwrap_or(0)]
+    }
+
+    fn scan_repo(
+        repo: &Arc<Repo>,
+        classifier: &Classifier,
+        rewalk_strategy: &RevWalkStrategy,
+        progress_bar: &ProgressBar,
+        missing_commits: &AtomicUsize,
+    ) -> Option<Vec<RepoCommit>> {
+        progress_bar.set_message(&format!("Scanning {}", repo.rel_path));
+
+        let progress_error = |msg: &str, error: &dyn std::error::Error| {
+            progress_bar.println(format!(
+                "{}: {}: {}",
+                style(&msg).red(),
+                style(&repo.rel_path).blue(),
+                error
+            ));
+            progress_bar.inc(1);
+            progress_bar.set_message("Idle");
+        };
+
+        let git_repo = Repository::open(&repo.abs_path)
+            .map_err(|e| progress_error("Failed to open", &e))
+            .ok()?;
+
+        let mut revwalk = git_repo
+            .revwalk()
+            .map_err(|e| progress_error("Failed create revwalk", &e))
+            .ok()?;
+
+        match classifier.range() {
+            Some((from, to)) => {
+                // repos where one of the refs doesn't exist are skipped
+                let (from, to) = match (
+                    git_repo
+                        .revparse_single(from)
+                        .and_then(|o| o.peel_to_commit()),
+                    git_repo
+                        .revparse_single(to)
+                        .and_then(|o| o.peel_to_commit()),
+                ) {
+                    (Ok(from), Ok(to)) => (from.id(), to.id()),
+                    _ => {
+                        progress_bar.set_message("Idle");
+                        return None;
+                    }
+                };
+                revwalk
+                    .push(to)
+                    .and_then(|_| revwalk.hide(from))
+                    .map_err(|e| progress_error("Failed query history", &e))
+                    .ok()?;
+            }
+            None => {
+                let branch = classifier.branch.as_ref().and_then(|branch| {
+                    let tip = Self::branch_tip(&git_repo, branch);
+                    if tip.is_none() {
+                        progress_bar.println(format!(
+                            "{}: {}: walking HEAD instead",
+                            style(format!("Branch {} not found", branch)).yellow(),
+                            style(&repo.rel_path).blue(),
+                        ));
+                    }
+                    tip
+                });
+                match branch {
+                    Some(tip) => revwalk.push(tip),
+                    None => revwalk.push_head(),
+                }
+                .map_err(|e| progress_error("Failed query history", &e))
+                .ok()?
+            }
+        }
+        if rewalk_strategy == &RevWalkStrategy::FirstParent {
+            revwalk.simplify_first_parent().ok()?;
+        }
+        revwalk.set_sorting(git2::Sort::TIME).ok()?;
+
+        let mut graph = GraphBuilder::new();
+        let mut distances = FirstParentDistances::new();
+        let mut commits = Vec::new();
+        let mut script_failed = false;
+        for (walked, commit_id) in revwalk.enumerate() {
+            if classifier.scan.max_commits == Some(walked) {
+                progress_bar.println(format!(
+                    "{}: {}: stopped after {} commits",
+                    style("Walk limit reached").yellow(),
+                    style(&repo.rel_path).blue(),
+                    walked
+                ));
+                break;
+            }
+            let commit = commit_id
+                .and_then(|commit_id| git_repo.find_commit(commit_id))
+                .map_err(|_e| missing_commits.fetch_add(1, std::sync::atomic::Ordering::SeqCst))
+                .ok()?;
+            let (include, abort) = classifier.classify(&commit);
+            let (glyphs, distance) = match rewalk_strategy {
+                RevWalkStrategy::AllParents => {
+                    let parent_ids = commit.parent_ids().collect::<Vec<Oid>>();
+                    (
+                        graph.row(commit.id(), &parent_ids),
+                        distances.visit(commit.id(), &parent_ids),
+                    )
+                }
+                RevWalkStrategy::FirstParent => (String::new(), 0),
+            };
+            if include {
+                let mut repo_commit = RepoCommit::from(repo.clone(), &commit);
+                repo_commit.graph = glyphs;
+                repo_commit.first_parent_distance = distance;
+                repo_commit.is_bot = classifier.is_bot(&repo_commit);
+                match classifier.run_script(&mut repo_commit) {
+                    Ok(true) => commits.push(repo_commit),
+                    Ok(false) => {}
+                    Err(e) => {
+                        // reported once per repository, the commit is kept
+                        if !script_failed {
+                            progress_bar.println(format!(
+                                "{}: {}: {}",
+                                style("Script failed").red(),
+                                style(&repo.rel_path).blue(),
+                                e
+                            ));
+                            script_failed = true;
+                        }
+                        commits.push(repo_commit);
+                    }
+                }
+            }
+            if abort {
+                break;
+            }
+        }
+        let previous_head = classifier.previous_heads.get(&repo.abs_path);
+        if let Some(previous_head) = previous_head {
+            let successors = rewrites::successors(&git_repo, *previous_head);
+            for commit in &mut commits {
+                commit.replaces = successors.get(&commit.commit_id).copied();
+            }
+        }
+        if let Some(first_seen) = &classifier.first_seen {
+            let arrived = previous_head
+                .map(|head| FirstSeen::arrived_since(&git_repo, *head))
+                .unwrap_or_default();
+            // the commits arrived when HEAD or a ref moved the last time
+            let now = chrono::Utc::now().timestamp();
+            let landed = match fingerprints::latest_mtime(git_repo.path()) as i64 {
+                0 => now,
+                mtime => mtime.min(now),
+            };
+            first_seen
+                .lock()
+                .unwrap()
+                .record(&repo.abs_path, &mut commits, &arrived, landed);
+        }
+        if classifier.wip && classifier.range.is_none() && classifier.skipped_age.is_none() {
+            match Self::scan_wip(repo, classifier) {
+                Ok(wip) => commits.extend(wip),
+                Err(e) => progress_bar.println(format!(
+                    "{}: {}: {}",
+                    style("Failed to scan work in progress").red(),
+                    style(&repo.rel_path).blue(),
+                    e
+                )),
+            }
+        }
+        progress_bar.set_message("Idle");
+        if commits.is_empty() {
+            None
+        } else {
+            Some(commits)
+        }
+    }
+
+    /// the commit the given branch points to, a local branch is preferred
+    /// over a remote-tracking branch or tag of the same name
+    fn branch_tip(git_repo: &Repository, branch: &str) -> Option<Oid> {
+        git_repo
+            .find_branch(branch, git2::BranchType::Local)
+            .map(|branch| branch.into_reference())
+            .or_else(|_| git_repo.resolve_reference_from_short_name(branch))
+            .and_then(|reference| reference.peel_to_commit())
+            .map(|commit| commit.id())
+            .ok()
+    }
+
+    /// the stash entries and the uncommitted changes of the repository
+    /// as pseudo-commits, see `Classifier::with_wip()`
+    fn scan_wip(repo: &Arc<Repo>, classifier: &Classifier) -> Result<Vec<RepoCommit>, git2::Error> {
+        let mut git_repo = Repository::open(&repo.abs_path)?;
+        let mut stashes = Vec::new();
+        git_repo.stash_foreach(|index, message, id| {
+            stashes.push((index, message.to_string(), *id));
+            true
+        })?;
+
+        let mut commits = Vec::new();
+        for (index, message, id) in stashes {
+            let commit = git_repo.find_commit(id)?;
+            if !classifier.classify(&commit).0 {
+                continue;
+            }
+            let mut repo_commit = RepoCommit::from(repo.clone(), &commit);
+            repo_commit.summary = format!("stash@{{{}}}: {}", index, message);
+            repo_commit.wip = Some(Wip::Stash(index));
+            commits.push(repo_commit);
+        }
+        commits.extend(RepoCommit::uncommitted(repo.clone(), &git_repo)?);
+        Ok(commits)
+    }
+
+    /// adds the commits of an extension of this history (see
+    /// `Classifier::extension()`), e.g. after the day window got extended
+    pub fn merge(&mut self, extension: MultiRepoHistory) {
+        self.commits.extend(extension.commits);
+        self.commits.sort_unstable_by(newest_first);
+        // the extension walked the same histories again, so missing
+        // commits have been counted twice
+        self.locally_missing_commits = std::cmp::max(
+            self.locally_missing_commits,
+            extension.locally_missing_commits,
+        );
+        self.classifier.age = extension.classifier.age;
+    }
+
+    /// replaces the commits of the repositories scanned again by `rescan`,
+    /// e.g. after they changed
+    pub fn update(&mut self, rescan: MultiRepoHistory) {
+        // repositories of several workspaces might share the relative path
+        let rescanned: HashSet<&Path> = rescan.repos.iter().map(|r| r.abs_path.as_path()).collect();
+        self.commits
+            .retain(|commit| !rescanned.contains(commit.repo.abs_path.as_path()));
+        self.commits.extend(rescan.commits);
+        self.commits.sort_unstable_by(newest_first);
+        self.locally_missing_commits =
+            std::cmp::max(self.locally_missing_commits, rescan.locally_missing_commits);
+        // commits of the other repositories might have left the day window
+        if self.classifier.range().is_none() {
+            self.shrink(self.classifier.age);
+        }
+    }
+
+    /// drops all commits which are older than the given number of days
+    pub fn shrink(&mut self, days: u32) {
+        self.classifier.age = days;
+        self.commits
+            .retain(|commit| age_in_days(&commit.commit_time) <= days);
+    }
+
+    fn create_progress_bars(repos: &[Arc<Repo>]) -> (MultiProgress, Vec<ProgressBar>, ProgressBar) {
         let progress = MultiProgress::new();
         let progress_bars = (0..rayon::current_num_threads())
             .enumerate()
@@ -130,7 +417,7 @@ impl MultiRepoHistory {
 
 impl fmt::Debug for MultiRepoHistory {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        println!("Commits: {}", self.commits.len());
+        writeln!(f, "Commits: {}", self.commits.len())?;
         for commit in &self.commits {
             write!(f, "{:?}", commit)?;
         }
@@ -143,6 +430,15 @@ pub struct Repo {
     pub abs_path: PathBuf,
     pub rel_path: String,
     pub description: String,
+    /// team owning the repository, see `owners.toml`
+    pub team: Option<String>,
+    /// workspace the repository is part of, in case several workspaces
+    /// are scanned at once
+    pub workspace: Option<String>,
+    /// groups of the project in the manifest
+    pub groups: Vec<String>,
+    /// URL of the remote, queried on first use
+    remote_url: OnceLock<Option<String>>,
 }
 
 impl Repo {
@@ -152,8 +448,48 @@ impl Repo {
             abs_path,
             rel_path,
             description,
+            team: None,
+            workspace: None,
+            groups: Vec::new(),
+            remote_url: OnceLock::new(),
+        }
+    }
+
+    /// the relative path, prefixed by the workspace in case several
+    /// workspaces are scanned at once, e.g. `product-a: kernel/msm`
+    pub fn name(&self) -> String {
+        match &self.workspace {
+            Some(workspace) => format!("{}: {}", workspace, self.rel_path),
+            None => self.rel_path.clone(),
         }
     }
+
+    /// the root folder of the workspace the repository is part of
+    pub fn workspace_folder(&self) -> &Path {
+        let depth = Path::new(&self.rel_path).components().count();
+        self.abs_path
+            .ancestors()
+            .nth(depth)
+            .unwrap_or(&self.abs_path)
+    }
+
+    /// the fetch URL of the `origin` remote, or of the first remote
+    /// in case there is no `origin`
+    pub fn remote_url(&self) -> Option<&str> {
+        self.remote_url
+            .get_or_init(|| {
+                let git_repo = Repository::open(&self.abs_path).ok()?;
+                let remote = match git_repo.find_remote("origin") {
+                    Ok(remote) => remote,
+                    Err(_) => {
+                        let remotes = git_repo.remotes().ok()?;
+                        git_repo.find_remote(remotes.get(0)?).ok()?
+                    }
+                };
+                remote.url().map(String::from)
+            })
+            .as_deref()
+    }
 }
 
 /// representation of a git commit associated
@@ -165,9 +501,42 @@ pub struct RepoCommit {
     pub summary: String,
     pub author_name: String,
     pub author_email: String,
+    pub author_time: Time,
     pub committer: String,
+    pub committer_email: String,
     pub commit_id: Oid,
+    pub parent_ids: Vec<Oid>,
     pub message: String,
+    /// glyphs of the per-repo commit graph, only available
+    /// when walking all parents
+    pub graph: String,
+    /// number of merges between the walked head and this commit,
+    /// 0 for commits of the first-parent history
+    pub first_parent_distance: usize,
+    /// true if the author is a bot, see `Classifier::with_bots()`
+    pub is_bot: bool,
+    /// pairs of key and value returned by the `annotate` hook of the
+    /// script, see `Classifier::with_script()`
+    pub annotations: Vec<(String, String)>,
+    /// set for the pseudo-commits of local work in progress, see
+    /// `Classifier::with_wip()`
+    pub wip: Option<Wip>,
+    /// the commit amended or rebased into this one since the previous
+    /// run, see `Classifier::with_previous_heads()`
+    pub replaces: Option<Oid>,
+    /// when the commit landed in the workspace, unknown for commits present
+    /// ever since the repository got tracked, see `FirstSeen`
+    pub first_seen: Option<Time>,
+}
+
+/// Local work in progress listed like a commit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Wip {
+    /// the stash entry with the given index, e.g. 0 for `stash@{0}`
+    Stash(usize),
+    /// the changes of the index and the working tree which aren't
+    /// committed yet, dated now and identified by the zero ID
+    Uncommitted,
 }
 
 impl RepoCommit {
@@ -178,29 +547,142 @@ impl RepoCommit {
             summary: commit.summary().unwrap_or("None").into(),
             author_name: commit.author().name().unwrap_or("None").into(),
             author_email: commit.author().email().unwrap_or("None").into(),
+            author_time: commit.author().when(),
             committer: commit.committer().name().unwrap_or("None").into(),
+            committer_email: commit.committer().email().unwrap_or("None").into(),
             commit_id: commit.id(),
+            parent_ids: commit.parent_ids().collect(),
             message: commit.message().unwrap_or("").to_string(),
+            graph: String::new(),
+            first_parent_distance: 0,
+            is_bot: false,
+            annotations: Vec::new(),
+            wip: None,
+            replaces: None,
+            first_seen: None,
         }
     }
 
+    /// a pseudo-commit of the changes to the tracked files of the
+    /// repository which aren't committed yet, if there are any
+    fn uncommitted(
+        repo: Arc<Repo>,
+        git_repo: &Repository,
+    ) -> Result<Option<RepoCommit>, git2::Error> {
+        let statuses = git_repo.statuses(Some(
+            StatusOptions::new()
+                .include_untracked(false)
+                .include_ignored(false)
+                .exclude_submodules(true),
+        ))?;
+        if statuses.is_empty() {
+            return Ok(None);
+        }
+        let head = git_repo.head()?.peel_to_commit()?;
+        let now = chrono::Local::now();
+        let now = Time::new(now.timestamp(), now.offset().local_minus_utc() / 60);
+        let (name, email) = match git_repo.signature() {
+            Ok(signature) => (
+                signature.name().unwrap_or("None").to_string(),
+                signature.email().unwrap_or("None").to_string(),
+            ),
+            Err(_) => (String::from("None"), String::from("None")),
+        };
+        let summary = format!("Uncommitted changes ({} files)", statuses.len());
+        Ok(Some(RepoCommit {
+            repo,
+            commit_time: now,
+            message: summary.clone(),
+            summary,
+            author_name: name.clone(),
+            author_email: email.clone(),
+            author_time: now,
+            committer: name,
+            committer_email: email,
+            commit_id: Oid::zero(),
+            parent_ids: vec![head.id()],
+            graph: String::new(),
+            first_parent_distance: 0,
+            is_bot: false,
+            annotations: Vec::new(),
+            wip: Some(Wip::Uncommitted),
+            replaces: None,
+            first_seen: None,
+        }))
+    }
+
+    pub fn is_merge(&self) -> bool {
+        self.parent_ids.len() > 1
+    }
+
+    /// true if the commit came in via a merged side branch
+    pub fn is_on_side_branch(&self) -> bool {
+        self.first_parent_distance > 0
+    }
+
+    /// time passed since the commit has been created
+    pub fn age(&self) -> Duration {
+        chrono::Utc::now().signed_duration_since(as_datetime_utc(&self.commit_time))
+    }
+
+    /// true if the commit is dated in the future, i.e. it has been created
+    /// on a machine with a wrong clock
+    pub fn is_future_dated(&self) -> bool {
+        self.age() < -Duration::minutes(FUTURE_TOLERANCE_MINUTES)
+    }
+
     pub fn time_as_str(&self) -> String {
-        let date_time = as_datetime(&self.commit_time);
-        let offset = Duration::seconds(i64::from(date_time.offset().local_minus_utc()));
+        format_time(&self.commit_time)
+    }
+
+    /// the author date, which differs from the commit date e.g. for
+    /// cherry-picked or rebased commits
+    pub fn author_time_as_str(&self) -> String {
+        format_time(&self.author_time)
+    }
 
+    /// when the commit landed in the workspace, empty if unknown
+    pub fn first_seen_as_str(&self) -> String {
+        self.first_seen
+            .as_ref()
+            .map(format_time)
+            .unwrap_or_default()
+    }
+
+    pub fn date_as_str(&self) -> String {
+        let date_time = as_datetime(&self.commit_time);
         format!(
-            "{:04}-{:02}-{:02} {:02}:{:02} {:+02}{:02}",
+            "{:04}-{:02}-{:02}",
             date_time.year(),
             date_time.month(),
-            date_time.day(),
-            date_time.hour(),
-            date_time.minute(),
-            offset.num_hours(),
-            offset.num_minutes() - offset.num_hours() * 60
+            date_time.day()
         )
     }
 }
 
+/// formats the given time like `2020-05-17 13:00 +2:00`
+pub fn format_time(time: &Time) -> String {
+    let date_time = as_datetime(time);
+    let offset = Duration::seconds(i64::from(date_time.offset().local_minus_utc()));
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} {:+02}{:02}",
+        date_time.year(),
+        date_time.month(),
+        date_time.day(),
+        date_time.hour(),
+        date_time.minute(),
+        offset.num_hours(),
+        offset.num_minutes() - offset.num_hours() * 60
+    )
+}
+
+impl PartialEq for RepoCommit {
+    fn eq(&self, other: &Self) -> bool {
+        self.commit_id == other.commit_id && self.repo.abs_path == other.repo.abs_path
+    }
+}
+
 impl fmt::Debug for RepoCommit {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(
@@ -214,47 +696,338 @@ impl fmt::Debug for RepoCommit {
     }
 }
 
+/// A pattern given by `--author` or `--message`, matched as case
+/// insensitive substring or, with `--regex`, as regular expression
+#[derive(Clone)]
+enum TextPattern {
+    /// the pattern as given and lowercased, regular expressions are built
+    /// from the former as lowercasing changes escapes like `\D` or `\W`
+    Substring {
+        pattern: String,
+        lowercase: String,
+    },
+    Regex(regex::Regex),
+}
+
+impl TextPattern {
+    fn substring(pattern: &str) -> TextPattern {
+        TextPattern::Substring {
+            pattern: pattern.to_string(),
+            lowercase: pattern.to_lowercase(),
+        }
+    }
+
+    fn to_regex(&self) -> Result<TextPattern, regex::Error> {
+        match self {
+            TextPattern::Substring { pattern, .. } => regex::RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .map(TextPattern::Regex),
+            TextPattern::Regex(_) => Ok(self.clone()),
+        }
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        match self {
+            TextPattern::Substring { lowercase, .. } => text.to_lowercase().contains(lowercase),
+            TextPattern::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+impl fmt::Display for TextPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextPattern::Substring { pattern, .. } => write!(f, "{}", pattern),
+            TextPattern::Regex(regex) => write!(f, "/{}/", regex.as_str()),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Classifier {
     age: u32,
-    author: Option<String>,
-    message: Option<String>,
+    /// commits within this number of days are skipped, used to
+    /// scan only an extension of the day window
+    skipped_age: Option<u32>,
+    author: Option<TextPattern>,
+    message: Option<TextPattern>,
+    /// commits before this time (seconds since the epoch) are skipped
+    since: Option<i64>,
+    /// commits from this time (seconds since the epoch) on are skipped
+    until: Option<i64>,
+    /// if set, the commits reachable from the second ref but not from the
+    /// first one are included instead of the ones of the day window
+    range: Option<(String, String)>,
+    /// branch walked instead of HEAD in repositories which have it
+    branch: Option<String>,
+    /// patterns matching the name or email of bots
+    bots: Vec<Pattern>,
+    /// limits of walking the history of a repository
+    scan: Scan,
+    script: Option<Arc<Script>>,
+    /// include stash entries and uncommitted changes
+    wip: bool,
+    /// HEAD of the repositories (by their absolute path) at the previous run
+    previous_heads: Arc<HashMap<PathBuf, Oid>>,
+    first_seen: Option<Arc<Mutex<FirstSeen>>>,
 }
 
 impl Classifier {
     pub fn new(age: u32, author: Option<&str>, message: Option<&str>) -> Classifier {
         Classifier {
             age,
-            author: author.map(str::to_lowercase),
-            message: message.map(str::to_lowercase),
+            skipped_age: None,
+            author: author.map(TextPattern::substring),
+            message: message.map(TextPattern::substring),
+            since: None,
+            until: None,
+            range: None,
+            branch: None,
+            bots: Vec::new(),
+            scan: Scan::default(),
+            script: None,
+            wip: false,
+            previous_heads: Arc::new(HashMap::new()),
+            first_seen: None,
+        }
+    }
+
+    /// includes the commits in `to` which are not in `from`, regardless
+    /// of their age - like `git log from..to` in each repository
+    pub fn with_range(self, from: &str, to: &str) -> Classifier {
+        Classifier {
+            range: Some((from.to_string(), to.to_string())),
+            ..self
         }
     }
 }
 
 impl Classifier {
+    /// includes only the commits of the given period (seconds since the
+    /// epoch, `until` excluded) within the day window
+    pub fn with_period(self, since: Option<i64>, until: Option<i64>) -> Classifier {
+        Classifier {
+            since,
+            until,
+            ..self
+        }
+    }
+
+    /// walks the history of the given branch instead of HEAD, e.g. `main`
+    /// or `origin/release`; repositories lacking it are walked from HEAD
+    pub fn with_branch(self, branch: Option<&str>) -> Classifier {
+        Classifier {
+            branch: branch.map(String::from),
+            ..self
+        }
+    }
+
+    /// interprets the author and message patterns as (case insensitive)
+    /// regular expressions, e.g. `JIRA-\d+` or `alice|bob`
+    pub fn with_regex(self) -> Result<Classifier, regex::Error> {
+        Ok(Classifier {
+            author: self
+                .author
+                .as_ref()
+                .map(TextPattern::to_regex)
+                .transpose()?,
+            message: self
+                .message
+                .as_ref()
+                .map(TextPattern::to_regex)
+                .transpose()?,
+            ..self
+        })
+    }
+
+    /// commits whose author's name or email matches one of the given
+    /// patterns are marked as commits of bots
+    pub fn with_bots(self, bots: Vec<Pattern>) -> Classifier {
+        Classifier { bots, ..self }
+    }
+
+    /// walks past commits older than the day window by up to the skew
+    /// tolerance, and stops after the maximum number of commits
+    pub fn with_scan(self, scan: Scan) -> Classifier {
+        Classifier { scan, ..self }
+    }
+
+    /// commits left out by the `filter` hook of the script aren't
+    /// included, the others are annotated by its `annotate` hook
+    pub fn with_script(self, script: Option<Arc<Script>>) -> Classifier {
+        Classifier { script, ..self }
+    }
+
+    /// lists the stash entries and the uncommitted changes of each
+    /// repository as pseudo-commits, the latter at the top of the history
+    pub fn with_wip(self, wip: bool) -> Classifier {
+        Classifier { wip, ..self }
+    }
+
+    /// commits which replaced a commit reachable from the given HEAD of
+    /// their repository, i.e. which have been amended or rebased since the
+    /// previous run, are marked by `RepoCommit::replaces`
+    pub fn with_previous_heads(self, previous_heads: HashMap<PathBuf, Oid>) -> Classifier {
+        Classifier {
+            previous_heads: Arc::new(previous_heads),
+            ..self
+        }
+    }
+
+    /// records when commits landed in the workspace, i.e. became reachable
+    /// since the previous run (see `with_previous_heads()`), and sets
+    /// `RepoCommit::first_seen` accordingly
+    pub fn with_first_seen(self, first_seen: FirstSeen) -> Classifier {
+        Classifier {
+            first_seen: Some(Arc::new(Mutex::new(first_seen))),
+            ..self
+        }
+    }
+
+    fn save_first_seen(&self) {
+        if let Some(first_seen) = &self.first_seen {
+            first_seen.lock().unwrap().save();
+        }
+    }
+
+    /// applies the hooks of the script to the included commit, returns
+    /// false if it is left out
+    fn run_script(&self, commit: &mut RepoCommit) -> anyhow::Result<bool> {
+        let script = match &self.script {
+            Some(script) => script,
+            None => return Ok(true),
+        };
+        if !script.filter(commit)? {
+            return Ok(false);
+        }
+        commit.annotations = script.annotate(commit)?;
+        Ok(true)
+    }
+
+    fn is_bot(&self, commit: &RepoCommit) -> bool {
+        self.bots
+            .iter()
+            .any(|bot| bot.is_match(&commit.author_name) || bot.is_match(&commit.author_email))
+    }
+
+    /// the number of days the history is reaching back
+    pub fn days(&self) -> u32 {
+        self.age
+    }
+
+    /// the refs compared, see `with_range()`
+    pub fn range(&self) -> Option<(&str, &str)> {
+        self.range
+            .as_ref()
+            .map(|(from, to)| (from.as_str(), to.as_str()))
+    }
+
+    /// returns a classifier which includes only the commits older than
+    /// the ones included by this classifier, up to the given number of days
+    pub fn extension(&self, days: u32) -> Classifier {
+        Classifier {
+            age: days,
+            skipped_age: Some(self.age),
+            // the extension reaches beyond the start of the period
+            since: None,
+            ..self.clone()
+        }
+    }
+
     fn classify(&self, commit: &Commit) -> (bool, bool) {
-        let utc = as_datetime_utc(&commit.time());
-        let diff = chrono::Utc::now().signed_duration_since(utc);
-        let include = diff.num_days() as u32 <= self.age;
-        let (mut include, abort) = (include, !include);
+        let age = age_in_days(&commit.time());
+        let mut include = self.range.is_some() || age <= self.age;
+        let skew = Duration::hours(i64::from(self.scan.skew_hours));
+        let abort = self.range.is_none() && days_since(&commit.time(), skew) > i64::from(self.age);
+
+        if let Some(skipped_age) = self.skipped_age {
+            include &= age > skipped_age;
+        }
+
+        let time = commit.time().seconds();
+        include &= self.since.is_none_or(|since| time >= since);
+        include &= self.until.is_none_or(|until| time < until);
 
         if let Some(ref message) = self.message {
-            let cm = commit.message().unwrap_or("").to_ascii_lowercase();
-            include &= cm.contains(message);
+            include &= message.is_match(commit.message().unwrap_or(""));
         }
 
         if let Some(ref author) = self.author {
-            let current_author_name = commit.author().name().unwrap_or("").to_ascii_lowercase();
-            let current_author_email = commit.author().email().unwrap_or("").to_ascii_lowercase();
-
-            include &= current_author_name.contains(author) || current_author_email.contains(author);
+            let current_author = commit.author();
+            include &= author.is_match(current_author.name().unwrap_or(""))
+                || author.is_match(current_author.email().unwrap_or(""));
         }
 
         (include, abort)
     }
 }
 
+impl fmt::Display for Classifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.range {
+            Some((from, to)) => write!(f, "{}..{}", from, to)?,
+            None => write!(f, "last {} days", self.age)?,
+        }
+        if let (None, Some(branch)) = (&self.range, &self.branch) {
+            write!(f, " of {}", branch)?;
+        }
+        let date = |time: i64| {
+            chrono::Local
+                .timestamp_opt(time, 0)
+                .single()
+                .map(|time| time.format("%Y-%m-%d").to_string())
+                .unwrap_or_default()
+        };
+        if let Some(since) = self.since {
+            write!(f, ", since {}", date(since))?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ", before {}", date(until))?;
+        }
+        if let Some(author) = &self.author {
+            write!(f, ", author: {}", author)?;
+        }
+        if let Some(message) = &self.message {
+            write!(f, ", message: {}", message)?;
+        }
+        Ok(())
+    }
+}
+
+/// orders commits newest first; commits of the same time by repository
+/// and ID, so they are listed in the same order by successive runs
+fn newest_first(a: &RepoCommit, b: &RepoCommit) -> std::cmp::Ordering {
+    b.commit_time
+        .seconds()
+        .cmp(&a.commit_time.seconds())
+        .then_with(|| a.repo.rel_path.cmp(&b.repo.rel_path))
+        .then_with(|| a.repo.abs_path.cmp(&b.repo.abs_path))
+        .then_with(|| a.commit_id.cmp(&b.commit_id))
+}
+
+/// the age of the given time in days, 0 for times in the future
+fn age_in_days(time: &Time) -> u32 {
+    days_since(time, Duration::zero()).max(0) as u32
+}
+
+/// the number of days passed since the given time, less the tolerance
+fn days_since(time: &Time, tolerance: Duration) -> i64 {
+    let utc = as_datetime_utc(time);
+    (chrono::Utc::now().signed_duration_since(utc) - tolerance).num_days()
+}
+
 #[derive(Copy, Clone, PartialEq)]
 pub enum RevWalkStrategy {
     FirstParent,
     AllParents,
 }
+
+impl fmt::Display for RevWalkStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RevWalkStrategy::FirstParent => write!(f, "first parent"),
+            RevWalkStrategy::AllParents => write!(f, "all parents"),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use publicsuffix::{List, Psl};
+use std::collections::HashMap;
+
+/// Used for emails without a domain
+const UNKNOWN: &str = "(unknown)";
+
+lazy_static! {
+    /// the suffixes under which domains can be registered, e.g. `com` or
+    /// `co.uk`, see https://publicsuffix.org
+    static ref PUBLIC_SUFFIXES: List = include_str!("../assets/public_suffix_list.dat")
+        .parse()
+        .expect("invalid public suffix list");
+}
+
+/// Derives the organization of authors from the domain of their email,
+/// e.g. `google.com` for `alice@android.google.com`. Domains (and their
+/// subdomains) can be mapped to names in the `[organizations]` section of
+/// the config file.
+pub struct Organizations {
+    names: HashMap<String, String>,
+}
+
+impl Organizations {
+    pub fn new(names: &HashMap<String, String>) -> Self {
+        Organizations {
+            names: names
+                .iter()
+                .map(|(domain, name)| (domain.to_lowercase(), name.clone()))
+                .collect(),
+        }
+    }
+
+    /// the organization of the author with the given email
+    pub fn of(&self, email: &str) -> String {
+        let domain = match email.rsplit_once('@') {
+            Some((_, domain)) if !domain.is_empty() => domain.to_lowercase(),
+            _ => return String::from(UNKNOWN),
+        };
+        let mut parent = domain.as_str();
+        loop {
+            if let Some(name) = self.names.get(parent) {
+                return name.clone();
+            }
+            match parent.split_once('.') {
+                Some((_, rest)) if rest.contains('.') => parent = rest,
+                _ => break,
+            }
+        }
+        base_domain(&domain).to_string()
+    }
+}
+
+/// the registrable domain, i.e. the domain without its subdomains, e.g.
+/// `corp.co.uk` for `dev.corp.co.uk`
+fn base_domain(domain: &str) -> &str {
+    match PUBLIC_SUFFIXES.domain(domain.as_bytes()) {
+        Some(base) => &domain[domain.len() - base.as_bytes().len()..],
+        None => domain,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_organizations_from_domains() {
+        let mut names = HashMap::new();
+        names.insert(String::from("Example.com"), String::from("Example Corp"));
+        let organizations = Organizations::new(&names);
+
+        assert_eq!(organizations.of("alice@android.google.com"), "google.com");
+        assert_eq!(organizations.of("bob@Google.com"), "google.com");
+        assert_eq!(organizations.of("carol@dev.corp.co.uk"), "corp.co.uk");
+        assert_eq!(
+            organizations.of("carl@shop.example.com.au"),
+            "example.com.au"
+        );
+        assert_eq!(organizations.of("clara@research.ibm.de"), "ibm.de");
+        assert_eq!(organizations.of("dave@mail.example.com"), "Example Corp");
+        assert_eq!(organizations.of("eve@example.com"), "Example Corp");
+        assert_eq!(organizations.of("localhost"), UNKNOWN);
+        assert_eq!(organizations.of("root@localhost"), "localhost");
+    }
+}
@@ -0,0 +1,87 @@
+use crate::utils::find_repo_base_folder;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::path::Path;
+
+/// Name of the file mapping project paths to teams, looked up in the root
+/// folder of the workspace unless configured otherwise
+const OWNERS_FILE: &str = "owners.toml";
+
+/// Maps the local paths of projects (or of parent folders) to the teams
+/// owning them, e.g. `"kernel" = "Kernel Team"`
+#[derive(Debug, Default)]
+pub struct Owners {
+    teams: HashMap<String, String>,
+}
+
+impl Owners {
+    /// reads the given owners file or, if none is given, the one in the
+    /// root folder of the workspace if it exists
+    pub fn load(file: Option<&Path>) -> Result<Owners> {
+        let text = match file {
+            Some(file) => read_to_string(file)
+                .with_context(|| format!("Failed to read owners from {}", file.display()))?,
+            None => match find_repo_base_folder()
+                .ok()
+                .and_then(|folder| read_to_string(folder.join(OWNERS_FILE)).ok())
+            {
+                Some(text) => text,
+                None => return Ok(Owners::default()),
+            },
+        };
+        Self::parse(&text)
+    }
+
+    fn parse(text: &str) -> Result<Owners> {
+        let teams: HashMap<String, String> =
+            toml::from_str(text).context("Failed to parse the owners")?;
+        Ok(Owners {
+            teams: teams
+                .into_iter()
+                .map(|(path, team)| (path.trim_matches('/').to_string(), team))
+                .collect(),
+        })
+    }
+
+    /// the team owning the project with the given local path, given by
+    /// the longest mapped path the project is part of
+    pub fn team_of(&self, rel_path: &str) -> Option<String> {
+        let mut path = rel_path.trim_matches('/');
+        loop {
+            if let Some(team) = self.teams.get(path) {
+                return Some(team.clone());
+            }
+            path = path.rsplit_once('/')?.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_paths_to_teams() {
+        let owners = Owners::parse(
+            r#"
+            "kernel/" = "Kernel"
+            "kernel/msm/drivers" = "Drivers"
+            "platform/build" = "Build"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(owners.team_of("kernel/msm"), Some(String::from("Kernel")));
+        assert_eq!(
+            owners.team_of("kernel/msm/drivers/gpu"),
+            Some(String::from("Drivers"))
+        );
+        assert_eq!(
+            owners.team_of("platform/build"),
+            Some(String::from("Build"))
+        );
+        assert_eq!(owners.team_of("platform/builder"), None);
+        assert_eq!(owners.team_of("kernelx"), None);
+        assert!(Owners::parse("kernel = 1").is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use crate::model::RepoCommit;
+use anyhow::{anyhow, Context, Result};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// exports the given commit as patch mail (`git format-patch`), which
+/// keeps its author and message when applied elsewhere
+pub fn format(commit: &RepoCommit) -> Result<Vec<u8>> {
+    if commit.commit_id.is_zero() {
+        return Err(anyhow!("Uncommitted changes cannot be exported as patch"));
+    }
+    git(
+        &commit.repo.abs_path,
+        &[
+            "format-patch",
+            "-1",
+            "--stdout",
+            &commit.commit_id.to_string(),
+        ],
+        None,
+    )
+}
+
+/// checks whether the patch applies to the work tree of the given
+/// repository without touching it, returns its diffstat
+pub fn check(patch: &[u8], target: &Path) -> Result<String> {
+    git(target, &["apply", "--check", "--stat", "-"], Some(patch))
+        .map(|stat| String::from_utf8_lossy(&stat).into_owned())
+}
+
+/// commits the patch to the current branch of the given repository
+/// (`git am -3`), the attempt is aborted if it doesn't apply. Refuses to
+/// start while another `git am` or rebase is in progress in the
+/// repository, which the abort would throw away.
+pub fn apply(patch: &[u8], target: &Path) -> Result<String> {
+    let rebase_apply = git(target, &["rev-parse", "--git-path", "rebase-apply"], None)?;
+    let rebase_apply = target.join(String::from_utf8_lossy(&rebase_apply).trim());
+    if rebase_apply.exists() {
+        return Err(anyhow!(
+            "A git am or rebase is in progress in {}, finish or abort it first",
+            target.display()
+        ));
+    }
+    match git(target, &["am", "-3"], Some(patch)) {
+        Ok(output) => Ok(String::from_utf8_lossy(&output).into_owned()),
+        Err(error) => {
+            let _ = git(target, &["am", "--abort"], None);
+            Err(error)
+        }
+    }
+}
+
+fn git(dir: &Path, args: &[&str], input: Option<&[u8]>) -> Result<Vec<u8>> {
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run git")?;
+    if let Some(input) = input {
+        // git reads the whole patch before answering
+        child.stdin.take().unwrap().write_all(input)?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed in {}: {}",
+            args[0],
+            dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
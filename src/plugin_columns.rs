@@ -0,0 +1,119 @@
+use crate::config::PluginColumn;
+use crate::model::RepoCommit;
+use crate::utils::shell_command;
+use cursive::CbSink;
+use git2::Oid;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Shown while the value of a plugin column is being computed
+const LOADING: &str = "…";
+
+type Values = Arc<Mutex<HashMap<(usize, Oid), String>>>;
+
+/// A run of the command of a plugin column for a commit
+struct Job {
+    column: usize,
+    commit_id: Oid,
+    folder: PathBuf,
+    command: String,
+    /// values of the placeholders of the command
+    placeholders: Vec<(&'static str, String)>,
+}
+
+/// Columns of the commit table whose values are printed by commands (see
+/// `[[column]]` in the config file). The commands run in the background
+/// when a row is drawn for the first time, their output is cached.
+pub struct PluginColumns {
+    commands: Vec<String>,
+    /// one job queue per column, served by as many workers as configured
+    queues: Vec<Sender<Job>>,
+    values: Values,
+    requested: RefCell<HashSet<(usize, Oid)>>,
+}
+
+impl PluginColumns {
+    /// starts the workers of the given columns; they redraw the UI via
+    /// `cb_sink` whenever a value got computed
+    pub fn new(columns: &[PluginColumn], cb_sink: CbSink) -> Self {
+        let values = Values::default();
+        let queues = columns
+            .iter()
+            .map(|column| {
+                let (sender, receiver) = mpsc::channel();
+                let receiver = Arc::new(Mutex::new(receiver));
+                for _ in 0..column.jobs.max(1) {
+                    let (receiver, values, cb_sink) =
+                        (receiver.clone(), values.clone(), cb_sink.clone());
+                    thread::spawn(move || work(&receiver, &values, &cb_sink));
+                }
+                sender
+            })
+            .collect();
+        PluginColumns {
+            commands: columns.iter().map(|c| c.command.clone()).collect(),
+            queues,
+            values,
+            requested: RefCell::new(HashSet::new()),
+        }
+    }
+
+    /// the value of the given column for the commit, its command is run in
+    /// the background on first access
+    pub fn value(&self, column: usize, commit: &RepoCommit) -> String {
+        let key = (column, commit.commit_id);
+        if let Some(value) = self.values.lock().unwrap().get(&key) {
+            return value.clone();
+        }
+        if self.requested.borrow_mut().insert(key) {
+            let _ = self.queues[column].send(Job {
+                column,
+                commit_id: commit.commit_id,
+                folder: commit.repo.abs_path.clone(),
+                command: self.commands[column].clone(),
+                placeholders: vec![
+                    ("id", commit.commit_id.to_string()),
+                    ("repo", commit.repo.rel_path.clone()),
+                    ("author", commit.author_email.clone()),
+                ],
+            });
+        }
+        String::from(LOADING)
+    }
+}
+
+fn work(queue: &Mutex<Receiver<Job>>, values: &Values, cb_sink: &CbSink) {
+    loop {
+        let job = queue.lock().unwrap().recv();
+        let job = match job {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+        let value = run(&job).unwrap_or_default();
+        values
+            .lock()
+            .unwrap()
+            .insert((job.column, job.commit_id), value);
+        // an empty callback makes cursive redraw the table
+        if cb_sink.send(Box::new(|_| {})).is_err() {
+            return;
+        }
+    }
+}
+
+/// the first line printed by the command of the job
+fn run(job: &Job) -> Option<String> {
+    let output = shell_command(&job.command, &job.placeholders)
+        .current_dir(&job.folder)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Some(stdout.lines().next().unwrap_or_default().trim().to_string())
+}
@@ -0,0 +1,124 @@
+use crate::config::APP_INFO;
+use app_dirs::{app_root, AppDataType};
+use hmac::{Hmac, Mac};
+use regex::{Captures, Regex};
+use sha2::Sha256;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+lazy_static! {
+    /// e.g. `Alice <alice@example.com>` of a `Signed-off-by` trailer
+    static ref IDENTITY: Regex =
+        Regex::new(r"(?:[^\s<>:][^<>:\n]*\s+)?<([^<>\s@]+@[^<>\s]+)>").unwrap();
+    static ref EMAIL: Regex = Regex::new(r"[\w.+-]+@[\w-]+(?:\.[\w-]+)+").unwrap();
+    /// e.g. `Reported-by: Alice Smith`, a trailer naming a person without
+    /// an email address
+    static ref NAMED: Regex =
+        Regex::new(r"(?mi)^((?:[\w-]+-by|Cc):[ \t]*)([^<>@\s](?:[^<>@\n]*[^<>@\s])?)[ \t]*$")
+            .unwrap();
+    static ref KEY: Vec<u8> = load_key();
+}
+
+/// the pseudonym of the author or committer of the given email address,
+/// e.g. `author-3f2a9c1b`. It's the same in every report, so activity can
+/// be correlated without revealing the identity.
+pub fn of(email: &str) -> String {
+    pseudonym(&KEY, email)
+}
+
+/// replaces the identities (`Name <email>`) and email addresses within
+/// the given text, e.g. of trailers of a commit message, by their
+/// pseudonyms; so are the names of trailers like `Reported-by: Alice`
+pub fn anonymize(text: &str) -> String {
+    anonymize_with(&KEY, text)
+}
+
+/// a keyed hash (HMAC-SHA256) of the given email address, unlike a plain
+/// hash it cannot be reversed by hashing candidate addresses without the
+/// key
+fn pseudonym(key: &[u8], email: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+    mac.update(email.trim().to_lowercase().as_bytes());
+    let hash = mac.finalize().into_bytes();
+    format!(
+        "author-{:02x}{:02x}{:02x}{:02x}",
+        hash[0], hash[1], hash[2], hash[3]
+    )
+}
+
+fn anonymize_with(key: &[u8], text: &str) -> String {
+    let text = NAMED.replace_all(text, |c: &Captures| {
+        format!("{}{}", &c[1], pseudonym(key, &c[2]))
+    });
+    let text = IDENTITY.replace_all(&text, |c: &Captures| pseudonym(key, &c[1]));
+    EMAIL
+        .replace_all(&text, |c: &Captures| pseudonym(key, &c[0]))
+        .into_owned()
+}
+
+/// the key of the pseudonyms, created on first use in oper's data folder
+/// so pseudonyms stay the same across runs. If it cannot be stored, a key
+/// of this run is used.
+fn load_key() -> Vec<u8> {
+    let file = app_root(AppDataType::UserData, &APP_INFO)
+        .ok()
+        .map(|folder| folder.join("pseudonym_key"));
+    if let Some(key) = file.as_ref().and_then(|file| fs::read(file).ok()) {
+        if !key.is_empty() {
+            return key;
+        }
+    }
+    let mut key = vec![0; 32];
+    getrandom::getrandom(&mut key).expect("Failed to generate the key of pseudonyms");
+    if let Some(file) = file {
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        match options.open(&file) {
+            Ok(mut stored) => {
+                if let Err(e) = stored.write_all(&key) {
+                    eprintln!("Failed to store the key of pseudonyms: {}", e);
+                }
+            }
+            // created by another instance of oper meanwhile
+            Err(_) => {
+                if let Ok(stored) = fs::read(&file) {
+                    if !stored.is_empty() {
+                        return stored;
+                    }
+                }
+            }
+        }
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_identities() {
+        let key = b"key";
+        let of = |email| pseudonym(key, email);
+        assert_eq!(of("Alice@Example.com"), of("alice@example.com "));
+        assert_ne!(of("alice@example.com"), of("bob@example.com"));
+        assert_ne!(
+            of("alice@example.com"),
+            pseudonym(b"other", "alice@example.com")
+        );
+        assert_eq!(
+            anonymize_with(
+                key,
+                "Fix\n\nSigned-off-by: Alice Smith <alice@example.com>\nCc: bob@example.com\nReported-by: Carol Jones"
+            ),
+            format!(
+                "Fix\n\nSigned-off-by: {}\nCc: {}\nReported-by: {}",
+                of("alice@example.com"),
+                of("bob@example.com"),
+                of("Carol Jones")
+            )
+        );
+    }
+}
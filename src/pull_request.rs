@@ -0,0 +1,98 @@
+use crate::credentials;
+use regex::Regex;
+use serde::Deserialize;
+
+/// Host of the repositories whose pull requests are known to oper
+pub const GITHUB_HOST: &str = "github.com";
+pub const GITHUB_API: &str = "https://api.github.com";
+/// Seconds to wait for an answer of the GitHub API
+const QUERY_TIMEOUT: &str = "10";
+
+lazy_static! {
+    /// subjects of the commits created by merging a pull request on
+    /// GitHub, either by a merge commit or by squashing its commits
+    static ref MERGE_SUBJECT: Regex =
+        Regex::new(r"^Merge pull request #(\d+)|\(#(\d+)\)$").unwrap();
+}
+
+/// The part of a pull request in the response of the GitHub API used
+#[derive(Deserialize)]
+struct PullRequest {
+    number: u64,
+}
+
+/// returns the number of the pull request which has been merged by the
+/// commit with the given summary, e.g. "Merge pull request #12 from ..."
+/// or "Fix typo (#12)"
+pub fn number_from_summary(summary: &str) -> Option<u64> {
+    let captures = MERGE_SUBJECT.captures(summary.trim_end())?;
+    captures
+        .get(1)
+        .or_else(|| captures.get(2))?
+        .as_str()
+        .parse()
+        .ok()
+}
+
+/// asks the GitHub API for the pull requests associated with the given
+/// commit of the repository with the given path (e.g. `owner/project`),
+/// returns the number of the first one. The token is needed for private
+/// repositories only.
+pub fn query(path: &str, commit_id: &str, token: Option<&str>) -> Result<Option<u64>, String> {
+    let url = format!("{}/repos/{}/commits/{}/pulls", GITHUB_API, path, commit_id);
+    let authorization: Vec<(&str, String)> = token
+        .map(|token| ("header", format!("Authorization: Bearer {}", token)))
+        .into_iter()
+        .collect();
+    let output = credentials::curl(
+        &[
+            "--silent",
+            "--fail",
+            "--max-time",
+            QUERY_TIMEOUT,
+            "--header",
+            "Accept: application/vnd.github+json",
+            &url,
+        ],
+        &authorization,
+    )
+    .map_err(|e| format!("Failed to run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("Failed to query {}: curl {}", url, output.status));
+    }
+    parse_pulls(&output.stdout)
+}
+
+/// the number of the first pull request of the given response
+fn parse_pulls(response: &[u8]) -> Result<Option<u64>, String> {
+    let pulls: Vec<PullRequest> = serde_json::from_slice(response)
+        .map_err(|e| format!("Unexpected response of GitHub: {}", e))?;
+    Ok(pulls.first().map(|pull| pull.number))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_merge_subjects() {
+        assert_eq!(
+            number_from_summary("Merge pull request #42 from me/feature"),
+            Some(42)
+        );
+        assert_eq!(number_from_summary("Fix the build (#1234)"), Some(1234));
+        assert_eq!(number_from_summary("Fix #12 and (#13) later"), None);
+        assert_eq!(number_from_summary("Merge branch 'main'"), None);
+    }
+
+    #[test]
+    fn parses_pulls() {
+        let response = br#"[
+            {"milestone": {"number": 3}, "title": "Fix", "number": 42},
+            {"number": 43}
+        ]"#;
+        assert_eq!(parse_pulls(response), Ok(Some(42)));
+        assert_eq!(parse_pulls(b"[]"), Ok(None));
+        assert!(parse_pulls(b"{\"message\": \"Not Found\"}").is_err());
+    }
+}
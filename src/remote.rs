@@ -0,0 +1,230 @@
+use crate::config::APP_INFO;
+use crate::manifest;
+use anyhow::{anyhow, Context, Result};
+use app_dirs::{app_dir, AppDataType};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// The manifest of a workspace which isn't checked out locally, given by
+/// `--manifest-url`
+pub struct RemoteManifest {
+    pub url: String,
+    /// branch of the manifest repository, its default branch if not given
+    pub branch: Option<String>,
+    /// manifest file, e.g. `default.xml`
+    pub name: String,
+}
+
+/// prepares a workspace in oper's cache folder, holding the manifest
+/// repository and bare mirrors of its projects. Their history is fetched
+/// down to the given day (`YYYY-MM-DD`) only; mirrors of previous runs are
+/// reused and updated. Returns the root folder of the workspace.
+pub fn prepare(manifest: &RemoteManifest, since: &str) -> Result<PathBuf> {
+    let key = format!(
+        "{}@{}",
+        manifest.url,
+        manifest.branch.as_deref().unwrap_or("HEAD")
+    );
+    let key: String = key
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let base_folder = app_dir(AppDataType::UserCache, &APP_INFO, "remote")
+        .context("Failed to create the cache folder")?
+        .join(key);
+    let repo_folder = base_folder.join(".repo");
+
+    eprintln!("Fetching the manifest from {}", manifest.url);
+    let manifests = repo_folder.join("manifests");
+    fetch(
+        &manifests,
+        false,
+        &manifest.url,
+        manifest.branch.as_deref().unwrap_or("HEAD"),
+        since,
+    )?;
+    git(
+        &manifests,
+        &["checkout", "-q", "--force", "--detach", "FETCH_HEAD"],
+    )?;
+    fs::write(
+        repo_folder.join("manifest.xml"),
+        format!(
+            "<manifest><include name=\"{}\"/></manifest>\n",
+            manifest.name
+        ),
+    )?;
+
+    let remotes = manifest::remotes(&base_folder)?;
+    let projects = manifest::read(&base_folder)?;
+    let mut fetched = Vec::new();
+    for (index, project) in projects.iter().enumerate() {
+        eprintln!(
+            "Fetching {} ({}/{})",
+            project.path,
+            index + 1,
+            projects.len()
+        );
+        let url = match project.remote.as_ref().and_then(|r| remotes.get(r)) {
+            Some(fetch_url) => format!("{}{}", resolve(&manifest.url, fetch_url), project.name),
+            None => {
+                eprintln!("  skipped, the manifest lacks its remote");
+                continue;
+            }
+        };
+        let folder = match folder_of(&base_folder, &project.path) {
+            Ok(folder) => folder,
+            Err(error) => {
+                eprintln!("  skipped: {:#}", error);
+                continue;
+            }
+        };
+        let existed = folder.exists();
+        let result = fetch(&folder, true, &url, &project.revision, since).and_then(|_| {
+            git(&folder, &["update-ref", "refs/heads/oper", "FETCH_HEAD"])?;
+            git(&folder, &["symbolic-ref", "HEAD", "refs/heads/oper"])
+        });
+        match result {
+            Ok(()) => fetched.push(project.path.clone()),
+            Err(error) => {
+                eprintln!("  skipped: {:#}", error);
+                // an empty mirror would be taken for a checked out project
+                if !existed {
+                    let _ = fs::remove_dir_all(&folder);
+                }
+            }
+        }
+    }
+    fs::write(
+        repo_folder.join("project.list"),
+        fetched
+            .iter()
+            .map(|path| format!("{}\n", path))
+            .collect::<String>(),
+    )?;
+    Ok(base_folder)
+}
+
+/// the folder of a project within the workspace; paths leaving it (e.g.
+/// `../..` given by the remote manifest) are rejected
+fn folder_of(base_folder: &Path, path: &str) -> Result<PathBuf> {
+    let is_relative = Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if path.is_empty() || !is_relative {
+        return Err(anyhow!("Invalid project path {:?}", path));
+    }
+    Ok(base_folder.join(path))
+}
+
+/// fetches the history of the given revision down to the given day into
+/// the repository at `folder`, which is created if necessary; at least
+/// the commit of the revision is fetched
+fn fetch(folder: &Path, bare: bool, url: &str, revision: &str, since: &str) -> Result<()> {
+    // would be taken for an option of git fetch
+    if revision.starts_with('-') {
+        return Err(anyhow!("Invalid revision {:?}", revision));
+    }
+    if !folder.exists() {
+        fs::create_dir_all(folder)?;
+        let mut init = vec!["init", "-q"];
+        if bare {
+            init.push("--bare");
+        }
+        git(folder, &init)?;
+    }
+    let shallow_since = format!("--shallow-since={}", since);
+    // fails if no commit is recent enough
+    git(
+        folder,
+        &["fetch", "-q", &shallow_since, "--", url, revision],
+    )
+    .or_else(|_| git(folder, &["fetch", "-q", "--depth=1", "--", url, revision]))
+}
+
+fn git(folder: &Path, args: &[&str]) -> Result<()> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(folder)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// the URL the names of projects are appended to, given the fetch URL of
+/// their remote which might be relative to the manifest URL (like git-repo
+/// resolves it)
+fn resolve(manifest_url: &str, fetch_url: &str) -> String {
+    if fetch_url.contains("://") || fetch_url.starts_with('/') {
+        return format!("{}/", fetch_url.trim_end_matches('/'));
+    }
+    // e.g. ["https:", "", "example.com", "platform"] without the manifest
+    // repository itself, the scheme and host are kept
+    let mut segments: Vec<&str> = manifest_url.trim_end_matches('/').split('/').collect();
+    segments.pop();
+    let root = if manifest_url.contains("://") { 3 } else { 1 };
+    for segment in fetch_url.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." if segments.len() > root => {
+                segments.pop();
+            }
+            ".." => {}
+            segment => segments.push(segment),
+        }
+    }
+    format!("{}/", segments.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_fetch_urls() {
+        let manifest_url = "https://example.com/platform/manifest";
+        assert_eq!(resolve(manifest_url, ".."), "https://example.com/");
+        assert_eq!(resolve(manifest_url, "."), "https://example.com/platform/");
+        assert_eq!(
+            resolve(manifest_url, "../vendor/"),
+            "https://example.com/vendor/"
+        );
+        assert_eq!(resolve(manifest_url, "../../.."), "https://example.com/");
+        assert_eq!(resolve("/srv/git/manifest", ".."), "/srv/");
+        assert_eq!(
+            resolve(manifest_url, "https://vendor.com/git"),
+            "https://vendor.com/git/"
+        );
+    }
+
+    #[test]
+    fn rejects_paths_leaving_the_workspace() {
+        let base_folder = Path::new("/cache/remote/ws");
+        assert_eq!(
+            folder_of(base_folder, "kernel/msm").unwrap(),
+            PathBuf::from("/cache/remote/ws/kernel/msm")
+        );
+        assert!(folder_of(base_folder, "../../.ssh").is_err());
+        assert!(folder_of(base_folder, "kernel/../../x").is_err());
+        assert!(folder_of(base_folder, "/etc").is_err());
+        assert!(folder_of(base_folder, "").is_err());
+        assert!(fetch(
+            base_folder,
+            true,
+            "/srv/git/a",
+            "--upload-pack=x",
+            "2020-01-01"
+        )
+        .is_err());
+    }
+}
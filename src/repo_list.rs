@@ -0,0 +1,120 @@
+use crate::model::Repo;
+use crate::utils::as_datetime;
+use anyhow::Result;
+use git2::Repository;
+use serde::Serialize;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Metadata of a project of the workspace, missing values are `None`
+#[derive(Serialize)]
+pub struct RepoInfo {
+    pub path: String,
+    pub remote: Option<String>,
+    /// `None` if HEAD is detached
+    pub branch: Option<String>,
+    pub head: Option<String>,
+    /// RFC 3339 date of the commit at HEAD
+    pub last_commit: Option<String>,
+    pub team: Option<String>,
+}
+
+impl RepoInfo {
+    pub fn of(repo: &Repo) -> RepoInfo {
+        let git_repo = Repository::open(&repo.abs_path).ok();
+        let head = git_repo.as_ref().and_then(|r| r.head().ok());
+        let commit = head.as_ref().and_then(|h| h.peel_to_commit().ok());
+        RepoInfo {
+            path: repo.rel_path.clone(),
+            remote: repo.remote_url().map(String::from),
+            branch: head
+                .as_ref()
+                .filter(|h| h.is_branch())
+                .and_then(|h| h.shorthand())
+                .map(String::from),
+            head: commit.as_ref().map(|c| c.id().to_string()),
+            last_commit: commit.map(|c| as_datetime(&c.time()).to_rfc3339()),
+            team: repo.team.clone(),
+        }
+    }
+
+    fn fields(&self) -> [(&'static str, Option<&str>); 6] {
+        [
+            ("path", Some(self.path.as_str())),
+            ("remote", self.remote.as_deref()),
+            ("branch", self.branch.as_deref()),
+            ("head", self.head.as_deref()),
+            ("last_commit", self.last_commit.as_deref()),
+            ("team", self.team.as_deref()),
+        ]
+    }
+}
+
+/// writes the metadata of the given projects as `csv` or `json`
+pub fn write(repos: &[Arc<Repo>], format: &str, writer: impl Write) -> Result<()> {
+    let infos: Vec<RepoInfo> = repos.iter().map(|repo| RepoInfo::of(repo)).collect();
+    match format {
+        "json" => write_json(&infos, writer),
+        _ => write_csv(&infos, writer),
+    }
+}
+
+fn write_csv(infos: &[RepoInfo], writer: impl Write) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    if let Some(info) = infos.first() {
+        writer.write_record(info.fields().iter().map(|(name, _)| name))?;
+    }
+    for info in infos {
+        writer.write_record(info.fields().iter().map(|(_, value)| value.unwrap_or("")))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_json(infos: &[RepoInfo], mut writer: impl Write) -> Result<()> {
+    serde_json::to_writer_pretty(&mut writer, infos)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_json_and_csv() {
+        let infos = vec![RepoInfo {
+            path: String::from("kernel/msm"),
+            remote: Some(String::from("https://example.com/\"msm\"")),
+            branch: None,
+            head: Some(String::from("abc")),
+            last_commit: Some(String::from("2020-05-17T13:00:00+02:00")),
+            team: None,
+        }];
+
+        let mut json = Vec::new();
+        write_json(&infos, &mut json).unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            r#"[
+  {
+    "path": "kernel/msm",
+    "remote": "https://example.com/\"msm\"",
+    "branch": null,
+    "head": "abc",
+    "last_commit": "2020-05-17T13:00:00+02:00",
+    "team": null
+  }
+]
+"#
+        );
+
+        let mut csv = Vec::new();
+        write_csv(&infos, &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "path,remote,branch,head,last_commit,team\n\
+             kernel/msm,\"https://example.com/\"\"msm\"\"\",,abc,2020-05-17T13:00:00+02:00,\n"
+        );
+    }
+}
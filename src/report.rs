@@ -1,50 +1,369 @@
-use crate::model::MultiRepoHistory;
+use crate::checkpoint::Checkpoint;
+use crate::churn;
+use crate::commit_url::CommitUrlResolver;
+use crate::coupling;
+use crate::i18n::tr;
+use crate::issues::IssueTracker;
+use crate::lint::{self, Linter};
+use crate::model::{format_time, Repo, RepoCommit};
+use crate::pseudonyms;
+use crate::utils::{as_datetime, as_datetime_utc, expand_placeholders};
 use anyhow::{anyhow, Result};
-use std::fs::File;
-use std::path::Path;
+use chrono::{Local, TimeZone, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use git2::Time;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
 
 use spsheet::ods;
+use spsheet::style::Style;
 use spsheet::xlsx;
-use spsheet::{Book, Cell, Sheet};
+use spsheet::{Book, Cell, Sheet, Value};
 
-pub fn generate(model: &MultiRepoHistory, output_file_path: &str) -> Result<()> {
+/// How to deal with an already existing report file
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ReportMode {
+    Overwrite,
+    /// appends the rows to an existing file, CSV reports only
+    Append,
+    FailIfExists,
+}
+
+/// writes the given commits into a report file, its format is derived
+/// from the file's extension; the progress is drawn to the given target.
+/// Returns a summary of what has been written, including the violations
+/// of the lint rules if there are any.
+pub fn generate(
+    commits: &[RepoCommit],
+    output_file_path: &str,
+    mode: ReportMode,
+    details: &Details,
+    draw_target: ProgressDrawTarget,
+) -> Result<String> {
+    let start = Instant::now();
+    let progress = ProgressBar::with_draw_target(commits.len() as u64, draw_target);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template(" {spinner:.bold.cyan}  [{bar:40}] {pos} of {len} records {wide_msg}"),
+    );
+    details.issues.prefetch(commits);
+
+    let report = Report {
+        commits,
+        details,
+        progress: &progress,
+    };
+    let summary = generate_report(&report, output_file_path, mode)?;
+    progress.finish_and_clear();
+    let mut lint_summary = lint::Summary::default();
+    lint_summary.add(details.linter, commits);
+    Ok(format!(
+        "{} in {:.1}s{}",
+        summary,
+        start.elapsed().as_secs_f64(),
+        lint_summary_of(details.linter, &lint_summary)
+    ))
+}
+
+/// the summary of the lint violations as further lines, if there are
+/// rules to check
+fn lint_summary_of(linter: &Linter, summary: &lint::Summary) -> String {
+    if linter.is_active() {
+        format!("\n{}", summary)
+    } else {
+        String::new()
+    }
+}
+
+/// Derives what is reported about commits besides their own fields
+pub struct Details<'a> {
+    pub commit_url: &'a CommitUrlResolver,
+    pub issues: &'a IssueTracker,
+    pub linter: &'a Linter,
+    /// replaces the identities of authors by pseudonyms, see `--anonymize`
+    pub anonymize: bool,
+    /// format code of date cells in .ods and .xlsx reports, see `[report]`
+    /// in the config file
+    pub date_format: &'a str,
+    /// rows per file of .ods and .xlsx reports, see `[report]` in the
+    /// config file
+    pub max_rows: usize,
+}
+
+impl Details<'_> {
+    fn author_name(&self, commit: &RepoCommit) -> String {
+        match self.anonymize {
+            true => pseudonyms::of(&commit.author_email),
+            false => commit.author_name.clone(),
+        }
+    }
+
+    /// the email address of the author, empty if anonymized
+    fn author_email(&self, commit: &RepoCommit) -> String {
+        match self.anonymize {
+            true => String::new(),
+            false => commit.author_email.clone(),
+        }
+    }
+
+    /// e.g. the message of a commit, without the identities it mentions if
+    /// anonymized
+    fn text(&self, text: &str) -> String {
+        match self.anonymize {
+            true => pseudonyms::anonymize(text),
+            false => text.to_string(),
+        }
+    }
+}
+
+/// The commits to write into a report
+struct Report<'a> {
+    commits: &'a [RepoCommit],
+    details: &'a Details<'a>,
+    progress: &'a ProgressBar,
+}
+
+fn generate_report(report: &Report, output_file_path: &str, mode: ReportMode) -> Result<String> {
     let path = Path::new(output_file_path);
-    let extension = path.extension().and_then(|s| s.to_str());
-    if extension.is_none() {
+    let format = format_of(path);
+    if format.is_none() {
         return Err(anyhow!(
-            "Couldn't derive report format from filename. Supported endings are: .csv, .ods, .xlsx"
+            "Couldn't derive report format from filename. Supported endings are: .csv, .csv.gz, .html, .md, .ods, .xlsx, .xml"
         ));
     }
 
-    match extension {
-        Some("csv") => generate_csv(model, path),
-        Some("ods") => generate_ods(model, path),
-        Some("xlsx") => generate_xlsx(model, path),
+    // CSV files are claimed when opened, split spreadsheets all at once
+    if let Some("html" | "md" | "xml") = format {
+        claim(&[path.to_path_buf()], mode)?;
+    }
+    match format {
+        Some("csv" | "csv.gz") => generate_csv(report, path, mode),
+        Some("html" | "md" | "ods" | "xlsx" | "xml") if mode == ReportMode::Append => {
+            Err(anyhow!("Appending is supported for .csv reports only"))
+        }
+        Some("html") => generate_html(report, path),
+        Some("md") => generate_markdown(report, path),
+        Some("xml") => generate_xml(report, path),
+        Some("ods") => generate_ods(report, path, mode),
+        Some("xlsx") => generate_xlsx(report, path, mode),
         _ => Err(anyhow!(
-            "Couldn't derive report format from filename. Supported endings are: .csv, .ods, .xlsx"
+            "Couldn't derive report format from filename. Supported endings are: .csv, .csv.gz, .html, .md, .ods, .xlsx, .xml"
         )),
     }
 }
 
+/// true if an interrupted report can be resumed, see `Checkpoint`: it's
+/// an uncompressed CSV file written while scanning
+pub fn is_resumable(output_file_path: &str) -> bool {
+    format_of(Path::new(output_file_path)) == Some("csv")
+}
+
+/// true if the report file is a (compressed) CSV file
+pub fn is_csv(output_file_path: &str) -> bool {
+    matches!(
+        format_of(Path::new(output_file_path)),
+        Some("csv" | "csv.gz")
+    )
+}
+
+/// the format of a report file derived from its extension, e.g. `xlsx`,
+/// or `csv.gz` for a compressed one
+fn format_of(path: &Path) -> Option<&str> {
+    match path.extension()?.to_str()? {
+        "gz" => match Path::new(path.file_stem()?).extension()?.to_str()? {
+            "csv" => Some("csv.gz"),
+            _ => None,
+        },
+        extension => Some(extension),
+    }
+}
+
+/// writes the commits received from the given channel into a CSV file as
+/// soon as they arrive, e.g. while the repositories are still scanned (see
+/// `MultiRepoHistory::stream()`). The rows are grouped by repository. Each
+/// message received is expected to carry the commits of one of the given
+/// number of repositories. The repositories written are recorded by the
+/// given checkpoint, if any, which is removed once the report is complete.
+pub fn generate_csv_streaming(
+    receiver: impl IntoIterator<Item = (Arc<Repo>, Vec<RepoCommit>)>,
+    output_file_path: &str,
+    mode: ReportMode,
+    details: &Details,
+    repos: usize,
+    mut checkpoint: Option<Checkpoint>,
+    draw_target: ProgressDrawTarget,
+) -> Result<String> {
+    let start = Instant::now();
+    let progress = ProgressBar::with_draw_target(repos as u64, draw_target);
+    progress.set_style(ProgressStyle::default_bar().template(
+        " {spinner:.bold.cyan}  Scanned {pos} of {len} repositories, wrote {msg} records",
+    ));
+
+    let path = Path::new(output_file_path);
+    let mut spreadsheet = CommaSeperatedSpreadsheet::new(path, mode)?;
+    if !spreadsheet.appending {
+        add_header(&mut spreadsheet)?;
+    }
+
+    let mut records = 0;
+    let mut lint_summary = lint::Summary::default();
+    for (repo, commits) in receiver {
+        details.issues.prefetch(&commits);
+        for commit in &commits {
+            add_commit(&mut spreadsheet, commit, details)?;
+        }
+        lint_summary.add(details.linter, &commits);
+        records += commits.len();
+        spreadsheet.write_to_disk()?;
+        if let Some(checkpoint) = checkpoint.as_mut() {
+            checkpoint.record(&repo, path.metadata()?.len())?;
+        }
+        progress.set_message(&records.to_string());
+        progress.inc(1);
+    }
+    let description = spreadsheet.description();
+    spreadsheet.finish()?;
+    if let Some(checkpoint) = checkpoint {
+        checkpoint.finish()?;
+    }
+    progress.finish_and_clear();
+
+    Ok(format!(
+        "Wrote {} records {} to {} in {:.1}s{}",
+        records,
+        description,
+        path.display(),
+        start.elapsed().as_secs_f64(),
+        lint_summary_of(details.linter, &lint_summary)
+    ))
+}
+
 trait SpreadSheetBuilder {
     fn add_cell(&mut self, cell: String) -> Result<()>;
+    /// a date, empty if unknown
+    fn add_date(&mut self, time: Option<&Time>) -> Result<()>;
+    fn add_number(&mut self, number: usize) -> Result<()>;
     fn finish_row(&mut self) -> Result<()>;
 }
 
+/// creates the given report files if they must not exist yet: creating a
+/// file with `create_new` fails if it exists, without a gap in which
+/// another process could create it. Files created are removed again if
+/// one of them exists already.
+pub fn claim(paths: &[PathBuf], mode: ReportMode) -> Result<()> {
+    if mode != ReportMode::FailIfExists {
+        return Ok(());
+    }
+    for (index, path) in paths.iter().enumerate() {
+        if let Err(e) = OpenOptions::new().write(true).create_new(true).open(path) {
+            for created in &paths[..index] {
+                let _ = fs::remove_file(created);
+            }
+            return Err(match e.kind() {
+                io::ErrorKind::AlreadyExists => {
+                    anyhow!("Report file {} already exists", path.display())
+                }
+                _ => anyhow!("Failed to create {}: {}", path.display(), e),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The file a CSV report is written to, optionally gzip-compressed
+enum CsvOutput {
+    Plain(File),
+    Compressed(GzEncoder<File>),
+}
+
+impl Write for CsvOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            CsvOutput::Plain(file) => file.write(buf),
+            CsvOutput::Compressed(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            CsvOutput::Plain(file) => file.flush(),
+            CsvOutput::Compressed(encoder) => encoder.flush(),
+        }
+    }
+}
+
 struct CommaSeperatedSpreadsheet {
-    writer: csv::Writer<File>,
+    writer: csv::Writer<CsvOutput>,
+    compressed: bool,
+    /// true if rows are appended to an existing report
+    appending: bool,
 }
 
 impl CommaSeperatedSpreadsheet {
-    pub fn new(output_file_path: &Path) -> Result<Self> {
+    pub fn new(output_file_path: &Path, mode: ReportMode) -> Result<Self> {
+        let appending = mode == ReportMode::Append
+            && output_file_path
+                .metadata()
+                .map(|metadata| metadata.len() > 0)
+                .unwrap_or(false);
+        let file = match mode {
+            ReportMode::FailIfExists => {
+                claim(&[output_file_path.to_path_buf()], mode)?;
+                OpenOptions::new().write(true).open(output_file_path)?
+            }
+            _ => OpenOptions::new()
+                .write(true)
+                .create(true)
+                .append(appending)
+                .truncate(!appending)
+                .open(output_file_path)?,
+        };
+        // appending a gzip member to a gzip file results in a valid gzip file
+        let compressed = format_of(output_file_path) == Some("csv.gz");
+        let output = if compressed {
+            CsvOutput::Compressed(GzEncoder::new(file, Compression::default()))
+        } else {
+            CsvOutput::Plain(file)
+        };
         Ok(CommaSeperatedSpreadsheet {
-            writer: csv::Writer::from_path(&output_file_path)?,
+            writer: csv::Writer::from_writer(output),
+            compressed,
+            appending,
         })
     }
 
     pub fn write_to_disk(&mut self) -> Result<()> {
         Ok(self.writer.flush()?)
     }
+
+    /// writes the remaining rows and completes the compression, if any
+    pub fn finish(self) -> Result<()> {
+        let output = self
+            .writer
+            .into_inner()
+            .map_err(|e| anyhow!("Failed to write .csv file: {}", e.error()))?;
+        match output {
+            CsvOutput::Plain(mut file) => file.flush()?,
+            CsvOutput::Compressed(encoder) => {
+                encoder.finish()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn description(&self) -> &'static str {
+        if self.compressed {
+            "as gzip-compressed comma-separated-values"
+        } else {
+            "as comma-separated-values"
+        }
+    }
 }
 
 impl SpreadSheetBuilder for CommaSeperatedSpreadsheet {
@@ -52,6 +371,15 @@ impl SpreadSheetBuilder for CommaSeperatedSpreadsheet {
         Ok(self.writer.write_field(cell)?)
     }
 
+    /// in the time zone of the commit, e.g. `2020-05-17 13:00 +0200`
+    fn add_date(&mut self, time: Option<&Time>) -> Result<()> {
+        self.add_cell(time.map(format_time).unwrap_or_default())
+    }
+
+    fn add_number(&mut self, number: usize) -> Result<()> {
+        self.add_cell(number.to_string())
+    }
+
     fn finish_row(&mut self) -> Result<()> {
         Ok(self.writer.write_record(None::<&[u8]>)?)
     }
@@ -61,25 +389,46 @@ struct OdsXlsxSpreadsheet {
     sheet: Sheet,
     current_row: usize,
     current_column: usize,
+    date_style: Style,
 }
 
 impl OdsXlsxSpreadsheet {
-    pub fn new() -> Result<Self> {
+    pub fn new(name: &str, date_format: &str) -> Result<Self> {
         Ok(OdsXlsxSpreadsheet {
-            sheet: Sheet::new("oper-delta report"),
+            sheet: Sheet::new(name),
             current_row: 0,
             current_column: 0,
+            date_style: Style::new(escape_date_format(date_format)),
         })
     }
-}
 
-impl SpreadSheetBuilder for OdsXlsxSpreadsheet {
-    fn add_cell(&mut self, cell: String) -> Result<()> {
+    fn add(&mut self, cell: Cell) -> Result<()> {
         self.sheet
-            .add_cell(Cell::str(cell), self.current_row, self.current_column);
+            .add_cell(cell, self.current_row, self.current_column);
         self.current_column += 1;
         Ok(())
     }
+}
+
+impl SpreadSheetBuilder for OdsXlsxSpreadsheet {
+    fn add_cell(&mut self, cell: String) -> Result<()> {
+        self.add(Cell::str(cell))
+    }
+
+    /// in the local time zone, spreadsheets lack time zones
+    fn add_date(&mut self, time: Option<&Time>) -> Result<()> {
+        let time = match time {
+            Some(time) => time,
+            None => return self.add_cell(String::new()),
+        };
+        let local = as_datetime_utc(time).with_timezone(&Local).naive_local();
+        let date = Value::Date(Utc.from_utc_datetime(&local));
+        self.add(Cell::new(date, self.date_style.clone()))
+    }
+
+    fn add_number(&mut self, number: usize) -> Result<()> {
+        self.add(Cell::float(number as f64))
+    }
 
     fn finish_row(&mut self) -> Result<()> {
         self.current_row += 1;
@@ -88,76 +437,714 @@ impl SpreadSheetBuilder for OdsXlsxSpreadsheet {
     }
 }
 
-fn generate_ods(model: &MultiRepoHistory, output_file_path: &Path) -> Result<()> {
-    let mut spreadsheet = OdsXlsxSpreadsheet::new()?;
+/// escapes the literal characters of a date format code (e.g. `-` of
+/// `yyyy-mm-dd`), spsheet knows just `/` and `:` unescaped
+fn escape_date_format(format: &str) -> String {
+    let mut escaped = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_ascii_alphabetic() || c == '/' || c == ':' => escaped.push(c),
+            '\\' => {
+                escaped.push(c);
+                escaped.extend(chars.next());
+            }
+            '"' => {
+                escaped.push(c);
+                for c in chars.by_ref() {
+                    escaped.push(c);
+                    if c == '"' {
+                        break;
+                    }
+                }
+            }
+            c => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+        }
+    }
+    escaped
+}
+
+fn generate_ods(report: &Report, output_file_path: &Path, mode: ReportMode) -> Result<String> {
+    let files = write_books(report, output_file_path, mode, |book, path| {
+        ods::write(book, path).map_err(|e| anyhow!("Failed to write .ods file: {:?}", e))
+    })?;
 
-    model_into_spreadsheet(&model, &mut spreadsheet)?;
+    Ok(format!(
+        "Wrote {} records in Open Document Format to {}",
+        report.commits.len(),
+        files
+    ))
+}
+
+fn generate_xlsx(report: &Report, output_file_path: &Path, mode: ReportMode) -> Result<String> {
+    let files = write_books(report, output_file_path, mode, |book, path| {
+        xlsx::write(book, path).map_err(|e| anyhow!("Failed to write .xlsx file: {:?}", e))
+    })?;
+
+    Ok(format!(
+        "Wrote {} records in MS Excel format to {}",
+        report.commits.len(),
+        files
+    ))
+}
+
+/// writes an .ods or .xlsx report by the given function. Reports of more
+/// than `max_rows` rows are split into numbered files, the last row of
+/// each lists them all; the churn and coupling sheets are part of the
+/// first file. Returns a description of the written files.
+fn write_books(
+    report: &Report,
+    output_file_path: &Path,
+    mode: ReportMode,
+    write: impl Fn(&Book, &Path) -> Result<()>,
+) -> Result<String> {
+    let mut parts: Vec<&[RepoCommit]> = report
+        .commits
+        .chunks(commits_per_part(
+            report.commits.len(),
+            report.details.max_rows,
+        ))
+        .collect();
+    if parts.is_empty() {
+        parts.push(&[]);
+    }
+    let paths = part_paths(output_file_path, parts.len());
+    if paths.len() > 1 {
+        // an unsplit report of an earlier run would be taken for this one
+        claim(
+            &[paths.as_slice(), &[output_file_path.to_path_buf()]].concat(),
+            mode,
+        )?;
+        if let Err(e) = fs::remove_file(output_file_path) {
+            if e.kind() != io::ErrorKind::NotFound {
+                return Err(anyhow!(
+                    "Failed to remove {}: {}",
+                    output_file_path.display(),
+                    e
+                ));
+            }
+        }
+    } else {
+        claim(&paths, mode)?;
+    }
+    let names: Vec<String> = paths.iter().map(|path| file_name_of(path)).collect();
+
+    for (index, (commits, path)) in parts.iter().zip(&paths).enumerate() {
+        let part = Report {
+            commits,
+            details: report.details,
+            progress: report.progress,
+        };
+        let footer = match paths.len() {
+            1 => None,
+            count => Some(format!(
+                "Part {} of {}: {}",
+                index + 1,
+                count,
+                names.join(", ")
+            )),
+        };
+        let mut book = book_of(&part, footer)?;
+        if index == 0 {
+            aggregates_into_book(report, &mut book)?;
+        }
+        report.progress.set_message(&match paths.len() {
+            1 => String::from("- writing file..."),
+            count => format!("- writing file {} of {}...", index + 1, count),
+        });
+        write(&book, path)?;
+    }
+
+    Ok(match paths.as_slice() {
+        [path] => path.display().to_string(),
+        [first, .., last] => format!(
+            "{} files, {} to {}",
+            paths.len(),
+            first.display(),
+            file_name_of(last)
+        ),
+        [] => unreachable!(),
+    })
+}
+
+/// the number of commits per file of an .ods or .xlsx report, such that
+/// no file has more than `max_rows` rows: the header, the commits and, if
+/// split, the footer below an empty row
+fn commits_per_part(commits: usize, max_rows: usize) -> usize {
+    // the commits below the header fit into a single file
+    if commits < max_rows {
+        commits.max(1)
+    } else {
+        max_rows.saturating_sub(3).max(1)
+    }
+}
+
+/// the paths of the files of a report split into the given number of
+/// files, e.g. `report-1.xlsx` and `report-2.xlsx` for `report.xlsx`
+fn part_paths(output_file_path: &Path, count: usize) -> Vec<PathBuf> {
+    if count <= 1 {
+        return vec![output_file_path.to_path_buf()];
+    }
+    let stem = output_file_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = output_file_path
+        .extension()
+        .map(|extension| extension.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    (1..=count)
+        .map(|number| output_file_path.with_file_name(format!("{}-{}.{}", stem, number, extension)))
+        .collect()
+}
+
+fn file_name_of(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// the sheet listing the commits of an .ods or .xlsx report, the footer
+/// is separated from them by an empty row
+fn book_of(report: &Report, footer: Option<String>) -> Result<Book> {
+    let mut spreadsheet = OdsXlsxSpreadsheet::new("oper-delta report", report.details.date_format)?;
+    add_header(&mut spreadsheet)?;
+    commits_into_spreadsheet(report, &mut spreadsheet)?;
+    if let Some(footer) = footer {
+        spreadsheet.finish_row()?;
+        spreadsheet.add_cell(footer)?;
+        spreadsheet.finish_row()?;
+    }
 
     let mut book = Book::new();
     book.add_sheet(spreadsheet.sheet);
-    ods::write(&book, output_file_path)
-        .map_err(|e| anyhow!("Failed to write .ods file: {:?}", e))?;
+    Ok(book)
+}
 
-    println!(
-        "Wrote {} records in Open Document Format to {}",
-        model.commits.len(),
-        output_file_path.display()
-    );
+/// adds the sheets about all commits of a report: their churn by
+/// directory and the coupling of the repositories
+fn aggregates_into_book(report: &Report, book: &mut Book) -> Result<()> {
+    let date_format = report.details.date_format;
+    report.progress.set_message("- computing churn...");
+    let mut churn_sheet = OdsXlsxSpreadsheet::new(tr("Churn"), date_format)?;
+    churn_into_spreadsheet(&churn::of(report.commits), &mut churn_sheet)?;
+
+    let mut coupling_sheet = OdsXlsxSpreadsheet::new(tr("Coupling"), date_format)?;
+    coupling_into_spreadsheet(&coupling::of(report.commits), &mut coupling_sheet)?;
+
+    book.add_sheet(churn_sheet.sheet);
+    book.add_sheet(coupling_sheet.sheet);
     Ok(())
 }
 
-fn generate_xlsx(model: &MultiRepoHistory, output_file_path: &Path) -> Result<()> {
-    let mut spreadsheet = OdsXlsxSpreadsheet::new()?;
+fn generate_csv(report: &Report, output_file_path: &Path, mode: ReportMode) -> Result<String> {
+    let mut spreadsheet = CommaSeperatedSpreadsheet::new(output_file_path, mode)?;
 
-    model_into_spreadsheet(&model, &mut spreadsheet)?;
+    if !spreadsheet.appending {
+        add_header(&mut spreadsheet)?;
+    }
+    commits_into_spreadsheet(report, &mut spreadsheet)?;
 
-    let mut book = Book::new();
-    book.add_sheet(spreadsheet.sheet);
-    xlsx::write(&book, output_file_path)
-        .map_err(|e| anyhow!("Failed to write .xlsx file: {:?}", e))?;
+    let description = spreadsheet.description();
+    spreadsheet.finish()?;
 
-    println!(
-        "Wrote {} records in MS Excel format to {}",
-        model.commits.len(),
+    Ok(format!(
+        "Wrote {} records {} to {}",
+        report.commits.len(),
+        description,
         output_file_path.display()
+    ))
+}
+
+/// writes the commits as XML, the schema is given by assets/report.xsd
+fn generate_xml(report: &Report, output_file_path: &Path) -> Result<String> {
+    let mut writer = BufWriter::new(File::create(output_file_path)?);
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<changes generator="oper {}">"#,
+        env!("CARGO_PKG_VERSION")
+    )?;
+    for commit in report.commits {
+        let url = match report.details.commit_url.resolve(commit) {
+            Some(url) => format!(r#" url="{}""#, escape_xml(&url)),
+            None => String::new(),
+        };
+        let pull_request = match pull_request_url(commit, report.details.commit_url) {
+            Some(url) => format!(r#" pull_request_url="{}""#, escape_xml(&url)),
+            None => String::new(),
+        };
+        let team = match &commit.repo.team {
+            Some(team) => format!(r#" team="{}""#, escape_xml(team)),
+            None => String::new(),
+        };
+        let landed = match &commit.first_seen {
+            Some(time) => format!(r#" landed="{}""#, as_datetime(time).to_rfc3339()),
+            None => String::new(),
+        };
+        writeln!(
+            writer,
+            r#"  <commit id="{}" repo="{}" date="{}"{}{}{}{}>"#,
+            commit.commit_id,
+            escape_xml(&commit.repo.name()),
+            as_datetime(&commit.commit_time).to_rfc3339(),
+            url,
+            pull_request,
+            team,
+            landed
+        )?;
+        writeln!(
+            writer,
+            r#"    <author email="{}" date="{}">{}</author>"#,
+            escape_xml(&report.details.author_email(commit)),
+            as_datetime(&commit.author_time).to_rfc3339(),
+            escape_xml(&report.details.author_name(commit))
+        )?;
+        writeln!(
+            writer,
+            "    <summary>{}</summary>",
+            escape_xml(&report.details.text(&commit.summary))
+        )?;
+        writeln!(
+            writer,
+            "    <message>{}</message>",
+            escape_xml(&report.details.text(&commit.message))
+        )?;
+        for (key, issue) in report.details.issues.issues(commit) {
+            write!(writer, r#"    <issue key="{}""#, escape_xml(&key))?;
+            let issue = issue.ok();
+            if let Some(status) = issue.as_ref().and_then(|issue| issue.status.as_ref()) {
+                write!(writer, r#" status="{}""#, escape_xml(status))?;
+            }
+            let summary = issue.and_then(|issue| issue.summary).unwrap_or_default();
+            writeln!(writer, ">{}</issue>", escape_xml(&summary))?;
+        }
+        for violation in report.details.linter.check(commit) {
+            writeln!(
+                writer,
+                "    <violation>{}</violation>",
+                escape_xml(&violation.to_string())
+            )?;
+        }
+        writeln!(writer, "  </commit>")?;
+        report.progress.inc(1);
+    }
+    writeln!(writer, "</changes>")?;
+    writer.flush()?;
+
+    Ok(format!(
+        "Wrote {} records as XML to {}",
+        report.commits.len(),
+        output_file_path.display()
+    ))
+}
+
+/// writes the commits into a standalone web page, its table is sorted by
+/// clicking a column's header and filtered by a search field
+fn generate_html(report: &Report, output_file_path: &Path) -> Result<String> {
+    let mut rows = String::new();
+    for commit in report.commits {
+        rows.push_str(&html_row_of(commit, report.details));
+        report.progress.inc(1);
+    }
+    let repos: HashSet<&PathBuf> = report
+        .commits
+        .iter()
+        .map(|commit| &commit.repo.abs_path)
+        .collect();
+    let mut summary = expand_placeholders(
+        tr("{commits} commits of {repos} repositories"),
+        &[
+            ("commits", report.commits.len().to_string()),
+            ("repos", repos.len().to_string()),
+        ],
     );
-    Ok(())
+    if let (Some(last), Some(first)) = (report.commits.first(), report.commits.last()) {
+        summary.push_str(&expand_placeholders(
+            tr(" from {first} to {last}"),
+            &[
+                ("first", format_time(&first.commit_time)),
+                ("last", format_time(&last.commit_time)),
+            ],
+        ));
+    }
+    summary.push_str(&expand_placeholders(
+        tr(", generated {date}"),
+        &[("date", Local::now().format("%Y-%m-%d %H:%M").to_string())],
+    ));
+    let header: String = [
+        "Date",
+        "Repository",
+        "Author",
+        "Summary",
+        "Issues",
+        "Team",
+        "Lint Violations",
+    ]
+    .iter()
+    .map(|title| format!("<th>{}</th>", escape_xml(tr(title))))
+    .collect();
+
+    let page = expand_placeholders(
+        include_str!("../assets/report.html"),
+        &[
+            ("version", env!("CARGO_PKG_VERSION").to_string()),
+            ("title", escape_xml(tr("Commit Report"))),
+            ("summary", escape_xml(&summary)),
+            ("header", header),
+            // last, so placeholders within commit messages are kept
+            ("rows", rows),
+        ],
+    );
+    let mut writer = BufWriter::new(File::create(output_file_path)?);
+    writer.write_all(page.as_bytes())?;
+    writer.flush()?;
+
+    Ok(format!(
+        "Wrote {} records as HTML to {}",
+        report.commits.len(),
+        output_file_path.display()
+    ))
 }
 
-fn generate_csv(model: &MultiRepoHistory, output_file_path: &Path) -> Result<()> {
-    let mut spreadsheet = CommaSeperatedSpreadsheet::new(output_file_path)?;
+/// a row of the commit table of HTML reports; the summary links to the
+/// commit and unfolds its message, the repository links to its remote
+fn html_row_of(commit: &RepoCommit, details: &Details) -> String {
+    let link = |text: &str, url: Option<String>| match url {
+        Some(url) => format!(r#"<a href="{}">{}</a>"#, escape_xml(&url), escape_xml(text)),
+        None => escape_xml(text),
+    };
+    let issues: Vec<String> = details
+        .issues
+        .issues(commit)
+        .into_iter()
+        .map(|(key, issue)| escape_xml(&issue.map(|issue| issue.to_string()).unwrap_or(key)))
+        .collect();
+    let violations: Vec<String> = details
+        .linter
+        .check(commit)
+        .iter()
+        .map(|violation| escape_xml(&violation.to_string()))
+        .collect();
+    format!(
+        concat!(
+            "<tr>",
+            r#"<td class="date" data-sort="{}">{}</td>"#,
+            "<td>{}</td>",
+            "<td>{}</td>",
+            "<td><details><summary>{}</summary><pre>{}</pre></details></td>",
+            "<td>{}</td>",
+            "<td>{}</td>",
+            r#"<td class="violations">{}</td>"#,
+            "</tr>\n"
+        ),
+        commit.commit_time.seconds(),
+        format_time(&commit.commit_time),
+        link(&commit.repo.name(), details.commit_url.repo_url(commit)),
+        escape_xml(&details.author_name(commit)),
+        link(
+            &details.text(&commit.summary),
+            details.commit_url.resolve(commit)
+        ),
+        escape_xml(&details.text(&commit.message)),
+        issues.join("<br>"),
+        escape_xml(commit.repo.team.as_deref().unwrap_or_default()),
+        violations.join("\n")
+    )
+}
 
-    model_into_spreadsheet(&model, &mut spreadsheet)?;
+/// writes the commits as Markdown, e.g. to paste them into a wiki page or
+/// the description of a pull request: a section per repository with a
+/// table of its commits, the summaries link to the commits
+fn generate_markdown(report: &Report, output_file_path: &Path) -> Result<String> {
+    let mut writer = BufWriter::new(File::create(output_file_path)?);
+    // by name and path, repositories of several workspaces might share
+    // the relative path
+    let mut repos: Vec<(String, &PathBuf)> = report
+        .commits
+        .iter()
+        .map(|commit| (commit.repo.name(), &commit.repo.abs_path))
+        .collect();
+    repos.sort_unstable();
+    repos.dedup();
 
-    spreadsheet.write_to_disk()?;
+    writeln!(writer, "# {}", tr("Commit Report"))?;
+    for (name, abs_path) in &repos {
+        writeln!(writer, "\n## {}\n", escape_markdown(name))?;
+        writeln!(
+            writer,
+            "| {} | {} | {} | {} | {} |",
+            tr("Date"),
+            tr("Commit"),
+            tr("Author"),
+            tr("Summary"),
+            tr("Issues")
+        )?;
+        writeln!(writer, "| --- | --- | --- | --- | --- |")?;
+        for commit in report
+            .commits
+            .iter()
+            .filter(|commit| commit.repo.abs_path == **abs_path)
+        {
+            let summary = escape_markdown(&report.details.text(&commit.summary));
+            let summary = match report.details.commit_url.resolve(commit) {
+                Some(url) => format!("[{}]({})", summary, url.replace(' ', "%20")),
+                None => summary,
+            };
+            let issues: Vec<String> = report
+                .details
+                .issues
+                .issues(commit)
+                .into_iter()
+                .map(|(key, issue)| {
+                    escape_markdown(&issue.map(|issue| issue.to_string()).unwrap_or(key))
+                })
+                .collect();
+            writeln!(
+                writer,
+                "| {} | `{}` | {} | {} | {} |",
+                format_time(&commit.commit_time),
+                &commit.commit_id.to_string()[..8],
+                escape_markdown(&report.details.author_name(commit)),
+                summary,
+                issues.join("<br>")
+            )?;
+            report.progress.inc(1);
+        }
+    }
+    writer.flush()?;
 
-    println!(
-        "Wrote {} records as comma-separated-values to {}",
-        model.commits.len(),
+    Ok(format!(
+        "Wrote {} records as Markdown to {}",
+        report.commits.len(),
         output_file_path.display()
-    );
+    ))
+}
+
+/// escapes the characters with a special meaning within a cell of a
+/// Markdown table, line breaks become spaces
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '\\' | '|' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#' => {
+                escaped.push('\\');
+                escaped.push(ch);
+            }
+            '\n' | '\r' | '\t' => escaped.push(' '),
+            ch if ch.is_control() => {}
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// the URL of the pull request merged by the given commit, as far as
+/// its summary tells (the GitHub API isn't queried for reports)
+fn pull_request_url(commit: &RepoCommit, commit_url: &CommitUrlResolver) -> Option<String> {
+    let number = commit_url.pull_request(commit)?;
+    commit_url.pull_request_url(commit, number)
+}
+
+/// escapes the characters with a special meaning in XML, and drops
+/// control characters which are not allowed in XML 1.0
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(ch),
+            ch if ch.is_control() => {}
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn commits_into_spreadsheet(report: &Report, builder: &mut dyn SpreadSheetBuilder) -> Result<()> {
+    for commit in report.commits {
+        add_commit(builder, commit, report.details)?;
+        report.progress.inc(1);
+    }
     Ok(())
 }
 
-fn model_into_spreadsheet(
-    model: &MultiRepoHistory,
+/// lists the lines changed per directory, the most changed first
+fn churn_into_spreadsheet(
+    churn: &churn::Churn,
     builder: &mut dyn SpreadSheetBuilder,
 ) -> Result<()> {
-    builder.add_cell("Commit Date".to_string())?;
-    builder.add_cell("Local Path of Repo".to_string())?;
-    builder.add_cell("Commit Author".to_string())?;
-    builder.add_cell("Summary".to_string())?;
-    builder.add_cell("Message".to_string())?;
+    for title in ["Directory", "Lines Changed", "Added", "Deleted", "Commits"] {
+        builder.add_cell(tr(title).to_string())?;
+    }
     builder.finish_row()?;
-
-    for commit in &model.commits {
-        builder.add_cell(commit.time_as_str())?;
-        builder.add_cell(commit.repo.rel_path.clone())?;
-        builder.add_cell(commit.author_name.to_string())?;
-        builder.add_cell(commit.summary.to_string())?;
-        builder.add_cell(commit.message.to_string())?;
+    for area in &churn.areas {
+        builder.add_cell(area.name.clone())?;
+        builder.add_number(area.lines())?;
+        builder.add_number(area.added)?;
+        builder.add_number(area.deleted)?;
+        builder.add_number(area.commits)?;
         builder.finish_row()?;
     }
+    Ok(())
+}
 
+/// lists the pairs of repositories modified by the same changes, the most
+/// frequently co-changed first
+fn coupling_into_spreadsheet(
+    pairs: &[coupling::Pair],
+    builder: &mut dyn SpreadSheetBuilder,
+) -> Result<()> {
+    for title in ["Repository", "Repository", "Changes", "Coupling (%)"] {
+        builder.add_cell(tr(title).to_string())?;
+    }
+    builder.finish_row()?;
+    for pair in pairs {
+        builder.add_cell(pair.repos.0.clone())?;
+        builder.add_cell(pair.repos.1.clone())?;
+        builder.add_number(pair.changes)?;
+        builder.add_number(pair.coupling)?;
+        builder.finish_row()?;
+    }
     Ok(())
 }
+
+fn add_header(builder: &mut dyn SpreadSheetBuilder) -> Result<()> {
+    builder.add_cell(tr("Commit Date").to_string())?;
+    builder.add_cell(tr("Local Path of Repo").to_string())?;
+    builder.add_cell(tr("Commit Author").to_string())?;
+    builder.add_cell(tr("Summary").to_string())?;
+    builder.add_cell(tr("Message").to_string())?;
+    builder.add_cell(tr("Commit URL").to_string())?;
+    builder.add_cell(tr("Pull Request URL").to_string())?;
+    builder.add_cell(tr("Issues").to_string())?;
+    builder.add_cell(tr("Team").to_string())?;
+    builder.add_cell(tr("Author Date").to_string())?;
+    builder.add_cell(tr("Landed Locally").to_string())?;
+    builder.add_cell(tr("Lint Violations").to_string())?;
+    builder.finish_row()
+}
+
+fn add_commit(
+    builder: &mut dyn SpreadSheetBuilder,
+    commit: &RepoCommit,
+    details: &Details,
+) -> Result<()> {
+    let commit_url = details.commit_url;
+    builder.add_date(Some(&commit.commit_time))?;
+    builder.add_cell(commit.repo.name())?;
+    builder.add_cell(details.author_name(commit))?;
+    builder.add_cell(details.text(&commit.summary))?;
+    builder.add_cell(details.text(&commit.message))?;
+    builder.add_cell(commit_url.resolve(commit).unwrap_or_default())?;
+    builder.add_cell(pull_request_url(commit, commit_url).unwrap_or_default())?;
+    let issues: Vec<String> = details
+        .issues
+        .issues(commit)
+        .into_iter()
+        .map(|(key, issue)| issue.map(|issue| issue.to_string()).unwrap_or(key))
+        .collect();
+    builder.add_cell(issues.join("\n"))?;
+    builder.add_cell(commit.repo.team.clone().unwrap_or_default())?;
+    builder.add_date(Some(&commit.author_time))?;
+    builder.add_date(commit.first_seen.as_ref())?;
+    let violations: Vec<String> = details
+        .linter
+        .check(commit)
+        .iter()
+        .map(|violation| violation.to_string())
+        .collect();
+    builder.add_cell(violations.join("\n"))?;
+    builder.finish_row()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_format_from_extension() {
+        assert_eq!(format_of(Path::new("nightly.csv.gz")), Some("csv.gz"));
+        assert_eq!(format_of(Path::new("report.xlsx")), Some("xlsx"));
+        assert_eq!(format_of(Path::new("report.ods.gz")), None);
+        assert_eq!(format_of(Path::new("report")), None);
+        assert!(is_csv("nightly.csv.gz") && is_csv("report.csv"));
+    }
+
+    #[test]
+    fn counts_header_and_footer_rows() {
+        assert_eq!(commits_per_part(9, 10), 9);
+        assert_eq!(commits_per_part(0, 10), 1);
+        // 7 commits, the header, an empty row and the footer
+        assert_eq!(commits_per_part(10, 10), 7);
+        assert_eq!(commits_per_part(10, 2), 1);
+    }
+
+    #[test]
+    fn numbers_split_reports() {
+        assert_eq!(
+            part_paths(Path::new("out/report.xlsx"), 1),
+            vec![PathBuf::from("out/report.xlsx")]
+        );
+        assert_eq!(
+            part_paths(Path::new("out/report.xlsx"), 2),
+            vec![
+                PathBuf::from("out/report-1.xlsx"),
+                PathBuf::from("out/report-2.xlsx")
+            ]
+        );
+    }
+
+    #[test]
+    fn claims_report_files_which_must_not_exist() {
+        let dir = std::env::temp_dir().join(format!("oper-claim-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let paths = vec![dir.join("report-1.ods"), dir.join("report-2.ods")];
+        fs::write(&paths[1], "earlier run").unwrap();
+
+        assert!(claim(&paths, ReportMode::FailIfExists).is_err());
+        // the first file is removed again
+        assert!(!paths[0].exists());
+        assert_eq!(fs::read_to_string(&paths[1]).unwrap(), "earlier run");
+        assert!(claim(&paths, ReportMode::Overwrite).is_ok());
+        assert!(claim(&paths[..1], ReportMode::FailIfExists).is_ok());
+        assert!(paths[0].exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn escapes_date_formats() {
+        assert_eq!(
+            escape_date_format("yyyy-mm-dd hh:mm"),
+            r"yyyy\-mm\-dd\ hh:mm"
+        );
+        assert_eq!(
+            escape_date_format(r#"dd\.mm. "at" hh"#),
+            r#"dd\.mm\.\ "at"\ hh"#
+        );
+    }
+
+    #[test]
+    fn escapes_markdown() {
+        assert_eq!(
+            escape_markdown("Fix a|b for `*_init` [#12]\nmore"),
+            r"Fix a\|b for \`\*\_init\` \[\#12\] more"
+        );
+    }
+
+    #[test]
+    fn escapes_xml() {
+        assert_eq!(
+            escape_xml("Fix <a> & \"b\"\u{1b}\n"),
+            "Fix &lt;a&gt; &amp; &quot;b&quot;\n"
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use git2::{Oid, Repository};
+use std::collections::HashMap;
+
+/// Number of commits compared on either side of a rewrite, only recent
+/// commits are amended or rebased in practice
+const MAX_COMPARED_COMMITS: usize = 100;
+
+/// A commit as far as needed to find the one replacing it
+#[derive(Clone, Debug, PartialEq)]
+struct Identity {
+    id: Oid,
+    author_email: String,
+    /// seconds since the epoch, kept by amending and rebasing
+    author_time: i64,
+}
+
+/// finds the commits which replaced commits visible in an earlier run,
+/// i.e. reachable from the HEAD recorded back then (see `Fingerprints`)
+/// but not from the current one. Returns the replaced commit by the ID
+/// of its successor. Amends are followed through the reflog of HEAD,
+/// rebased commits are recognized by their author and author date.
+pub fn successors(git_repo: &Repository, previous_head: Oid) -> HashMap<Oid, Oid> {
+    let head = match git_repo.head().ok().and_then(|head| head.target()) {
+        Some(head) => head,
+        None => return HashMap::new(),
+    };
+    if head == previous_head
+        || git_repo
+            .graph_descendant_of(head, previous_head)
+            .unwrap_or(true)
+    {
+        return HashMap::new();
+    }
+    let replaced = commits_between(git_repo, previous_head, head);
+    let candidates = commits_between(git_repo, head, previous_head);
+    match_successors(&replaced, &candidates, &amends(git_repo))
+}
+
+/// the most recent commits reachable from `from` but not from `hidden`
+fn commits_between(git_repo: &Repository, from: Oid, hidden: Oid) -> Vec<Identity> {
+    let mut revwalk = match git_repo.revwalk() {
+        Ok(revwalk) => revwalk,
+        Err(_) => return Vec::new(),
+    };
+    if revwalk
+        .push(from)
+        .and_then(|_| revwalk.hide(hidden))
+        .is_err()
+    {
+        return Vec::new();
+    }
+    revwalk
+        .flatten()
+        .take(MAX_COMPARED_COMMITS)
+        .filter_map(|id| {
+            let commit = git_repo.find_commit(id).ok()?;
+            let author = commit.author();
+            Some(Identity {
+                id,
+                author_email: author.email().unwrap_or_default().to_string(),
+                author_time: author.when().seconds(),
+            })
+        })
+        .collect()
+}
+
+/// the amended commits by the ID of the commit amending them, as
+/// recorded by the reflog of HEAD
+fn amends(git_repo: &Repository) -> HashMap<Oid, Oid> {
+    let reflog = match git_repo.reflog("HEAD") {
+        Ok(reflog) => reflog,
+        Err(_) => return HashMap::new(),
+    };
+    reflog
+        .iter()
+        .filter(|entry| {
+            entry
+                .message()
+                .is_some_and(|message| message.starts_with("commit (amend)"))
+        })
+        .map(|entry| (entry.id_old(), entry.id_new()))
+        .collect()
+}
+
+fn match_successors(
+    replaced: &[Identity],
+    candidates: &[Identity],
+    amends: &HashMap<Oid, Oid>,
+) -> HashMap<Oid, Oid> {
+    let mut successors = HashMap::new();
+    for commit in replaced {
+        // an amended commit might have been amended again
+        let mut amended = commit.id;
+        for _ in 0..MAX_COMPARED_COMMITS {
+            match amends.get(&amended) {
+                Some(next) => amended = *next,
+                None => break,
+            }
+        }
+        let successor = candidates
+            .iter()
+            .find(|candidate| candidate.id == amended)
+            .or_else(|| {
+                candidates.iter().find(|candidate| {
+                    candidate.author_time == commit.author_time
+                        && candidate.author_email == commit.author_email
+                })
+            });
+        if let Some(successor) = successor {
+            successors.entry(successor.id).or_insert(commit.id);
+        }
+    }
+    successors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(id: u8, author_email: &str, author_time: i64) -> Identity {
+        Identity {
+            id: Oid::from_bytes(&[id; 20]).unwrap(),
+            author_email: author_email.to_string(),
+            author_time,
+        }
+    }
+
+    #[test]
+    fn matches_amended_and_rebased_commits() {
+        let replaced = [
+            identity(1, "alice@example.com", 100),
+            identity(2, "bob@example.com", 200),
+            identity(3, "carol@example.com", 300),
+        ];
+        let candidates = [
+            identity(4, "alice@example.com", 150),
+            identity(5, "bob@example.com", 200),
+            identity(6, "dave@example.com", 300),
+        ];
+        // 1 has been amended twice, the second time changing its date
+        let mut amends = HashMap::new();
+        amends.insert(replaced[0].id, identity(7, "", 0).id);
+        amends.insert(identity(7, "", 0).id, candidates[0].id);
+
+        let successors = match_successors(&replaced, &candidates, &amends);
+        assert_eq!(successors.len(), 2);
+        assert_eq!(successors[&candidates[0].id], replaced[0].id);
+        assert_eq!(successors[&candidates[1].id], replaced[1].id);
+    }
+}
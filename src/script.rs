@@ -0,0 +1,137 @@
+use crate::model::RepoCommit;
+use anyhow::{anyhow, Context, Result};
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use std::fs::read_to_string;
+use std::path::Path;
+
+/// Limits the number of operations of a hook call, so a script with an
+/// endless loop fails instead of hanging the scan
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// A Rhai script (see `script` in the config file) defining the hooks
+///
+/// - `filter(commit)`, returning false for commits to leave out
+/// - `annotate(commit)`, returning a map of annotations of the commit
+///
+/// Both hooks are optional. They get the commit as map with the fields
+/// `id`, `repo`, `team`, `summary`, `message`, `author_name`,
+/// `author_email`, `committer`, `committer_email`, `time` (seconds since
+/// the epoch), `parents` (number of), `is_merge` and `is_bot`.
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    has_filter: bool,
+    has_annotate: bool,
+}
+
+impl Script {
+    pub fn load(file: &Path) -> Result<Script> {
+        let text = read_to_string(file)
+            .with_context(|| format!("Failed to read the script {}", file.display()))?;
+        Self::compile(&text)
+            .with_context(|| format!("Failed to compile the script {}", file.display()))
+    }
+
+    fn compile(text: &str) -> Result<Script> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast = engine.compile(text).map_err(|e| anyhow!("{}", e))?;
+        let has_hook = |name: &str| {
+            ast.iter_functions()
+                .any(|f| f.name == name && f.params.len() == 1)
+        };
+        let (has_filter, has_annotate) = (has_hook("filter"), has_hook("annotate"));
+        if !has_filter && !has_annotate {
+            return Err(anyhow!(
+                "Neither filter(commit) nor annotate(commit) is defined"
+            ));
+        }
+        Ok(Script {
+            engine,
+            ast,
+            has_filter,
+            has_annotate,
+        })
+    }
+
+    /// false if the `filter` hook leaves the commit out
+    pub fn filter(&self, commit: &RepoCommit) -> Result<bool> {
+        if !self.has_filter {
+            return Ok(true);
+        }
+        self.call("filter", to_map(commit))
+    }
+
+    /// the annotations of the commit returned by the `annotate` hook, as
+    /// pairs of key and value
+    pub fn annotate(&self, commit: &RepoCommit) -> Result<Vec<(String, String)>> {
+        if !self.has_annotate {
+            return Ok(Vec::new());
+        }
+        let annotations: Map = self.call("annotate", to_map(commit))?;
+        Ok(annotations
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect())
+    }
+
+    fn call<T: Clone + Send + Sync + 'static>(&self, hook: &str, commit: Map) -> Result<T> {
+        self.engine
+            .call_fn(&mut Scope::new(), &self.ast, hook, (commit,))
+            .map_err(|e| anyhow!("{}(): {}", hook, e))
+    }
+}
+
+fn to_map(commit: &RepoCommit) -> Map {
+    let mut map = Map::new();
+    let mut set = |key: &str, value: Dynamic| {
+        map.insert(key.into(), value);
+    };
+    set("id", commit.commit_id.to_string().into());
+    set("repo", commit.repo.rel_path.clone().into());
+    set("team", commit.repo.team.clone().unwrap_or_default().into());
+    set("summary", commit.summary.clone().into());
+    set("message", commit.message.clone().into());
+    set("author_name", commit.author_name.clone().into());
+    set("author_email", commit.author_email.clone().into());
+    set("committer", commit.committer.clone().into());
+    set("committer_email", commit.committer_email.clone().into());
+    set("time", commit.commit_time.seconds().into());
+    set("parents", (commit.parent_ids.len() as i64).into());
+    set("is_merge", commit.is_merge().into());
+    set("is_bot", commit.is_bot.into());
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calls_hooks() {
+        let script = Script::compile(
+            r#"
+            fn filter(commit) { !commit.summary.starts_with("WIP") }
+            fn annotate(commit) {
+                if commit.summary.contains("Revert") { #{ kind: "revert", parents: commit.parents } } else { #{} }
+            }
+            "#,
+        )
+        .unwrap();
+        let commit = |summary: &str| {
+            let mut map = Map::new();
+            map.insert("summary".into(), summary.to_string().into());
+            map.insert("parents".into(), Dynamic::from(1_i64));
+            map
+        };
+        assert!(script.call::<bool>("filter", commit("Fix crash")).unwrap());
+        assert!(!script.call::<bool>("filter", commit("WIP: crash")).unwrap());
+        let annotations: Map = script.call("annotate", commit("Revert fix")).unwrap();
+        assert_eq!(annotations["kind"].to_string(), "revert");
+        assert_eq!(annotations["parents"].to_string(), "1");
+        assert!(script.call::<bool>("filter", Map::new()).is_err());
+
+        assert!(Script::compile("fn other(commit) { true }").is_err());
+        assert!(Script::compile("fn filter(commit) {").is_err());
+    }
+}
@@ -0,0 +1,329 @@
+use crate::pull_request::GITHUB_API;
+use anyhow::{anyhow, Context, Result};
+use flate2::read::GzDecoder;
+use regex::Regex;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Repository whose releases are checked for updates
+const RELEASES: &str = "elektronenhirn/oper";
+/// Seconds to wait for the release feed
+const QUERY_TIMEOUT: &str = "10";
+/// Seconds to wait for the download of a release's binary
+const DOWNLOAD_TIMEOUT: &str = "300";
+
+lazy_static! {
+    static ref CHECKSUM: Regex = Regex::new(r"^\s*([0-9a-fA-F]{64})\b").unwrap();
+}
+
+/// The latest release of oper, as told by the GitHub API
+struct Release {
+    /// e.g. `v0.6.0`
+    tag: String,
+    /// page of the release
+    url: String,
+    /// download URLs of the files attached to the release
+    assets: Vec<String>,
+}
+
+/// The parts of a release in the response of the GitHub API used
+#[derive(Deserialize)]
+struct ReleaseResponse {
+    tag_name: String,
+    html_url: Option<String>,
+    #[serde(default)]
+    assets: Vec<AssetResponse>,
+}
+
+#[derive(Deserialize)]
+struct AssetResponse {
+    browser_download_url: String,
+}
+
+/// Checks whether a newer release of oper is available and, unless only
+/// checking, replaces the running executable by its binary for this
+/// platform, once verified against the SHA-256 checksum attached to the
+/// release. Packages (e.g. `.deb`) are left to the package manager.
+pub fn run(check_only: bool) -> Result<()> {
+    let release = latest_release()?;
+    let installed = env!("CARGO_PKG_VERSION");
+    if !is_newer(&release.tag, installed) {
+        println!("oper {} is up to date", installed);
+        return Ok(());
+    }
+    println!(
+        "oper {} is available (installed: {}): {}",
+        release.tag, installed, release.url
+    );
+    if check_only {
+        return Ok(());
+    }
+
+    let asset =
+        asset_for(&release.assets, env::consts::OS, env::consts::ARCH).ok_or_else(|| {
+            anyhow!(
+                "The release has no binary for {} {}, download it from {}",
+                env::consts::OS,
+                env::consts::ARCH,
+                release.url
+            )
+        })?;
+    let checksum = checksum_of(&release.assets, asset).ok_or_else(|| {
+        anyhow!(
+            "The release has no checksum of {}, not installing it unverified",
+            asset
+        )
+    })?;
+    let checksum = download_checksum(checksum)?;
+    let exe = env::current_exe()?;
+    replace_executable(&exe, asset, &checksum)
+        .with_context(|| format!("Failed to replace {}", exe.display()))?;
+    println!("Updated {} to oper {}", exe.display(), release.tag);
+    Ok(())
+}
+
+fn latest_release() -> Result<Release> {
+    let url = format!("{}/repos/{}/releases/latest", GITHUB_API, RELEASES);
+    let output = Command::new("curl")
+        .args([
+            "--silent",
+            "--fail",
+            "--location",
+            "--max-time",
+            QUERY_TIMEOUT,
+        ])
+        .args(["--header", "Accept: application/vnd.github+json"])
+        .arg(&url)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .context("Failed to run curl")?;
+    if !output.status.success() {
+        return Err(anyhow!("Failed to query {}: curl {}", url, output.status));
+    }
+    parse_release(&output.stdout).with_context(|| format!("No release found at {}", url))
+}
+
+fn parse_release(response: &[u8]) -> Result<Release> {
+    let response: ReleaseResponse = serde_json::from_slice(response)?;
+    Ok(Release {
+        tag: response.tag_name,
+        url: response
+            .html_url
+            .unwrap_or_else(|| format!("https://github.com/{}/releases/latest", RELEASES)),
+        assets: response
+            .assets
+            .into_iter()
+            .map(|asset| asset.browser_download_url)
+            .collect(),
+    })
+}
+
+/// true if the version of the given release tag (e.g. `v0.6.0`) is newer
+/// than the installed version
+fn is_newer(tag: &str, installed: &str) -> bool {
+    let numbers = |version: &str| -> Vec<u64> {
+        version
+            .trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|number| number.parse().unwrap_or(0))
+            .collect()
+    };
+    numbers(tag) > numbers(installed)
+}
+
+/// the download URL of the binary of the given platform attached to a
+/// release, e.g. `oper-linux-x86_64.gz`; packages and archives of several
+/// files are skipped
+fn asset_for<'a>(assets: &'a [String], os: &str, arch: &str) -> Option<&'a str> {
+    const PACKAGES: [&str; 6] = [".deb", ".rpm", ".tar.gz", ".zip", ".sha256", ".asc"];
+    assets
+        .iter()
+        .map(String::as_str)
+        .filter(|url| !PACKAGES.iter().any(|package| url.ends_with(package)))
+        .find(|url| {
+            let name = url.rsplit('/').next().unwrap_or_default().to_lowercase();
+            name.contains(os) && name.contains(arch)
+        })
+}
+
+/// the download URL of the checksum of the given asset, e.g.
+/// `oper-linux-x86_64.gz.sha256`
+fn checksum_of<'a>(assets: &'a [String], asset: &str) -> Option<&'a str> {
+    let checksum = format!("{}.sha256", asset);
+    assets
+        .iter()
+        .map(String::as_str)
+        .find(|url| *url == checksum)
+}
+
+/// the SHA-256 checksum in the file at the given URL, as written by
+/// `sha256sum` (`<checksum>  <file>`)
+fn download_checksum(url: &str) -> Result<String> {
+    let output = Command::new("curl")
+        .args([
+            "--silent",
+            "--fail",
+            "--location",
+            "--max-time",
+            QUERY_TIMEOUT,
+        ])
+        .arg(url)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .context("Failed to run curl")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to download {}: curl {}",
+            url,
+            output.status
+        ));
+    }
+    parse_checksum(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| anyhow!("No SHA-256 checksum found in {}", url))
+}
+
+fn parse_checksum(content: &str) -> Option<String> {
+    CHECKSUM
+        .captures(content)
+        .map(|captures| captures[1].to_lowercase())
+}
+
+fn sha256_of(file: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(file)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// downloads the binary next to the executable, verifies it against the
+/// given checksum and moves it over the executable, so it is replaced at
+/// once
+fn replace_executable(exe: &Path, url: &str, checksum: &str) -> Result<()> {
+    let download = exe.with_extension("download");
+    let status = Command::new("curl")
+        .args([
+            "--silent",
+            "--fail",
+            "--location",
+            "--max-time",
+            DOWNLOAD_TIMEOUT,
+        ])
+        .args(["--output".as_ref(), download.as_os_str()])
+        .arg(url)
+        .stdin(Stdio::null())
+        .status()
+        .context("Failed to run curl")?;
+    if !status.success() {
+        let _ = fs::remove_file(&download);
+        return Err(anyhow!("Failed to download {}: curl {}", url, status));
+    }
+    match sha256_of(&download) {
+        Ok(actual) if actual == checksum => {}
+        Ok(actual) => {
+            let _ = fs::remove_file(&download);
+            return Err(anyhow!(
+                "Checksum mismatch of {}: expected {}, got {}",
+                url,
+                checksum,
+                actual
+            ));
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&download);
+            return Err(e.into());
+        }
+    }
+    let new_exe = exe.with_extension("new");
+    let result = unpack(&download, &new_exe, url.ends_with(".gz"))
+        .and_then(|_| fs::set_permissions(&new_exe, fs::metadata(exe)?.permissions()))
+        .and_then(|_| fs::rename(&new_exe, exe));
+    let _ = fs::remove_file(&download);
+    if result.is_err() {
+        let _ = fs::remove_file(&new_exe);
+    }
+    Ok(result?)
+}
+
+fn unpack(download: &Path, binary: &Path, compressed: bool) -> io::Result<()> {
+    let mut output = File::create(binary)?;
+    let mut input = File::open(download)?;
+    match compressed {
+        true => io::copy(&mut GzDecoder::new(input), &mut output)?,
+        false => io::copy(&mut input, &mut output)?,
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_newer_releases() {
+        assert!(is_newer("v0.6.0", "0.5.0"));
+        assert!(is_newer("0.10.0", "0.9.3"));
+        assert!(!is_newer("v0.5.0", "0.5.0"));
+        assert!(!is_newer("v0.4.9-rc1", "0.5.0"));
+
+        let assets = vec![
+            String::from("https://example.com/oper_0.6.0_amd64_focal.deb"),
+            String::from("https://example.com/oper-macos-aarch64.gz"),
+            String::from("https://example.com/oper-linux-x86_64.gz"),
+        ];
+        assert_eq!(
+            asset_for(&assets, "linux", "x86_64"),
+            Some("https://example.com/oper-linux-x86_64.gz")
+        );
+        assert_eq!(asset_for(&assets, "windows", "x86_64"), None);
+    }
+
+    #[test]
+    fn parses_releases() {
+        let release = parse_release(
+            br#"{"tag_name": "v0.6.0", "html_url": "https://github.com/elektronenhirn/oper/releases/tag/v0.6.0",
+                "author": {"html_url": "https://github.com/elektronenhirn"},
+                "assets": [{"name": "oper", "browser_download_url": "https://example.com/oper-linux-x86_64.gz"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(release.tag, "v0.6.0");
+        assert_eq!(
+            release.url,
+            "https://github.com/elektronenhirn/oper/releases/tag/v0.6.0"
+        );
+        assert_eq!(
+            release.assets,
+            vec!["https://example.com/oper-linux-x86_64.gz"]
+        );
+        assert!(parse_release(br#"{"message": "Not Found"}"#).is_err());
+    }
+
+    #[test]
+    fn finds_checksums() {
+        let assets = vec![
+            String::from("https://example.com/oper-linux-x86_64.gz"),
+            String::from("https://example.com/oper-linux-x86_64.gz.sha256"),
+            String::from("https://example.com/oper-macos-aarch64.gz"),
+        ];
+        assert_eq!(
+            checksum_of(&assets, &assets[0]),
+            Some("https://example.com/oper-linux-x86_64.gz.sha256")
+        );
+        assert_eq!(checksum_of(&assets, &assets[2]), None);
+
+        let checksum = "9F86D081884C7D659A2FEAA0C55AD015A3BF4F1B2B0B822CD15D6C15B0F00A08";
+        assert_eq!(
+            parse_checksum(&format!("{}  oper-linux-x86_64.gz\n", checksum)),
+            Some(checksum.to_lowercase())
+        );
+        assert_eq!(parse_checksum("not found"), None);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::model::RepoCommit;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Number of commits, authors and repositories of a group of commits
+pub struct Group {
+    pub name: String,
+    pub commits: usize,
+    pub authors: usize,
+    pub repos: usize,
+}
+
+/// groups the given commits by the given key (e.g. the organization of
+/// their author), the largest group first
+pub fn group_by<F>(commits: &[RepoCommit], key: F) -> Vec<Group>
+where
+    F: Fn(&RepoCommit) -> String,
+{
+    let mut groups: HashMap<String, (usize, HashSet<&str>, HashSet<&Path>)> = HashMap::new();
+    for commit in commits {
+        let group = groups.entry(key(commit)).or_default();
+        group.0 += 1;
+        group.1.insert(commit.author_email.as_str());
+        group.2.insert(commit.repo.abs_path.as_path());
+    }
+    let mut groups: Vec<Group> = groups
+        .into_iter()
+        .map(|(name, (commits, authors, repos))| Group {
+            name,
+            commits,
+            authors: authors.len(),
+            repos: repos.len(),
+        })
+        .collect();
+    groups.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.name.cmp(&b.name)));
+    groups
+}
+
+/// formats the groups as table, the first column titled by `title`
+pub fn to_table(title: &str, groups: &[Group]) -> String {
+    let width = groups
+        .iter()
+        .map(|group| group.name.chars().count())
+        .chain(std::iter::once(title.len()))
+        .max()
+        .unwrap_or(0);
+    let mut table = format!(
+        "{:width$}  {:>7}  {:>7}  {:>5}\n",
+        title,
+        "Commits",
+        "Authors",
+        "Repos",
+        width = width
+    );
+    for group in groups {
+        let _ = writeln!(
+            table,
+            "{:width$}  {:>7}  {:>7}  {:>5}",
+            group.name,
+            group.commits,
+            group.authors,
+            group.repos,
+            width = width
+        );
+    }
+    table
+}
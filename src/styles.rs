@@ -23,4 +23,22 @@ lazy_static! {
         Color::Dark(BaseColor::Magenta),
         Color::Dark(BaseColor::Black),
     );
+    /// commits of bots
+    pub static ref BOT: ColorStyle = ColorStyle::new(
+        Color::Light(BaseColor::Black),
+        Color::Dark(BaseColor::Black),
+    );
+    /// commits listed while the repositories are scanned again
+    pub static ref STALE: ColorStyle = ColorStyle::new(
+        Color::Light(BaseColor::Black),
+        Color::Dark(BaseColor::Black),
+    );
+    /// separator rows, e.g. where a build has been cut
+    pub static ref SEPARATOR: ColorStyle =
+        ColorStyle::new(Color::Dark(BaseColor::Cyan), Color::Dark(BaseColor::Black),);
+    /// matches of the active filter
+    pub static ref MATCH: ColorStyle = ColorStyle::new(
+        Color::Dark(BaseColor::Black),
+        Color::Dark(BaseColor::Yellow),
+    );
 }
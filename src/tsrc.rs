@@ -0,0 +1,66 @@
+use crate::manifest::Project;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Branch of repositories if the manifest names neither branch, tag nor SHA
+const DEFAULT_BRANCH: &str = "master";
+
+/// A tsrc manifest (`manifest.yml`)
+#[derive(Deserialize)]
+struct Manifest {
+    repos: Vec<TsrcRepo>,
+    #[serde(default)]
+    groups: BTreeMap<String, Group>,
+}
+
+#[derive(Deserialize)]
+struct TsrcRepo {
+    /// local path, relative to the root folder of the workspace
+    dest: String,
+    branch: Option<String>,
+    tag: Option<String>,
+    sha1: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Group {
+    /// the `dest` of the repositories of the group
+    repos: Vec<String>,
+}
+
+/// the local path of the clone of the manifest repository, relative to the
+/// root folder of the workspace
+pub fn rel_path() -> String {
+    String::from(".tsrc/manifest")
+}
+
+/// the repositories declared by the manifest of the workspace, with the
+/// groups listing them
+pub fn read(base_folder: &Path) -> Result<Vec<Project>> {
+    let path = base_folder.join(rel_path()).join("manifest.yml");
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read the manifest {}", path.display()))?;
+    let Manifest { repos, groups } = serde_yaml::from_str(&content)
+        .with_context(|| format!("Failed to parse the manifest {}", path.display()))?;
+    Ok(repos
+        .into_iter()
+        .map(|repo| Project {
+            groups: groups
+                .iter()
+                .filter(|(_, group)| group.repos.contains(&repo.dest))
+                .map(|(name, _)| name.clone())
+                .collect(),
+            name: repo.dest.clone(),
+            path: repo.dest,
+            revision: repo
+                .sha1
+                .or(repo.tag)
+                .or(repo.branch)
+                .unwrap_or_else(|| String::from(DEFAULT_BRANCH)),
+            remote: None,
+        })
+        .collect())
+}
@@ -1,38 +1,130 @@
-use crate::config::Config;
+use crate::bisect;
+use crate::builds::Build;
+use crate::churn;
+use crate::config::{self, Config};
+use crate::coupling;
+use crate::crash;
 use crate::cursive::traits::View;
+use crate::diff_cache::DiffCache;
+use crate::fingerprints::Fingerprints;
+use crate::fuzzy;
+use crate::history::InputHistory;
+use crate::i18n::tr;
+use crate::issues::{Issue, IssueTracker};
 use crate::model::{MultiRepoHistory, RepoCommit};
-use crate::utils::execute_on_commit;
-use crate::views::{DiffView, MainView, SeperatorView};
-use cursive::event::{Event, Key};
+use crate::patch;
+use crate::report::{self, ReportMode};
+use crate::stats;
+use crate::utils::{self, copy_to_clipboard, execute_on_commit, expand_placeholders};
+use crate::views::{DiffView, InputView, MainView, SeperatorView};
+use cursive::event::{Event, EventResult, EventTrigger, Key};
 use cursive::theme::{BaseColor, Color, ColorStyle};
 use cursive::traits::Nameable;
 use cursive::traits::Resizable;
-use cursive::views::{Canvas, LayerPosition, LinearLayout};
-use cursive::views::{ResizedView, ViewRef};
+use cursive::traits::Scrollable;
+use cursive::views::{Canvas, Dialog, EditView, HideableView, LayerPosition, LinearLayout};
+use cursive::views::{OnEventView, ResizedView, SelectView, TextView, ViewRef};
 use cursive::Cursive;
 use cursive::CursiveExt;
 use cursive::XY;
+use indicatif::ProgressDrawTarget;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
 use std::default::Default;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 
-fn build_status_bar(
+/// Number of days the day window grows/shrinks by pressing `+`/`-`
+const DAYS_STEP: i64 = 30;
+
+/// Maximum number of matches listed by the fuzzy finder
+const MAX_FUZZY_MATCHES: usize = 200;
+
+/// Callback of a key sequence like `]r`
+type SequenceCallback = Box<dyn Fn(&mut Cursive)>;
+
+/// Content of the status bar
+struct StatusBarState {
     commits: usize,
     repos: usize,
     missing_commits: usize,
+    /// the settings deciding which commits are listed
+    settings: String,
     size: XY<usize>,
-) -> impl cursive::view::View {
-    Canvas::new((commits, repos, missing_commits, size))
-        .with_draw(|(commits, repos, missing_commits, size), printer| {
+    format: Option<String>,
+    /// the formatted text in case a format is configured
+    text: String,
+}
+
+impl StatusBarState {
+    fn new(main_view: &MainView, size: XY<usize>, format: Option<String>) -> StatusBarState {
+        let mut state = StatusBarState {
+            commits: 0,
+            repos: 0,
+            missing_commits: 0,
+            settings: String::new(),
+            size,
+            format,
+            text: String::new(),
+        };
+        state.update(main_view);
+        state
+    }
+
+    fn update(&mut self, main_view: &MainView) {
+        let model = main_view.model();
+        self.commits = model.commits.len();
+        self.repos = model.repos.len();
+        self.missing_commits = model.locally_missing_commits;
+
+        let mut settings = vec![
+            model.classifier.to_string(),
+            model.revwalk_strategy.to_string(),
+        ];
+        if main_view.is_collapsing() {
+            settings.push(String::from(tr("runs folded")));
+        }
+        if let Some(grouping) = main_view.grouping() {
+            settings.push(String::from(grouping));
+        }
+        match main_view.hidden_repos() {
+            0 => {}
+            1 => settings.push(String::from(tr("1 repo hidden by filter"))),
+            n => settings.push(expand_placeholders(
+                tr("{repos} repos hidden by filter"),
+                &[("repos", n.to_string())],
+            )),
+        }
+        self.settings = settings.join(" | ");
+
+        if let Some(format) = &self.format {
+            self.text = main_view.format(format);
+        }
+    }
+}
+
+fn build_status_bar(state: StatusBarState) -> impl cursive::view::View {
+    Canvas::new(state)
+        .with_draw(|state, printer| {
             let style = ColorStyle::new(
                 Color::Dark(BaseColor::Black),
                 Color::Light(BaseColor::Black),
             );
 
             printer.with_style(style, |p| {
-                let text_left = match missing_commits {
-                    0 => format!("Found {} commits across {} repositories", commits, repos),
-                    _ => format!("Found {} commits across {} repositories - {} parent commits not found locally (shallow git clone?)", commits, repos, missing_commits)
+                let placeholders = [
+                    ("commits", state.commits.to_string()),
+                    ("repos", state.repos.to_string()),
+                    ("missing", state.missing_commits.to_string()),
+                ];
+                let text_left = match state.missing_commits {
+                    _ if state.format.is_some() => state.text.clone(),
+                    0 => expand_placeholders(tr("Found {commits} commits across {repos} repositories"), &placeholders),
+                    _ => expand_placeholders(tr("Found {commits} commits across {repos} repositories - {missing} parent commits not found locally (shallow git clone?)"), &placeholders),
                 };
-                let text_right = format!(" [{}x{}]", size.x, size.y);
+                let text_right = format!(" {} [{}x{}]", state.settings, state.size.x, state.size.y);
                 p.print((0, 0), &text_left);
                 let gap: i32 = p.size.x as i32 - text_left.len() as i32 - text_right.len() as i32;
                 if gap > 0 {
@@ -42,17 +134,905 @@ fn build_status_bar(
             });
         })
         .with_required_size(|_model, req| cursive::Vec2::new(req.x, 1))
+        .with_name("statusBar")
 }
 
-fn update(siv: &mut Cursive, index: usize, commits: usize, entry: &RepoCommit) {
-    let mut diff_view: ViewRef<DiffView> = siv.find_name("diffView").unwrap();
-    diff_view.set_commit(&entry);
+/// Builds the pane showing the diff of the selected commit, and on top of
+/// it the (initially hidden) diff of the pinned commit
+fn build_diff_pane(config: &Config, cache: &Arc<Mutex<DiffCache>>) -> impl cursive::view::View {
+    let pinned_pane = LinearLayout::vertical()
+        .child(
+            TextView::new("")
+                .style(ColorStyle::new(
+                    Color::Dark(BaseColor::Black),
+                    Color::Dark(BaseColor::Yellow),
+                ))
+                .with_name("pinnedTitle"),
+        )
+        .child(
+            DiffView::empty()
+                .page_step(config.page_step)
+                .notes(config.notes.clone())
+                .cache(cache.clone())
+                .max_lines(config.diff.max_lines)
+                .with_name("pinnedDiffView")
+                .full_height(),
+        )
+        .child(SeperatorView::horizontal());
+
+    LinearLayout::vertical()
+        .child(
+            HideableView::new(pinned_pane)
+                .hidden()
+                .with_name("pinnedPane"),
+        )
+        .child(
+            DiffView::empty()
+                .page_step(config.page_step)
+                .notes(config.notes.clone())
+                .cache(cache.clone())
+                .prefetch_neighbours(config.diff.prefetch)
+                .max_lines(config.diff.max_lines)
+                .with_name("diffView")
+                .full_height(),
+        )
+}
+
+/// Pins the selected commit, its diff stays visible while browsing other
+/// commits. Unpins it in case the selected commit is already pinned.
+fn toggle_pin(siv: &mut Cursive) {
+    let diff_view: ViewRef<DiffView> = siv.find_name("diffView").unwrap();
+    let selected = diff_view.commit().clone();
+    drop(diff_view);
+
+    let mut pinned_diff_view: ViewRef<DiffView> = siv.find_name("pinnedDiffView").unwrap();
+    let pane_visible = siv
+        .call_on_name("pinnedPane", |pane: &mut HideableView<LinearLayout>| {
+            pane.is_visible()
+        })
+        .unwrap();
+    let already_pinned = pane_visible && *pinned_diff_view.commit() == selected;
+
+    let pin = match selected {
+        Some(commit) if !already_pinned => {
+            pinned_diff_view.set_commit(&commit);
+            siv.call_on_name("pinnedTitle", |title: &mut TextView| {
+                title.set_content(format!(
+                    "Pinned: {} {} - {} (press p to unpin)",
+                    commit.repo.name(),
+                    &commit.commit_id.to_string()[..8],
+                    commit.summary
+                ))
+            });
+            true
+        }
+        _ => false,
+    };
+    drop(pinned_diff_view);
+
+    siv.call_on_name("pinnedPane", |pane: &mut HideableView<LinearLayout>| {
+        pane.set_visible(pin)
+    });
+}
+
+/// Shows the diff of the given commit in a layer covering the whole
+/// screen, closed with `q`
+fn show_diff_fullscreen(
+    siv: &mut Cursive,
+    entry: &RepoCommit,
+    page_step: Option<usize>,
+    notes: &[String],
+    max_lines: usize,
+    cache: &Arc<Mutex<DiffCache>>,
+) {
+    let main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+    let mut diff_view = DiffView::empty()
+        .page_step(page_step)
+        .notes(notes.to_vec())
+        .max_lines(max_lines)
+        .cache(cache.clone());
+    diff_view.set_highlighter(main_view.highlighter());
+    drop(main_view);
+    diff_view.set_commit(entry);
+    siv.add_fullscreen_layer(diff_view.full_screen());
+}
+
+/// Shows a dialog to find a listed commit by fuzzy matching its summary
+/// and repository, the chosen commit gets selected
+fn show_fuzzy_finder(siv: &mut Cursive) {
+    let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+    let candidates: Rc<Vec<(String, RepoCommit)>> = Rc::new(
+        main_view
+            .listed_commits()
+            .into_iter()
+            .map(|commit| {
+                (
+                    format!("{}  {}", commit.repo.name(), commit.summary),
+                    commit,
+                )
+            })
+            .collect(),
+    );
+    drop(main_view);
+
+    let mut matches = SelectView::new().on_submit(|s, commit: &RepoCommit| {
+        crash::record_action(format!("fuzzy match {}", commit.commit_id));
+        choose_fuzzy_match(s, commit)
+    });
+    update_fuzzy_matches(&mut matches, &candidates, "");
+
+    let query = EditView::new()
+        .on_edit(move |s, text, _cursor| {
+            s.call_on_name("fuzzyMatches", |matches: &mut SelectView<RepoCommit>| {
+                update_fuzzy_matches(matches, &candidates, text)
+            });
+        })
+        .on_submit(|s, _text| {
+            crash::record_action("fuzzy query submit".to_string());
+            let selection = s
+                .call_on_name("fuzzyMatches", |matches: &mut SelectView<RepoCommit>| {
+                    matches.selection()
+                })
+                .flatten();
+            if let Some(commit) = selection {
+                choose_fuzzy_match(s, &commit);
+            }
+        });
+    let query = OnEventView::new(InputView::new(query.with_name("fuzzyQuery")))
+        .on_event(Key::Up, |s| {
+            s.call_on_name("fuzzyMatches", |matches: &mut SelectView<RepoCommit>| {
+                matches.select_up(1);
+            });
+        })
+        .on_event(Key::Down, |s| {
+            s.call_on_name("fuzzyMatches", |matches: &mut SelectView<RepoCommit>| {
+                matches.select_down(1);
+            });
+        });
+
+    let size = siv.screen_size();
+    let dialog = Dialog::around(
+        LinearLayout::vertical()
+            .child(query)
+            .child(SeperatorView::horizontal())
+            .child(matches.with_name("fuzzyMatches").scrollable()),
+    )
+    .title(tr("Find commit"));
+    siv.add_layer(
+        OnEventView::new(
+            dialog
+                .fixed_width(size.x * 3 / 4)
+                .max_height(size.y * 3 / 4),
+        )
+        .on_event(Key::Esc, |s| {
+            s.pop_layer();
+        }),
+    );
+}
+
+/// Shows a dialog asking for the path of a report file, the listed
+/// commits are written into it
+fn show_export_dialog(siv: &mut Cursive) {
+    let path = EditView::new().on_submit(export_listed_commits);
+    let dialog = Dialog::around(
+        LinearLayout::vertical()
+            .child(TextView::new(
+                "Export the listed commits to (.csv, .csv.gz, .ods, .xlsx or .xml):",
+            ))
+            .child(
+                InputView::new(path.with_name("exportPath")).history(InputHistory::load("export")),
+            ),
+    )
+    .title(tr("Export"))
+    .dismiss_button(tr("Cancel"))
+    .button(tr("Export"), |s| {
+        let path = s
+            .call_on_name("exportPath", |path: &mut EditView| path.get_content())
+            .unwrap();
+        export_listed_commits(s, &path);
+    });
+    siv.add_layer(
+        OnEventView::new(dialog.fixed_width(siv.screen_size().x / 2)).on_event(Key::Esc, |s| {
+            s.pop_layer();
+        }),
+    );
+}
+
+/// Shows a dialog to save the diff shown for the selected commit, with
+/// its header, to a file or to copy it to the clipboard
+fn show_save_diff_dialog(siv: &mut Cursive) {
+    let path = EditView::new().on_submit(save_diff);
+    let dialog = Dialog::around(
+        LinearLayout::vertical()
+            .child(TextView::new("Save the shown diff to:"))
+            .child(InputView::new(path.with_name("diffPath")).history(InputHistory::load("diff"))),
+    )
+    .title("Save Diff")
+    .dismiss_button("Cancel")
+    .button("Copy", |s| {
+        crash::record_action("copy diff".to_string());
+        s.pop_layer();
+        let text = s
+            .call_on_name("diffView", |diff_view: &mut DiffView| diff_view.text())
+            .unwrap();
+        let mut main_view: ViewRef<MainView> = s.find_name("mainView").unwrap();
+        match copy_to_clipboard(&text) {
+            Ok(()) => main_view.show_message(tr("Copied the diff to the clipboard")),
+            Err(error) => main_view.show_error(tr("Failed to copy the diff"), &error),
+        }
+    })
+    .button("Save", |s| {
+        let path = s
+            .call_on_name("diffPath", |path: &mut EditView| path.get_content())
+            .unwrap();
+        save_diff(s, &path);
+    });
+    siv.add_layer(
+        OnEventView::new(dialog.fixed_width(siv.screen_size().x / 2)).on_event(Key::Esc, |s| {
+            s.pop_layer();
+        }),
+    );
+}
+
+fn save_diff(siv: &mut Cursive, path: &str) {
+    crash::record_action(format!("save diff to {}", path));
+    siv.pop_layer();
+    let text = siv
+        .call_on_name("diffView", |diff_view: &mut DiffView| diff_view.text())
+        .unwrap();
+    let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+    match std::fs::write(path, text) {
+        Ok(()) => main_view.show_message(&format!("Saved the diff to {}", path)),
+        Err(error) => main_view.show_error(&format!("Failed to save {}", path), &error),
+    }
+}
+
+/// Shows a dialog asking for the repository to apply the selected commit
+/// to, `Check` previews whether it applies without touching the target
+fn show_apply_dialog(siv: &mut Cursive) {
+    let diff_view: ViewRef<DiffView> = siv.find_name("diffView").unwrap();
+    let commit = match diff_view.commit().clone() {
+        Some(commit) => commit,
+        None => return,
+    };
+    drop(diff_view);
+
+    let check = {
+        let commit = commit.clone();
+        move |s: &mut Cursive| {
+            let target = s
+                .call_on_name("applyPath", |path: &mut EditView| path.get_content())
+                .unwrap();
+            crash::record_action(format!("check patch against {}", target));
+            let result = patch::format(&commit)
+                .and_then(|patch| patch::check(&patch, Path::new(target.as_str())));
+            let (title, text) = match result {
+                Ok(stat) => ("The commit applies cleanly", stat),
+                Err(error) => ("The commit doesn't apply", format!("{:#}", error)),
+            };
+            s.add_layer(
+                Dialog::around(TextView::new(text).scrollable())
+                    .title(title)
+                    .dismiss_button("Close"),
+            );
+        }
+    };
+    let path = EditView::new().on_submit({
+        let check = check.clone();
+        move |s, _| check(s)
+    });
+    let dialog = Dialog::around(
+        LinearLayout::vertical()
+            .child(TextView::new(format!(
+                "Apply {} {} to the repository at:",
+                &commit.commit_id.to_string()[..8],
+                commit.summary
+            )))
+            .child(
+                InputView::new(path.with_name("applyPath")).history(InputHistory::load("apply")),
+            ),
+    )
+    .title("Apply Patch")
+    .dismiss_button("Cancel")
+    .button("Check", check)
+    .button("Apply", move |s| {
+        let target = s
+            .call_on_name("applyPath", |path: &mut EditView| path.get_content())
+            .unwrap();
+        crash::record_action(format!("apply patch to {}", target));
+        s.pop_layer();
+        let mut main_view: ViewRef<MainView> = s.find_name("mainView").unwrap();
+        main_view.show_message(&format!("Applying the commit to {} …", target));
+
+        // git am might take a while (e.g. running hooks), not to block the UI
+        let commit = commit.clone();
+        let target = target.to_string();
+        let cb_sink = s.cb_sink().clone();
+        std::thread::spawn(move || {
+            let result = patch::format(&commit)
+                .and_then(|patch| patch::apply(&patch, Path::new(target.as_str())));
+            let _ = cb_sink.send(Box::new(move |siv| {
+                let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+                match result {
+                    Ok(_) => main_view.show_message(&format!(
+                        "Applied {} to {}",
+                        &commit.commit_id.to_string()[..8],
+                        target
+                    )),
+                    Err(error) => main_view.show_error("Failed to apply the commit", &error),
+                }
+            }));
+        });
+    });
+    siv.add_layer(
+        OnEventView::new(dialog.fixed_width(siv.screen_size().x / 2)).on_event(Key::Esc, |s| {
+            s.pop_layer();
+        }),
+    );
+}
+
+/// Shows a dialog asking for the name of a branch to create at the
+/// selected commit in its repository; existing branches are kept and
+/// the checkout isn't touched
+fn show_branch_dialog(siv: &mut Cursive) {
+    let diff_view: ViewRef<DiffView> = siv.find_name("diffView").unwrap();
+    let commit = match diff_view.commit().clone() {
+        Some(commit) if !commit.commit_id.is_zero() => commit,
+        _ => return,
+    };
+    drop(diff_view);
+
+    let prompt = format!(
+        "Create a branch at {} {} in {} named:",
+        &commit.commit_id.to_string()[..8],
+        commit.summary,
+        commit.repo.rel_path
+    );
+    let create = Rc::new(move |s: &mut Cursive| {
+        let name = s
+            .call_on_name("branchName", |name: &mut EditView| name.get_content())
+            .unwrap();
+        crash::record_action(format!("create branch {}", name));
+        s.pop_layer();
+        let mut main_view: ViewRef<MainView> = s.find_name("mainView").unwrap();
+        match create_branch(&commit, name.trim()) {
+            Ok(()) => {
+                main_view.show_message(&format!(
+                    "Created branch {} at {} in {}",
+                    name.trim(),
+                    &commit.commit_id.to_string()[..8],
+                    commit.repo.rel_path
+                ));
+                drop(main_view);
+                s.call_on_name("diffView", DiffView::forget_refs);
+            }
+            Err(error) => main_view.show_error("Failed to create the branch", &error),
+        }
+    });
+    let name = EditView::new().on_submit({
+        let create = create.clone();
+        move |s, _| create(s)
+    });
+    let dialog = Dialog::around(
+        LinearLayout::vertical()
+            .child(TextView::new(prompt))
+            .child(name.with_name("branchName")),
+    )
+    .title("Create Branch")
+    .dismiss_button("Cancel")
+    .button("Create", move |s| create(s));
+    siv.add_layer(
+        OnEventView::new(dialog.fixed_width(siv.screen_size().x / 2)).on_event(Key::Esc, |s| {
+            s.pop_layer();
+        }),
+    );
+}
+
+/// Shows the command reproducing the workspace state given by the
+/// selected commit of the manifest repository, which can be run from here
+fn show_checkout_dialog(siv: &mut Cursive, settings: Option<&config::Bisect>) {
+    let diff_view: ViewRef<DiffView> = siv.find_name("diffView").unwrap();
+    let commit = match diff_view.commit().clone() {
+        Some(commit) if !commit.commit_id.is_zero() => commit,
+        _ => return,
+    };
+    drop(diff_view);
+
+    let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+    let command = match bisect::checkout_command(&commit.repo, commit.commit_id, settings) {
+        Ok(Some(command)) => command,
+        Ok(None) => {
+            main_view.show_message(
+                "Workspace states are given by commits of the manifest repository (see -x)",
+            );
+            return;
+        }
+        Err(error) => {
+            main_view.show_error("Failed to prepare the checkout", &error);
+            return;
+        }
+    };
+    drop(main_view);
+
+    let copied = command.to_string();
+    let dialog = Dialog::around(
+        LinearLayout::vertical()
+            .child(TextView::new(format!(
+                "Run in the root folder of the workspace to reproduce the state of {} {}:\n",
+                &commit.commit_id.to_string()[..8],
+                commit.summary
+            )))
+            .child(TextView::new(copied.clone())),
+    )
+    .title("Check Out Workspace State")
+    .dismiss_button("Close")
+    .button("Copy", move |s| {
+        crash::record_action("copy checkout command".to_string());
+        s.pop_layer();
+        let mut main_view: ViewRef<MainView> = s.find_name("mainView").unwrap();
+        match copy_to_clipboard(&copied) {
+            Ok(()) => main_view.show_message(&expand_placeholders(
+                tr("Copied {text}"),
+                &[("text", copied.clone())],
+            )),
+            Err(error) => main_view.show_error(tr("Failed to copy the command"), &error),
+        }
+    })
+    .button("Run", move |s| {
+        crash::record_action("run checkout command".to_string());
+        s.pop_layer();
+        run_checkout(s, command.clone());
+    });
+    siv.add_layer(
+        OnEventView::new(dialog.fixed_width(siv.screen_size().x / 2)).on_event(Key::Esc, |s| {
+            s.pop_layer();
+        }),
+    );
+}
+
+/// runs the checkout command in the background, its outcome is shown in
+/// the commit bar, its error output in a dialog if it fails
+fn run_checkout(siv: &mut Cursive, checkout: bisect::Checkout) {
+    let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+    main_view.show_message(&format!("Running {} …", checkout));
+    let cb_sink = siv.cb_sink().clone();
+    std::thread::spawn(move || {
+        let output = checkout
+            .command()
+            .stdin(std::process::Stdio::null())
+            .output();
+        let (message, errors) = match output {
+            Ok(output) if output.status.success() => {
+                (format!("Finished {}, press F5 to refresh", checkout), None)
+            }
+            Ok(output) => (
+                format!("{} failed with {}", checkout, output.status),
+                Some(String::from_utf8_lossy(&output.stderr).into_owned()),
+            ),
+            Err(error) => (format!("Failed to run {}: {}", checkout, error), None),
+        };
+        let _ = cb_sink.send(Box::new(move |siv| {
+            let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+            main_view.show_message(&message);
+            drop(main_view);
+            if let Some(errors) = errors {
+                siv.add_layer(
+                    Dialog::around(TextView::new(errors).scrollable())
+                        .title(message)
+                        .dismiss_button("Close"),
+                );
+            }
+        }));
+    });
+}
+
+fn create_branch(commit: &RepoCommit, name: &str) -> Result<(), git2::Error> {
+    if !git2::Branch::name_is_valid(name)? {
+        return Err(git2::Error::from_str(&format!(
+            "'{}' is no valid branch name",
+            name
+        )));
+    }
+    let git_repo = git2::Repository::open(&commit.repo.abs_path)?;
+    let target = git_repo.find_commit(commit.commit_id)?;
+    // fails instead of moving a branch which exists already
+    git_repo.branch(name, &target, false).map(|_| ())
+}
+
+fn export_listed_commits(siv: &mut Cursive, path: &str) {
+    crash::record_action(format!("export to {}", path));
+    siv.pop_layer();
+    let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+    match report::generate(
+        &main_view.listed_commits(),
+        path,
+        ReportMode::Overwrite,
+        &report::Details {
+            commit_url: &main_view.commit_url(),
+            issues: &main_view.issues(),
+            linter: &main_view.linter(),
+            anonymize: false,
+            date_format: &main_view.report_date_format(),
+            max_rows: main_view.report_max_rows(),
+        },
+        ProgressDrawTarget::hidden(),
+    ) {
+        Ok(summary) => main_view.show_message(&summary),
+        Err(error) => main_view.show_error("Failed to export", &error),
+    }
+}
+
+/// Copies the URL of the selected commit to the clipboard
+fn copy_commit_url(siv: &mut Cursive) {
+    let diff_view: ViewRef<DiffView> = siv.find_name("diffView").unwrap();
+    let commit = diff_view.commit().clone();
+    drop(diff_view);
+
+    let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+    let url = match commit.and_then(|commit| main_view.commit_url().resolve(&commit)) {
+        Some(url) => url,
+        None => {
+            main_view.show_message("No commit URL available, see commit_url in the config file");
+            return;
+        }
+    };
+    match copy_to_clipboard(&url) {
+        Ok(()) => main_view.show_message(&expand_placeholders(
+            tr("Copied {text}"),
+            &[("text", url.clone())],
+        )),
+        Err(error) => main_view.show_error(
+            &expand_placeholders(tr("Failed to copy {text}"), &[("text", url.clone())]),
+            &error,
+        ),
+    }
+}
+
+/// Opens the code review of the selected commit in the browser, or the
+/// commit itself if there is no review URL configured
+fn open_in_browser(siv: &mut Cursive) {
+    let diff_view: ViewRef<DiffView> = siv.find_name("diffView").unwrap();
+    let commit = diff_view.commit().clone();
+    drop(diff_view);
+
+    let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+    let commit_url = main_view.commit_url();
+    let url = commit.and_then(|commit| {
+        commit_url
+            .review_url(&commit)
+            .or_else(|| commit_url.resolve(&commit))
+    });
+    match url {
+        Some(url) => {
+            if let Err(error) = utils::open_in_browser(&url) {
+                main_view.show_error(&format!("Failed to open {}", url), &error);
+            }
+        }
+        None => {
+            main_view.show_message("No commit URL available, see commit_url in the config file")
+        }
+    }
+}
+
+/// Opens the pull request merged by the selected commit in the browser,
+/// asks GitHub for it in case the summary of the commit doesn't tell
+fn open_pull_request(siv: &mut Cursive) {
+    let diff_view: ViewRef<DiffView> = siv.find_name("diffView").unwrap();
+    let commit = diff_view.commit().clone();
+    drop(diff_view);
+
+    let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+    let commit = match commit {
+        Some(commit) => commit,
+        None => return,
+    };
+    let commit_url = main_view.commit_url();
+    main_view.show_message("Looking up the pull request …");
+    drop(main_view);
+
+    // GitHub is asked in the background, not to block the UI
+    let cb_sink = siv.cb_sink().clone();
+    std::thread::spawn(move || {
+        let url = commit_url
+            .query_pull_request(&commit)
+            .map(|number| number.and_then(|number| commit_url.pull_request_url(&commit, number)));
+        let _ = cb_sink.send(Box::new(move |siv| {
+            let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+            match url {
+                Ok(Some(url)) => match utils::open_in_browser(&url) {
+                    Ok(()) => main_view.show_message(&format!("Opened {}", url)),
+                    Err(error) => main_view.show_error(&format!("Failed to open {}", url), &error),
+                },
+                Ok(None) => main_view.show_message("No pull request found for the selected commit"),
+                Err(error) => main_view.show_error("Failed to find the pull request", &error),
+            }
+        }));
+    });
+}
+
+/// Shows the issues mentioned in the message of the selected commit,
+/// with their summary and status if a JIRA server is configured;
+/// `Enter` opens the selected issue in the browser
+fn show_issues(siv: &mut Cursive) {
+    let diff_view: ViewRef<DiffView> = siv.find_name("diffView").unwrap();
+    let commit = diff_view.commit().clone();
+    drop(diff_view);
+
+    let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+    let commit = match commit {
+        Some(commit) => commit,
+        None => return,
+    };
+    let tracker = main_view.issues();
+    if tracker.keys(&commit).is_empty() {
+        main_view.show_message("No issues mentioned, see [issues] in the config file");
+        return;
+    }
+    main_view.show_message("Looking up the issues …");
+    drop(main_view);
+
+    // JIRA is asked in the background, not to block the UI
+    let cb_sink = siv.cb_sink().clone();
+    std::thread::spawn(move || {
+        tracker.prefetch(std::slice::from_ref(&commit));
+        let issues = tracker.issues(&commit);
+        let _ = cb_sink.send(Box::new(move |siv| {
+            show_issue_list(siv, &commit, tracker, issues)
+        }));
+    });
+}
+
+/// Shows the looked up issues of the given commit in a dialog
+fn show_issue_list(
+    siv: &mut Cursive,
+    commit: &RepoCommit,
+    tracker: Arc<IssueTracker>,
+    issues: Vec<(String, Result<Issue, String>)>,
+) {
+    let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+    main_view.show_message(&format!("{} issue(s) mentioned", issues.len()));
+    drop(main_view);
+
+    let mut list = SelectView::new();
+    for (key, issue) in issues {
+        let label = match issue {
+            Ok(issue) => issue.to_string(),
+            Err(error) => format!("{} ({})", key, error),
+        };
+        list.add_item(label, key);
+    }
+    let list = list.on_submit(move |s, key: &String| {
+        crash::record_action(format!("issue {}", key));
+        let url = match tracker.url(key) {
+            Some(url) => url,
+            None => return,
+        };
+        if let Err(error) = utils::open_in_browser(&url) {
+            s.pop_layer();
+            let mut main_view: ViewRef<MainView> = s.find_name("mainView").unwrap();
+            main_view.show_error(&format!("Failed to open {}", url), &error);
+        }
+    });
+
+    let dialog = Dialog::around(list.scrollable())
+        .title(format!("Issues of {}", &commit.commit_id.to_string()[..8]))
+        .dismiss_button("Close");
+    siv.add_layer(
+        OnEventView::new(dialog.max_width(siv.screen_size().x * 3 / 4)).on_event(Key::Esc, |s| {
+            s.pop_layer();
+        }),
+    );
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Relation {
+    Parent,
+    Child,
+}
+
+/// Shows a parent or a child of the commit in the diff view, which is
+/// selected if listed; a dialog lets choose one if there are several
+fn show_related(siv: &mut Cursive, relation: Relation) {
+    let diff_view: ViewRef<DiffView> = siv.find_name("diffView").unwrap();
+    let commit = diff_view.commit().clone();
+    drop(diff_view);
 
+    let commit = match commit {
+        Some(commit) => commit,
+        None => return,
+    };
     let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
-    main_view.update_commit_bar(index, commits, &entry);
+    let candidates = match relation {
+        Relation::Parent => commit
+            .parent_ids
+            .iter()
+            .filter_map(|id| main_view.find_commit(&commit.repo, *id))
+            .collect(),
+        Relation::Child => main_view.children(&commit),
+    };
+    match (candidates.len(), relation) {
+        (0, Relation::Parent) => main_view.show_message("No parent found locally"),
+        (0, Relation::Child) => main_view.show_message("No child found in the history"),
+        (1, _) => {
+            drop(main_view);
+            MainView::show_commit(siv, &candidates[0]);
+        }
+        _ => {
+            drop(main_view);
+            let mut list = SelectView::new();
+            for candidate in candidates {
+                let label = format!(
+                    "{} {}",
+                    &candidate.commit_id.to_string()[..8],
+                    candidate.summary
+                );
+                list.add_item(label, candidate);
+            }
+            let list = list.on_submit(|s, commit: &RepoCommit| {
+                crash::record_action(format!("related commit {}", commit.commit_id));
+                s.pop_layer();
+                MainView::show_commit(s, commit);
+            });
+            let title = match relation {
+                Relation::Parent => "Parents",
+                Relation::Child => "Children",
+            };
+            let dialog = Dialog::around(list.scrollable())
+                .title(format!(
+                    "{} of {}",
+                    title,
+                    &commit.commit_id.to_string()[..8]
+                ))
+                .dismiss_button("Close");
+            siv.add_layer(
+                OnEventView::new(dialog.max_width(siv.screen_size().x * 3 / 4)).on_event(
+                    Key::Esc,
+                    |s| {
+                        s.pop_layer();
+                    },
+                ),
+            );
+        }
+    }
 }
 
-pub fn show(model: MultiRepoHistory, config: Config) {
+/// Shows the number of listed commits, authors and repositories by
+/// organization of the authors
+fn show_statistics(siv: &mut Cursive) {
+    let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+    let commits = main_view.listed_commits();
+    let organizations = main_view.organizations();
+    drop(main_view);
+
+    let groups = stats::group_by(&commits, |commit| organizations.of(&commit.author_email));
+    let dialog =
+        Dialog::around(TextView::new(stats::to_table("Organization", &groups)).scrollable())
+            .title(format!("Statistics of {} listed commits", commits.len()))
+            .dismiss_button("Close");
+    siv.add_layer(
+        OnEventView::new(dialog.max_width(siv.screen_size().x * 3 / 4)).on_event(Key::Esc, |s| {
+            s.pop_layer();
+        }),
+    );
+}
+
+/// Shows the lines changed by the listed commits per top-level directory
+/// of their repositories, the most changed first. The diffs are read in
+/// the background.
+fn show_churn(siv: &mut Cursive) {
+    let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+    let commits = main_view.listed_commits();
+    main_view.show_message(&format!(
+        "Computing the churn of {} commits …",
+        commits.len()
+    ));
+    drop(main_view);
+
+    let cb_sink = siv.cb_sink().clone();
+    std::thread::spawn(move || {
+        let churn = churn::of(&commits);
+        let _ = cb_sink.send(Box::new(move |siv| {
+            let mut text = churn::to_table(&churn.areas);
+            if churn.skipped > 0 {
+                text.push_str(&format!(
+                    "\n{} commits skipped, their changes couldn't be read",
+                    churn.skipped
+                ));
+            }
+            let dialog = Dialog::around(TextView::new(text).scrollable())
+                .title(format!("Churn of {} listed commits", commits.len()))
+                .dismiss_button("Close");
+            siv.add_layer(
+                OnEventView::new(dialog.max_width(siv.screen_size().x * 3 / 4)).on_event(
+                    Key::Esc,
+                    |s| {
+                        s.pop_layer();
+                    },
+                ),
+            );
+        }));
+    });
+}
+
+/// Shows the pairs of repositories modified by the same changes of the
+/// listed commits, the most frequently co-changed first
+fn show_coupling(siv: &mut Cursive) {
+    let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+    let commits = main_view.listed_commits();
+    drop(main_view);
+
+    let pairs = coupling::of(&commits);
+    let text = match pairs.is_empty() {
+        true => String::from("No changes modified several repositories"),
+        false => coupling::to_table(&pairs),
+    };
+    let dialog = Dialog::around(TextView::new(text).scrollable())
+        .title(format!("Coupling of {} listed commits", commits.len()))
+        .dismiss_button("Close");
+    siv.add_layer(
+        OnEventView::new(dialog.max_width(siv.screen_size().x * 3 / 4)).on_event(Key::Esc, |s| {
+            s.pop_layer();
+        }),
+    );
+}
+
+fn update_fuzzy_matches(
+    matches: &mut SelectView<RepoCommit>,
+    candidates: &[(String, RepoCommit)],
+    query: &str,
+) {
+    let mut scored: Vec<(i64, &(String, RepoCommit))> = candidates
+        .iter()
+        .filter_map(|candidate| Some((fuzzy::score(query, &candidate.0)?, candidate)))
+        .collect();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+    matches.clear();
+    for (_, (label, commit)) in scored.into_iter().take(MAX_FUZZY_MATCHES) {
+        matches.add_item(label.clone(), commit.clone());
+    }
+}
+
+fn choose_fuzzy_match(siv: &mut Cursive, commit: &RepoCommit) {
+    siv.pop_layer();
+    MainView::jump_to(siv, commit);
+}
+
+fn update(siv: &mut Cursive, index: usize, entry: &RepoCommit) {
+    let mut diff_view: ViewRef<DiffView> = siv.find_name("diffView").unwrap();
+    let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+    diff_view.set_highlighter(main_view.highlighter());
+    diff_view.set_commit(entry);
+    let prefetch_count = diff_view.prefetch_count();
+    if prefetch_count > 0 {
+        diff_view.prefetch(&main_view.neighbours(prefetch_count));
+    }
+    main_view.update_commit_bar(index, entry);
+
+    // the history or the filter might have been changed in the meantime
+    siv.call_on_name("statusBar", |status_bar: &mut Canvas<StatusBarState>| {
+        status_bar.state_mut().update(&main_view)
+    });
+}
+
+/// State the UI starts in, e.g. when launched by other tools
+pub struct Startup {
+    /// prefix of the ID of the commit to select
+    pub select: Option<String>,
+    /// path of the repository to restrict the listed commits to, see
+    /// `--focus-repo`
+    pub focus_repo: Option<String>,
+    /// filter applied to the listed commits
+    pub filter: Option<String>,
+}
+
+pub fn show(
+    model: MultiRepoHistory,
+    fingerprints: Fingerprints,
+    config: Config,
+    builds: Vec<Build>,
+    startup: Startup,
+) {
     let mut siv = Cursive::default();
     siv.load_toml(include_str!("../assets/style.toml")).unwrap();
 
@@ -60,28 +1040,38 @@ pub fn show(model: MultiRepoHistory, config: Config) {
     // query the terminal dimensions with screen_size()
     siv.cb_sink()
         .send(Box::new(move |siv| {
-            let commits = model.commits.len();
-            let repos = model.repos.len();
-            let locally_missing_commits = model.locally_missing_commits;
-
-            let first_commit = if commits > 0 {
-                Some(model.commits.get(0).unwrap().clone())
+            let first_commit = if !model.commits.is_empty() {
+                Some(model.commits.first().unwrap().clone())
             } else {
                 None
             };
 
             let screen_size = siv.screen_size();
 
-            let mut main_view = MainView::from(model);
+            let mut main_view =
+                MainView::from(model, fingerprints, &config, builds, siv.cb_sink().clone());
 
             main_view.set_on_select(
                 move |siv: &mut Cursive, _row: usize, index: usize, entry: &RepoCommit| {
-                    let mut diff_view: ViewRef<DiffView> = siv.find_name("diffView").unwrap();
-                    diff_view.set_commit(&entry);
-                    let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
-                    main_view.update_commit_bar(index, commits, &entry);
+                    crash::record_action(format!("select {}", entry.commit_id));
+                    update(siv, index, entry)
                 },
             );
+            let page_step = config.page_step;
+            let notes = config.notes.clone();
+            let max_lines = config.diff.max_lines;
+            let diff_cache = Arc::new(Mutex::new(DiffCache::new(
+                config.diff.cache_mb * 1024 * 1024,
+            )));
+            let cache = diff_cache.clone();
+            main_view.set_on_submit(
+                move |siv: &mut Cursive, _row: usize, _index: usize, entry: &RepoCommit| {
+                    crash::record_action(format!("submit {}", entry.commit_id));
+                    show_diff_fullscreen(siv, entry, page_step, &notes, max_lines, &cache)
+                },
+            );
+            let status_bar_state =
+                StatusBarState::new(&main_view, screen_size, config.status_bar_format.clone());
             let landscape_format = screen_size.x / (screen_size.y * 3) >= 1;
             let layout = if landscape_format {
                 LinearLayout::vertical()
@@ -91,28 +1081,18 @@ pub fn show(model: MultiRepoHistory, config: Config) {
                             .child(SeperatorView::vertical())
                             .child(ResizedView::with_fixed_width(
                                 screen_size.x / 2 - 1,
-                                DiffView::empty().with_name("diffView"),
+                                build_diff_pane(&config, &diff_cache),
                             )),
                     )
-                    .child(build_status_bar(
-                        commits,
-                        repos,
-                        locally_missing_commits,
-                        screen_size,
-                    ))
+                    .child(build_status_bar(status_bar_state))
             } else {
                 LinearLayout::vertical()
                     .child(main_view.with_name("mainView").full_screen())
                     .child(ResizedView::with_fixed_height(
                         screen_size.y / 2 - 1,
-                        DiffView::empty().with_name("diffView"),
-                    ))
-                    .child(build_status_bar(
-                        commits,
-                        repos,
-                        locally_missing_commits,
-                        screen_size,
+                        build_diff_pane(&config, &diff_cache),
                     ))
+                    .child(build_status_bar(status_bar_state))
             };
 
             siv.add_layer(layout);
@@ -133,14 +1113,105 @@ pub fn show(model: MultiRepoHistory, config: Config) {
                 let mut diff_view: ViewRef<DiffView> = s.find_name("diffView").unwrap();
                 diff_view.on_event(Event::Key(Key::Down));
             });
+            register_builtin_command('L', siv, |s| {
+                let mut diff_view: ViewRef<DiffView> = s.find_name("diffView").unwrap();
+                diff_view.load_more();
+            });
+            register_builtin_command('F', siv, |s| {
+                let mut diff_view: ViewRef<DiffView> = s.find_name("diffView").unwrap();
+                diff_view.fetch_missing();
+            });
+            register_builtin_command('/', siv, |s| {
+                s.focus_name("filterBar").unwrap();
+            });
+            register_builtin_command('s', siv, |s| {
+                MainView::update_filter(s, |main_view| main_view.toggle_filter_term("-is:side"));
+            });
+            register_builtin_command('b', siv, |s| {
+                MainView::update_filter(s, |main_view| main_view.toggle_filter_term("-is:bot"));
+            });
+            register_builtin_command('p', siv, toggle_pin);
+            register_builtin_command('R', siv, |s| {
+                quick_filter(s, |commit| format!("repo:\"{}\"", commit.repo.name()))
+            });
+            register_builtin_command('A', siv, |s| {
+                quick_filter(s, |commit| format!("author:\"{}\"", commit.author_email))
+            });
+            register_builtin_command('D', siv, |s| {
+                quick_filter(s, |commit| format!("date:{}", commit.date_as_str()))
+            });
+            siv.add_global_callback(Event::Key(Key::F5), |s| {
+                crash::record_action("key F5".to_string());
+                MainView::refresh(s)
+            });
+            register_builtin_command('+', siv, |s| MainView::change_days(s, DAYS_STEP));
+            register_builtin_command('-', siv, |s| MainView::change_days(s, -DAYS_STEP));
+            register_builtin_command('e', siv, MainView::toggle_expand);
+            register_builtin_command('c', siv, MainView::toggle_collapse);
+            register_builtin_command('g', siv, MainView::toggle_grouping);
+            register_builtin_command('u', siv, MainView::undo);
+            siv.add_global_callback(Event::CtrlChar('r'), |s| {
+                crash::record_action("key Ctrl-R".to_string());
+                MainView::redo(s)
+            });
+            siv.add_global_callback(Event::CtrlChar('p'), |s| {
+                crash::record_action("key Ctrl-P".to_string());
+                show_fuzzy_finder(s)
+            });
+            register_builtin_command('E', siv, show_export_dialog);
+            register_builtin_command('W', siv, show_save_diff_dialog);
+            register_builtin_command('a', siv, show_apply_dialog);
+            register_builtin_command('B', siv, show_branch_dialog);
+            let bisect = config.bisect.clone();
+            register_builtin_command('M', siv, move |s| show_checkout_dialog(s, bisect.as_ref()));
+            register_builtin_command('y', siv, copy_commit_url);
+            register_builtin_command('o', siv, open_in_browser);
+            register_builtin_command('P', siv, open_pull_request);
+            register_builtin_command('I', siv, show_issues);
+            register_builtin_command('S', siv, show_statistics);
+            register_builtin_command('H', siv, show_churn);
+            register_builtin_command('K', siv, show_coupling);
+            register_builtin_command('t', siv, MainView::open_tab);
+            register_builtin_command('x', siv, MainView::close_tab);
+            for (index, key) in ('1'..='9').enumerate() {
+                register_builtin_command(key, siv, move |s| MainView::switch_tab(s, index));
+            }
+            register_builtin_command('C', siv, |s| {
+                MainView::update_filter(s, |main_view| main_view.set_filter(""));
+            });
+            register_key_sequences(
+                siv,
+                vec![
+                    ((']', 'r'), Box::new(|s| select_same_repo(s, true))),
+                    (('[', 'r'), Box::new(|s| select_same_repo(s, false))),
+                    ((']', 'a'), Box::new(|s| select_same_author(s, true))),
+                    (('[', 'a'), Box::new(|s| select_same_author(s, false))),
+                    (('[', 'p'), Box::new(|s| show_related(s, Relation::Parent))),
+                    ((']', 'p'), Box::new(|s| show_related(s, Relation::Child))),
+                ],
+            );
 
             if let Some(commit) = first_commit {
-                update(siv, 0, commits, &commit)
+                update(siv, 0, &commit)
+            }
+            if let Some(filter) = startup.filter {
+                MainView::update_filter(siv, |main_view| main_view.set_filter(&filter));
+            }
+            if let Some(prefix) = startup.select {
+                MainView::show_commit_with_prefix(siv, &prefix);
             }
         }))
         .unwrap();
 
-    siv.run(); //this call blocks until UI gets terminated
+    crash::before_ui();
+    //this call blocks until UI gets terminated, a panic unwinds out of it
+    // so the backend restores the terminal before the crash is printed
+    let result = panic::catch_unwind(AssertUnwindSafe(|| siv.run()));
+    drop(siv);
+    crash::after_ui();
+    if let Err(panic) = result {
+        panic::resume_unwind(panic);
+    }
 }
 
 fn register_builtin_command<F>(ch: char, siv: &mut Cursive, cb: F)
@@ -148,7 +1219,79 @@ where
     F: FnMut(&mut Cursive) + 'static,
 {
     siv.clear_global_callbacks(ch); //to avoid that custom commands are taking over one of our builtin shortcuts
-    siv.add_global_callback(ch, cb);
+    let mut cb = cb;
+    siv.add_global_callback(ch, move |siv| {
+        crash::record_action(format!("key '{}'", ch));
+        cb(siv)
+    });
+}
+
+/// Registers sequences of two keys (e.g. `]r`): the first key arms the
+/// sequence, the second one selects the callback to execute. Any other
+/// key cancels the sequence.
+fn register_key_sequences(siv: &mut Cursive, sequences: Vec<((char, char), SequenceCallback)>) {
+    let pending = Rc::new(Cell::new(None));
+    let sequences: Rc<HashMap<(char, char), SequenceCallback>> =
+        Rc::new(sequences.into_iter().collect());
+
+    let prefixes: HashSet<char> = sequences.keys().map(|(prefix, _)| *prefix).collect();
+    for prefix in prefixes {
+        let pending = pending.clone();
+        register_builtin_command(prefix, siv, move |_| pending.set(Some(prefix)));
+    }
+
+    siv.set_on_pre_event_inner(EventTrigger::any(), move |event| {
+        let prefix = pending.get()?;
+        let sequence = match event {
+            Event::Char(ch) if sequences.contains_key(&(prefix, *ch)) => (prefix, *ch),
+            Event::Char(_) | Event::Key(_) => {
+                pending.set(None);
+                return Some(EventResult::Consumed(None));
+            }
+            // e.g. mouse events and resizes neither complete nor cancel
+            // the sequence
+            _ => return None,
+        };
+        pending.set(None);
+        let sequences = sequences.clone();
+        Some(EventResult::with_cb(move |siv| {
+            crash::record_action(format!("keys '{}{}'", sequence.0, sequence.1));
+            sequences[&sequence](siv)
+        }))
+    });
+}
+
+/// Restricts the listed commits to the given attribute of the selected
+/// commit, or removes the restriction if it is already active
+fn quick_filter<F>(siv: &mut Cursive, term_of: F)
+where
+    F: FnOnce(&RepoCommit) -> String,
+{
+    let diff_view: ViewRef<DiffView> = siv.find_name("diffView").unwrap();
+    let term = diff_view.commit().as_ref().map(term_of);
+    drop(diff_view);
+
+    if let Some(term) = term {
+        MainView::update_filter(siv, |main_view| main_view.toggle_filter_term(&term));
+    }
+}
+
+fn select_same_repo(siv: &mut Cursive, forward: bool) {
+    MainView::select_next_matching(
+        siv,
+        forward,
+        tr("No further commit of this repository"),
+        |selected, candidate| selected.repo.abs_path == candidate.repo.abs_path,
+    );
+}
+
+fn select_same_author(siv: &mut Cursive, forward: bool) {
+    MainView::select_next_matching(
+        siv,
+        forward,
+        tr("No further commit of this author"),
+        |selected, candidate| selected.author_email == candidate.author_email,
+    );
 }
 
 fn register_custom_commands(config: &Config, siv: &mut Cursive) {
@@ -156,7 +1299,9 @@ fn register_custom_commands(config: &Config, siv: &mut Cursive) {
         let executable = cmd.executable.clone();
         let args = cmd.args.clone();
 
-        siv.add_global_callback(cmd.key, move |s| {
+        let key = cmd.key;
+        siv.add_global_callback(key, move |s| {
+            crash::record_action(format!("key '{}'", key));
             let diff_view: ViewRef<DiffView> = s.find_name("diffView").unwrap();
             if let Some(commit) = &diff_view.commit() {
                 let result =
@@ -0,0 +1,72 @@
+/// Undo/redo stack of states, e.g. of the filter and selection of a view.
+pub struct UndoStack<T> {
+    undo: Vec<T>,
+    redo: Vec<T>,
+    limit: usize,
+}
+
+impl<T> UndoStack<T> {
+    pub fn new(limit: usize) -> UndoStack<T> {
+        UndoStack {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            limit,
+        }
+    }
+
+    /// remembers the state which is about to be left, this
+    /// discards all states which could have been redone
+    pub fn push(&mut self, state: T) {
+        self.undo.push(state);
+        if self.undo.len() > self.limit {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// returns the state to go back to, the current state can be redone
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let state = self.undo.pop()?;
+        self.redo.push(current);
+        Some(state)
+    }
+
+    /// returns the state to go forward to, the current state can be undone
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let state = self.redo.pop()?;
+        self.undo.push(current);
+        Some(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_and_redo() {
+        let mut stack = UndoStack::new(10);
+        stack.push(1);
+        stack.push(2);
+        assert_eq!(stack.undo(3), Some(2));
+        assert_eq!(stack.undo(2), Some(1));
+        assert_eq!(stack.undo(1), None);
+        assert_eq!(stack.redo(1), Some(2));
+        assert_eq!(stack.redo(2), Some(3));
+        assert_eq!(stack.redo(3), None);
+    }
+
+    #[test]
+    fn push_discards_redo_and_oldest_states() {
+        let mut stack = UndoStack::new(2);
+        stack.push(1);
+        stack.push(2);
+        stack.push(3);
+        assert_eq!(stack.undo(4), Some(3));
+        stack.push(5);
+        assert_eq!(stack.redo(6), None);
+        assert_eq!(stack.undo(6), Some(5));
+        assert_eq!(stack.undo(5), Some(2));
+        assert_eq!(stack.undo(2), None);
+    }
+}
@@ -1,59 +1,85 @@
 use crate::model::RepoCommit;
+use crate::workspace;
 use chrono::{DateTime, FixedOffset, TimeZone, Utc};
 use git2::Time;
 use std::env;
 use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
 /// returns a path pointing to he project.list file in
-/// the .repo folder, or an io::Error in case the file
-/// couldn't been found.
-pub fn find_project_file() -> Result<PathBuf, io::Error> {
-    let project_file = find_repo_folder()?.join("project.list");
+/// the .repo folder of the given workspace, or an io::Error
+/// in case the file couldn't been found.
+pub fn find_project_file(base_folder: &Path) -> Result<PathBuf, io::Error> {
+    let project_file = base_folder.join(".repo/project.list");
     if project_file.is_file() {
         Ok(project_file)
     } else {
-        Err(io::Error::new(
-            io::ErrorKind::Other,
-            "no project.list in .repo found",
-        ))
+        Err(io::Error::other("no project.list in .repo found"))
     }
 }
 
-/// returns a path pointing to the .repo folder,
-/// or io::Error in case the .repo folder couldn't been
+/// returns a path pointing to the folder containing .repo
+/// (or the folder of another tool managing the workspace, see
+/// `WorkspaceType`), or io::Error in case no such folder could be
 /// found in the cwd or any of its parent folders.
-pub fn find_repo_folder() -> Result<PathBuf, io::Error> {
-    let base_folder = find_repo_base_folder()?;
-    Ok(base_folder.join(".repo"))
+pub fn find_repo_base_folder() -> Result<PathBuf, io::Error> {
+    repo_base_folder_of(&env::current_dir()?)
 }
 
-/// returns a path pointing to the folder containing .repo,
-/// or io::Error in case the .repo folder couldn't been
-/// found in the cwd or any of its parent folders.
-pub fn find_repo_base_folder() -> Result<PathBuf, io::Error> {
-    let cwd = env::current_dir()?;
-    for parent in cwd.ancestors() {
-        for entry in fs::read_dir(&parent)? {
-            let entry = entry?;
-            if entry.path().is_dir() && entry.file_name() == ".repo" {
-                return Ok(parent.to_path_buf());
+/// returns a path pointing to the folder containing .repo (or the folder
+/// of another tool managing the workspace), searching the given folder
+/// and its parent folders
+pub fn repo_base_folder_of(folder: &Path) -> Result<PathBuf, io::Error> {
+    workspace::base_folder_of(folder, None)
+}
+
+/// returns the work trees of the git repositories (folders containing
+/// `.git`) in the given folder and its subfolders, sorted by path.
+/// Repositories nested in the work tree of another one are found as well,
+/// symbolic links aren't followed.
+pub fn find_git_repos(folder: &Path) -> Result<Vec<PathBuf>, io::Error> {
+    let mut repos = Vec::new();
+    let mut folders = vec![folder.to_path_buf()];
+    while let Some(folder) = folders.pop() {
+        // unreadable subfolders are skipped
+        let entries = match fs::read_dir(&folder) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(_) => continue,
+            };
+            if entry.file_name() == ".git" {
+                // a folder, or a file for linked work trees and submodules
+                repos.push(folder.clone());
+            } else if file_type.is_dir() && entry.file_name() != ".repo" {
+                folders.push(entry.path());
             }
         }
     }
-    Err(io::Error::new(
-        io::ErrorKind::Other,
-        "no .repo folder found",
-    ))
+    if repos.is_empty() {
+        return Err(io::Error::other(format!(
+            "no git repositories found in {}",
+            folder.display()
+        )));
+    }
+    repos.sort();
+    Ok(repos)
 }
 
 /// converts a git2 time datastructure into its
 /// rust-idiomatic equivalent
 pub fn as_datetime(git_time: &Time) -> DateTime<FixedOffset> {
     let offset_in_secs = git_time.offset_minutes() * 60;
-    FixedOffset::east_opt(offset_in_secs).unwrap().timestamp_opt(git_time.seconds(), 0).unwrap()
+    FixedOffset::east_opt(offset_in_secs)
+        .unwrap()
+        .timestamp_opt(git_time.seconds(), 0)
+        .unwrap()
 }
 
 /// converts a git2 time datastructure into its
@@ -63,6 +89,46 @@ pub fn as_datetime_utc(git_time: &Time) -> DateTime<Utc> {
     as_datetime(git_time).with_timezone(&Utc)
 }
 
+/// replaces the placeholders (e.g. "{repo}") in the given format
+/// string with their values, unknown placeholders are kept
+pub fn expand_placeholders(format: &str, placeholders: &[(&str, String)]) -> String {
+    placeholders
+        .iter()
+        .fold(format.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+/// a `sh -c` command running the given script, whose placeholders (e.g.
+/// "{repo}") are passed as positional parameters instead of being pasted
+/// into the script, so their values can't inject shell code. Placeholders
+/// quoted as a whole (e.g. "'{repo}'"), as scripts written for pasted
+/// values do, are passed the same way.
+pub fn shell_command(script: &str, placeholders: &[(&str, String)]) -> Command {
+    let mut script = script.to_string();
+    for (index, (name, _)) in placeholders.iter().enumerate() {
+        let placeholder = format!("{{{}}}", name);
+        let parameter = format!("\"${{{}}}\"", index + 1);
+        script = script
+            .replace(&format!("'{}'", placeholder), &parameter)
+            .replace(&format!("\"{}\"", placeholder), &parameter)
+            .replace(&placeholder, &parameter);
+    }
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(script)
+        .arg("sh")
+        .args(placeholders.iter().map(|(_, value)| value));
+    command
+}
+
+/// the given value quoted for the shell, e.g. to be pasted into a command
+/// shown to the user
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
 /// executes an external executable with given arguments;
 /// if the pattern "{}" is found in the args parameter, it
 /// is replaced with the ID of the given commit
@@ -82,3 +148,86 @@ pub fn execute_on_commit(
         .stderr(Stdio::null())
         .spawn()
 }
+
+/// copies the given text to the clipboard with the first of the
+/// well-known clipboard tools which is available and succeeds (e.g.
+/// wl-copy fails outside of Wayland sessions)
+pub fn copy_to_clipboard(text: &str) -> Result<(), io::Error> {
+    let tools: [(&str, &[&str]); 4] = [
+        ("pbcopy", &[]),
+        ("wl-copy", &[]),
+        ("xclip", &["-selection", "clipboard"]),
+        ("xsel", &["--clipboard", "--input"]),
+    ];
+    let mut error = io::Error::other("no clipboard tool found (pbcopy, wl-copy, xclip or xsel)");
+    for (tool, args) in &tools {
+        let child = Command::new(tool)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        if let Ok(mut child) = child {
+            // the tool might exit before reading, its status tells
+            let _ = child.stdin.take().unwrap().write_all(text.as_bytes());
+            let status = child.wait()?;
+            if status.success() {
+                return Ok(());
+            }
+            error = io::Error::other(format!("{} failed with {}", tool, status));
+        }
+    }
+    Err(error)
+}
+
+/// opens the given URL with the default browser
+pub fn open_in_browser(url: &str) -> Result<(), io::Error> {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    Command::new(opener)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_placeholders_as_parameters() {
+        let placeholders = [
+            ("id", String::from("abc")),
+            ("author", String::from("$(echo pwned)'; echo \"pwned")),
+        ];
+        let output = shell_command(
+            "printf '%s|' {id} {author} '{author}' \"{author}\" 'v{id}'",
+            &placeholders,
+        )
+        .output()
+        .unwrap();
+        // placeholders within single quoted text aren't expanded
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "abc|$(echo pwned)'; echo \"pwned|$(echo pwned)'; echo \"pwned|$(echo pwned)'; echo \"pwned|v\"${1}\"|"
+        );
+    }
+
+    #[test]
+    fn expands_known_placeholders_only() {
+        let placeholders = [
+            ("index", "3".to_string()),
+            ("repo", "kernel/msm".to_string()),
+        ];
+        assert_eq!(
+            expand_placeholders("{index}: {repo} {unknown}", &placeholders),
+            "3: kernel/msm {unknown}"
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use crate::manifest::{self, Project};
+use crate::utils::find_repo_base_folder;
+use anyhow::{anyhow, Result};
+use git2::{Oid, Repository};
+
+/// Checks that each project of the manifest is checked out at the revision
+/// it is pinned to, listing the projects which drifted (checked out on
+/// another branch, detached at another commit, with extra commits or
+/// behind). Fails if any project drifted, e.g. to break CI builds.
+pub fn run() -> Result<()> {
+    let base_folder = find_repo_base_folder()?;
+    let projects = manifest::read(&base_folder)?;
+    let mut drifted = 0;
+    for project in &projects {
+        let drift = match Repository::open(base_folder.join(&project.path)) {
+            Ok(repo) => drift_of(&repo, project).unwrap_or_else(|e| vec![e.to_string()]),
+            Err(_) => vec![String::from("not checked out")],
+        };
+        if !drift.is_empty() {
+            println!("{}: {}", project.path, drift.join(", "));
+            drifted += 1;
+        }
+    }
+    if drifted > 0 {
+        return Err(anyhow!(
+            "{} of {} projects drifted from the manifest",
+            drifted,
+            projects.len()
+        ));
+    }
+    println!("All {} projects match the manifest", projects.len());
+    Ok(())
+}
+
+/// the differences between the checked out state of the project and its
+/// pinned revision, empty if there are none
+fn drift_of(repo: &Repository, project: &Project) -> Result<Vec<String>> {
+    let pinned = pinned_commit(repo, project)
+        .ok_or_else(|| anyhow!("revision {} not found", project.revision))?;
+    let head = repo.head()?;
+    let head_commit = head.peel_to_commit()?.id();
+
+    let mut drift = Vec::new();
+    let branch = branch_of(&project.revision);
+    if head.is_branch() {
+        let name = head.shorthand().unwrap_or_default();
+        match branch {
+            Some(branch) if branch != name => {
+                drift.push(format!("on branch {} instead of {}", name, branch))
+            }
+            None => drift.push(format!("on branch {}", name)),
+            _ => {}
+        }
+    } else if head_commit != pinned {
+        drift.push(format!("detached at {}", short(head_commit)));
+    }
+    let (ahead, behind) = repo.graph_ahead_behind(head_commit, pinned)?;
+    if ahead > 0 {
+        drift.push(format!("{} extra commits", ahead));
+    }
+    if behind > 0 {
+        drift.push(format!("{} commits behind", behind));
+    }
+    Ok(drift)
+}
+
+/// the branch a revision refers to, `None` for tags and SHAs
+fn branch_of(revision: &str) -> Option<&str> {
+    if revision.starts_with("refs/tags/") || is_sha(revision) {
+        return None;
+    }
+    Some(revision.trim_start_matches("refs/heads/"))
+}
+
+fn is_sha(revision: &str) -> bool {
+    revision.len() == 40 && revision.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// the commit the project is pinned to: given by SHA, or the state of the
+/// branch at the remote as fetched by the last sync
+fn pinned_commit(repo: &Repository, project: &Project) -> Option<Oid> {
+    if is_sha(&project.revision) {
+        return Oid::from_str(&project.revision).ok();
+    }
+    let remote_branch = match (&project.remote, branch_of(&project.revision)) {
+        (Some(remote), Some(branch)) => format!("refs/remotes/{}/{}", remote, branch),
+        _ => project.revision.clone(),
+    };
+    [remote_branch.as_str(), project.revision.as_str()]
+        .iter()
+        .find_map(|revision| {
+            let object = repo.revparse_single(revision).ok()?;
+            Some(object.peel_to_commit().ok()?.id())
+        })
+}
+
+fn short(oid: Oid) -> String {
+    oid.to_string()[..8].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_branches_of_revisions() {
+        assert_eq!(branch_of("main"), Some("main"));
+        assert_eq!(branch_of("refs/heads/release/1.0"), Some("release/1.0"));
+        assert_eq!(branch_of("refs/tags/v1.0"), None);
+        assert_eq!(branch_of("0123456789abcdef0123456789abcdef01234567"), None);
+    }
+}
@@ -1,13 +1,48 @@
+use crate::crash;
+use crate::diff_cache::{git_diff, missing_blobs, DiffCache, Prefetcher};
+use crate::manifest;
 use crate::model::RepoCommit;
-use crate::styles::{BLUE, GREEN, LIGHT_BLUE, MAGENTA, RED, WHITE, YELLOW};
-use crate::views::ListView;
-use cursive::theme::ColorStyle;
-use cursive::view::ViewWrapper;
-use std::process::Command;
+use crate::styles::{BLUE, GREEN, LIGHT_BLUE, LIGHT_GREEN, MAGENTA, MATCH, RED, WHITE, YELLOW};
+use crate::utils::as_datetime;
+use crate::views::{ListView, MainView};
+use cursive::event::{Event, EventResult};
+use cursive::theme::{ColorStyle, Style};
+use cursive::utils::span::SpannedString;
+use cursive::view::{View, ViewWrapper};
+use cursive::Vec2;
+use git2::{Oid, Repository, Time};
+use regex::Regex;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Number of recently viewed commits whose scroll position is restored
+const MAX_REMEMBERED_POSITIONS: usize = 100;
 
 pub struct DiffView {
     list_view: ListView,
     commit: Option<RepoCommit>,
+    page_step: Option<usize>,
+    highlighter: Option<Regex>,
+    /// refs of the notes shown above the diff
+    notes: Vec<String>,
+    cache: Arc<Mutex<DiffCache>>,
+    prefetcher: Option<Prefetcher>,
+    /// selected line and scroll offset of the recently viewed commits,
+    /// least recently viewed first
+    positions: VecDeque<(PathBuf, Oid, (usize, Vec2))>,
+    /// number of diff lines loaded at once, 0 loads all of them
+    max_lines: usize,
+    /// the diff output and the byte offset of its lines not loaded yet
+    rest: Option<(Arc<String>, usize)>,
+    /// commits of partial clones whose missing blobs may be fetched to
+    /// show their diff (see `fetch_missing()`)
+    fetches: HashSet<(PathBuf, Oid)>,
+    /// the shown commit lacks blobs which haven't been fetched yet
+    requires_fetch: bool,
+    /// the names of the refs of each repository by the commit they point
+    /// at, collected once (see `forget_refs()`)
+    refs: HashMap<PathBuf, HashMap<Oid, Vec<String>>>,
 }
 
 impl DiffView {
@@ -15,66 +50,370 @@ impl DiffView {
         DiffView {
             list_view: ListView::new(),
             commit: None,
+            page_step: None,
+            highlighter: None,
+            notes: Vec::new(),
+            cache: Arc::new(Mutex::new(DiffCache::new(0))),
+            prefetcher: None,
+            positions: VecDeque::new(),
+            max_lines: 0,
+            rest: None,
+            fetches: HashSet::new(),
+            requires_fetch: false,
+            refs: HashMap::new(),
+        }
+    }
+
+    /// matches of the given regex are highlighted in the diffs of
+    /// commits set from now on
+    pub fn set_highlighter(&mut self, highlighter: Option<Regex>) {
+        self.highlighter = highlighter;
+    }
+
+    /// number of lines PageUp/PageDown scroll, defaults to the view's height
+    pub fn page_step(mut self, page_step: Option<usize>) -> Self {
+        self.page_step = page_step;
+        self.list_view.set_page_step(page_step);
+        self
+    }
+
+    /// the notes of the given refs (e.g. `refs/notes/commits`) are shown
+    /// in a section of their own above the diff
+    pub fn notes(mut self, notes: Vec<String>) -> Self {
+        self.notes = notes;
+        self
+    }
+
+    /// the output of `git diff` is looked up in and added to the given
+    /// cache, which might be shared with other diff views
+    pub fn cache(mut self, cache: Arc<Mutex<DiffCache>>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// the diffs of up to `count` commits above and below the selected
+    /// one are added to the cache in the background, see `prefetch()`
+    pub fn prefetch_neighbours(mut self, count: usize) -> Self {
+        if count > 0 {
+            self.prefetcher = Some(Prefetcher::new(self.cache.clone(), count));
         }
+        self
+    }
+
+    /// only the first `max_lines` lines of a diff are loaded, the next ones
+    /// are loaded by `load_more()` (0 loads all lines at once)
+    pub fn max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = max_lines;
+        self
     }
 
-    pub fn set_commit(self: &mut Self, entry: &RepoCommit) {
+    /// number of neighbours of the selected commit to prefetch
+    pub fn prefetch_count(&self) -> usize {
+        self.prefetcher.as_ref().map_or(0, Prefetcher::count)
+    }
+
+    /// adds the diffs of the given commits to the cache in the background
+    pub fn prefetch(&self, commits: &[RepoCommit]) {
+        if let Some(prefetcher) = &self.prefetcher {
+            prefetcher.prefetch(commits);
+        }
+    }
+
+    pub fn set_commit(&mut self, entry: &RepoCommit) {
+        if let Some(previous) = self.commit.take() {
+            self.remember_position(&previous);
+        }
         self.commit = Some(entry.clone());
+        self.rest = None;
 
-        self.list_view = ListView::new();
+        self.list_view = ListView::new().page_step(self.page_step);
+        // diffs might be huge, their lines are styled when drawn
+        let highlighter = self.highlighter.clone();
         self.list_view
-            .insert_colorful_string(format!("Repo:       {}", entry.repo.rel_path), *RED);
+            .set_styler(move |line| style_line(line, highlighter.as_ref()));
+        self.list_view
+            .insert_colorful_string(format!("Repo:       {}", entry.repo.name()), *RED);
 
-        // we first add the output of git show without diff (does not work nicely for merge
-        // commits yet - but support will come in never versions of git-show...)
-        self.add_git_show_output(&entry);
+        let git_repo = Repository::open(&entry.repo.abs_path).ok();
+        self.add_header(entry, git_repo.as_ref());
+        if let Some(git_repo) = &git_repo {
+            self.add_notes(entry, git_repo);
+        }
 
         self.list_view
             .insert_colorful_string("―――".to_string(), *YELLOW);
 
-        // now at the diff output between the given commit and its first parent
-        // this will then also work nicely with merge commits
-        self.add_git_diff_output(&entry);
+        // manifest changes are summarized by project instead of showing
+        // the XML hunks
+        let manifest_changes = git_repo
+            .as_ref()
+            // the only repository within .repo, see `manifest::repo_folder`
+            .filter(|_| entry.repo.rel_path.starts_with(".repo/"))
+            .and_then(|git_repo| {
+                manifest::changes_of(git_repo, &git_repo.find_commit(entry.commit_id).ok()?)
+            });
+        match manifest_changes {
+            Some(changes) => self.add_manifest_changes(&changes),
+            // now at the diff output between the given commit and its first parent
+            // this will then also work nicely with merge commits
+            None => self.add_git_diff_output(entry),
+        }
+
+        let position = self
+            .positions
+            .iter()
+            .position(|(repo, id, _)| *id == entry.commit_id && *repo == entry.repo.abs_path);
+        if let Some((_, _, position)) = position.and_then(|index| self.positions.remove(index)) {
+            self.list_view.restore_position(position);
+        }
     }
 
-    #[rustfmt::skip]
-    fn add_git_show_output(self: &mut Self, entry: &RepoCommit){
-        let output = Command::new("git")
-                     .current_dir(&entry.repo.abs_path)
-                     .arg("--no-pager")
-                     .arg("show")
-                     .arg("--patch-with-stat")
-                     .arg("--encoding=UTF-8")
-                     .arg("--pretty=fuller")
-                     .arg("--no-color")
-                     .arg("--no-patch")
-                     .arg(format!("{}", entry.commit_id))
-                     .output()
-                     .expect("Failed to execute git-show command. git not installed?");
+    /// remembers where the diff of the given commit has been scrolled to,
+    /// it is restored when the commit is viewed again
+    fn remember_position(&mut self, entry: &RepoCommit) {
+        let position = self.list_view.position();
+        self.positions
+            .retain(|(repo, id, _)| *id != entry.commit_id || *repo != entry.repo.abs_path);
+        if position != (0, Vec2::zero()) {
+            self.positions
+                .push_back((entry.repo.abs_path.clone(), entry.commit_id, position));
+        }
+        if self.positions.len() > MAX_REMEMBERED_POSITIONS {
+            self.positions.pop_front();
+        }
+    }
 
-        for line in String::from_utf8_lossy(&output.stdout).lines() {
-            self.list_view.insert_colorful_string(line.to_string(), Self::color_of(line));
+    /// adds the metadata of the commit, taken from the model instead of
+    /// the output of `git show`. Parents are jumped to by pressing Enter.
+    fn add_header(&mut self, entry: &RepoCommit, git_repo: Option<&Repository>) {
+        self.list_view
+            .insert_colorful_string(format!("commit {}", entry.commit_id), *BLUE);
+
+        let mut links: Vec<(usize, Oid)> = Vec::new();
+        for parent_id in &entry.parent_ids {
+            let summary = git_repo
+                .and_then(|git_repo| git_repo.find_commit(*parent_id).ok())
+                .map(|parent| parent.summary().unwrap_or_default().to_string())
+                .unwrap_or_else(|| String::from("(not available locally)"));
+            links.push((self.list_view.len(), *parent_id));
+            self.list_view.insert_colorful_string(
+                format!("Parent:     {} {}", &parent_id.to_string()[..8], summary),
+                *BLUE,
+            );
+        }
+        if let Some(replaced_id) = entry.replaces {
+            let summary = git_repo
+                .and_then(|git_repo| git_repo.find_commit(replaced_id).ok())
+                .map(|replaced| replaced.summary().unwrap_or_default().to_string())
+                .unwrap_or_default();
+            links.push((self.list_view.len(), replaced_id));
+            self.list_view.insert_colorful_string(
+                format!(
+                    "Replaces:   {} {} (amended or rebased since the last run)",
+                    &replaced_id.to_string()[..8],
+                    summary
+                ),
+                *MAGENTA,
+            );
+        }
+        let repo = entry.repo.clone();
+        self.list_view.set_on_submit(move |siv, row, _index| {
+            if let Some((_, parent_id)) = links.iter().find(|(link, _)| *link == row) {
+                crash::record_action(format!("follow link to {}", parent_id));
+                MainView::show_commit_with_id(siv, &repo, *parent_id);
+            }
+        });
+
+        let mut header = vec![
+            (
+                "Author:     ",
+                format!("{} <{}>", entry.author_name, entry.author_email),
+                *LIGHT_BLUE,
+            ),
+            ("AuthorDate: ", format_time(&entry.author_time), *YELLOW),
+            (
+                "Commit:     ",
+                format!("{} <{}>", entry.committer, entry.committer_email),
+                *MAGENTA,
+            ),
+            ("CommitDate: ", format_time(&entry.commit_time), *YELLOW),
+        ];
+        if let Some(time) = &entry.first_seen {
+            header.push(("Landed:     ", format_time(time), *YELLOW));
+        }
+        for (label, value, color) in header {
+            self.list_view
+                .insert_colorful_string(format!("{}{}", label, value), color);
+        }
+
+        let refs = git_repo
+            .and_then(|git_repo| {
+                self.refs
+                    .entry(entry.repo.abs_path.clone())
+                    .or_insert_with(|| refs_by_commit(git_repo))
+                    .get(&entry.commit_id)
+                    .cloned()
+            })
+            .unwrap_or_default();
+        if !refs.is_empty() {
+            self.list_view
+                .insert_colorful_string(format!("Refs:       {}", refs.join(", ")), *GREEN);
+        }
+        for (key, value) in &entry.annotations {
+            self.list_view
+                .insert_colorful_string(format!("{}: {}", key, value), *LIGHT_BLUE);
+        }
+
+        // trailers are listed as part of the header, and not repeated
+        // below in the message
+        let mut message = entry.message.trim_end();
+        let trailers = git2::message_trailers_strs(message).ok();
+        let trailers: Vec<(&str, &str)> = trailers.iter().flat_map(|t| t.iter()).collect();
+        for (key, value) in &trailers {
+            self.list_view
+                .insert_colorful_string(format!("{}: {}", key, value), *LIGHT_GREEN);
+        }
+        if !trailers.is_empty() {
+            if let Some((body, _)) = message.rsplit_once("\n\n") {
+                message = body;
+            }
+        }
+
+        self.list_view.insert_string(String::new());
+        for line in message.lines() {
+            self.insert_line(&format!("    {}", line));
+        }
+    }
+
+    fn add_notes(&mut self, entry: &RepoCommit, git_repo: &Repository) {
+        for notes_ref in &self.notes.clone() {
+            let notes_ref = if notes_ref.starts_with("refs/") {
+                notes_ref.clone()
+            } else {
+                format!("refs/notes/{}", notes_ref)
+            };
+            let note = match git_repo.find_note(Some(&notes_ref), entry.commit_id) {
+                Ok(note) => note,
+                Err(_) => continue,
+            };
+            self.list_view
+                .insert_colorful_string("―――".to_string(), *YELLOW);
+            self.list_view
+                .insert_colorful_string(format!("Notes ({}):", notes_ref), *LIGHT_GREEN);
+            for line in String::from_utf8_lossy(note.message_bytes()).lines() {
+                self.insert_line(&format!("    {}", line));
+            }
         }
     }
 
-    #[rustfmt::skip]
-    fn add_git_diff_output(self: &mut Self, entry: &RepoCommit){
-        let output = Command::new("git")
-                     .current_dir(&entry.repo.abs_path)
-                     .arg("--no-pager")
-                     .arg("diff")
-                     .arg("--patch-with-stat")
-                     .arg("--encoding=UTF-8")
-                     .arg("--pretty=fuller")
-                     .arg("--patch-with-stat")
-                     .arg("--no-color")
-                     .arg(format!("{}..{}^", entry.commit_id, entry.commit_id))
-                     .output()
-                     .expect("Failed to execute git-show command. git not installed?");
+    fn add_manifest_changes(&mut self, changes: &[manifest::Change]) {
+        for change in changes {
+            let color = match change {
+                manifest::Change::Pinned { .. } => *YELLOW,
+                manifest::Change::Added { .. } => *GREEN,
+                manifest::Change::Removed { .. } => *RED,
+            };
+            self.list_view
+                .insert_colorful_string(change.to_string(), color);
+        }
+    }
+
+    fn add_git_diff_output(&mut self, entry: &RepoCommit) {
+        let cached = self
+            .cache
+            .lock()
+            .unwrap()
+            .get(&entry.repo.abs_path, entry.commit_id);
+        self.requires_fetch = false;
+        let key = (entry.repo.abs_path.clone(), entry.commit_id);
+        if cached.is_none() && !self.fetches.contains(&key) {
+            let missing = missing_blobs(&entry.repo.abs_path, entry.commit_id);
+            if missing > 0 {
+                self.requires_fetch = true;
+                self.list_view.insert_colorful_string(
+                    format!("Requires fetch: {} blobs missing (partial clone)", missing),
+                    *MAGENTA,
+                );
+                self.list_view.insert_colorful_string(
+                    String::from("Press F to fetch them and show the diff"),
+                    *MAGENTA,
+                );
+                return;
+            }
+        }
+        let output = cached.unwrap_or_else(|| {
+            let output = git_diff(&entry.repo.abs_path, entry.commit_id)
+                .expect("Failed to execute git-show command. git not installed?");
+            let output = Arc::new(output);
+            self.cache.lock().unwrap().insert(
+                &entry.repo.abs_path,
+                entry.commit_id,
+                output.clone(),
+            );
+            output
+        });
+        self.insert_lines(output, 0);
+    }
+
+    /// the shown lines, including the ones not loaded yet, e.g. to save
+    /// the diff to a file
+    pub fn text(&self) -> String {
+        let mut lines: Vec<&str> = (0..self.list_view.len())
+            .filter_map(|index| self.list_view.item_text(index))
+            .collect();
+        if let Some((output, start)) = &self.rest {
+            // instead of the footer telling about them
+            lines.pop();
+            lines.extend(output[*start..].lines());
+        }
+        let mut text = lines.join("\n");
+        text.push('\n');
+        text
+    }
+
+    /// shows the diff of the shown commit even though it requires to fetch
+    /// blobs from the promisor remote of its partial clone
+    pub fn fetch_missing(&mut self) {
+        if let Some(commit) = self.commit.clone().filter(|_| self.requires_fetch) {
+            self.fetches
+                .insert((commit.repo.abs_path.clone(), commit.commit_id));
+            self.set_commit(&commit);
+        }
+    }
+
+    /// loads the next `max_lines` lines of a diff which has been cut
+    pub fn load_more(&mut self) {
+        if let Some((output, start)) = self.rest.take() {
+            // the last line is the footer telling about the lines left
+            self.list_view.remove_item(self.list_view.len() - 1);
+            self.insert_lines(output, start);
+        }
+    }
 
-        for line in String::from_utf8_lossy(&output.stdout).lines() {
-            self.list_view.insert_colorful_string(line.to_string(), Self::color_of(line));
+    /// inserts up to `max_lines` lines of the output, starting at the given
+    /// byte offset, followed by a footer if lines are left
+    fn insert_lines(&mut self, output: Arc<String>, start: usize) {
+        let limit = match self.max_lines {
+            0 => usize::MAX,
+            max_lines => max_lines,
+        };
+        let mut end = start;
+        for line in output[start..].split_inclusive('\n').take(limit) {
+            end += line.len();
+            self.insert_line(line.trim_end_matches('\n').trim_end_matches('\r'));
         }
+        let left = output[end..].lines().count();
+        if left > 0 {
+            self.list_view.insert_colorful_string(
+                format!("… {} more lines, press L to load", left),
+                *MAGENTA,
+            );
+            self.rest = Some((output, end));
+        }
+    }
+
+    fn insert_line(&mut self, line: &str) {
+        self.list_view.insert_raw_line(line.to_string());
     }
 
     fn color_of(line: &str) -> ColorStyle {
@@ -99,17 +438,83 @@ impl DiffView {
                 return cc.1;
             }
         }
-        return *WHITE;
+        *WHITE
     }
 
-    pub fn commit(self: &Self) -> &Option<RepoCommit> {
+    /// the refs are collected again when the next commit is shown, e.g.
+    /// after a branch has been created
+    pub fn forget_refs(&mut self) {
+        self.refs.clear();
+    }
+
+    pub fn commit(&self) -> &Option<RepoCommit> {
         &self.commit
     }
 }
 
+/// colors a line of the diff by its kind, matches of the highlighter
+/// are highlighted
+fn style_line(line: &str, highlighter: Option<&Regex>) -> SpannedString<Style> {
+    let color = DiffView::color_of(line);
+    let mut text = SpannedString::new();
+    let mut end = 0;
+    if let Some(highlighter) = highlighter {
+        for m in highlighter.find_iter(line) {
+            text.append_styled(&line[end..m.start()], color);
+            text.append_styled(m.as_str(), *MATCH);
+            end = m.end();
+        }
+    }
+    text.append_styled(&line[end..], color);
+    text
+}
+
+fn format_time(time: &Time) -> String {
+    as_datetime(time).format("%Y-%m-%d %H:%M:%S %z").to_string()
+}
+
+/// the names of the branches, remote branches and tags of the repository
+/// by the commit they point at
+fn refs_by_commit(git_repo: &Repository) -> HashMap<Oid, Vec<String>> {
+    let mut refs: HashMap<Oid, Vec<String>> = HashMap::new();
+    let references = match git_repo.references() {
+        Ok(references) => references,
+        Err(_) => return refs,
+    };
+    for reference in references.flatten() {
+        if !(reference.is_branch() || reference.is_remote() || reference.is_tag()) {
+            continue;
+        }
+        if let (Ok(commit), Some(name)) = (reference.peel_to_commit(), reference.shorthand()) {
+            refs.entry(commit.id())
+                .or_default()
+                .push(if reference.is_tag() {
+                    format!("tag: {}", name)
+                } else {
+                    name.to_string()
+                });
+        }
+    }
+    refs
+}
+
 impl ViewWrapper for DiffView {
     type V = ListView;
 
+    fn wrap_on_event(&mut self, event: Event) -> EventResult {
+        match event {
+            Event::Char('L') if self.rest.is_some() => {
+                self.load_more();
+                EventResult::Consumed(None)
+            }
+            Event::Char('F') if self.requires_fetch => {
+                self.fetch_missing();
+                EventResult::Consumed(None)
+            }
+            event => self.list_view.on_event(event),
+        }
+    }
+
     fn with_view<F, R>(&self, f: F) -> Option<R>
     where
         F: FnOnce(&Self::V) -> R,
@@ -0,0 +1,118 @@
+use crate::history::InputHistory;
+use cursive::event::{Event, EventResult, Key};
+use cursive::view::{View, ViewWrapper};
+use cursive::views::{EditView, NamedView};
+
+/// An input field supporting readline-style editing (e.g. `Ctrl-a`,
+/// `Ctrl-e`, `Ctrl-k`, `Ctrl-u`, `Ctrl-w`) and, if given, a history
+/// browsed with the up and down arrows
+pub struct InputView {
+    edit_view: NamedView<EditView>,
+    history: Option<InputHistory>,
+}
+
+impl InputView {
+    pub fn new(edit_view: NamedView<EditView>) -> Self {
+        InputView {
+            edit_view,
+            history: None,
+        }
+    }
+
+    pub fn history(mut self, history: InputHistory) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    fn recall<F>(&mut self, f: F) -> EventResult
+    where
+        F: FnOnce(&mut InputHistory, &str) -> Option<String>,
+    {
+        let mut edit_view = self.edit_view.get_mut();
+        let entry = match &mut self.history {
+            Some(history) => f(history, &edit_view.get_content()),
+            None => return EventResult::Ignored,
+        };
+        match entry {
+            Some(entry) => {
+                let cb = edit_view.set_content(entry);
+                EventResult::Consumed(Some(cb))
+            }
+            None => EventResult::Consumed(None),
+        }
+    }
+
+    /// sends the given key to the edit view as long as this changes its
+    /// content, or until `stop` returns true for a removed character; `stop`
+    /// gets passed as well whether a non-whitespace character was removed
+    fn repeat<F>(&mut self, key: Key, stop: F) -> EventResult
+    where
+        F: Fn(char, bool) -> bool,
+    {
+        let mut edit_view = self.edit_view.get_mut();
+        let mut result = EventResult::Consumed(None);
+        let mut removed_word = false;
+        loop {
+            let before = edit_view.get_content();
+            let event_result = edit_view.on_event(Event::Key(key));
+            let after = edit_view.get_content();
+            let removed = match removed_char(&before, &after) {
+                Some(removed) => removed,
+                None => break,
+            };
+            if stop(removed, removed_word) {
+                // undo the removal of the character stopping the deletion
+                let cb = edit_view.insert(removed);
+                result = result.and(EventResult::Consumed(Some(cb)));
+                break;
+            }
+            result = result.and(event_result);
+            removed_word |= !removed.is_whitespace();
+        }
+        result
+    }
+}
+
+/// returns the character removed from `before` to get `after`
+fn removed_char(before: &str, after: &str) -> Option<char> {
+    if before.len() == after.len() {
+        return None;
+    }
+    before
+        .chars()
+        .zip(after.chars().map(Some).chain(std::iter::once(None)))
+        .find(|(b, a)| Some(*b) != *a)
+        .map(|(b, _)| b)
+}
+
+impl ViewWrapper for InputView {
+    cursive::wrap_impl!(self.edit_view: NamedView<EditView>);
+
+    fn wrap_on_event(&mut self, event: Event) -> EventResult {
+        let forward = |view: &mut Self, key: Key| view.edit_view.on_event(Event::Key(key));
+        match event {
+            Event::Key(Key::Up) => {
+                self.recall(|history, current| history.previous(current).map(String::from))
+            }
+            Event::Key(Key::Down) => self.recall(|history, _| history.next().map(String::from)),
+            Event::Key(Key::Enter) => {
+                let content = self.edit_view.get_mut().get_content();
+                if let Some(history) = &mut self.history {
+                    history.push(&content);
+                }
+                self.edit_view.on_event(event)
+            }
+            Event::CtrlChar('a') => forward(self, Key::Home),
+            Event::CtrlChar('e') => forward(self, Key::End),
+            Event::CtrlChar('b') => forward(self, Key::Left),
+            Event::CtrlChar('f') => forward(self, Key::Right),
+            Event::CtrlChar('d') => forward(self, Key::Del),
+            Event::CtrlChar('k') => self.repeat(Key::Del, |_, _| false),
+            Event::CtrlChar('u') => self.repeat(Key::Backspace, |_, _| false),
+            Event::CtrlChar('w') => self.repeat(Key::Backspace, |removed, removed_word| {
+                removed_word && removed.is_whitespace()
+            }),
+            _ => self.edit_view.on_event(event),
+        }
+    }
+}
@@ -6,6 +6,12 @@
 //  - Removed Header and grid
 //  - Removed columns and sorting
 //  - Allowing different colors items
+//  - Added a scroll position indicator
+//  - Page-size-aware PageUp/PageDown with configurable step
+//  - Submit on double click instead of a click on the selected row
+//  - Lines are styled lazily when drawn, only visible lines are drawn
+//  - The focused line keeps its colors, inverted
+//  - The selected row and the scroll offset can be restored
 
 //! A basic list view implementation for [cursive](https://crates.io/crates/cursive).
 #![deny(
@@ -24,23 +30,50 @@ extern crate cursive;
 // STD Dependencies -----------------------------------------------------------
 use std::cmp;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 // External Dependencies ------------------------------------------------------
 use cursive::direction::Direction;
 use cursive::event::{Callback, Event, EventResult, Key, MouseButton, MouseEvent};
-use cursive::theme::{ColorStyle, Style};
+use cursive::theme::{ColorStyle, Effect, Style};
 use cursive::utils::span::{SpannedStr, SpannedString};
 use cursive::vec::Vec2;
 use cursive::view::{scroll, CannotFocus, View};
+use cursive::Rect;
 use cursive::With;
-use cursive::{theme, Rect};
 use cursive::{Cursive, Printer};
 
+use super::scroll_position::scroll_position;
+
+/// Maximum time between two clicks on the same row to submit it.
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+
 /// Callback taking as argument the row and the index of an element.
 ///
 /// This is a private type to help readability.
 type IndexCallback = Rc<dyn Fn(&mut Cursive, usize, usize)>;
 
+/// Callback building the styled representation of a raw line.
+type Styler = Rc<dyn Fn(&str) -> SpannedString<Style>>;
+
+/// A line of the list, its spans are built only when it is drawn
+enum Line {
+    Plain(String),
+    Colored(String, ColorStyle),
+    Spanned(SpannedString<Style>),
+    /// styled by the styler of the list
+    Raw(String),
+}
+
+impl Line {
+    fn text(&self) -> &str {
+        match self {
+            Line::Plain(text) | Line::Colored(text, _) | Line::Raw(text) => text,
+            Line::Spanned(text) => text.source(),
+        }
+    }
+}
+
 /// View to select an SpnnedString among a list
 pub struct ListView {
     enabled: bool,
@@ -48,8 +81,15 @@ pub struct ListView {
     needs_relayout: bool,
 
     focus: usize,
-    items: Vec<SpannedString<Style>>,
+    /// rows to move by PageUp/PageDown, defaults to the viewport height
+    page_step: Option<usize>,
+    /// row and time of the last click, to detect double clicks
+    last_click: Option<(usize, Instant)>,
+    items: Vec<Line>,
     rows_to_items: Vec<usize>,
+    styler: Option<Styler>,
+    /// scroll offset to restore by the next layout, see `restore_position`
+    pending_offset: Option<Vec2>,
 
     // TODO Pass drawing offsets into the handlers so a popup menu
     // can be created easily?
@@ -80,8 +120,12 @@ impl ListView {
             needs_relayout: true,
 
             focus: 0,
+            page_step: None,
+            last_click: None,
             items: Vec::new(),
             rows_to_items: Vec::new(),
+            styler: None,
+            pending_offset: None,
 
             on_submit: None,
             on_select: None,
@@ -219,6 +263,20 @@ impl ListView {
         }
     }
 
+    /// Sets the number of rows PageUp/PageDown move the selection and the
+    /// viewport by, `None` to move by the height of the viewport.
+    pub fn set_page_step(&mut self, page_step: Option<usize>) {
+        self.page_step = page_step;
+    }
+
+    /// Sets the number of rows PageUp/PageDown move the selection and the
+    /// viewport by, `None` to move by the height of the viewport.
+    ///
+    /// Chainable variant.
+    pub fn page_step(self, page_step: Option<usize>) -> Self {
+        self.with(|t| t.set_page_step(page_step))
+    }
+
     /// Selects the row at the specified index.
     pub fn set_selected_row(&mut self, row_index: usize) {
         self.focus = row_index;
@@ -232,28 +290,32 @@ impl ListView {
         self.with(|t| t.set_selected_row(row_index))
     }
 
-    /// Returns a immmutable reference to the item at the specified index
-    /// within the underlying storage vector.
-    pub fn borrow_item(&mut self, index: usize) -> Option<&SpannedString<Style>> {
-        self.items.get(index)
+    /// Returns the selected row and the scroll offset, e.g. to restore them
+    /// with `restore_position` after the items have been replaced.
+    pub fn position(&self) -> (usize, Vec2) {
+        (self.focus, self.scroll_core.content_viewport().top_left())
     }
 
-    /// Returns a mutable reference to the item at the specified index within
-    /// the underlying storage vector.
-    pub fn borrow_item_mut(&mut self, index: usize) -> Option<&mut SpannedString<Style>> {
-        self.items.get_mut(index)
+    /// Selects the given row and scrolls to the given offset, as far as the
+    /// items allow it. The offset is applied by the next layout.
+    pub fn restore_position(&mut self, (row, offset): (usize, Vec2)) {
+        self.focus = cmp::min(row, self.items.len().saturating_sub(1));
+        self.pending_offset = Some(offset);
     }
 
-    /// Returns a immmutable reference to the items contained within the table.
-    pub fn borrow_items(&mut self) -> &Vec<SpannedString<Style>> {
-        &self.items
+    /// Returns the text of the item at the specified index within the
+    /// underlying storage vector.
+    pub fn item_text(&self, index: usize) -> Option<&str> {
+        self.items.get(index).map(Line::text)
     }
 
-    /// Returns a mutable reference to the items contained within the table.
-    ///
-    /// Can be used to modify the items in place.
-    pub fn borrow_items_mut(&mut self) -> &mut Vec<SpannedString<Style>> {
-        &mut self.items
+    /// Sets the callback styling the lines inserted by `insert_raw_line`,
+    /// it is called whenever such a line is drawn.
+    pub fn set_styler<F>(&mut self, styler: F)
+    where
+        F: Fn(&str) -> SpannedString<Style> + 'static,
+    {
+        self.styler = Some(Rc::new(styler));
     }
 
     /// Returns the index of the currently selected item within the underlying
@@ -293,7 +355,7 @@ impl ListView {
     ///
     /// The currently active sort order is preserved and will be applied to the
     /// newly inserted item.
-    fn insert_item(&mut self, item: SpannedString<Style>) {
+    fn insert_item(&mut self, item: Line) {
         self.items.push(item);
         self.rows_to_items.push(self.items.len() - 1);
 
@@ -302,19 +364,29 @@ impl ListView {
 
     pub fn insert_string(&mut self, s: String) {
         for line in s.split('\n') {
-            self.insert_item(SpannedString::<Style>::plain(line));
+            self.insert_item(Line::Plain(line.to_string()));
         }
     }
 
     pub fn insert_colorful_string(&mut self, s: String, c: ColorStyle) {
         for line in s.split('\n') {
-            self.insert_item(SpannedString::styled(line, c));
+            self.insert_item(Line::Colored(line.to_string(), c));
         }
     }
 
+    pub fn insert_spanned_string(&mut self, s: SpannedString<Style>) {
+        self.insert_item(Line::Spanned(s));
+    }
+
+    /// Inserts a line which is styled by the styler of the list (see
+    /// `set_styler`) when it is drawn, e.g. a line of a huge diff.
+    pub fn insert_raw_line(&mut self, line: String) {
+        self.insert_item(Line::Raw(line));
+    }
+
     /// Removes the item at the specified index within the underlying storage
-    /// vector and returns it.
-    pub fn remove_item(&mut self, item_index: usize) -> Option<SpannedString<Style>> {
+    /// vector and returns its text.
+    pub fn remove_item(&mut self, item_index: usize) -> Option<String> {
         if item_index < self.items.len() {
             // Move the selection if the currently selected item gets removed
             if let Some(selected_index) = self.item() {
@@ -336,31 +408,46 @@ impl ListView {
             self.needs_relayout = true;
 
             // Remove actual item from the underlying storage
-            Some(self.items.remove(item_index))
+            Some(self.items.remove(item_index).text().to_string())
         } else {
             None
         }
     }
 
-    /// Removes all items from the underlying storage and returns them.
-    pub fn take_items(&mut self) -> Vec<SpannedString<Style>> {
+    /// Removes all items from the underlying storage and returns their text.
+    pub fn take_items(&mut self) -> Vec<String> {
         self.set_selected_row(0);
         self.rows_to_items.clear();
         self.needs_relayout = true;
-        self.items.drain(0..).collect()
+        self.items
+            .drain(0..)
+            .map(|item| item.text().to_string())
+            .collect()
     }
 }
 
 impl ListView {
     fn draw_item(&self, focused: bool, printer: &Printer, i: usize) {
-        let item = &self.items[i];
+        let mut text = self.styled(&self.items[i]);
         if focused {
-            let item_without_color = SpannedString::<Style>::plain(item.source());
-            printer.with_style(ColorStyle::highlight(), |printer: &Printer| {
-                printer.print_styled((0, 0), SpannedStr::from(&item_without_color));
-            });
-        } else {
-            printer.print_styled((0, 0), SpannedStr::from(item));
+            // the colors are inverted instead of replaced, so e.g. added
+            // and removed lines of a diff stay recognizable
+            for span in text.spans_attr_mut() {
+                span.attr.effects.insert(Effect::Reverse);
+            }
+        }
+        printer.print_styled((0, 0), SpannedStr::from(&text));
+    }
+
+    fn styled(&self, item: &Line) -> SpannedString<Style> {
+        match item {
+            Line::Plain(text) => SpannedString::<Style>::plain(text.as_str()),
+            Line::Colored(text, color) => SpannedString::styled(text.as_str(), *color),
+            Line::Spanned(text) => text.clone(),
+            Line::Raw(text) => match &self.styler {
+                Some(styler) => styler(text),
+                None => SpannedString::<Style>::plain(text.as_str()),
+            },
         }
     }
 
@@ -374,6 +461,22 @@ impl ListView {
         )
     }
 
+    /// remembers the click on the given row, returns true if the
+    /// previous click hit the same row shortly before
+    fn is_double_click(&mut self, row: usize) -> bool {
+        let now = Instant::now();
+        let double_click = matches!(self.last_click,
+            Some((last_row, time)) if last_row == row && now.duration_since(time) < DOUBLE_CLICK_INTERVAL);
+        self.last_click = if double_click { None } else { Some((row, now)) };
+        double_click
+    }
+
+    fn page_step_rows(&self) -> usize {
+        self.page_step
+            .unwrap_or_else(|| self.scroll_core.content_viewport().height())
+            .max(1)
+    }
+
     fn focus_up(&mut self, n: usize) {
         self.focus -= cmp::min(self.focus, n);
     }
@@ -395,7 +498,10 @@ impl ListView {
     }
 
     fn draw_content(&self, printer: &Printer) {
-        for i in 0..self.rows_to_items.len() {
+        // only draw the rows which are visible
+        let first_row = printer.content_offset.y;
+        let last_row = cmp::min(first_row + printer.output_size.y, self.rows_to_items.len());
+        for i in first_row..last_row {
             let printer = printer.offset((0, i));
             self.draw_item(self.focus == i, &printer, i);
         }
@@ -417,10 +523,14 @@ impl ListView {
                 self.focus_down(1);
             }
             Event::Key(Key::PageUp) => {
-                self.focus_up(10);
+                let step = self.page_step_rows();
+                self.focus_up(step);
+                self.scroll_core.scroll_up(step);
             }
             Event::Key(Key::PageDown) => {
-                self.focus_down(10);
+                let step = self.page_step_rows();
+                self.focus_down(step);
+                self.scroll_core.scroll_down(step);
             }
             Event::Key(Key::Home) => {
                 self.focus = 0;
@@ -436,21 +546,13 @@ impl ListView {
             Event::Mouse {
                 position,
                 offset,
-                event: MouseEvent::Press(MouseButton::Left),
-            } if !self.is_empty()
-                && position
-                    .checked_sub(offset)
-                    .map_or(false, |p| p.y == self.focus) =>
-            {
-                return self.on_submit_event();
-            }
-            Event::Mouse {
-                position,
-                offset,
-                event: MouseEvent::Press(_),
+                event: MouseEvent::Press(button),
             } if !self.is_empty() => match position.checked_sub(offset) {
                 Some(position) if position.y < self.rows_to_items.len() => {
                     self.focus = position.y;
+                    if button == MouseButton::Left && self.is_double_click(position.y) {
+                        return self.on_submit_event();
+                    }
                 }
                 _ => return EventResult::Ignored,
             },
@@ -480,6 +582,14 @@ impl ListView {
 impl View for ListView {
     fn draw(&self, printer: &Printer) {
         scroll::draw(self, printer, Self::draw_content);
+
+        // top right corner, left of the scrollbar
+        if let Some(position) = scroll_position(&self.scroll_core) {
+            let x = printer.size.x.saturating_sub(position.len() + 2);
+            printer.with_color(ColorStyle::highlight_inactive(), |printer| {
+                printer.print((x, 0), &format!(" {}", position));
+            });
+        }
     }
 
     fn layout(&mut self, size: Vec2) {
@@ -490,6 +600,9 @@ impl View for ListView {
             Self::layout_content,
             Self::content_required_size,
         );
+        if let Some(offset) = self.pending_offset.take() {
+            self.scroll_core.set_offset(offset);
+        }
     }
 
     fn take_focus(&mut self, _: Direction) -> Result<EventResult, CannotFocus> {
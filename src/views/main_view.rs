@@ -1,101 +1,1605 @@
-use crate::model::{MultiRepoHistory, RepoCommit};
-use crate::styles::{GREEN, RED, WHITE};
+use crate::builds::{self, Build, BuildCut};
+use crate::commit_url::CommitUrlResolver;
+use crate::config::{
+    Config, DateColors, ExtraColumn, Highlight, HighlightColumn, HighlightEffect, HighlightField,
+};
+use crate::filter::Filter;
+use crate::fingerprints::Fingerprints;
+use crate::history::InputHistory;
+use crate::i18n::{relative_date, tr};
+use crate::issues::IssueTracker;
+use crate::lint::Linter;
+use crate::model::{MultiRepoHistory, Repo, RepoCommit, RevWalkStrategy};
+use crate::organizations::Organizations;
+use crate::plugin_columns::PluginColumns;
+use crate::styles::{BOT, GREEN, LIGHT_BLUE, MAGENTA, RED, STALE, WHITE, YELLOW};
+use crate::undo::UndoStack;
+use crate::utils::{as_datetime, expand_placeholders};
 use crate::views::table_view::{TableView, TableViewItem};
-use cursive::theme::{BaseColor, Color, ColorStyle};
+use crate::views::{DiffView, InputView};
+use chrono::Datelike;
+use cursive::event::Key;
+use cursive::theme::{BaseColor, Color, ColorStyle, Effect, Style};
 use cursive::traits::*;
 use cursive::view::ViewWrapper;
-use cursive::views::{Canvas, LinearLayout, ViewRef};
+use cursive::views::{
+    Canvas, EditView, HideableView, LinearLayout, OnEventView, TextView, ViewRef,
+};
 use cursive::Cursive;
-use std::cell::RefCell;
-use std::cmp::Ordering;
+use git2::{Oid, Repository};
+use regex::Regex;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::hash::Hash;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::sync::Arc;
+use std::thread;
 
 const COLUMN_WIDTH_COMMIT_DATE: usize = 22;
 const COLUMN_WIDTH_REPO_NAME: usize = 15;
 const COLUMN_WIDTH_COMITTER: usize = 17;
 const COLUMN_WIDTH_SUBJECT: usize = 70;
+const COLUMN_MAX_WIDTH_GRAPH: usize = 15;
+const COLUMN_WIDTH_PARENTS: usize = 7;
+const COLUMN_WIDTH_PULL_REQUEST: usize = 6;
+const COLUMN_WIDTH_LANE: usize = 4;
+const COLUMN_WIDTH_ORGANIZATION: usize = 15;
+const COLUMN_WIDTH_TEAM: usize = 15;
+const COLUMN_WIDTH_WORKSPACE: usize = 15;
+const COLUMN_WIDTH_LINT: usize = 30;
+const UNDO_LIMIT: usize = 100;
+const DEFAULT_COMMIT_BAR_FORMAT: &str = "Commit {index} of {total}{filtered} - {repo}";
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash)]
 enum Column {
+    Graph,
+    Lane,
+    Merge,
+    Parents,
+    PullRequest,
     CommitDateTime,
+    AuthorDateTime,
+    Landed,
+    Organization,
+    Team,
     Comitter,
+    Workspace,
     Repo,
     Summary,
+    /// violated rules of `[lint]` in the config file
+    Lint,
+    /// see `[[column]]` in the config file
+    Plugin(usize),
 }
 
-impl TableViewItem<Column> for RepoCommit {
+/// Settings shared by all rows of the commit table
+struct RowSettings {
+    date_colors: DateColors,
+    highlights: Vec<Highlight>,
+    commit_url: Arc<CommitUrlResolver>,
+    organizations: Rc<Organizations>,
+    linter: Rc<Linter>,
+    plugin_columns: PluginColumns,
+    /// true while the listed commits are scanned again
+    stale: Cell<bool>,
+}
+
+impl RowSettings {
+    /// returns the style of the first highlight rule of the whole row
+    /// matching the given commit
+    fn highlight_style(&self, commit: &RepoCommit) -> Option<Style> {
+        let highlight = self.matching_highlight(commit, None)?;
+
+        let mut style = match &highlight.color {
+            Some(color) => Style::from(ColorStyle::front(color.color())),
+            None => Style::default(),
+        };
+        for effect in &highlight.effects {
+            style.effects.insert(match effect {
+                HighlightEffect::Bold => Effect::Bold,
+                HighlightEffect::Underline => Effect::Underline,
+                HighlightEffect::Italic => Effect::Italic,
+                HighlightEffect::Reverse => Effect::Reverse,
+            });
+        }
+        Some(style)
+    }
+
+    /// returns the color of the first highlight rule of the given cell
+    /// matching the given commit
+    fn highlight_color(&self, commit: &RepoCommit, column: HighlightColumn) -> Option<ColorStyle> {
+        let highlight = self.matching_highlight(commit, Some(column))?;
+        Some(ColorStyle::front(highlight.color.as_ref()?.color()))
+    }
+
+    fn matching_highlight(
+        &self,
+        commit: &RepoCommit,
+        column: Option<HighlightColumn>,
+    ) -> Option<&Highlight> {
+        self.highlights.iter().find(|h| {
+            h.column == column
+                && match h.field {
+                    HighlightField::Author => {
+                        h.pattern.is_match(&commit.author_name)
+                            || h.pattern.is_match(&commit.author_email)
+                    }
+                    HighlightField::Summary => h.pattern.is_match(&commit.summary),
+                    HighlightField::Repo => h.pattern.is_match(&commit.repo.rel_path),
+                }
+        })
+    }
+}
+
+/// A row of the commit table
+#[derive(Clone)]
+struct CommitRow {
+    commit: RepoCommit,
+    settings: Rc<RowSettings>,
+    /// matches the words of the active filter
+    highlighter: Option<Rc<Regex>>,
+    /// text of a separator row, shown above the commit
+    separator: Option<String>,
+    /// number of the following commits of the same author folded into
+    /// this row
+    folded: usize,
+    /// number of the following commits of its collapsed group folded into
+    /// this row, see `Grouping`
+    grouped: usize,
+}
+
+impl CommitRow {
+    fn new(
+        commit: RepoCommit,
+        settings: &Rc<RowSettings>,
+        highlighter: &Option<Rc<Regex>>,
+    ) -> CommitRow {
+        CommitRow {
+            commit,
+            settings: settings.clone(),
+            highlighter: highlighter.clone(),
+            separator: None,
+            folded: 0,
+            grouped: 0,
+        }
+    }
+
+    /// a separator row above the given commit
+    fn separator(commit: RepoCommit, settings: &Rc<RowSettings>, text: String) -> CommitRow {
+        CommitRow {
+            separator: Some(text),
+            ..CommitRow::new(commit, settings, &None)
+        }
+    }
+
+    fn is_commit(&self) -> bool {
+        self.separator.is_none()
+    }
+}
+
+impl PartialEq for CommitRow {
+    fn eq(&self, other: &Self) -> bool {
+        self.commit == other.commit
+            && self.separator == other.separator
+            && self.folded == other.folded
+            && self.grouped == other.grouped
+    }
+}
+
+impl TableViewItem<Column> for CommitRow {
     fn to_column(&self, column: Column) -> String {
+        let commit = &self.commit;
         match column {
-            Column::CommitDateTime => self.time_as_str(),
-            Column::Comitter => self.committer.clone(),
-            Column::Repo => self.repo.description.clone(),
-            Column::Summary => self.summary.clone(),
+            Column::Graph => commit.graph.clone(),
+            Column::Lane => match commit.first_parent_distance {
+                0 => String::new(),
+                distance => format!("+{}", distance),
+            },
+            Column::Merge => String::from(if commit.is_merge() { "M" } else { "" }),
+            Column::Parents => commit.parent_ids.len().to_string(),
+            Column::PullRequest => match self.settings.commit_url.pull_request(commit) {
+                Some(number) => format!("#{}", number),
+                None => String::new(),
+            },
+            Column::CommitDateTime => commit.time_as_str(),
+            Column::AuthorDateTime => commit.author_time_as_str(),
+            Column::Landed => commit.first_seen_as_str(),
+            Column::Comitter => commit.committer.clone(),
+            Column::Organization => self.settings.organizations.of(&commit.author_email),
+            Column::Team => commit.repo.team.clone().unwrap_or_default(),
+            Column::Workspace => commit.repo.workspace.clone().unwrap_or_default(),
+            Column::Plugin(index) => self.settings.plugin_columns.value(index, commit),
+            Column::Lint => self
+                .settings
+                .linter
+                .check(commit)
+                .iter()
+                .map(|violation| violation.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            Column::Repo => commit.repo.description.clone(),
+            Column::Summary => match self.folded {
+                _ if self.grouped > 0 => expand_placeholders(
+                    tr("[{count} commits collapsed] {summary}"),
+                    &[
+                        ("count", (self.grouped + 1).to_string()),
+                        ("summary", commit.summary.clone()),
+                    ],
+                ),
+                0 => commit.summary.clone(),
+                folded => expand_placeholders(
+                    tr("[{count} commits by {author}] {summary}"),
+                    &[
+                        ("count", (folded + 1).to_string()),
+                        ("author", commit.author_name.clone()),
+                        ("summary", commit.summary.clone()),
+                    ],
+                ),
+            },
         }
     }
 
-    fn cmp(&self, _other: &Self, _column: Column) -> Ordering
-    where
-        Self: Sized,
-    {
-        Ordering::Equal
+    fn style_for(&self, column: Column) -> Option<ColorStyle> {
+        let highlighted = match column {
+            Column::CommitDateTime => Some(HighlightColumn::Date),
+            Column::Repo => Some(HighlightColumn::Repo),
+            Column::Comitter => Some(HighlightColumn::Committer),
+            Column::Summary => Some(HighlightColumn::Summary),
+            _ => None,
+        };
+        if let Some(color) =
+            highlighted.and_then(|column| self.settings.highlight_color(&self.commit, column))
+        {
+            return Some(color);
+        }
+        match column {
+            Column::CommitDateTime => {
+                let date_colors = &self.settings.date_colors;
+                let age = self.commit.age().num_hours();
+                if self.commit.is_future_dated() {
+                    Some(*RED)
+                } else if age < i64::from(date_colors.fresh_hours) {
+                    Some(*GREEN)
+                } else if age < i64::from(date_colors.recent_hours) {
+                    Some(*YELLOW)
+                } else {
+                    None
+                }
+            }
+            Column::Summary if self.commit.wip.is_some() => Some(*MAGENTA),
+            _ => None,
+        }
     }
+
+    fn highlights_for(&self, column: Column) -> Vec<Range<usize>> {
+        match (&self.highlighter, column) {
+            (Some(highlighter), Column::Comitter | Column::Repo | Column::Summary) => highlighter
+                .find_iter(&self.to_column(column))
+                .map(|m| m.range())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn row_style(&self) -> Option<Style> {
+        if self.settings.stale.get() {
+            return Some(Style::from(*STALE));
+        }
+        self.settings
+            .highlight_style(&self.commit)
+            .or_else(|| self.commit.is_bot.then(|| Style::from(*BOT)))
+    }
+
+    fn separator(&self) -> Option<String> {
+        self.separator.clone()
+    }
+}
+
+/// Groups of consecutive commits listed below a separator row telling
+/// their number, e.g. to navigate year-long day windows
+#[derive(Copy, Clone, PartialEq)]
+enum Grouping {
+    None,
+    Week,
+    Month,
+}
+
+impl Grouping {
+    fn next(self) -> Grouping {
+        match self {
+            Grouping::None => Grouping::Week,
+            Grouping::Week => Grouping::Month,
+            Grouping::Month => Grouping::None,
+        }
+    }
+
+    /// the label of the group of the given commit, e.g. `2020-W20` or
+    /// `2020-05`
+    fn label(self, commit: &RepoCommit) -> String {
+        let date = as_datetime(&commit.commit_time);
+        match self {
+            Grouping::None => String::new(),
+            Grouping::Week => {
+                let week = date.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            Grouping::Month => format!("{:04}-{:02}", date.year(), date.month()),
+        }
+    }
+}
+
+/// Filter and selection of the main view which can be undone/redone
+struct ViewState {
+    filter: Filter,
+    selected: Option<RepoCommit>,
+}
+
+/// State of an inactive tab, the active tab's state is held by the main view
+struct Tab {
+    state: ViewState,
+    undo_stack: UndoStack<ViewState>,
+}
+
+/// Titles of the tabs and the index of the active one
+#[derive(Default)]
+struct TabBarModel {
+    titles: Vec<String>,
+    active: usize,
 }
 
+/// Callback taking as argument the row, the index and the selected commit.
+type SelectCallback = Rc<dyn Fn(&mut Cursive, usize, usize, &RepoCommit)>;
+
 pub struct MainView {
     layout: LinearLayout,
     commit_bar_model: Rc<RefCell<String>>,
+    commit_bar_format: String,
+    /// placeholders for formatting the commit and status bar, see `format()`
+    placeholders: Vec<(&'static str, String)>,
+    model: MultiRepoHistory,
+    scanning: bool,
+    /// fingerprints of the repositories as of their last scan
+    fingerprints: Fingerprints,
+    row_settings: Rc<RowSettings>,
+    filter: Filter,
+    hidden_repos: usize,
+    undo_stack: UndoStack<ViewState>,
+    /// all tabs, `None` for the active one
+    tabs: Vec<Option<Tab>>,
+    active_tab: usize,
+    tab_bar_model: Rc<RefCell<TabBarModel>>,
+    commit_url: Arc<CommitUrlResolver>,
+    issues: Arc<IssueTracker>,
+    /// see `[report]` in the config file
+    report_date_format: String,
+    report_max_rows: usize,
+    builds: Vec<Build>,
+    /// where the builds have been cut, newest first
+    build_cuts: Vec<BuildCut>,
+    /// indices of the separator rows of the commit table
+    separator_rows: Vec<usize>,
+    /// listed commits (by repo and ID) followed by commits of their repo
+    /// which are hidden by the filter
+    hidden_below: HashSet<(PathBuf, Oid)>,
+    /// listed commits whose hidden commits below are shown anyway, until
+    /// the filter changes
+    expanded: HashSet<(PathBuf, Oid)>,
+    /// whether runs of consecutive commits of the same author are folded
+    /// into the row of their first commit
+    collapse: bool,
+    /// first commits of runs which are folded
+    folded: HashSet<(PathBuf, Oid)>,
+    /// first commits of runs which are not folded despite `collapse`
+    unfolded: HashSet<(PathBuf, Oid)>,
+    grouping: Grouping,
+    /// labels of the groups folded into the row of their first commit
+    collapsed_groups: HashSet<String>,
+    on_select: Option<SelectCallback>,
 }
 
 impl MainView {
-    pub fn from(model: MultiRepoHistory) -> Self {
-        let table = Self::new_table(model);
+    pub fn from(
+        model: MultiRepoHistory,
+        fingerprints: Fingerprints,
+        config: &Config,
+        builds: Vec<Build>,
+        cb_sink: cursive::CbSink,
+    ) -> Self {
+        let commit_url = Arc::new(CommitUrlResolver::new(config));
+        let row_settings = Rc::new(RowSettings {
+            date_colors: config.date_colors.clone(),
+            highlights: config.highlight.clone(),
+            commit_url: commit_url.clone(),
+            organizations: Rc::new(Organizations::new(&config.organizations)),
+            linter: Rc::new(Linter::new(config)),
+            plugin_columns: PluginColumns::new(&config.column, cb_sink),
+            stale: Cell::new(false),
+        });
+        let table = Self::new_table(&model, config);
+        let build_cuts = builds::resolve(&builds, &model.commits);
         let commit_bar_model = Rc::new(RefCell::new(String::from("")));
         let commit_bar = Self::new_commit_bar(commit_bar_model.clone());
+        let tab_bar_model = Rc::new(RefCell::new(TabBarModel::default()));
+        let tab_bar = Self::new_tab_bar(tab_bar_model.clone());
 
-        MainView {
+        let mut main_view = MainView {
             layout: LinearLayout::vertical()
+                .child(HideableView::new(tab_bar).hidden().with_name("tabBar"))
                 .child(table.with_name("table").full_screen())
+                .child(Self::new_filter_bar())
                 .child(commit_bar),
             commit_bar_model,
+            commit_bar_format: config
+                .commit_bar_format
+                .clone()
+                .unwrap_or_else(|| String::from(tr(DEFAULT_COMMIT_BAR_FORMAT))),
+            placeholders: Vec::new(),
+            model,
+            scanning: false,
+            fingerprints,
+            row_settings,
+            filter: Filter::default(),
+            hidden_repos: 0,
+            undo_stack: UndoStack::new(UNDO_LIMIT),
+            tabs: vec![None],
+            active_tab: 0,
+            tab_bar_model,
+            commit_url,
+            issues: Arc::new(IssueTracker::new(config)),
+            report_date_format: config.report.date_format.clone(),
+            report_max_rows: config.report.max_rows,
+            build_cuts,
+            builds,
+            separator_rows: Vec::new(),
+            hidden_below: HashSet::new(),
+            expanded: HashSet::new(),
+            collapse: false,
+            folded: HashSet::new(),
+            unfolded: HashSet::new(),
+            grouping: Grouping::None,
+            collapsed_groups: HashSet::new(),
+            on_select: None,
+        };
+        main_view.apply_filter(Filter::default());
+        main_view
+    }
+
+    /// opens a new tab listing all commits
+    pub fn open_tab(siv: &mut Cursive) {
+        siv.call_on_name("mainView", |main_view: &mut MainView| {
+            main_view.tabs.push(None);
+            main_view.activate_tab(main_view.tabs.len() - 1, None);
+        });
+        Self::notify_selection(siv);
+    }
+
+    /// switches to the tab with the given index
+    pub fn switch_tab(siv: &mut Cursive, index: usize) {
+        siv.call_on_name("mainView", |main_view: &mut MainView| {
+            if index < main_view.tabs.len() && index != main_view.active_tab {
+                let tab = main_view.tabs[index].take();
+                main_view.activate_tab(index, tab);
+            }
+        });
+        Self::notify_selection(siv);
+    }
+
+    /// closes the active tab and switches to its neighbour
+    pub fn close_tab(siv: &mut Cursive) {
+        let closed = siv.call_on_name("mainView", |main_view: &mut MainView| {
+            if main_view.tabs.len() < 2 {
+                main_view.show_error(tr("Cannot close tab"), &tr("it is the last one"));
+                return false;
+            }
+            main_view.tabs.remove(main_view.active_tab);
+            let index = main_view.active_tab.min(main_view.tabs.len() - 1);
+            let tab = main_view.tabs[index].take();
+            // the closed tab must not be stored again
+            main_view.active_tab = usize::MAX;
+            main_view.activate_tab(index, tab);
+            true
+        });
+        if closed == Some(true) {
+            Self::notify_selection(siv);
         }
     }
 
+    /// stores the state of the active tab and restores the one of
+    /// the given tab (`None` for a new tab)
+    fn activate_tab(&mut self, index: usize, tab: Option<Tab>) {
+        let (state, undo_stack) = match tab {
+            Some(tab) => (tab.state, tab.undo_stack),
+            None => (
+                ViewState {
+                    filter: Filter::default(),
+                    selected: None,
+                },
+                UndoStack::new(UNDO_LIMIT),
+            ),
+        };
+        let current = Tab {
+            state: self.view_state(),
+            undo_stack: std::mem::replace(&mut self.undo_stack, undo_stack),
+        };
+        if let Some(slot) = self.tabs.get_mut(self.active_tab) {
+            *slot = Some(current);
+        }
+        self.active_tab = index;
+
+        self.apply_filter(state.filter);
+        match state.selected {
+            Some(commit) => {
+                self.select_commit(&commit);
+            }
+            None => self.select_row(0),
+        }
+    }
+
+    fn update_tab_bar(&mut self) {
+        let titles = self
+            .tabs
+            .iter()
+            .map(|tab| {
+                let filter = match tab {
+                    Some(tab) => &tab.state.filter,
+                    None => &self.filter,
+                };
+                if filter.is_empty() {
+                    String::from(tr("all"))
+                } else {
+                    filter.to_string()
+                }
+            })
+            .collect();
+        self.tab_bar_model.replace(TabBarModel {
+            titles,
+            active: self.active_tab,
+        });
+
+        let visible = self.tabs.len() > 1;
+        self.layout.call_on_name(
+            "tabBar",
+            |tab_bar: &mut HideableView<Canvas<Rc<RefCell<TabBarModel>>>>| {
+                tab_bar.set_visible(visible)
+            },
+        );
+    }
+
     pub fn set_on_select<F>(&mut self, cb: F)
     where
         F: Fn(&mut Cursive, usize, usize, &RepoCommit) + 'static,
     {
-        let mut table: ViewRef<TableView<RepoCommit, Column>> =
+        let cb: SelectCallback = Rc::new(cb);
+        self.on_select = Some(cb.clone());
+
+        let mut table: ViewRef<TableView<CommitRow, Column>> =
             self.layout.find_name("table").unwrap();
         table.set_on_select(move |siv: &mut Cursive, row: usize, index: usize| {
             let entry = siv
-                .call_on_name("table", move |table: &mut TableView<RepoCommit, Column>| {
-                    table.borrow_item(index).unwrap().clone()
+                .call_on_name("table", move |table: &mut TableView<CommitRow, Column>| {
+                    table.borrow_item(index).unwrap().commit.clone()
+                })
+                .unwrap();
+            cb(siv, row, index, &entry)
+        });
+    }
+
+    /// sets the callback invoked when a commit is submitted (Enter
+    /// or double click)
+    pub fn set_on_submit<F>(&mut self, cb: F)
+    where
+        F: Fn(&mut Cursive, usize, usize, &RepoCommit) + 'static,
+    {
+        let mut table: ViewRef<TableView<CommitRow, Column>> =
+            self.layout.find_name("table").unwrap();
+        table.set_on_submit(move |siv: &mut Cursive, row: usize, index: usize| {
+            let entry = siv
+                .call_on_name("table", move |table: &mut TableView<CommitRow, Column>| {
+                    table.borrow_item(index).unwrap().commit.clone()
                 })
                 .unwrap();
             cb(siv, row, index, &entry)
         });
     }
 
-    fn new_table(model: MultiRepoHistory) -> TableView<RepoCommit, Column> {
-        let mut table = TableView::<RepoCommit, Column>::new()
-            .column(Column::CommitDateTime, "CommitDate", |c| {
+    /// filters the table by the given filter expression, see `Filter`
+    /// for the supported syntax
+    pub fn set_filter(&mut self, text: &str) -> Result<(), String> {
+        self.apply_filter(Filter::parse(text)?);
+        Ok(())
+    }
+
+    /// adds the given term to the current filter or removes it
+    /// in case the filter contains it already
+    pub fn toggle_filter_term(&mut self, term: &str) -> Result<(), String> {
+        let mut filter = self.filter.clone();
+        filter.toggle(term)?;
+        self.apply_filter(filter);
+        Ok(())
+    }
+
+    /// modifies the filter of the main view and updates the
+    /// selection (or shows the error) afterwards
+    pub fn update_filter<F>(siv: &mut Cursive, f: F)
+    where
+        F: FnOnce(&mut MainView) -> Result<(), String>,
+    {
+        let result = siv
+            .call_on_name("mainView", |main_view: &mut MainView| {
+                let state = main_view.view_state();
+                let result = f(main_view);
+                if result.is_ok() && main_view.filter != state.filter {
+                    main_view.undo_stack.push(state);
+                }
+                result
+            })
+            .unwrap();
+        match result {
+            Ok(()) => Self::notify_selection(siv),
+            Err(error) => {
+                siv.call_on_name("mainView", |main_view: &mut MainView| {
+                    main_view.show_error(tr("Invalid filter"), &error)
+                });
+            }
+        }
+    }
+
+    fn apply_filter(&mut self, filter: Filter) {
+        self.layout
+            .call_on_name("filterBar", |filter_bar: &mut EditView| {
+                filter_bar.set_content(filter.to_string())
+            });
+        let unchanged = filter == self.filter;
+        if !unchanged {
+            self.expanded.clear();
+            self.unfolded.clear();
+        }
+        self.filter = filter;
+
+        let filter = &self.filter;
+        let highlighter = filter.highlighter().map(Rc::new);
+        let matching: Vec<bool> = self
+            .model
+            .commits
+            .iter()
+            .map(|c| filter.matches(c))
+            .collect();
+        let (gaps, mut shown) = self.hidden_gaps(&matching);
+        // folded commits are listed, if only in the row of another one
+        let listed = shown.clone();
+        let groups = self.group(&mut shown);
+        let runs = self.fold_runs(&mut shown);
+        // the lines of the graph would end in the rows of hidden commits
+        let complete_graph = shown.iter().all(|shown| *shown);
+        let mut items: Vec<CommitRow> = Vec::new();
+        let mut separator_rows = Vec::new();
+        let mut build_cuts = self.build_cuts.iter().peekable();
+        let mut hidden_below = HashSet::new();
+        let mut folded = HashSet::new();
+        for (index, commit) in self.model.commits.iter().enumerate() {
+            if !shown[index] {
+                continue;
+            }
+            // the commits are listed newest first, a build contains the
+            // commits up to its cut
+            while let Some(cut) = build_cuts.next_if(|cut| cut.time >= commit.commit_time.seconds())
+            {
+                separator_rows.push(items.len());
+                items.push(CommitRow::separator(
+                    commit.clone(),
+                    &self.row_settings,
+                    expand_placeholders(tr("build {build} cut here"), &[("build", cut.id.clone())]),
+                ));
+            }
+            if let Some((label, count)) = groups.get(&index) {
+                let placeholders = [
+                    ("label", label.clone()),
+                    ("count", count.to_string()),
+                    (
+                        "commits",
+                        tr(if *count == 1 { "commit" } else { "commits" }).to_string(),
+                    ),
+                ];
+                separator_rows.push(items.len());
+                items.push(CommitRow::separator(
+                    commit.clone(),
+                    &self.row_settings,
+                    if self.collapsed_groups.contains(label) {
+                        expand_placeholders(
+                            tr("▸ {label} · {count} {commits} collapsed, press e to expand"),
+                            &placeholders,
+                        )
+                    } else {
+                        expand_placeholders("▾ {label} · {count} {commits}", &placeholders)
+                    },
+                ));
+            }
+            let mut row = CommitRow::new(commit.clone(), &self.row_settings, &highlighter);
+            if !complete_graph {
+                row.commit.graph.clear();
+            }
+            if let Some((label, count)) = groups.get(&index) {
+                if self.collapsed_groups.contains(label) {
+                    row.grouped = count - 1;
+                }
+            }
+            if let Some(run) = runs.get(&index) {
+                row.folded = *run;
+                folded.insert((commit.repo.abs_path.clone(), commit.commit_id));
+            }
+            items.push(row);
+            if let Some(hidden) = gaps.get(&index) {
+                separator_rows.push(items.len());
+                items.push(CommitRow::separator(
+                    commit.clone(),
+                    &self.row_settings,
+                    expand_placeholders(
+                        tr("… {count} {commits} of {repo} hidden by the filter, press e to show …"),
+                        &[
+                            ("count", hidden.to_string()),
+                            (
+                                "commits",
+                                tr(if *hidden == 1 { "commit" } else { "commits" }).to_string(),
+                            ),
+                            ("repo", commit.repo.name()),
+                        ],
+                    ),
+                ));
+                hidden_below.insert((commit.repo.abs_path.clone(), commit.commit_id));
+            }
+        }
+        self.separator_rows = separator_rows;
+        self.hidden_below = hidden_below;
+        self.folded = folded;
+
+        let repos_of = |commits: &mut dyn Iterator<Item = &RepoCommit>| {
+            commits
+                .map(|commit| &commit.repo.abs_path)
+                .collect::<HashSet<&PathBuf>>()
+                .len()
+        };
+        self.hidden_repos = repos_of(&mut self.model.commits.iter())
+            - repos_of(
+                &mut self
+                    .model
+                    .commits
+                    .iter()
+                    .zip(listed)
+                    .filter_map(|(commit, listed)| listed.then_some(commit)),
+            );
+
+        self.layout
+            .call_on_name("table", |table: &mut TableView<CommitRow, Column>| {
+                let listed = table.len();
+                if unchanged
+                    && listed > 0
+                    && items.len() > listed
+                    && table.borrow_items()[..] == items[..listed]
+                {
+                    // e.g. older commits after extending the day window,
+                    // the viewport stays where it is
+                    table.append_items(items.split_off(listed))
+                } else {
+                    table.set_items_stable(items)
+                }
+            });
+        self.update_tab_bar();
+    }
+
+    /// finds the commits hidden by the filter between two matching commits
+    /// of the same repository. Returns the number of hidden commits below
+    /// the matching commits (by index) and which commits are shown: the
+    /// matching ones and the hidden ones below expanded commits.
+    fn hidden_gaps(&self, matching: &[bool]) -> (HashMap<usize, usize>, Vec<bool>) {
+        let mut shown = matching.to_vec();
+        let mut gaps = HashMap::new();
+        // by repo: the last matching commit and the hidden commits since
+        let mut open: HashMap<&Path, (usize, Vec<usize>)> = HashMap::new();
+        for (index, commit) in self.model.commits.iter().enumerate() {
+            let repo = commit.repo.abs_path.as_path();
+            if !matching[index] {
+                if let Some((_, hidden)) = open.get_mut(repo) {
+                    hidden.push(index);
+                }
+                continue;
+            }
+            let (upper, hidden) = match open.insert(repo, (index, Vec::new())) {
+                Some(gap) if !gap.1.is_empty() => gap,
+                _ => continue,
+            };
+            let upper_commit = &self.model.commits[upper];
+            if self
+                .expanded
+                .contains(&(repo.to_path_buf(), upper_commit.commit_id))
+            {
+                for hidden in hidden {
+                    shown[hidden] = true;
+                }
+            } else {
+                gaps.insert(upper, hidden.len());
+            }
+        }
+        (gaps, shown)
+    }
+
+    /// groups the consecutive shown commits (if enabled) and hides the
+    /// commits of collapsed groups but their first one. Returns the label
+    /// and the number of commits of each group by the index of its first
+    /// commit.
+    fn group(&self, shown: &mut [bool]) -> HashMap<usize, (String, usize)> {
+        let mut groups: HashMap<usize, (String, usize)> = HashMap::new();
+        if self.grouping == Grouping::None {
+            return groups;
+        }
+        let mut first: Option<usize> = None;
+        for (index, commit) in self.model.commits.iter().enumerate() {
+            if !shown[index] {
+                continue;
+            }
+            let label = self.grouping.label(commit);
+            match first.and_then(|first| groups.get_mut(&first)) {
+                Some((first_label, count)) if *first_label == label => {
+                    *count += 1;
+                    shown[index] = !self.collapsed_groups.contains(&label);
+                }
+                _ => {
+                    groups.insert(index, (label, 1));
+                    first = Some(index);
+                }
+            }
+        }
+        groups
+    }
+
+    /// groups the listed commits by week, by month or not at all, in turn
+    pub fn toggle_grouping(siv: &mut Cursive) {
+        siv.call_on_name("mainView", |main_view: &mut MainView| {
+            let selected = main_view.view_state().selected;
+            main_view.grouping = main_view.grouping.next();
+            main_view.collapsed_groups.clear();
+            main_view.apply_filter(main_view.filter.clone());
+            if let Some(commit) = selected {
+                main_view.select_commit(&commit);
+            }
+        });
+        Self::notify_selection(siv);
+    }
+
+    /// how the listed commits are grouped, if they are, e.g. `grouped by
+    /// week`
+    pub fn grouping(&self) -> Option<&'static str> {
+        match self.grouping {
+            Grouping::None => None,
+            Grouping::Week => Some(tr("grouped by week")),
+            Grouping::Month => Some(tr("grouped by month")),
+        }
+    }
+
+    /// folds the runs of consecutive shown commits of the same author (if
+    /// enabled) unless they have been unfolded. Returns the number of
+    /// commits folded into the first commit of each run (by index).
+    fn fold_runs(&self, shown: &mut [bool]) -> HashMap<usize, usize> {
+        let mut runs = HashMap::new();
+        if !self.collapse {
+            return runs;
+        }
+        let commits = &self.model.commits;
+        let mut first: Option<usize> = None;
+        for index in 0..commits.len() {
+            if !shown[index] {
+                continue;
+            }
+            match first {
+                Some(first) if commits[first].author_email == commits[index].author_email => {
+                    let key = (
+                        commits[first].repo.abs_path.clone(),
+                        commits[first].commit_id,
+                    );
+                    if !self.unfolded.contains(&key) {
+                        *runs.entry(first).or_insert(0) += 1;
+                        shown[index] = false;
+                    }
+                }
+                _ => first = Some(index),
+            }
+        }
+        runs
+    }
+
+    /// folds (or unfolds) the runs of consecutive commits of the same
+    /// author, e.g. of bots
+    pub fn toggle_collapse(siv: &mut Cursive) {
+        siv.call_on_name("mainView", |main_view: &mut MainView| {
+            let selected = main_view.view_state().selected;
+            main_view.collapse = !main_view.collapse;
+            main_view.unfolded.clear();
+            main_view.apply_filter(main_view.filter.clone());
+            if let Some(commit) = selected {
+                main_view.select_commit(&commit);
+            }
+        });
+        Self::notify_selection(siv);
+    }
+
+    /// whether runs of commits of the same author are folded
+    pub fn is_collapsing(&self) -> bool {
+        self.collapse
+    }
+
+    /// unfolds the run of commits folded into the selected row (or folds
+    /// it again), otherwise shows the commits hidden by the filter below
+    /// the selected commit (or hides them again)
+    pub fn toggle_expand(siv: &mut Cursive) {
+        siv.call_on_name("mainView", |main_view: &mut MainView| {
+            let commit = match main_view.view_state().selected {
+                Some(commit) => commit,
+                None => return,
+            };
+            let key = (commit.repo.abs_path.clone(), commit.commit_id);
+            let label = main_view.grouping.label(&commit);
+            if main_view.folded.contains(&key) || main_view.unfolded.contains(&key) {
+                toggle(&mut main_view.unfolded, key);
+            } else if main_view.hidden_below.contains(&key) || main_view.expanded.contains(&key) {
+                toggle(&mut main_view.expanded, key);
+            } else if main_view.grouping != Grouping::None {
+                toggle(&mut main_view.collapsed_groups, label.clone());
+            } else {
+                main_view.show_message(tr("No commits hidden below the selected commit"));
+                return;
+            }
+            main_view.apply_filter(main_view.filter.clone());
+            if !main_view.select_commit(&commit) {
+                // folded into the first commit of its group
+                let grouping = main_view.grouping;
+                main_view.select_first(|candidate| grouping.label(candidate) == label);
+            }
+        });
+        Self::notify_selection(siv);
+    }
+
+    /// invokes the select callback for the currently selected commit,
+    /// e.g. after the items of the table have been changed
+    pub fn notify_selection(siv: &mut Cursive) {
+        let selection = siv
+            .call_on_name("mainView", |main_view: &mut MainView| {
+                let on_select = main_view.on_select.clone()?;
+                main_view
+                    .layout
+                    .call_on_name("table", |table: &mut TableView<CommitRow, Column>| {
+                        let row = table.row()?;
+                        let index = table.item()?;
+                        Some((row, index, table.borrow_item(index)?.commit.clone()))
+                    })
+                    .flatten()
+                    .map(|(row, index, entry)| (on_select, row, index, entry))
+            })
+            .flatten();
+
+        match selection {
+            Some((on_select, row, index, entry)) => on_select(siv, row, index, &entry),
+            None => {
+                siv.call_on_name("mainView", |main_view: &mut MainView| {
+                    (*main_view.commit_bar_model)
+                        .replace(String::from(tr("No commits match the filter")));
+                });
+            }
+        }
+    }
+
+    /// the commits listed up to `count` rows below and above the selected
+    /// one, nearest first
+    pub fn neighbours(&mut self, count: usize) -> Vec<RepoCommit> {
+        self.layout
+            .call_on_name("table", |table: &mut TableView<CommitRow, Column>| {
+                let row = match table.row() {
+                    Some(row) => row,
+                    None => return Vec::new(),
+                };
+                (1..=count)
+                    .flat_map(|distance| [row.checked_add(distance), row.checked_sub(distance)])
+                    .flatten()
+                    .filter_map(|row| table.borrow_item_of_row(row))
+                    .filter(|item| item.is_commit())
+                    .map(|item| item.commit.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// moves the selection to the next (or previous) listed commit for
+    /// which the given predicate holds, the predicate gets passed the
+    /// currently selected commit and the candidate
+    pub fn select_next_matching<P>(siv: &mut Cursive, forward: bool, not_found: &str, predicate: P)
+    where
+        P: Fn(&RepoCommit, &RepoCommit) -> bool,
+    {
+        let found = siv
+            .call_on_name("mainView", |main_view: &mut MainView| {
+                let state = main_view.view_state();
+                main_view
+                    .layout
+                    .call_on_name("table", |table: &mut TableView<CommitRow, Column>| {
+                        let row = table.row()?;
+                        let selected = &table.borrow_item_of_row(row)?.commit;
+                        let matches = |r: &usize| {
+                            let candidate = table.borrow_item_of_row(*r).unwrap();
+                            candidate.is_commit() && predicate(selected, &candidate.commit)
+                        };
+                        let next = if forward {
+                            (row + 1..table.len()).find(matches)
+                        } else {
+                            (0..row).rev().find(matches)
+                        }?;
+                        table.set_selected_row(next);
+                        Some(())
+                    })
+                    .flatten()
+                    .map(|()| main_view.undo_stack.push(state))
+            })
+            .flatten();
+
+        match found {
+            Some(()) => Self::notify_selection(siv),
+            None => {
+                siv.call_on_name("mainView", |main_view: &mut MainView| {
+                    (*main_view.commit_bar_model).replace(String::from(not_found));
+                });
+            }
+        }
+    }
+
+    /// restores the filter and selection before the last filter change
+    /// or jump
+    pub fn undo(siv: &mut Cursive) {
+        Self::restore_view_state(siv, tr("Nothing to undo"), |main_view, current| {
+            main_view.undo_stack.undo(current)
+        });
+    }
+
+    /// restores the filter and selection which have been undone last
+    pub fn redo(siv: &mut Cursive) {
+        Self::restore_view_state(siv, tr("Nothing to redo"), |main_view, current| {
+            main_view.undo_stack.redo(current)
+        });
+    }
+
+    fn restore_view_state<F>(siv: &mut Cursive, nothing_to_restore: &str, f: F)
+    where
+        F: FnOnce(&mut MainView, ViewState) -> Option<ViewState>,
+    {
+        let restored = siv
+            .call_on_name("mainView", |main_view: &mut MainView| {
+                let current = main_view.view_state();
+                match f(main_view, current) {
+                    Some(state) => {
+                        main_view.apply_filter(state.filter);
+                        if let Some(commit) = state.selected {
+                            main_view.select_commit(&commit);
+                        }
+                        true
+                    }
+                    None => {
+                        (*main_view.commit_bar_model).replace(String::from(nothing_to_restore));
+                        false
+                    }
+                }
+            })
+            .unwrap();
+
+        if restored {
+            Self::notify_selection(siv);
+        }
+    }
+
+    fn view_state(&mut self) -> ViewState {
+        let selected = self
+            .layout
+            .call_on_name("table", |table: &mut TableView<CommitRow, Column>| {
+                Some(table.borrow_item(table.item()?)?.commit.clone())
+            })
+            .flatten();
+        ViewState {
+            filter: self.filter.clone(),
+            selected,
+        }
+    }
+
+    fn select_row(&mut self, row: usize) {
+        self.layout
+            .call_on_name("table", |table: &mut TableView<CommitRow, Column>| {
+                table.set_selected_row(row)
+            });
+    }
+
+    /// selects the given commit, if it is listed
+    fn select_commit(&mut self, commit: &RepoCommit) -> bool {
+        self.select_first(|candidate| candidate == commit)
+    }
+
+    /// selects the first listed commit matching the given predicate
+    fn select_first<P>(&mut self, predicate: P) -> bool
+    where
+        P: Fn(&RepoCommit) -> bool,
+    {
+        self.layout
+            .call_on_name(
+                "table",
+                |table: &mut TableView<CommitRow, Column>| match (0..table.len()).find(|row| {
+                    let candidate = table.borrow_item_of_row(*row).unwrap();
+                    candidate.is_commit() && predicate(&candidate.commit)
+                }) {
+                    Some(row) => {
+                        table.set_selected_row(row);
+                        true
+                    }
+                    None => false,
+                },
+            )
+            .unwrap_or(false)
+    }
+
+    /// the commits passing the filter, in the order they are listed
+    pub fn listed_commits(&mut self) -> Vec<RepoCommit> {
+        self.layout
+            .call_on_name("table", |table: &mut TableView<CommitRow, Column>| {
+                (0..table.len())
+                    .map(|row| table.borrow_item_of_row(row).unwrap())
+                    .filter(|row| row.is_commit())
+                    .map(|row| row.commit.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// selects the given commit, the selection before can be restored
+    /// with undo
+    pub fn jump_to(siv: &mut Cursive, commit: &RepoCommit) {
+        siv.call_on_name("mainView", |main_view: &mut MainView| {
+            let state = main_view.view_state();
+            main_view.undo_stack.push(state);
+            main_view.select_commit(commit);
+        });
+        Self::notify_selection(siv);
+    }
+
+    /// selects the given commit like `jump_to()` if it is listed, otherwise
+    /// (e.g. it is hidden by the filter) it is shown in the diff view only
+    pub fn show_commit(siv: &mut Cursive, commit: &RepoCommit) {
+        let listed = siv
+            .call_on_name("mainView", |main_view: &mut MainView| {
+                let state = main_view.view_state();
+                let listed = main_view.select_commit(commit);
+                if listed {
+                    main_view.undo_stack.push(state);
+                } else {
+                    main_view.show_message(&expand_placeholders(
+                        tr("Showing {hash} - {summary}, which isn't listed"),
+                        &[
+                            ("hash", commit.commit_id.to_string()[..8].to_string()),
+                            ("summary", commit.summary.clone()),
+                        ],
+                    ));
+                }
+                listed
+            })
+            .unwrap();
+        if listed {
+            Self::notify_selection(siv);
+        } else {
+            siv.call_on_name("diffView", |diff_view: &mut DiffView| {
+                diff_view.set_commit(commit)
+            });
+        }
+    }
+
+    /// shows the commit with the given ID of the given repository, see
+    /// `show_commit()`
+    pub fn show_commit_with_id(siv: &mut Cursive, repo: &Arc<Repo>, commit_id: Oid) {
+        let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+        let commit = main_view.find_commit(repo, commit_id);
+        if commit.is_none() {
+            main_view.show_message(&expand_placeholders(
+                tr("Commit {hash} not found locally"),
+                &[("hash", commit_id.to_string()[..8].to_string())],
+            ));
+        }
+        drop(main_view);
+        if let Some(commit) = commit {
+            Self::show_commit(siv, &commit);
+        }
+    }
+
+    /// shows the commit whose ID starts with the given prefix, the listed
+    /// commits are searched first, then all repositories
+    pub fn show_commit_with_prefix(siv: &mut Cursive, prefix: &str) {
+        let mut main_view: ViewRef<MainView> = siv.find_name("mainView").unwrap();
+        let prefix = prefix.to_lowercase();
+        let commit = main_view.find_commit_by_prefix(&prefix);
+        if commit.is_none() {
+            main_view.show_message(&expand_placeholders(
+                tr("Commit {hash} not found"),
+                &[("hash", prefix.clone())],
+            ));
+        }
+        drop(main_view);
+        if let Some(commit) = commit {
+            Self::show_commit(siv, &commit);
+        }
+    }
+
+    fn find_commit_by_prefix(&self, prefix: &str) -> Option<RepoCommit> {
+        self.model
+            .commits
+            .iter()
+            .find(|c| c.commit_id.to_string().starts_with(prefix))
+            .cloned()
+            .or_else(|| {
+                self.model.repos.iter().find_map(|repo| {
+                    let git_repo = Repository::open(&repo.abs_path).ok()?;
+                    let commit = git_repo
+                        .revparse_single(prefix)
+                        .ok()?
+                        .peel_to_commit()
+                        .ok()?;
+                    Some(RepoCommit::from(repo.clone(), &commit))
+                })
+            })
+    }
+
+    /// the commit with the given ID of the given repository, taken from the
+    /// history or read from the repository if it isn't part of the history
+    pub fn find_commit(&self, repo: &Arc<Repo>, commit_id: Oid) -> Option<RepoCommit> {
+        self.model
+            .commits
+            .iter()
+            .find(|c| c.commit_id == commit_id && c.repo.abs_path == repo.abs_path)
+            .cloned()
+            .or_else(|| {
+                let git_repo = Repository::open(&repo.abs_path).ok()?;
+                let commit = git_repo.find_commit(commit_id).ok()?;
+                Some(RepoCommit::from(repo.clone(), &commit))
+            })
+    }
+
+    /// the commits of the history having the given commit as parent,
+    /// children outside of the history aren't known
+    pub fn children(&self, commit: &RepoCommit) -> Vec<RepoCommit> {
+        self.model
+            .commits
+            .iter()
+            .filter(|c| {
+                c.repo.abs_path == commit.repo.abs_path && c.parent_ids.contains(&commit.commit_id)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// regex matching the words of the active filter
+    pub fn highlighter(&self) -> Option<Regex> {
+        self.filter.highlighter()
+    }
+
+    /// resolves the URLs of commits as configured
+    pub fn commit_url(&self) -> Arc<CommitUrlResolver> {
+        self.commit_url.clone()
+    }
+
+    /// derives the organizations of the authors
+    pub fn organizations(&self) -> Rc<Organizations> {
+        self.row_settings.organizations.clone()
+    }
+
+    pub fn issues(&self) -> Arc<IssueTracker> {
+        self.issues.clone()
+    }
+
+    /// format code of date cells in exported .ods and .xlsx files
+    pub fn report_date_format(&self) -> String {
+        self.report_date_format.clone()
+    }
+
+    /// commits per file of exported .ods and .xlsx files
+    pub fn report_max_rows(&self) -> usize {
+        self.report_max_rows
+    }
+
+    /// checks the commit messages against the configured rules
+    pub fn linter(&self) -> Rc<Linter> {
+        self.row_settings.linter.clone()
+    }
+
+    pub fn model(&self) -> &MultiRepoHistory {
+        &self.model
+    }
+
+    /// number of repositories whose commits are all hidden by the filter
+    pub fn hidden_repos(&self) -> usize {
+        self.hidden_repos
+    }
+
+    /// changes the number of days the history is reaching back: older
+    /// commits are dropped when shrinking the window, extending the window
+    /// rescans only the extended range in the background
+    pub fn change_days(siv: &mut Cursive, delta: i64) {
+        let cb_sink = siv.cb_sink().clone();
+        let shrunk = siv
+            .call_on_name("mainView", |main_view: &mut MainView| {
+                let current = main_view.model.classifier.days();
+                let days = (i64::from(current) + delta).clamp(1, i64::from(u32::MAX)) as u32;
+                if main_view.scanning {
+                    main_view
+                        .show_error(tr("Cannot change the day window"), &tr("scan in progress"));
+                    false
+                } else if main_view.model.classifier.range().is_some() {
+                    main_view.show_error(tr("Cannot change the day window"), &tr("comparing refs"));
+                    false
+                } else if days < current {
+                    main_view.model.shrink(days);
+                    main_view.apply_filter(main_view.filter.clone());
+                    true
+                } else if days > current {
+                    main_view.extend_days(days, cb_sink);
+                    false
+                } else {
+                    false
+                }
+            })
+            .unwrap();
+
+        if shrunk {
+            Self::notify_selection(siv);
+        }
+    }
+
+    fn extend_days(&mut self, days: u32, cb_sink: cursive::CbSink) {
+        let repos = self.model.repos.clone();
+        let classifier = self.model.classifier.extension(days);
+        let revwalk_strategy = self.model.revwalk_strategy;
+
+        self.scanning = true;
+        (*self.commit_bar_model).replace(format!("Scanning commits of the last {} days...", days));
+
+        thread::spawn(move || {
+            let result = MultiRepoHistory::from_quietly(repos, &classifier, &revwalk_strategy);
+            cb_sink
+                .send(Box::new(move |siv| {
+                    let merged = siv
+                        .call_on_name("mainView", |main_view: &mut MainView| {
+                            main_view.scanning = false;
+                            match result {
+                                Ok(extension) => {
+                                    main_view.model.merge(extension);
+                                    main_view.build_cuts = builds::resolve(
+                                        &main_view.builds,
+                                        &main_view.model.commits,
+                                    );
+                                    main_view.apply_filter(main_view.filter.clone());
+                                    true
+                                }
+                                Err(error) => {
+                                    main_view.show_error(tr("Failed to scan"), &error);
+                                    false
+                                }
+                            }
+                        })
+                        .unwrap();
+                    if merged {
+                        Self::notify_selection(siv);
+                    }
+                }))
+                .unwrap();
+        });
+    }
+
+    /// scans the repositories which changed since their last scan (see
+    /// `Fingerprints`) again in the background, the listed commits are
+    /// greyed out until the scan finished
+    pub fn refresh(siv: &mut Cursive) {
+        let cb_sink = siv.cb_sink().clone();
+        siv.call_on_name("mainView", |main_view: &mut MainView| {
+            if main_view.scanning {
+                main_view.show_error(tr("Cannot refresh"), &tr("scan in progress"));
+            } else {
+                main_view.rescan(cb_sink);
+            }
+        });
+    }
+
+    fn rescan(&mut self, cb_sink: cursive::CbSink) {
+        let repos = self.model.repos.clone();
+        let previous = self.fingerprints.clone();
+        let classifier = self.model.classifier.clone();
+        let revwalk_strategy = self.model.revwalk_strategy;
+
+        self.scanning = true;
+        self.row_settings.stale.set(true);
+        (*self.commit_bar_model).replace(String::from("Refreshing..."));
+
+        thread::spawn(move || {
+            let fingerprints = Fingerprints::of(&repos);
+            let changed = repos
+                .into_iter()
+                .filter(|repo| fingerprints.has_changed(&previous, repo))
+                .collect();
+            let result = MultiRepoHistory::from_quietly(changed, &classifier, &revwalk_strategy);
+            cb_sink
+                .send(Box::new(move |siv| {
+                    let refreshed = siv
+                        .call_on_name("mainView", |main_view: &mut MainView| {
+                            main_view.scanning = false;
+                            main_view.row_settings.stale.set(false);
+                            match result {
+                                Ok(rescan) => {
+                                    main_view.fingerprints = fingerprints;
+                                    main_view.model.update(rescan);
+                                    main_view.build_cuts = builds::resolve(
+                                        &main_view.builds,
+                                        &main_view.model.commits,
+                                    );
+                                    main_view.apply_filter(main_view.filter.clone());
+                                    true
+                                }
+                                Err(error) => {
+                                    main_view.show_error(tr("Failed to refresh"), &error);
+                                    false
+                                }
+                            }
+                        })
+                        .unwrap();
+                    if refreshed {
+                        siv.call_on_name("diffView", DiffView::forget_refs);
+                        Self::notify_selection(siv);
+                    }
+                }))
+                .unwrap();
+        });
+    }
+
+    fn on_filter_submit(siv: &mut Cursive, text: &str) {
+        siv.focus_name("table").unwrap();
+        Self::update_filter(siv, |main_view| main_view.set_filter(text));
+    }
+
+    fn new_table(model: &MultiRepoHistory, config: &Config) -> TableView<CommitRow, Column> {
+        let mut table = TableView::<CommitRow, Column>::new()
+            .page_step(config.page_step)
+            .column(Column::Merge, "M", |c| c.width(1).color(*MAGENTA))
+            .column(Column::CommitDateTime, tr("CommitDate"), |c| {
                 c.width(COLUMN_WIDTH_COMMIT_DATE)
             })
-            .column(Column::Repo, "Git Repo", |c| {
+            .column(Column::Repo, tr("Git Repo"), |c| {
                 c.width(COLUMN_WIDTH_REPO_NAME).color(*RED)
             })
-            .column(Column::Comitter, "Committer", |c| {
+            .column(Column::Comitter, tr("Committer"), |c| {
                 c.width(COLUMN_WIDTH_COMITTER).color(*GREEN)
             })
-            .column(Column::Summary, "Summary", |c| {
+            .column(Column::Summary, tr("Summary"), |c| {
                 c.width(COLUMN_WIDTH_SUBJECT).color(*WHITE)
             });
-        table.set_items(model.commits);
+        for (index, column) in config.column.iter().enumerate() {
+            // in front of the summary
+            table.insert_column(4 + index, Column::Plugin(index), &column.title, |c| {
+                c.width(column.width).color(*LIGHT_BLUE)
+            });
+        }
+        if config.lint.is_some() {
+            // behind the summary
+            let index = table.column_index(Column::Summary).map_or(0, |i| i + 1);
+            table.insert_column(index, Column::Lint, tr("Lint"), |c| {
+                c.width(COLUMN_WIDTH_LINT).color(*YELLOW)
+            });
+        }
+        if config.extra_columns.contains(&ExtraColumn::Organization) {
+            // in front of the summary
+            table.insert_column(4, Column::Organization, tr("Organization"), |c| {
+                c.width(COLUMN_WIDTH_ORGANIZATION).color(*GREEN)
+            });
+        }
+        if config.extra_columns.contains(&ExtraColumn::Team) {
+            // behind the repo
+            table.insert_column(3, Column::Team, tr("Team"), |c| {
+                c.width(COLUMN_WIDTH_TEAM).color(*RED)
+            });
+        }
+        if model.commits.iter().any(|c| c.repo.workspace.is_some()) {
+            // in front of the repo, several workspaces are scanned
+            let index = table.column_index(Column::Repo).unwrap_or(0);
+            table.insert_column(index, Column::Workspace, tr("Workspace"), |c| {
+                c.width(COLUMN_WIDTH_WORKSPACE).color(*RED)
+            });
+        }
+        if config.extra_columns.contains(&ExtraColumn::Parents) {
+            table.insert_column(1, Column::Parents, tr("Parents"), |c| {
+                c.width(COLUMN_WIDTH_PARENTS).color(*MAGENTA)
+            });
+        }
+        if config.extra_columns.contains(&ExtraColumn::PullRequest) {
+            table.insert_column(1, Column::PullRequest, tr("PR"), |c| {
+                c.width(COLUMN_WIDTH_PULL_REQUEST).color(*LIGHT_BLUE)
+            });
+        }
+        if config.extra_columns.contains(&ExtraColumn::AuthorDate) {
+            // behind the commit date
+            let index = table
+                .column_index(Column::CommitDateTime)
+                .map_or(0, |i| i + 1);
+            table.insert_column(index, Column::AuthorDateTime, tr("AuthorDate"), |c| {
+                c.width(COLUMN_WIDTH_COMMIT_DATE)
+            });
+        }
+        if config.extra_columns.contains(&ExtraColumn::Landed) {
+            // behind the dates
+            let index = table
+                .column_index(Column::AuthorDateTime)
+                .or_else(|| table.column_index(Column::CommitDateTime))
+                .map_or(0, |i| i + 1);
+            table.insert_column(index, Column::Landed, tr("Landed"), |c| {
+                c.width(COLUMN_WIDTH_COMMIT_DATE).color(*LIGHT_BLUE)
+            });
+        }
+        if model.revwalk_strategy == RevWalkStrategy::AllParents {
+            let width = model
+                .commits
+                .iter()
+                .map(|c| c.graph.chars().count())
+                .max()
+                .unwrap_or(0);
+            table.insert_column(0, Column::Graph, "Graph", |c| {
+                c.width(width.clamp("Graph".len(), COLUMN_MAX_WIDTH_GRAPH))
+                    .color(*YELLOW)
+            });
+            table.insert_column(1, Column::Lane, tr("Lane"), |c| {
+                c.width(COLUMN_WIDTH_LANE).color(*YELLOW)
+            });
+        }
+        // the columns up to the commit date stay visible when scrolling
+        // horizontally
+        let frozen = table
+            .column_index(Column::CommitDateTime)
+            .map_or(0, |i| i + 1);
+        table.set_frozen_columns(frozen);
         table.set_selected_row(0);
 
         table
     }
 
+    fn new_filter_bar() -> impl cursive::view::View {
+        let edit_view = EditView::new()
+            .on_submit(Self::on_filter_submit)
+            .with_name("filterBar");
+        let edit_view = InputView::new(edit_view)
+            .history(InputHistory::load("filter"))
+            .full_width();
+
+        LinearLayout::horizontal()
+            .child(TextView::new("Filter: "))
+            .child(OnEventView::new(edit_view).on_event(Key::Esc, |siv| {
+                siv.focus_name("table").unwrap();
+            }))
+    }
+
+    fn new_tab_bar(model: Rc<RefCell<TabBarModel>>) -> Canvas<Rc<RefCell<TabBarModel>>> {
+        Canvas::new(model)
+            .with_draw(|model, printer| {
+                let model = model.borrow();
+                let mut x = 0;
+                for (index, title) in model.titles.iter().enumerate() {
+                    let style = if index == model.active {
+                        ColorStyle::highlight()
+                    } else {
+                        ColorStyle::secondary()
+                    };
+                    let text = format!(" {}: {} ", index + 1, title);
+                    printer.with_style(style, |p| p.print((x, 0), &text));
+                    x += text.chars().count() + 1;
+                }
+            })
+            .with_required_size(|_model, req| cursive::Vec2::new(req.x, 1))
+    }
+
     fn new_commit_bar(model: Rc<RefCell<String>>) -> impl cursive::view::View {
         Canvas::new(model)
             .with_draw(|model, printer| {
@@ -112,17 +1616,57 @@ impl MainView {
             .with_required_size(|_model, req| cursive::Vec2::new(req.x, 1))
     }
 
-    pub fn update_commit_bar(self: &mut Self, index: usize, size: usize, entry: &RepoCommit) {
-        (*self.commit_bar_model).replace(format!(
-            "Commit {} of {} - {}",
-            index + 1,
-            size,
-            entry.repo.rel_path
-        ));
+    pub fn update_commit_bar(&mut self, index: usize, entry: &RepoCommit) {
+        let size = self
+            .layout
+            .call_on_name("table", |table: &mut TableView<CommitRow, Column>| {
+                table.len()
+            })
+            .unwrap_or(0)
+            - self.separator_rows.len();
+        let index = index - self.separator_rows.partition_point(|row| *row < index);
+        let filtered = if self.filter.is_empty() {
+            String::new()
+        } else {
+            expand_placeholders(
+                tr(" (filtered from {commits})"),
+                &[("commits", self.model.commits.len().to_string())],
+            )
+        };
+        self.placeholders = vec![
+            ("index", (index + 1).to_string()),
+            ("total", size.to_string()),
+            ("filtered", filtered),
+            ("repo", entry.repo.rel_path.clone()),
+            ("hash", entry.commit_id.to_string()[..8].to_string()),
+            ("author", entry.author_name.clone()),
+            ("date", entry.time_as_str()),
+            ("age", relative_date(entry.age())),
+            ("summary", entry.summary.clone()),
+            ("url", self.commit_url.resolve(entry).unwrap_or_default()),
+        ];
+        (*self.commit_bar_model).replace(self.format(&self.commit_bar_format));
     }
 
-    pub fn show_error(self: &mut Self, context: &str, error: &std::io::Error) {
-        (*self.commit_bar_model).replace(format!("{}: {}", context, error));
+    /// expands the placeholders of the given format string with the
+    /// properties of the selected commit and of the history
+    pub fn format(&self, format: &str) -> String {
+        let mut placeholders = self.placeholders.clone();
+        placeholders.extend(vec![
+            ("commits", self.model.commits.len().to_string()),
+            ("repos", self.model.repos.len().to_string()),
+            ("missing", self.model.locally_missing_commits.to_string()),
+        ]);
+        expand_placeholders(format, &placeholders)
+    }
+
+    pub fn show_error(&mut self, context: &str, error: &dyn Display) {
+        self.show_message(&format!("{}: {}", context, error));
+    }
+
+    /// shows the given message in the commit bar until the selection changes
+    pub fn show_message(&mut self, message: &str) {
+        (*self.commit_bar_model).replace(message.to_string());
     }
 }
 
@@ -143,3 +1687,10 @@ impl ViewWrapper for MainView {
         Some(f(&mut self.layout))
     }
 }
+
+/// removes the given value from the set, or inserts it if it is missing
+fn toggle<T: Eq + Hash>(set: &mut HashSet<T>, value: T) {
+    if !set.remove(&value) {
+        set.insert(value);
+    }
+}
@@ -1,10 +1,13 @@
 mod diff_view;
+mod input_view;
 mod list_view;
 mod main_view;
+mod scroll_position;
 mod seperator_view;
 mod table_view;
 
 pub use self::diff_view::DiffView;
+pub use self::input_view::InputView;
 pub use self::list_view::ListView;
 pub use self::main_view::MainView;
 pub use self::seperator_view::SeperatorView;
@@ -0,0 +1,37 @@
+use cursive::view::scroll;
+
+/// Describes the vertical scroll position of a view like a pager does:
+/// "Top", "Bot" or the scrolled percentage. Returns None if the whole
+/// content fits into the view.
+pub fn scroll_position(core: &scroll::Core) -> Option<String> {
+    position(
+        core.content_viewport().top(),
+        core.content_viewport().height(),
+        core.inner_size().y,
+    )
+}
+
+fn position(top: usize, height: usize, content_height: usize) -> Option<String> {
+    if content_height <= height {
+        None
+    } else if top == 0 {
+        Some(String::from("Top"))
+    } else if top + height >= content_height {
+        Some(String::from("Bot"))
+    } else {
+        Some(format!("{}%", top * 100 / (content_height - height)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_the_scroll_position() {
+        assert_eq!(position(0, 10, 10), None);
+        assert_eq!(position(0, 10, 100), Some(String::from("Top")));
+        assert_eq!(position(45, 10, 100), Some(String::from("50%")));
+        assert_eq!(position(90, 10, 100), Some(String::from("Bot")));
+    }
+}
@@ -15,6 +15,12 @@ impl SeperatorView {
             orientation: Orientation::Vertical,
         }
     }
+
+    pub fn horizontal() -> SeperatorView {
+        SeperatorView {
+            orientation: Orientation::Horizontal,
+        }
+    }
 }
 impl View for SeperatorView {
     fn draw(&self, printer: &Printer<'_, '_>) {
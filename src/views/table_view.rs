@@ -4,7 +4,15 @@
 
 // Changes:
 //  - Added support for column-specific colors
+//  - Added support for cell-specific colors
+//  - Added support for row-specific styles
 //  - Removed sorting of columns
+//  - Added borrow_item_of_row()
+//  - Added a scroll position indicator
+//  - Page-size-aware PageUp/PageDown with configurable step
+//  - Submit on double click instead of a click on the selected row
+//  - Mouse wheel moves the selection
+//  - Added support for highlighted ranges within cells
 
 #![deny(
     missing_docs,
@@ -16,14 +24,13 @@
     unused_qualifications
 )]
 
-// Crate Dependencies ---------------------------------------------------------
-use cursive;
-
 // STD Dependencies -----------------------------------------------------------
 use std::cmp::{self, Ordering};
 use std::collections::HashMap;
 use std::hash::Hash;
+use std::ops::Range;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 // External Dependencies ------------------------------------------------------
 use cursive::{
@@ -36,7 +43,10 @@ use cursive::{
     Cursive, Printer, Rect, With,
 };
 
-/// A trait for displaying and sorting items inside a
+use super::scroll_position::scroll_position;
+use crate::styles::{MATCH, SEPARATOR};
+
+/// A trait for displaying items inside a
 /// [`TableView`](struct.TableView.html).
 pub trait TableViewItem<H>: Clone + Sized
 where
@@ -46,10 +56,30 @@ where
     /// specified column from type `H`.
     fn to_column(&self, column: H) -> String;
 
-    /// Method comparing two items via their specified column from type `H`.
-    fn cmp(&self, other: &Self, column: H) -> Ordering
-    where
-        Self: Sized;
+    /// Method returning the color of the item for the specified column from
+    /// type `H`, `None` applies the color of the column.
+    fn style_for(&self, _column: H) -> Option<theme::ColorStyle> {
+        None
+    }
+
+    /// Method returning the style of the whole row of the item, its color
+    /// (if not inherited) takes precedence over the colors of the columns.
+    fn row_style(&self) -> Option<theme::Style> {
+        None
+    }
+
+    /// Method returning the byte ranges of the string representation for
+    /// the specified column from type `H` which are to be highlighted.
+    fn highlights_for(&self, _column: H) -> Vec<Range<usize>> {
+        Vec::new()
+    }
+
+    /// Method returning the text of the item in case it is a separator
+    /// row, e.g. marking a point in time. Separator rows span all columns
+    /// and can't be selected.
+    fn separator(&self) -> Option<String> {
+        None
+    }
 }
 
 /// Callback used when a column is sorted.
@@ -59,6 +89,12 @@ where
 /// This is a private type to help readability.
 type OnSortCallback<H> = Rc<dyn Fn(&mut Cursive, H, Ordering)>;
 
+/// Maximum time between two clicks on the same row to submit it.
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Number of rows the selection moves per step of the mouse wheel.
+const WHEEL_STEP: usize = 3;
+
 /// Callback taking as argument the row and the index of an element.
 ///
 /// This is a private type to help readability.
@@ -101,14 +137,6 @@ type IndexCallback = Rc<dyn Fn(&mut Cursive, usize, usize)>;
 ///         }
 ///     }
 ///
-///     fn cmp(&self, other: &Self, column: BasicColumn) -> Ordering where Self: Sized {
-///         match column {
-///             BasicColumn::Name => self.name.cmp(&other.name),
-///             BasicColumn::Count => self.count.cmp(&other.count),
-///             BasicColumn::Rate => self.rate.cmp(&other.rate)
-///         }
-///     }
-///
 /// }
 ///
 /// // Configure the actual table
@@ -129,8 +157,19 @@ pub struct TableView<T, H> {
     column_select: bool,
     columns: Vec<TableColumn<H>>,
     column_indicies: HashMap<H, usize>,
+    /// number of leading columns which stay visible when scrolling
+    /// horizontally
+    frozen_columns: usize,
+    /// number of columns behind the frozen ones scrolled out of view
+    scrolled_columns: usize,
+    /// width of the view given by the last layout
+    width: usize,
 
     focus: usize,
+    /// rows to move by PageUp/PageDown, defaults to the viewport height
+    page_step: Option<usize>,
+    /// row and time of the last click, to detect double clicks
+    last_click: Option<(usize, Instant)>,
     items: Vec<T>,
     rows_to_items: Vec<usize>,
 
@@ -203,8 +242,13 @@ where
             column_select: false,
             columns: Vec::new(),
             column_indicies: HashMap::new(),
+            frozen_columns: 0,
+            scrolled_columns: 0,
+            width: 0,
 
             focus: 0,
+            page_step: None,
+            last_click: None,
             items: Vec::new(),
             rows_to_items: Vec::new(),
 
@@ -279,6 +323,28 @@ where
         self.needs_relayout = true;
     }
 
+    /// Returns the position of the specified column from type `H`.
+    pub fn column_index(&self, column: H) -> Option<usize> {
+        self.column_indicies.get(&column).copied()
+    }
+
+    /// Sets the number of leading columns which stay visible while the
+    /// other columns are scrolled horizontally (`<` and `>`), in case not
+    /// all columns fit into the view.
+    pub fn set_frozen_columns(&mut self, count: usize) {
+        self.frozen_columns = count;
+        self.scrolled_columns = 0;
+        self.needs_relayout = true;
+    }
+
+    /// Sets the number of leading columns which stay visible while the
+    /// other columns are scrolled horizontally.
+    ///
+    /// Chainable variant.
+    pub fn frozen_columns(self, count: usize) -> Self {
+        self.with(|t| t.set_frozen_columns(count))
+    }
+
     /// Disables this view.
     ///
     /// A disabled view cannot be selected.
@@ -446,10 +512,25 @@ where
         }
     }
 
+    /// Sets the number of rows PageUp/PageDown move the selection and the
+    /// viewport by, `None` to move by the height of the viewport.
+    pub fn set_page_step(&mut self, page_step: Option<usize>) {
+        self.page_step = page_step;
+    }
+
+    /// Sets the number of rows PageUp/PageDown move the selection and the
+    /// viewport by, `None` to move by the height of the viewport.
+    ///
+    /// Chainable variant.
+    pub fn page_step(self, page_step: Option<usize>) -> Self {
+        self.with(|t| t.set_page_step(page_step))
+    }
+
     /// Selects the row at the specified index.
     pub fn set_selected_row(&mut self, row_index: usize) {
         self.focus = row_index;
-        self.scroll_core.scroll_to_y(row_index);
+        self.skip_separators(true);
+        self.scroll_core.scroll_to_y(self.focus);
     }
 
     /// Selects the row at the specified index.
@@ -479,6 +560,20 @@ where
         self.needs_relayout = true;
     }
 
+    /// Appends the given items to the end of the table.
+    ///
+    /// Compared to `set_items`, neither the existing rows nor the selection
+    /// and the scroll position are touched.
+    pub fn append_items(&mut self, items: Vec<T>) {
+        let first = self.items.len();
+        self.rows_to_items.extend(first..first + items.len());
+        self.items.extend(items);
+        if first == 0 {
+            self.skip_separators(true);
+        }
+        self.needs_relayout = true;
+    }
+
     /// Sets the contained items of the table.
     ///
     /// The order of the items will be preserved even when the table is sorted.
@@ -494,6 +589,12 @@ where
         self.items.get(index)
     }
 
+    /// Returns a immmutable reference to the item displayed in the specified
+    /// row.
+    pub fn borrow_item_of_row(&self, row_index: usize) -> Option<&T> {
+        self.items.get(*self.rows_to_items.get(row_index)?)
+    }
+
     /// Returns a mutable reference to the item at the specified index within
     /// the underlying storage vector.
     pub fn borrow_item_mut(&mut self, index: usize) -> Option<&mut T> {
@@ -527,10 +628,11 @@ where
             for (row, item) in self.rows_to_items.iter().enumerate() {
                 if *item == item_index {
                     self.focus = row;
-                    self.scroll_core.scroll_to_y(row);
                     break;
                 }
             }
+            self.skip_separators(true);
+            self.scroll_core.scroll_to_y(self.focus);
         }
     }
 
@@ -614,6 +716,41 @@ where
     T: TableViewItem<H>,
     H: Eq + Hash + Copy + Clone + 'static,
 {
+    /// true if the column with the given index isn't scrolled out of view
+    fn is_column_visible(&self, index: usize) -> bool {
+        index < self.frozen_columns || index >= self.frozen_columns + self.scrolled_columns
+    }
+
+    /// the columns which aren't scrolled out of view, with their index
+    fn visible_columns(&self) -> impl Iterator<Item = (usize, &TableColumn<H>)> {
+        self.columns
+            .iter()
+            .enumerate()
+            .filter(move |(index, _)| self.is_column_visible(*index))
+    }
+
+    /// the width of the visible columns including their separators
+    fn visible_width(&self) -> usize {
+        let widths = self.visible_columns().map(|(_, column)| column.width + 3);
+        widths.sum::<usize>().saturating_sub(3)
+    }
+
+    fn scroll_columns_right(&mut self) {
+        if self.visible_width() > self.width
+            && self.frozen_columns + self.scrolled_columns + 1 < self.columns.len()
+        {
+            self.scrolled_columns += 1;
+            self.needs_relayout = true;
+        }
+    }
+
+    fn scroll_columns_left(&mut self) {
+        if self.scrolled_columns > 0 {
+            self.scrolled_columns -= 1;
+            self.needs_relayout = true;
+        }
+    }
+
     fn draw_columns<C: Fn(&Printer, &TableColumn<H>)>(
         &self,
         printer: &Printer,
@@ -621,8 +758,8 @@ where
         callback: C,
     ) {
         let mut column_offset = 0;
-        let column_count = self.columns.len();
-        for (index, column) in self.columns.iter().enumerate() {
+        let column_count = self.visible_columns().count();
+        for (index, (_, column)) in self.visible_columns().enumerate() {
             let printer = &printer.offset((column_offset, 0)).focused(true);
 
             callback(printer, column);
@@ -636,9 +773,20 @@ where
     }
 
     fn draw_item(&self, focused: bool, printer: &Printer, i: usize) {
+        let item = &self.items[self.rows_to_items[i]];
+        let row_style = item.row_style().unwrap_or_default();
         self.draw_columns(printer, "┆ ", |printer, column| {
-            let value = self.items[self.rows_to_items[i]].to_column(column.column);
-            column.draw_row(focused, printer, value.as_str());
+            let value = item.to_column(column.column);
+            let color = item.style_for(column.column);
+            let highlights = item.highlights_for(column.column);
+            column.draw_row(
+                focused,
+                printer,
+                value.as_str(),
+                color,
+                row_style,
+                &highlights,
+            );
         });
     }
 
@@ -652,12 +800,55 @@ where
         )
     }
 
+    /// remembers the click on the given row, returns true if the
+    /// previous click hit the same row shortly before
+    fn is_double_click(&mut self, row: usize) -> bool {
+        let now = Instant::now();
+        let double_click = matches!(self.last_click,
+            Some((last_row, time)) if last_row == row && now.duration_since(time) < DOUBLE_CLICK_INTERVAL);
+        self.last_click = if double_click { None } else { Some((row, now)) };
+        double_click
+    }
+
+    fn page_step_rows(&self) -> usize {
+        self.page_step
+            .unwrap_or_else(|| self.scroll_core.content_viewport().height())
+            .max(1)
+    }
+
     fn focus_up(&mut self, n: usize) {
         self.focus -= cmp::min(self.focus, n);
+        self.skip_separators(false);
     }
 
     fn focus_down(&mut self, n: usize) {
         self.focus = cmp::min(self.focus + n, self.items.len().saturating_sub(1));
+        self.skip_separators(true);
+    }
+
+    fn is_separator_row(&self, row: usize) -> bool {
+        self.borrow_item_of_row(row)
+            .is_some_and(|item| item.separator().is_some())
+    }
+
+    /// moves the focus off a separator row, in the given direction
+    /// unless there is no selectable row left in it
+    fn skip_separators(&mut self, forward: bool) {
+        let rows = self.rows_to_items.len();
+        if self.focus >= rows || !self.is_separator_row(self.focus) {
+            return;
+        }
+        let selectable = |row: &usize| !self.is_separator_row(*row);
+        let after = (self.focus + 1..rows).find(selectable);
+        let before = (0..self.focus).rev().find(selectable);
+        let next = if forward {
+            after.or(before)
+        } else {
+            before.or(after)
+        };
+        if let Some(row) = next {
+            self.focus = row;
+        }
     }
 
     fn active_column(&self) -> usize {
@@ -669,7 +860,7 @@ where
     }
 
     fn column_for_x(&self, mut x: usize) -> Option<usize> {
-        for (i, col) in self.columns.iter().enumerate() {
+        for (i, col) in self.visible_columns() {
             x = match x.checked_sub(col.width) {
                 None => return Some(i),
                 Some(x) => x.checked_sub(3)?,
@@ -680,7 +871,10 @@ where
     }
 
     fn draw_content(&self, printer: &Printer) {
-        for i in 0..self.rows_to_items.len() {
+        // only draw the rows which are visible
+        let first_row = printer.content_offset.y;
+        let last_row = cmp::min(first_row + printer.output_size.y, self.rows_to_items.len());
+        for i in first_row..last_row {
             let printer = printer.offset((0, i));
             let color = if i == self.focus && self.enabled {
                 if !self.column_select && self.enabled && printer.focused {
@@ -692,7 +886,12 @@ where
                 theme::ColorStyle::primary()
             };
 
-            if i < self.items.len() {
+            if let Some(text) = self.borrow_item_of_row(i).and_then(|item| item.separator()) {
+                printer.with_color(*SEPARATOR, |printer| {
+                    printer.print_hline((0, 0), printer.size.x, "─");
+                    printer.print((2, 0), &format!(" {} ", text));
+                });
+            } else if i < self.items.len() {
                 printer.with_color(color, |printer| {
                     self.draw_item(i == self.focus, printer, i);
                 });
@@ -701,12 +900,17 @@ where
     }
 
     fn layout_content(&mut self, size: Vec2) {
-        let column_count = self.columns.len();
+        self.width = size.x;
+        let column_count = self.visible_columns().count();
+        let (frozen, scrolled) = (self.frozen_columns, self.scrolled_columns);
 
-        // Split up all columns into sized / unsized groups
+        // Split up all visible columns into sized / unsized groups
         let (mut sized, mut usized): (Vec<&mut TableColumn<H>>, Vec<&mut TableColumn<H>>) = self
             .columns
             .iter_mut()
+            .enumerate()
+            .filter(|(index, _)| *index < frozen || *index >= frozen + scrolled)
+            .map(|(_, column)| column)
             .partition(|c| c.requested_width.is_some());
 
         // Subtract one for the seperators between our columns (that's column_count - 1)
@@ -747,6 +951,14 @@ where
             Event::Key(Key::Left) => {
                 return EventResult::Ignored;
             }
+            Event::Char('>') => {
+                self.scroll_columns_right();
+                return EventResult::Consumed(None);
+            }
+            Event::Char('<') => {
+                self.scroll_columns_left();
+                return EventResult::Consumed(None);
+            }
             Event::Key(Key::Up) if self.focus > 0 || self.column_select => {
                 if self.column_select {
                     self.column_cancel();
@@ -763,19 +975,39 @@ where
             }
             Event::Key(Key::PageUp) => {
                 self.column_cancel();
-                self.focus_up(10);
+                let step = self.page_step_rows();
+                self.focus_up(step);
+                self.scroll_core.scroll_up(step);
             }
             Event::Key(Key::PageDown) => {
                 self.column_cancel();
-                self.focus_down(10);
+                let step = self.page_step_rows();
+                self.focus_down(step);
+                self.scroll_core.scroll_down(step);
+            }
+            Event::Mouse {
+                event: MouseEvent::WheelUp,
+                ..
+            } => {
+                self.column_cancel();
+                self.focus_up(WHEEL_STEP);
+            }
+            Event::Mouse {
+                event: MouseEvent::WheelDown,
+                ..
+            } => {
+                self.column_cancel();
+                self.focus_down(WHEEL_STEP);
             }
             Event::Key(Key::Home) => {
                 self.column_cancel();
                 self.focus = 0;
+                self.skip_separators(true);
             }
             Event::Key(Key::End) => {
                 self.column_cancel();
                 self.focus = self.items.len().saturating_sub(1);
+                self.skip_separators(false);
             }
             Event::Key(Key::Enter) => {
                 if self.column_select {
@@ -787,23 +1019,15 @@ where
             Event::Mouse {
                 position,
                 offset,
-                event: MouseEvent::Press(MouseButton::Left),
-            } if !self.is_empty()
-                && position
-                    .checked_sub(offset)
-                    .map_or(false, |p| p.y == self.focus) =>
-            {
-                self.column_cancel();
-                return self.on_submit_event();
-            }
-            Event::Mouse {
-                position,
-                offset,
-                event: MouseEvent::Press(_),
+                event: MouseEvent::Press(button),
             } if !self.is_empty() => match position.checked_sub(offset) {
+                Some(position) if self.is_separator_row(position.y) => return EventResult::Ignored,
                 Some(position) if position.y < self.rows_to_items.len() => {
                     self.column_cancel();
                     self.focus = position.y;
+                    if button == MouseButton::Left && self.is_double_click(position.y) {
+                        return self.on_submit_event();
+                    }
                 }
                 _ => return EventResult::Ignored,
             },
@@ -871,6 +1095,21 @@ where
             self.draw_columns(&printer.offset((0, y)), "┆ ", |_, _| ());
         }
 
+        // marks where columns are scrolled out of view
+        if self.scrolled_columns > 0 {
+            let frozen = self.columns.iter().take(self.frozen_columns);
+            let x = frozen.map(|column| column.width + 3).sum::<usize>();
+            printer.print((x.saturating_sub(2), 0), "◂");
+        }
+
+        // right end of the header
+        if let Some(position) = scroll_position(&self.scroll_core) {
+            let x = printer.size.x.saturating_sub(position.len() + 1);
+            printer.with_color(theme::ColorStyle::highlight_inactive(), |printer| {
+                printer.print((x, 0), &format!(" {}", position));
+            });
+        }
+
         let printer = &printer.offset((0, 2)).focused(true);
         scroll::draw(self, printer, Self::draw_content);
     }
@@ -899,7 +1138,7 @@ where
                 position,
                 offset,
                 event: MouseEvent::Press(MouseButton::Left),
-            } if position.checked_sub(offset).map_or(false, |p| p.y == 0) => {
+            } if position.checked_sub(offset).is_some_and(|p| p.y == 0) => {
                 if let Some(position) = position.checked_sub(offset) {
                     if let Some(col) = self.column_for_x(position.x) {
                         if self.column_select && self.columns[col].selected {
@@ -995,23 +1234,51 @@ impl<H: Copy + Clone + 'static> TableColumn<H> {
         printer.print((0, 0), header.as_str());
     }
 
-    fn draw_row(&self, focused: bool, printer: &Printer, value: &str) {
+    fn draw_row(
+        &self,
+        focused: bool,
+        printer: &Printer,
+        value: &str,
+        color: Option<theme::ColorStyle>,
+        row_style: theme::Style,
+        highlights: &[Range<usize>],
+    ) {
+        let padding = self.width.saturating_sub(value.chars().count());
+        let padding = match self.alignment {
+            HAlign::Left => 0,
+            HAlign::Right => padding,
+            HAlign::Center => padding / 2,
+        };
+        let original = value;
         let value = match self.alignment {
             HAlign::Left => format!("{:<width$} ", value, width = self.width),
             HAlign::Right => format!("{:>width$} ", value, width = self.width),
             HAlign::Center => format!("{:^width$} ", value, width = self.width),
         };
 
-        printer.with_color(
-            if focused {
-                theme::ColorStyle::highlight()
-            } else {
-                self.color
+        let color = if focused {
+            theme::ColorStyle::highlight()
+        } else {
+            theme::ColorStyle::merge(color.unwrap_or(self.color), row_style.color)
+        };
+        printer.with_style(
+            theme::Style {
+                effects: row_style.effects,
+                color,
             },
             |printer| {
                 printer.print((0, 0), value.as_str());
             },
         );
+
+        for range in highlights {
+            let start = padding + original[..range.start].chars().count();
+            let text: String = original[range.clone()]
+                .chars()
+                .take(self.width.saturating_sub(start))
+                .collect();
+            printer.with_color(*MATCH, |printer| printer.print((start, 0), &text));
+        }
     }
 }
 
@@ -1022,6 +1289,8 @@ mod tests {
     #[derive(Copy, Clone, PartialEq, Eq, Hash)]
     enum SimpleColumn {
         Name,
+        Id,
+        Note,
     }
 
     #[allow(dead_code)]
@@ -1029,6 +1298,8 @@ mod tests {
         fn as_str(&self) -> &str {
             match *self {
                 SimpleColumn::Name => "Name",
+                SimpleColumn::Id => "Id",
+                SimpleColumn::Note => "Note",
             }
         }
     }
@@ -1042,16 +1313,12 @@ mod tests {
         fn to_column(&self, column: SimpleColumn) -> String {
             match column {
                 SimpleColumn::Name => self.name.to_string(),
+                SimpleColumn::Id | SimpleColumn::Note => String::new(),
             }
         }
 
-        fn cmp(&self, other: &Self, column: SimpleColumn) -> Ordering
-        where
-            Self: Sized,
-        {
-            match column {
-                SimpleColumn::Name => self.name.cmp(&other.name),
-            }
+        fn separator(&self) -> Option<String> {
+            self.name.strip_prefix("-- ").map(String::from)
         }
     }
 
@@ -1094,4 +1361,70 @@ mod tests {
 
         assert!(simple_table.len() == 1);
     }
+
+    #[test]
+    fn should_append_items() {
+        let mut simple_table = setup_test_table();
+        let items = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| SimpleItem {
+                    name: name.to_string(),
+                })
+                .collect()
+        };
+        simple_table.append_items(items(&["-- first", "1", "2"]));
+        assert_eq!(simple_table.row(), Some(1));
+
+        simple_table.focus_down(1);
+        simple_table.append_items(items(&["3", "-- second", "4"]));
+        assert_eq!(simple_table.len(), 6);
+        assert_eq!(simple_table.row(), Some(2));
+        assert_eq!(simple_table.rows_to_items, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(simple_table.borrow_item_of_row(5).unwrap().name, "4");
+    }
+
+    #[test]
+    fn should_skip_separators() {
+        let mut simple_table = setup_test_table();
+        let names = ["-- first", "1", "-- second", "2"];
+        simple_table.set_items(
+            names
+                .iter()
+                .map(|name| SimpleItem {
+                    name: name.to_string(),
+                })
+                .collect(),
+        );
+        assert_eq!(simple_table.row(), Some(1));
+
+        simple_table.focus_down(1);
+        assert_eq!(simple_table.row(), Some(3));
+        simple_table.focus_up(1);
+        assert_eq!(simple_table.row(), Some(1));
+        simple_table.focus_up(1);
+        assert_eq!(simple_table.row(), Some(1));
+    }
+
+    #[test]
+    fn should_scroll_unfrozen_columns() {
+        let mut simple_table = TableView::<SimpleItem, SimpleColumn>::new()
+            .column(SimpleColumn::Name, "Name", |c| c.width(10))
+            .column(SimpleColumn::Id, "Id", |c| c.width(10))
+            .column(SimpleColumn::Note, "Note", |c| c.width(10))
+            .frozen_columns(1);
+        simple_table.layout_content(Vec2::new(25, 10));
+        assert_eq!(simple_table.column_for_x(14), Some(1));
+
+        simple_table.scroll_columns_right();
+        simple_table.layout_content(Vec2::new(25, 10));
+        assert_eq!(simple_table.column_for_x(14), Some(2));
+        // all visible columns fit
+        simple_table.scroll_columns_right();
+        assert_eq!(simple_table.scrolled_columns, 1);
+
+        simple_table.scroll_columns_left();
+        simple_table.scroll_columns_left();
+        assert_eq!(simple_table.scrolled_columns, 0);
+    }
 }
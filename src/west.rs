@@ -0,0 +1,401 @@
+use crate::manifest::Project;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Revision of projects if neither they nor the `defaults` name one
+const DEFAULT_REVISION: &str = "master";
+
+/// The top-level of a west manifest file (`west.yml`)
+#[derive(Deserialize)]
+struct File {
+    manifest: Manifest,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Manifest {
+    defaults: Defaults,
+    projects: Vec<WestProject>,
+    #[serde(rename = "self")]
+    self_: SelfSection,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Defaults {
+    remote: Option<String>,
+    revision: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WestProject {
+    name: String,
+    path: Option<String>,
+    revision: Option<String>,
+    remote: Option<String>,
+    #[serde(default)]
+    groups: Vec<String>,
+    import: Option<Import>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct SelfSection {
+    import: Option<Import>,
+}
+
+/// The manifests imported by a project or by the manifest repository
+/// itself
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+enum Import {
+    /// `west.yml` of the project if true
+    Enabled(bool),
+    /// a manifest file or a folder of manifest files
+    File(String),
+    Detailed(DetailedImport),
+    List(Vec<Import>),
+}
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(default, rename_all = "kebab-case")]
+struct DetailedImport {
+    file: Option<String>,
+    /// prepended to the paths of the imported projects
+    path_prefix: Option<String>,
+    /// imports only the projects of these names if not empty
+    name_allowlist: Names,
+    name_blocklist: Names,
+}
+
+/// A single name or a list of names
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+enum Names {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Default for Names {
+    fn default() -> Names {
+        Names::Many(Vec::new())
+    }
+}
+
+impl Names {
+    fn as_slice(&self) -> &[String] {
+        match self {
+            Names::One(name) => std::slice::from_ref(name),
+            Names::Many(names) => names,
+        }
+    }
+}
+
+/// How the projects of an imported manifest are added to the workspace
+#[derive(Default)]
+struct Scope {
+    path_prefix: String,
+    /// the allowlists of the enclosing imports, a project has to be on
+    /// each of them which isn't empty
+    name_allowlists: Vec<Vec<String>>,
+    name_blocklist: Vec<String>,
+}
+
+impl Scope {
+    fn admits(&self, name: &str) -> bool {
+        self.name_allowlists
+            .iter()
+            .all(|allowlist| allowlist.is_empty() || allowlist.iter().any(|n| n == name))
+            && !self.name_blocklist.iter().any(|n| n == name)
+    }
+
+    /// the scope of a manifest imported from within this one, it admits
+    /// only projects admitted by both
+    fn nested(&self, import: &DetailedImport) -> Scope {
+        let mut name_allowlists = self.name_allowlists.clone();
+        name_allowlists.push(import.name_allowlist.as_slice().to_vec());
+        let mut name_blocklist = self.name_blocklist.clone();
+        name_blocklist.extend(import.name_blocklist.as_slice().iter().cloned());
+        Scope {
+            path_prefix: join(
+                &self.path_prefix,
+                import.path_prefix.as_deref().unwrap_or_default(),
+            ),
+            name_allowlists,
+            name_blocklist,
+        }
+    }
+}
+
+/// the local path of the manifest repository and the name of the manifest
+/// file in it, as configured by `.west/config` (`zephyr` and `west.yml`
+/// by default)
+fn manifest_location(base_folder: &Path) -> (String, String) {
+    let config = fs::read_to_string(base_folder.join(".west/config")).unwrap_or_default();
+    let mut path = String::from("zephyr");
+    let mut file = String::from("west.yml");
+    let mut in_manifest_section = false;
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_manifest_section = line == "[manifest]";
+        } else if let (true, Some((key, value))) = (in_manifest_section, line.split_once('=')) {
+            match key.trim() {
+                "path" => path = value.trim().to_string(),
+                "file" => file = value.trim().to_string(),
+                _ => {}
+            }
+        }
+    }
+    (path, file)
+}
+
+/// the local path of the manifest repository, relative to the root folder
+/// of the workspace
+pub fn rel_path(base_folder: &Path) -> String {
+    manifest_location(base_folder).0
+}
+
+/// the projects of the workspace, including the ones of the manifests
+/// imported by the manifest repository and by projects. Manifests are read
+/// from the work trees of their repositories, imports of projects which
+/// aren't checked out are left out. Projects declared by a manifest take
+/// precedence over imported ones of the same name.
+pub fn read(base_folder: &Path) -> Result<Vec<Project>> {
+    let (path, file) = manifest_location(base_folder);
+    let mut reader = Reader {
+        base_folder,
+        projects: Vec::new(),
+        visited: HashSet::new(),
+    };
+    reader.read_file(
+        &path,
+        &base_folder.join(&path).join(file),
+        &Scope::default(),
+    )?;
+    Ok(reader.projects)
+}
+
+struct Reader<'a> {
+    base_folder: &'a Path,
+    projects: Vec<Project>,
+    /// the manifest files read so far, imports might be cyclic
+    visited: HashSet<PathBuf>,
+}
+
+impl Reader<'_> {
+    /// adds the projects of a manifest file of the repository at the given
+    /// local path
+    fn read_file(&mut self, repo_path: &str, file: &Path, scope: &Scope) -> Result<()> {
+        if !self.visited.insert(file.to_path_buf()) {
+            return Ok(());
+        }
+        let content = fs::read_to_string(file)
+            .with_context(|| format!("Failed to read the manifest {}", file.display()))?;
+        let Manifest {
+            defaults,
+            projects,
+            self_,
+        } = parse(&content)
+            .with_context(|| format!("Failed to parse the manifest {}", file.display()))?
+            .manifest;
+
+        let mut imports = Vec::new();
+        for project in projects {
+            let project_path = join(
+                &scope.path_prefix,
+                project.path.as_deref().unwrap_or(&project.name),
+            );
+            if !scope.admits(&project.name) || self.has_project(&project.name) {
+                continue;
+            }
+            if let Some(import) = &project.import {
+                imports.push((project_path.clone(), import.clone()));
+            }
+            self.projects.push(Project {
+                name: project.name,
+                path: project_path,
+                revision: project
+                    .revision
+                    .or_else(|| defaults.revision.clone())
+                    .unwrap_or_else(|| String::from(DEFAULT_REVISION)),
+                remote: project.remote.or_else(|| defaults.remote.clone()),
+                groups: project.groups,
+            });
+        }
+        if let Some(import) = &self_.import {
+            self.import(repo_path, import, scope)?;
+        }
+        for (project_path, import) in imports {
+            if self.base_folder.join(&project_path).is_dir() {
+                self.import(&project_path, &import, scope)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn import(&mut self, repo_path: &str, import: &Import, scope: &Scope) -> Result<()> {
+        match import {
+            Import::Enabled(false) => Ok(()),
+            Import::Enabled(true) => self.import_path(repo_path, "west.yml", scope),
+            Import::File(file) => self.import_path(repo_path, file, scope),
+            Import::Detailed(detailed) => self.import_path(
+                repo_path,
+                detailed.file.as_deref().unwrap_or("west.yml"),
+                &scope.nested(detailed),
+            ),
+            Import::List(imports) => {
+                for import in imports {
+                    self.import(repo_path, import, scope)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// imports a manifest file, or the `.yml` files of a folder in
+    /// alphabetical order
+    fn import_path(&mut self, repo_path: &str, file: &str, scope: &Scope) -> Result<()> {
+        let path = self.base_folder.join(repo_path).join(file);
+        if !path.is_dir() {
+            return self.read_file(repo_path, &path, scope);
+        }
+        let mut files: Vec<PathBuf> = fs::read_dir(&path)?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "yml"))
+            .collect();
+        files.sort();
+        for file in files {
+            self.read_file(repo_path, &file, scope)?;
+        }
+        Ok(())
+    }
+
+    fn has_project(&self, name: &str) -> bool {
+        self.projects.iter().any(|project| project.name == name)
+    }
+}
+
+fn parse(content: &str) -> Result<File, serde_yaml::Error> {
+    serde_yaml::from_str(content)
+}
+
+fn join(prefix: &str, path: &str) -> String {
+    match (prefix.trim_end_matches('/'), path) {
+        ("", path) => path.to_string(),
+        (prefix, "") => prefix.to_string(),
+        (prefix, path) => format!("{}/{}", prefix, path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_west_manifests() {
+        let manifest = parse(
+            r#"
+manifest:
+  defaults:
+    remote: upstream
+  remotes:
+    - name: upstream
+      url-base: https://github.com/zephyrproject-rtos
+  projects:
+    - name: hal_nordic
+      path: modules/hal/nordic
+      revision: abc123
+      groups: [hal]
+    - name: mcuboot
+      import:
+        path-prefix: bootloader
+        name-allowlist: mbedtls
+  self:
+    path: zephyr
+    import: submanifests
+"#,
+        )
+        .unwrap()
+        .manifest;
+        assert_eq!(manifest.defaults.remote.as_deref(), Some("upstream"));
+        assert_eq!(manifest.projects.len(), 2);
+        assert_eq!(manifest.projects[0].groups, vec![String::from("hal")]);
+        let scope = match &manifest.projects[1].import {
+            Some(Import::Detailed(import)) => Scope::default().nested(import),
+            _ => panic!("expected a detailed import"),
+        };
+        assert_eq!(scope.path_prefix, "bootloader");
+        assert!(scope.admits("mbedtls") && !scope.admits("zephyr"));
+        assert!(matches!(
+            manifest.self_.import,
+            Some(Import::File(ref file)) if file == "submanifests"
+        ));
+    }
+
+    #[test]
+    fn applies_the_allow_and_block_lists_of_nested_imports() {
+        let base = std::env::temp_dir().join(format!("oper-west-{}", std::process::id()));
+        let manifests = [
+            (
+                "zephyr/west.yml",
+                r#"
+manifest:
+  projects:
+    - name: modules
+      import:
+        name-allowlist: [hal, tools, shared]
+        name-blocklist: legacy
+"#,
+            ),
+            (
+                "modules/west.yml",
+                r#"
+manifest:
+  projects:
+    - name: hal
+    - name: legacy
+    - name: tools
+      import:
+        path-prefix: tools
+        name-allowlist: [shared, extra]
+"#,
+            ),
+            (
+                "tools/west.yml",
+                r#"
+manifest:
+  projects:
+    - name: shared
+    - name: extra
+    - name: hal
+"#,
+            ),
+        ];
+        for (file, content) in manifests {
+            let file = base.join(file);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, content).unwrap();
+        }
+
+        let projects: Vec<(String, String)> = read(&base)
+            .unwrap()
+            .into_iter()
+            .map(|project| (project.name, project.path))
+            .collect();
+        fs::remove_dir_all(&base).unwrap();
+        // extra is allowed by the inner import only, legacy is blocked
+        assert_eq!(
+            projects,
+            vec![
+                (String::from("modules"), String::from("modules")),
+                (String::from("hal"), String::from("hal")),
+                (String::from("tools"), String::from("tools")),
+                (String::from("shared"), String::from("tools/shared")),
+            ]
+        );
+    }
+}
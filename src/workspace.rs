@@ -0,0 +1,86 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The tool managing a workspace, detected by the file or folder it keeps
+/// in the root folder of the workspace unless given by `--workspace-type`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WorkspaceType {
+    /// git-repo, `.repo`
+    Repo,
+    /// Zephyr's west, `.west`
+    West,
+    /// tsrc, `.tsrc`
+    Tsrc,
+    /// Chromium's gclient, `.gclient`
+    Gclient,
+}
+
+impl WorkspaceType {
+    pub const NAMES: [&'static str; 4] = ["repo", "west", "tsrc", "gclient"];
+    const ALL: [WorkspaceType; 4] = [
+        WorkspaceType::Repo,
+        WorkspaceType::West,
+        WorkspaceType::Tsrc,
+        WorkspaceType::Gclient,
+    ];
+
+    pub fn from_name(name: &str) -> Option<WorkspaceType> {
+        Self::NAMES
+            .iter()
+            .position(|n| *n == name)
+            .map(|index| Self::ALL[index])
+    }
+
+    /// e.g. `west`, as given to `--workspace-type`
+    pub fn name(self) -> &'static str {
+        Self::NAMES[Self::ALL.iter().position(|kind| *kind == self).unwrap()]
+    }
+
+    fn marker(self) -> &'static str {
+        match self {
+            WorkspaceType::Repo => ".repo",
+            WorkspaceType::West => ".west",
+            WorkspaceType::Tsrc => ".tsrc",
+            WorkspaceType::Gclient => ".gclient",
+        }
+    }
+
+    /// the type of the workspace rooted in the given folder, if any
+    pub fn of(base_folder: &Path) -> Option<WorkspaceType> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.is_root(base_folder))
+    }
+
+    fn is_root(self, folder: &Path) -> bool {
+        let marker = folder.join(self.marker());
+        match self {
+            // a Python file listing the solutions
+            WorkspaceType::Gclient => marker.is_file(),
+            _ => marker.is_dir(),
+        }
+    }
+}
+
+/// returns the root folder of the workspace containing the given folder,
+/// i.e. the nearest of it and its parent folders holding the marker of a
+/// workspace of the given type (of any type if not given)
+pub fn base_folder_of(
+    folder: &Path,
+    workspace_type: Option<WorkspaceType>,
+) -> Result<PathBuf, io::Error> {
+    for parent in folder.ancestors() {
+        let found = match workspace_type {
+            Some(workspace_type) => workspace_type.is_root(parent),
+            None => WorkspaceType::of(parent).is_some(),
+        };
+        if found {
+            return Ok(parent.to_path_buf());
+        }
+    }
+    Err(io::Error::other(match workspace_type {
+        Some(workspace_type) => format!("no {} folder found", workspace_type.marker()),
+        None => String::from("no .repo, .west, .tsrc or .gclient found"),
+    }))
+}